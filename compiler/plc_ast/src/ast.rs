@@ -27,7 +27,17 @@ pub struct GenericBinding {
     pub nature: TypeNature,
 }
 
-#[derive(PartialEq)]
+/// A type argument of an instance of a generic pou, e.g. `DINT` for the binding `T : ANY_NUM` in
+/// `Fifo<DINT>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeArgument {
+    pub binding: GenericBinding,
+    pub type_name: String,
+    /// the location of the reference the instance was created for
+    pub location: SourceLocation,
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Pou {
     pub name: String,
     pub variable_blocks: Vec<VariableBlock>,
@@ -39,11 +49,13 @@ pub struct Pou {
     pub name_location: SourceLocation,
     pub poly_mode: Option<PolymorphismMode>,
     pub generics: Vec<GenericBinding>,
+    /// the type arguments of an instance of a generic pou, empty for all other pous
+    pub type_arguments: Vec<TypeArgument>,
     pub linkage: LinkageType,
     pub super_class: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PolymorphismMode {
     None,
     Abstract,
@@ -219,7 +231,7 @@ impl Pou {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Implementation {
     pub name: String,
     pub type_name: String,
@@ -240,7 +252,7 @@ pub enum LinkageType {
     BuiltIn,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AccessModifier {
     Private,
     Public,
@@ -282,7 +294,7 @@ impl PouType {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct CompilationUnit {
     pub global_vars: Vec<VariableBlock>,
     pub units: Vec<Pou>,
@@ -350,11 +362,13 @@ pub enum ArgumentProperty {
     ByRef,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct VariableBlock {
     pub access: AccessModifier,
     pub constant: bool,
     pub retain: bool,
+    /// true for `PERSISTENT` blocks, whose values also survive a cold restart
+    pub persistent: bool,
     pub variables: Vec<Variable>,
     pub variable_block_type: VariableBlockType,
     pub linkage: LinkageType,
@@ -443,7 +457,7 @@ impl DataTypeDeclaration {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub struct UserTypeDeclaration {
     pub data_type: DataType,
     pub initializer: Option<AstNode>,
@@ -468,6 +482,11 @@ pub enum DataType {
         name: Option<String>, //maybe None for inline structs
         variables: Vec<Variable>,
     },
+    /// a struct whose members share the same memory
+    UnionType {
+        name: Option<String>, //maybe None for inline unions
+        variables: Vec<Variable>,
+    },
     EnumType {
         name: Option<String>, //maybe empty for inline enums
         numeric_type: String,
@@ -508,6 +527,7 @@ impl DataType {
     pub fn set_name(&mut self, new_name: String) {
         match self {
             DataType::StructType { name, .. }
+            | DataType::UnionType { name, .. }
             | DataType::EnumType { name, .. }
             | DataType::SubRangeType { name, .. }
             | DataType::ArrayType { name, .. }
@@ -521,6 +541,7 @@ impl DataType {
     pub fn get_name(&self) -> Option<&str> {
         match &self {
             DataType::StructType { name, .. }
+            | DataType::UnionType { name, .. }
             | DataType::EnumType { name, .. }
             | DataType::ArrayType { name, .. }
             | DataType::PointerType { name, .. }
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use plc_source::source_location::{CodeSpan, NewLines, TextLocation};
+
 use crate::{
     diagnostics::{Diagnostic, Severity},
     reporter::{
@@ -14,6 +16,9 @@ pub struct Diagnostician {
     reporter: Box<dyn DiagnosticReporter>,
     assessor: Box<dyn DiagnosticAssessor>,
     filename_fileid_mapping: HashMap<String, usize>,
+    /// the files compiled wrapped into generated code with the number of generated bytes in
+    /// front of them, the length and the line breaks of their own source
+    wrapped_files: HashMap<String, (usize, usize, NewLines)>,
 }
 
 impl Diagnostician {
@@ -21,11 +26,41 @@ impl Diagnostician {
     /// preview errors in the source
     /// returns the id to use to reference the given file
     pub fn register_file(&mut self, id: String, src: String) -> usize {
+        // a wrapped file keeps its own source, the generated code around it is not shown
+        if let Some(handle) = self.wrapped_files.get(&id).and_then(|_| self.get_file_handle(Some(&id))) {
+            return handle;
+        }
         let handle = self.reporter.register(id.clone(), src);
         self.filename_fileid_mapping.insert(id, handle);
         handle
     }
 
+    /// registers the source-code of a file that is compiled wrapped into generated code, e.g. a
+    /// statement wrapped into a function. `prefix` is the number of bytes generated in front of
+    /// the source, the locations in the wrapped code are moved back by it to point into the source
+    pub fn register_wrapped_file(&mut self, id: String, src: String, prefix: usize) -> usize {
+        self.wrapped_files.remove(&id);
+        let newlines = NewLines::build(&src);
+        let length = src.len();
+        let handle = self.register_file(id.clone(), src);
+        self.wrapped_files.insert(id, (prefix, length, newlines));
+        handle
+    }
+
+    /// moves the given location in wrapped code back to the file's own source
+    fn unwrap_span(&self, file_name: Option<&str>, span: &CodeSpan) -> CodeSpan {
+        let (Some((prefix, length, newlines)), CodeSpan::Range(_)) =
+            (file_name.and_then(|it| self.wrapped_files.get(it)), span)
+        else {
+            return span.clone();
+        };
+        let range = span.to_range().unwrap_or_default();
+        // locations in the generated code are moved to the closest end of the source
+        let unwrap =
+            |offset: usize| TextLocation::from_offset(offset.saturating_sub(*prefix).min(*length), newlines);
+        CodeSpan::Range(unwrap(range.start)..unwrap(range.end))
+    }
+
     fn get_file_handle(&self, file_name: Option<&str>) -> Option<usize> {
         file_name.and_then(|it| self.filename_fileid_mapping.get(it).cloned())
     }
@@ -46,7 +81,7 @@ impl Diagnostician {
                     file_handle: self
                         .get_file_handle(d.get_location().get_file_name().or(Some("<internal>")))
                         .unwrap_or(usize::MAX),
-                    span: d.get_location().get_span().clone(),
+                    span: self.unwrap_span(d.get_location().get_file_name(), d.get_location().get_span()),
                 },
                 additional_locations: d.get_secondary_locations().map(|it| {
                     it.iter()
@@ -54,7 +89,7 @@ impl Diagnostician {
                             file_handle: self
                                 .get_file_handle(l.get_file_name().or(Some("<internal>")))
                                 .unwrap_or(usize::MAX),
-                            span: l.get_span().clone(),
+                            span: self.unwrap_span(l.get_file_name(), l.get_span()),
                         })
                         .collect()
                 }),
@@ -72,6 +107,7 @@ impl Diagnostician {
             assessor: Box::<DefaultDiagnosticAssessor>::default(),
             reporter: Box::<NullDiagnosticReporter>::default(),
             filename_fileid_mapping: HashMap::new(),
+            wrapped_files: HashMap::new(),
         }
     }
 
@@ -81,6 +117,7 @@ impl Diagnostician {
             assessor: Box::<DefaultDiagnosticAssessor>::default(),
             reporter: Box::new(CodeSpanDiagnosticReporter::buffered()),
             filename_fileid_mapping: HashMap::new(),
+            wrapped_files: HashMap::new(),
         }
     }
 
@@ -90,6 +127,7 @@ impl Diagnostician {
             reporter: Box::<ClangFormatDiagnosticReporter>::default(),
             assessor: Box::<DefaultDiagnosticAssessor>::default(),
             filename_fileid_mapping: HashMap::new(),
+            wrapped_files: HashMap::new(),
         }
     }
 }
@@ -125,6 +163,7 @@ impl Default for Diagnostician {
             reporter: Box::<CodeSpanDiagnosticReporter>::default(),
             assessor: Box::<DefaultDiagnosticAssessor>::default(),
             filename_fileid_mapping: HashMap::new(),
+            wrapped_files: HashMap::new(),
         }
     }
 }
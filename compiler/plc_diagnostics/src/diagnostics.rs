@@ -205,6 +205,38 @@ mod diagnostics_registry {
         include_str!("./error_codes/E089.md"),
         E090,
         include_str!("./error_codes/E090.md"),
+        E091,
+        include_str!("./error_codes/E091.md"),
+        E092,
+        include_str!("./error_codes/E092.md"),
+        E093,
+        include_str!("./error_codes/E093.md"),
+        E094,
+        include_str!("./error_codes/E094.md"),
+        E095,
+        include_str!("./error_codes/E095.md"),
+        E096,
+        include_str!("./error_codes/E096.md"),
+        E097,
+        include_str!("./error_codes/E097.md"),
+        E098,
+        include_str!("./error_codes/E098.md"),
+        E099,
+        include_str!("./error_codes/E099.md"),
+        E100,
+        include_str!("./error_codes/E100.md"),
+        E101,
+        include_str!("./error_codes/E101.md"),
+        E102,
+        include_str!("./error_codes/E102.md"),
+        E103,
+        include_str!("./error_codes/E103.md"),
+        E104,
+        include_str!("./error_codes/E104.md"),
+        E105,
+        include_str!("./error_codes/E105.md"),
+        E106,
+        include_str!("./error_codes/E106.md"),
     );
 }
 
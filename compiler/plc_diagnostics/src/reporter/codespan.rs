@@ -130,7 +130,11 @@ impl DiagnosticReporter for CodeSpanDiagnosticReporter {
                 }));
             }
 
-            let diag = diagnostic_factory.with_labels(labels).with_message(d.message.as_str());
+            // code generated from a diagram is additionally located by its block's id and position
+            let notes = d.main_location.span.get_block_descriptions();
+
+            let diag =
+                diagnostic_factory.with_labels(labels).with_notes(notes).with_message(d.message.as_str());
 
             let result = self.emit(diag);
             if result.is_err() && d.main_location.is_internal() {
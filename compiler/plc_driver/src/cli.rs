@@ -1,12 +1,22 @@
 // Copyright (c) 2021 Ghaith Hachem and Mathias Rieder
 use clap::{ArgGroup, CommandFactory, ErrorKind, Parser, Subcommand};
 use encoding_rs::Encoding;
-use std::{ffi::OsStr, num::ParseIntError, path::PathBuf};
+use std::{
+    ffi::OsStr,
+    num::ParseIntError,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use plc::{output::FormatOption, ConfigFormat, DebugLevel, ErrorFormat, Target, Threads};
 
+use crate::simulation::RunOptions;
+
 pub type ParameterError = clap::Error;
 
+/// The name of the file recording the memory layout of a build for online changes
+pub const ONLINE_CHANGE_LAYOUT: &str = "online_change.json";
+
 #[derive(Parser, Debug)]
 #[clap(
     group = ArgGroup::new("format"),
@@ -75,16 +85,28 @@ pub struct CompileParameters {
     // having a vec allows bash to resolve *.st itself
     pub input: Vec<String>,
 
-    #[clap(name = "library-path", long, short = 'L', help = "Search path for libraries, used for linking")]
+    #[clap(
+        name = "library-path",
+        long,
+        short = 'L',
+        global = true,
+        help = "Search path for libraries, used for linking"
+    )]
     pub library_paths: Vec<String>,
 
-    #[clap(name = "library", long, short = 'l', help = "Library name to link")]
+    #[clap(name = "library", long, short = 'l', global = true, help = "Library name to link")]
     pub libraries: Vec<String>,
 
     #[clap(long, name = "sysroot", global = true, help = "Path to system root, used for linking")]
     pub sysroot: Vec<String>,
 
-    #[clap(name = "include", long, short = 'i', help = "Include source files for external functions")]
+    #[clap(
+        name = "include",
+        long,
+        short = 'i',
+        global = true,
+        help = "Include source files for external functions"
+    )]
     pub includes: Vec<String>,
 
     #[clap(
@@ -98,6 +120,65 @@ pub struct CompileParameters {
     ) ]
     pub hardware_config: Option<String>,
 
+    #[clap(
+        name = "io-mapping",
+        long,
+        global = true,
+        help = "Assign the variables declared at wildcard addresses (%I*, %Q*) to the addresses in the given mapping file.
+    The file has the same shape as the hardware configuration.
+    Supported formats : json, toml",
+    parse(try_from_str = validate_config)
+    ) ]
+    pub io_mapping: Option<String>,
+
+    #[clap(
+        name = "symbol-map",
+        long,
+        global = true,
+        help = "Generate a JSON file describing all instances and types with their memory layout to the given location"
+    )]
+    pub symbol_map: Option<String>,
+
+    #[clap(
+        name = "stack-report",
+        long,
+        global = true,
+        help = "Generate a JSON file listing the stack frame and code size of every function and the worst case stack usage of every program to the given location"
+    )]
+    pub stack_report: Option<String>,
+
+    #[clap(
+        name = "stack-limit",
+        long,
+        global = true,
+        help = "Report an error if the worst case stack usage of a program exceeds the given number of bytes"
+    )]
+    pub stack_limit: Option<u64>,
+
+    #[clap(
+        name = "coverage",
+        long,
+        global = true,
+        help = "Count the execution of every statement and branch, the counters are appended to the lcov file named by PLC_COVERAGE_FILE (default: coverage.info) at exit"
+    )]
+    pub coverage: bool,
+
+    #[clap(
+        name = "embed-symbols",
+        long,
+        global = true,
+        help = "Embed a table of all instances into the output that can be searched by name at runtime"
+    )]
+    pub embed_symbols: bool,
+
+    #[clap(
+        name = "online-change",
+        long,
+        global = true,
+        help = "Keep the memory layout of the previous build stable and generate functions to migrate its state. The layout is recorded in the build location or next to the output file"
+    )]
+    pub online_change: bool,
+
     #[clap(
         name = "optimization",
         long,
@@ -224,6 +305,77 @@ pub enum SubCommands {
         build_config: Option<String>,
     },
 
+    /// Exports the project instead of compiling it.
+    ///
+    /// export --xml [<build-config> | <input-files>]
+    ///
+    /// Writes the POUs, data types and global variables to a PLCopen TC6 XML project
+    Export {
+        #[clap(long, required = true, help = "Export to a PLCopen TC6 XML project")]
+        xml: bool,
+
+        #[clap(
+            name = "export-input",
+            help = "A build description file or the input files to export, defaults to the 'plc.json' of the current directory"
+        )]
+        input: Vec<String>,
+    },
+
+    /// Compiles the project and runs a program in the JIT instead of writing any output.
+    ///
+    /// run [<build-config> | <input-files>] --program <name> [--cycles <n>] [--watch <variable>]...
+    ///
+    /// Calls the program once per cycle and prints the watched variables after every cycle
+    Run {
+        #[clap(
+            name = "run-input",
+            help = "A build description file or the input files to run, defaults to the 'plc.json' of the current directory"
+        )]
+        input: Vec<String>,
+
+        #[clap(long, default_value = "main", help = "The PROGRAM to call every cycle")]
+        program: String,
+
+        #[clap(long, help = "The number of cycles to run, runs until interrupted (Ctrl-C) if not given")]
+        cycles: Option<u64>,
+
+        #[clap(
+            name = "cycle-time",
+            long,
+            help = "The time in milliseconds from the start of one cycle to the next"
+        )]
+        cycle_time: Option<u64>,
+
+        #[clap(
+            long,
+            short = 'w',
+            help = "A variable to print after every cycle, e.g. `main.counter` or a global variable"
+        )]
+        watch: Vec<String>,
+
+        #[clap(long, help = "Log the watched variables to the given CSV file instead of printing them")]
+        csv: Option<String>,
+
+        #[clap(long, help = "Record the traced variables to the given Value Change Dump (VCD) file")]
+        vcd: Option<String>,
+
+        #[clap(
+            long,
+            short = 't',
+            requires = "vcd",
+            help = "A variable or instance to record after every cycle, records every elementary variable of an instance, defaults to the program"
+        )]
+        trace: Vec<String>,
+    },
+
+    /// Starts an interactive session evaluating declarations and statements in the JIT.
+    ///
+    /// repl [-i <include>]... [-l <library>]...
+    ///
+    /// Declarations are kept for the rest of the session, statements are run once and the value
+    /// of a trailing expression is printed with its type
+    Repl,
+
     /// Prints out various configuration options
     Config {
         #[clap(
@@ -247,10 +399,15 @@ pub enum ConfigOption {
 
 impl SubCommands {
     pub fn get_build_configuration(&self) -> Option<&str> {
-        let (SubCommands::Build { build_config, .. } | SubCommands::Check { build_config }) = self else {
-            return None;
-        };
-        build_config.as_deref()
+        match self {
+            SubCommands::Build { build_config, .. } | SubCommands::Check { build_config } => {
+                build_config.as_deref()
+            }
+            SubCommands::Export { input, .. } | SubCommands::Run { input, .. } => {
+                input.first().map(String::as_str).filter(|it| get_config_format(it).is_some())
+            }
+            SubCommands::Repl | SubCommands::Config { .. } => None,
+        }
     }
 }
 
@@ -357,6 +514,11 @@ impl CompileParameters {
         self.hardware_config.as_deref().and_then(get_config_format)
     }
 
+    /// Returns the I/O mapping file together with its format, if one was given
+    pub fn get_io_mapping(&self) -> Option<(&str, ConfigFormat)> {
+        self.io_mapping.as_deref().and_then(|it| get_config_format(it).map(|format| (it, format)))
+    }
+
     /// Returns the location where the build artifacts should be stored / output
     pub fn get_build_location(&self) -> Option<PathBuf> {
         match &self.commands {
@@ -367,6 +529,17 @@ impl CompileParameters {
         }
     }
 
+    /// Returns the file recording the memory layout of the build if online change is enabled
+    pub fn get_online_change_layout(&self) -> Option<PathBuf> {
+        if !self.online_change {
+            return None;
+        }
+        let location = self
+            .get_build_location()
+            .or_else(|| self.output.as_deref().map(Path::new).and_then(Path::parent).map(Path::to_path_buf));
+        Some(location.unwrap_or_default().join(ONLINE_CHANGE_LAYOUT))
+    }
+
     pub fn get_lib_location(&self) -> Option<PathBuf> {
         match &self.commands {
             Some(SubCommands::Build { build_location, lib_location, .. }) => {
@@ -376,6 +549,43 @@ impl CompileParameters {
         }
     }
 
+    /// Returns true if the project should be exported to PLCopen XML instead of being compiled
+    pub fn is_xml_export(&self) -> bool {
+        matches!(self.commands, Some(SubCommands::Export { xml: true, .. }))
+    }
+
+    pub fn is_repl(&self) -> bool {
+        matches!(self.commands, Some(SubCommands::Repl))
+    }
+
+    /// Returns the input files of the export or run subcommand, if it was not given a build
+    /// description file
+    pub fn get_subcommand_files(&self) -> Option<&[String]> {
+        let (Some(SubCommands::Export { input, .. }) | Some(SubCommands::Run { input, .. })) = &self.commands
+        else {
+            return None;
+        };
+        input.first().filter(|it| get_config_format(it).is_none()).map(|_| input.as_slice())
+    }
+
+    /// Returns what to run if the project should be run in the JIT instead of being compiled
+    pub fn get_run_options(&self) -> Option<RunOptions> {
+        let Some(SubCommands::Run { program, cycles, cycle_time, watch, csv, vcd, trace, .. }) =
+            &self.commands
+        else {
+            return None;
+        };
+        Some(RunOptions {
+            program: program.clone(),
+            cycles: *cycles,
+            cycle_time: cycle_time.map(Duration::from_millis),
+            watch: watch.clone(),
+            csv: csv.as_ref().map(PathBuf::from),
+            vcd: vcd.as_ref().map(PathBuf::from),
+            trace: trace.clone(),
+        })
+    }
+
     pub fn get_config_options(&self) -> Option<(ConfigOption, ConfigFormat)> {
         let Some(SubCommands::Config { format, option }) = &self.commands else { return None };
         Some((*option, *format))
@@ -384,12 +594,15 @@ impl CompileParameters {
 
 #[cfg(test)]
 mod cli_tests {
-    use super::{CompileParameters, SubCommands};
+    use super::{CompileParameters, SubCommands, ONLINE_CHANGE_LAYOUT};
+    use crate::simulation::RunOptions;
     use clap::{CommandFactory, ErrorKind};
     use plc::{output::FormatOption, ConfigFormat, ErrorFormat, OptimizationLevel};
     use pretty_assertions::assert_eq;
     use std::ffi::OsStr;
     use std::fmt::Debug;
+    use std::path::PathBuf;
+    use std::time::Duration;
 
     #[test]
     fn verify_cli() {
@@ -708,6 +921,98 @@ mod cli_tests {
         }
     }
 
+    #[test]
+    fn export_subcommand() {
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("export", "--xml", "src/ProjectPlc.json")).unwrap();
+        assert!(parameters.is_xml_export());
+        assert_eq!(parameters.get_subcommand_files(), None);
+        assert_eq!(
+            parameters.commands.as_ref().and_then(SubCommands::get_build_configuration),
+            Some("src/ProjectPlc.json")
+        );
+
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("export", "--xml", "a.st", "b.st", "-o", "out.xml"))
+                .unwrap();
+        assert!(parameters.is_xml_export());
+        assert_eq!(
+            parameters.get_subcommand_files(),
+            Some(["a.st".to_string(), "b.st".to_string()].as_slice())
+        );
+        assert_eq!(parameters.output, Some("out.xml".to_string()));
+    }
+
+    #[test]
+    fn run_subcommand() {
+        let parameters = CompileParameters::parse(vec_of_strings!(
+            "run",
+            "main.st",
+            "-i",
+            "timers.st",
+            "-l",
+            "iec61131std",
+            "--program",
+            "prog",
+            "--cycles",
+            "10",
+            "--cycle-time",
+            "20",
+            "-w",
+            "prog.x",
+            "--watch",
+            "prog.y",
+            "--csv",
+            "trace.csv",
+            "--vcd",
+            "trace.vcd",
+            "-t",
+            "prog.motor"
+        ))
+        .unwrap();
+        assert_eq!(parameters.get_subcommand_files(), Some(["main.st".to_string()].as_slice()));
+        assert_eq!(parameters.includes, vec!["timers.st".to_string()]);
+        assert_eq!(parameters.libraries, vec!["iec61131std".to_string()]);
+        assert_eq!(
+            parameters.get_run_options(),
+            Some(RunOptions {
+                program: "prog".to_string(),
+                cycles: Some(10),
+                cycle_time: Some(Duration::from_millis(20)),
+                watch: vec!["prog.x".to_string(), "prog.y".to_string()],
+                csv: Some(PathBuf::from("trace.csv")),
+                vcd: Some(PathBuf::from("trace.vcd")),
+                trace: vec!["prog.motor".to_string()],
+            })
+        );
+
+        let parameters = CompileParameters::parse(vec_of_strings!("run")).unwrap();
+        assert_eq!(parameters.get_subcommand_files(), None);
+        assert_eq!(parameters.get_run_options().map(|it| it.program), Some("main".to_string()));
+        assert_eq!(parameters.get_run_options().and_then(|it| it.cycles), None);
+
+        // traced variables are recorded to a VCD file
+        expect_argument_error(vec_of_strings!("run", "-t", "prog.x"), ErrorKind::MissingRequiredArgument);
+    }
+
+    #[test]
+    fn repl_subcommand() {
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("repl", "-i", "timers.st", "-l", "iec61131std"))
+                .unwrap();
+        assert!(parameters.is_repl());
+        assert_eq!(parameters.includes, vec!["timers.st".to_string()]);
+        assert_eq!(parameters.libraries, vec!["iec61131std".to_string()]);
+
+        let parameters = CompileParameters::parse(vec_of_strings!("input.st")).unwrap();
+        assert!(!parameters.is_repl());
+    }
+
+    #[test]
+    fn export_subcommand_requires_format() {
+        expect_argument_error(vec_of_strings!["export", "a.st"], ErrorKind::MissingRequiredArgument);
+    }
+
     #[test]
     fn sysroot_added() {
         let parameters = CompileParameters::parse(vec_of_strings!(
@@ -752,6 +1057,76 @@ mod cli_tests {
         expect_argument_error(vec_of_strings!("foo", "--hardware-conf=conf.xml"), ErrorKind::ValueValidation);
     }
 
+    #[test]
+    fn io_mapping_argument() {
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("foo", "--io-mapping=mapping.json")).unwrap();
+        assert_eq!(parameters.get_io_mapping(), Some(("mapping.json", ConfigFormat::JSON)));
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("foo", "--io-mapping=mapping.toml")).unwrap();
+        assert_eq!(parameters.get_io_mapping(), Some(("mapping.toml", ConfigFormat::TOML)));
+        let parameters = CompileParameters::parse(vec_of_strings!("foo")).unwrap();
+        assert_eq!(parameters.get_io_mapping(), None);
+
+        expect_argument_error(vec_of_strings!("foo", "--io-mapping=mapping.xml"), ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn symbol_map_argument() {
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("foo", "--symbol-map=symbols.json")).unwrap();
+        assert_eq!(parameters.symbol_map, Some("symbols.json".to_string()));
+        let parameters = CompileParameters::parse(vec_of_strings!("foo")).unwrap();
+        assert_eq!(parameters.symbol_map, None);
+    }
+
+    #[test]
+    fn embed_symbols_argument() {
+        let parameters = CompileParameters::parse(vec_of_strings!("foo", "--embed-symbols")).unwrap();
+        assert!(parameters.embed_symbols);
+        let parameters = CompileParameters::parse(vec_of_strings!("foo")).unwrap();
+        assert!(!parameters.embed_symbols);
+    }
+
+    #[test]
+    fn stack_report_arguments() {
+        let parameters = CompileParameters::parse(vec_of_strings!(
+            "foo",
+            "--stack-report=stack.json",
+            "--stack-limit",
+            "4096"
+        ))
+        .unwrap();
+        assert_eq!(parameters.stack_report, Some("stack.json".to_string()));
+        assert_eq!(parameters.stack_limit, Some(4096));
+        let parameters = CompileParameters::parse(vec_of_strings!("foo")).unwrap();
+        assert_eq!(parameters.stack_report, None);
+        assert_eq!(parameters.stack_limit, None);
+    }
+
+    #[test]
+    fn coverage_argument() {
+        let parameters = CompileParameters::parse(vec_of_strings!("foo", "--coverage")).unwrap();
+        assert!(parameters.coverage);
+        let parameters = CompileParameters::parse(vec_of_strings!("foo")).unwrap();
+        assert!(!parameters.coverage);
+    }
+
+    #[test]
+    fn online_change_argument() {
+        let parameters = CompileParameters::parse(vec_of_strings!("foo", "--online-change")).unwrap();
+        assert_eq!(parameters.get_online_change_layout(), Some(PathBuf::from(ONLINE_CHANGE_LAYOUT)));
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("foo", "--online-change", "-o", "out/app.so")).unwrap();
+        assert_eq!(parameters.get_online_change_layout(), Some(PathBuf::from("out/online_change.json")));
+        let parameters =
+            CompileParameters::parse(vec_of_strings!("build", "--online-change", "--build-location", "bin"))
+                .unwrap();
+        assert_eq!(parameters.get_online_change_layout(), Some(PathBuf::from("bin/online_change.json")));
+        let parameters = CompileParameters::parse(vec_of_strings!("foo")).unwrap();
+        assert_eq!(parameters.get_online_change_layout(), None);
+    }
+
     #[test]
     fn error_format_default_set() {
         // make sure the default error format is set
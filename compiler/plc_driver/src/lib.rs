@@ -10,26 +10,37 @@
 
 use anyhow::Result;
 use std::{
+    collections::HashSet,
     env,
     ffi::OsStr,
     fmt::{Debug, Display},
+    fs, io,
     path::{Path, PathBuf},
 };
 
 use cli::{CompileParameters, ParameterError};
 use pipelines::AnnotatedProject;
 use plc::{
-    codegen::CodegenContext, output::FormatOption, DebugLevel, ErrorFormat, OptimizationLevel, Threads,
+    codegen::{CodegenContext, GeneratedModule},
+    hardware_binding::IoMapping,
+    online_change::LayoutRecord,
+    output::FormatOption,
+    ConfigFormat, DebugLevel, ErrorFormat, OptimizationLevel, Target, Threads,
 };
 
-use plc_diagnostics::{diagnostician::Diagnostician, diagnostics::Diagnostic};
+use plc_diagnostics::{
+    diagnostician::Diagnostician,
+    diagnostics::{Diagnostic, Severity},
+};
 use plc_index::GlobalContext;
 use project::project::{LibraryInformation, Project};
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use source_code::SourceContainer;
 
 pub mod cli;
 pub mod pipelines;
+pub mod repl;
+pub mod simulation;
 
 #[cfg(test)]
 mod tests;
@@ -52,6 +63,16 @@ pub struct CompileOptions {
     pub optimization: OptimizationLevel,
     pub error_format: ErrorFormat,
     pub debug_level: DebugLevel,
+    /// Embed a table of all instances and functions to look them up by name into the output
+    pub embed_symbols: bool,
+    /// The memory layout of the previous build, functions migrating its state are generated if present
+    pub previous_layout: Option<LayoutRecord>,
+    /// Assigns the variables declared at wildcard addresses to their location in the process image
+    pub io_mapping: Option<IoMapping>,
+    /// Measure the stack frame and code size of every generated function
+    pub stack_report: bool,
+    /// Instrument the generated statements with statement and branch coverage counters
+    pub coverage: bool,
 }
 
 impl Default for CompileOptions {
@@ -64,6 +85,11 @@ impl Default for CompileOptions {
             optimization: OptimizationLevel::None,
             error_format: ErrorFormat::None,
             debug_level: DebugLevel::None,
+            embed_symbols: false,
+            previous_layout: None,
+            io_mapping: None,
+            stack_report: false,
+            coverage: false,
         }
     }
 }
@@ -74,6 +100,7 @@ pub struct LinkOptions {
     pub library_pathes: Vec<PathBuf>,
     pub format: FormatOption,
     pub linker: Option<String>,
+    pub linker_script: Option<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -125,6 +152,9 @@ pub fn compile<T: AsRef<str> + AsRef<OsStr> + Debug>(args: &[T]) -> Result<()> {
     if let Some((options, format)) = compile_parameters.get_config_options() {
         return print_config_options(options, format);
     }
+    if compile_parameters.is_repl() {
+        return start_repl(&compile_parameters);
+    }
     let project = get_project(&compile_parameters)?;
     let output_format = compile_parameters.output_format().unwrap_or_else(|| project.get_output_format());
     let location = project.get_location().map(|it| it.to_path_buf());
@@ -142,11 +172,7 @@ pub fn compile<T: AsRef<str> + AsRef<OsStr> + Debug>(args: &[T]) -> Result<()> {
         log::debug!("LIB_LOCATION={}", location.to_string_lossy());
         env::set_var("LIB_LOCATION", location);
     }
-    let mut diagnostician = match compile_parameters.error_format {
-        ErrorFormat::Rich => Diagnostician::default(),
-        ErrorFormat::Clang => Diagnostician::clang_format_diagnostician(),
-        ErrorFormat::None => Diagnostician::null_diagnostician(),
-    };
+    let mut diagnostician = create_diagnostician(compile_parameters.error_format);
 
     //Set the global thread count
     let thread_pool = rayon::ThreadPoolBuilder::new();
@@ -177,22 +203,121 @@ pub fn compile<T: AsRef<str> + AsRef<OsStr> + Debug>(args: &[T]) -> Result<()> {
             None,
         )?;
 
-    // 1 : Parse, 2. Index and 3. Resolve / Annotate
-    let annotated_project = pipelines::ParsedProject::parse(&ctxt, &project, &mut diagnostician)?
-        .index(ctxt.provider())
-        .annotate(ctxt.provider());
+    if compile_parameters.is_xml_export() {
+        let output = compile_parameters.output.clone().map(PathBuf::from).unwrap_or_else(|| {
+            Path::new(project.get_name())
+                .with_extension("xml")
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_default()
+        });
+        return pipelines::export_xml(&ctxt, &project, &mut diagnostician, &output).map_err(Into::into);
+    }
+
+    // 1 : Parse and 2. Index
+    let indexed_project =
+        pipelines::ParsedProject::parse(&ctxt, &project, &mut diagnostician)?.index(ctxt.provider());
+
+    // 3. Resolve / Annotate for every target, constants like `SIZEOF` depend on the target's layout
+    let targets = if compile_parameters.target.is_empty() || compile_parameters.get_run_options().is_some() {
+        vec![Target::System]
+    } else {
+        compile_parameters.target.clone()
+    };
+    // the layout of a previous build is kept before constants are evaluated with it
+    let layout_location = compile_parameters.get_online_change_layout();
+    let previous_layout = layout_location.as_deref().map(read_layout_record).transpose()?.flatten();
+    let mut reported = HashSet::new();
+    let mut annotated_projects = vec![];
+    for target in targets {
+        let mut indexed_project = indexed_project.clone();
+        indexed_project.set_target(&target)?;
+        if let Some(previous_layout) = &previous_layout {
+            let diagnostics = indexed_project.stabilize_layout(previous_layout);
+            pipelines::report_once(&mut diagnostician, &mut reported, diagnostics);
+        }
+        annotated_projects.push((target, indexed_project.annotate(ctxt.provider())));
+    }
+    drop(indexed_project);
+    let io_mapping = compile_parameters.get_io_mapping().map(read_io_mapping).transpose()?;
+    if let Some(io_mapping) = &io_mapping {
+        let mut reported = HashSet::new();
+        let mut severity = Severity::default();
+        for (_, annotated_project) in &mut annotated_projects {
+            let diagnostics = annotated_project.apply_io_mapping(io_mapping)?;
+            severity = severity.max(pipelines::report_once(&mut diagnostician, &mut reported, diagnostics));
+        }
+        if severity == Severity::Error {
+            return Err(Diagnostic::error("Compilation aborted due to an invalid I/O mapping")
+                .with_error_code("E102")
+                .into());
+        }
+    }
 
     // 4 : Validate
-    annotated_project.validate(&ctxt, &mut diagnostician)?;
+    AnnotatedProject::validate_all(annotated_projects.iter().map(|(_, it)| it), &ctxt, &mut diagnostician)?;
+    // the symbol map and the layout record are written for the first target
+    let (_, annotated_project) = &annotated_projects[0];
+
+    // 5 : Run in the JIT instead of generating any output
+    if let Some(run_options) = compile_parameters.get_run_options() {
+        let compile_options = CompileOptions {
+            root: location,
+            optimization: compile_parameters.optimization,
+            debug_level: DebugLevel::None,
+            io_mapping,
+            coverage: compile_parameters.coverage,
+            ..Default::default()
+        };
+        let library_paths = compile_parameters
+            .library_paths
+            .iter()
+            .map(PathBuf::from)
+            .chain(
+                project
+                    .get_libraries()
+                    .iter()
+                    .filter_map(LibraryInformation::get_path)
+                    .map(Path::to_path_buf),
+            )
+            .collect::<Vec<_>>();
+        let libraries = project
+            .get_libraries()
+            .iter()
+            .map(|it| simulation::find_library(it.get_link_name(), &library_paths))
+            .collect::<Vec<_>>();
+        return simulation::run(annotated_project, &compile_options, &libraries, &run_options)
+            .map_err(Into::into);
+    }
 
-    // 5 : Codegen
+    // 6 : Codegen
     if !compile_parameters.is_check() {
+        if let Some(location) = &compile_parameters.symbol_map {
+            annotated_project.generate_symbol_map(&ctxt, location)?;
+        }
+        let layout_record =
+            layout_location.as_ref().map(|_| annotated_project.collect_layout_record()).transpose()?;
+        let compile_options = CompileOptions {
+            root: location,
+            build_location: compile_parameters.get_build_location(),
+            output: project.get_output_name(),
+            output_format,
+            optimization: compile_parameters.optimization,
+            error_format: compile_parameters.error_format,
+            debug_level: compile_parameters.debug_level(),
+            embed_symbols: compile_parameters.embed_symbols,
+            previous_layout,
+            io_mapping,
+            stack_report: compile_parameters.stack_report.is_some()
+                || compile_parameters.stack_limit.is_some(),
+            coverage: compile_parameters.coverage,
+        };
         let res = generate(
-            location,
+            compile_options,
             compile_parameters,
+            &mut diagnostician,
             project,
-            output_format,
-            annotated_project,
+            annotated_projects,
             build_location,
             lib_location,
         )
@@ -203,8 +328,36 @@ pub fn compile<T: AsRef<str> + AsRef<OsStr> + Debug>(args: &[T]) -> Result<()> {
                 .with_error_code("E071")
                 .into());
         }
+        // only record the layout once the build succeeded, the next build migrates from this one
+        if let Some((location, record)) = layout_location.zip(layout_record) {
+            write_layout_record(&record, &location)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn create_diagnostician(error_format: ErrorFormat) -> Diagnostician {
+    match error_format {
+        ErrorFormat::Rich => Diagnostician::default(),
+        ErrorFormat::Clang => Diagnostician::clang_format_diagnostician(),
+        ErrorFormat::None => Diagnostician::null_diagnostician(),
     }
+}
 
+/// Starts an interactive session on the terminal, with the includes and libraries of the parameters
+fn start_repl(compile_parameters: &CompileParameters) -> Result<()> {
+    let project = Project::new("repl".to_string())
+        .with_include_pathes(compile_parameters.includes.iter().map(PathBuf::from).collect());
+    let ctxt = GlobalContext::new().with_source(project.get_includes(), compile_parameters.encoding)?;
+    let includes =
+        project.get_includes().iter().filter_map(|it| ctxt.get(it.get_location_str())).cloned().collect();
+    let library_paths = compile_parameters.library_paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+    for library in &compile_parameters.libraries {
+        GeneratedModule::load_library(&simulation::find_library(library, &library_paths))?;
+    }
+    let mut repl = repl::Repl::new(ctxt, includes, create_diagnostician(compile_parameters.error_format));
+    repl::run(&mut repl, io::stdin().lock(), io::stdout())?;
     Ok(())
 }
 
@@ -271,29 +424,40 @@ fn generate_to_string_internal<T: SourceContainer>(
 }
 
 fn generate(
-    location: Option<PathBuf>,
+    compile_options: CompileOptions,
     compile_parameters: CompileParameters,
+    diagnostician: &mut Diagnostician,
     project: Project<PathBuf>,
-    output_format: FormatOption,
-    annotated_project: AnnotatedProject,
+    annotated_projects: Vec<(Target, AnnotatedProject)>,
     build_location: Option<PathBuf>,
     lib_location: Option<PathBuf>,
 ) -> Result<(), Diagnostic> {
-    let compile_options = CompileOptions {
-        root: location,
-        build_location: compile_parameters.get_build_location(),
-        output: project.get_output_name(),
-        output_format,
-        optimization: compile_parameters.optimization,
-        error_format: compile_parameters.error_format,
-        debug_level: compile_parameters.debug_level(),
-    };
-    let res = if compile_parameters.single_module {
+    let output_format = compile_options.output_format;
+    if compile_parameters.single_module {
         log::info!("Using single module mode");
-        annotated_project.codegen_single_module(compile_options, &compile_parameters.target)?
-    } else {
-        annotated_project.codegen(compile_options, &compile_parameters.target)?
-    };
+    }
+    let res = annotated_projects
+        .par_iter()
+        .map(|(target, annotated_project)| {
+            if compile_parameters.single_module {
+                annotated_project.codegen_single_module(&compile_options, target)
+            } else {
+                annotated_project.codegen(&compile_options, target)
+            }
+        })
+        .collect::<Result<Vec<_>, Diagnostic>>()?;
+    if compile_parameters.stack_report.is_some() || compile_parameters.stack_limit.is_some() {
+        for (generated, (_, annotated_project)) in res.iter().zip(&annotated_projects) {
+            let diagnostics = annotated_project.generate_stack_report(
+                generated,
+                compile_parameters.stack_report.as_deref().map(Path::new),
+                compile_parameters.stack_limit,
+            )?;
+            if diagnostician.handle(&diagnostics) == Severity::Error {
+                return Err(Diagnostic::error("The stack limit is exceeded").with_error_code("E103"));
+            }
+        }
+    }
     let libraries =
         project.get_libraries().iter().map(LibraryInformation::get_link_name).map(str::to_string).collect();
     let library_pathes = project
@@ -307,22 +471,30 @@ fn generate(
         library_pathes,
         format: output_format,
         linker: compile_parameters.linker.to_owned(),
+        linker_script: project.get_linker_script().map(Path::to_path_buf),
     };
-    let output_name = project.get_output_name();
-    res.into_par_iter()
+    res.par_iter()
         .map(|res| {
+            let mut linker_options = linker_options.clone();
+            // the wasm linker searches the `-L` paths for the libraries linked into the module
+            if res.get_target().is_wasm() {
+                linker_options
+                    .library_pathes
+                    .extend(compile_parameters.library_paths.iter().map(PathBuf::from));
+            }
             res.link(
                 project.get_objects(),
                 build_location.as_deref(),
                 lib_location.as_deref(),
-                &output_name,
-                linker_options.clone(),
+                &project.get_output_name_for(res.get_target()),
+                linker_options,
             )
         })
         .collect::<Result<Vec<_>, _>>()?;
     if let Some((location, format)) =
         compile_parameters.hardware_config.as_ref().zip(compile_parameters.config_format())
     {
+        let (_, annotated_project) = &annotated_projects[0];
         annotated_project.generate_hardware_information(format, location)?;
     }
     if let Some(lib_location) = lib_location {
@@ -344,7 +516,10 @@ fn generate(
 fn get_project(compile_parameters: &CompileParameters) -> Result<Project<PathBuf>> {
     let current_dir = env::current_dir()?;
     //Create a project from either the subcommand or single params
-    let project = if let Some(command) = &compile_parameters.commands {
+    let input_files = compile_parameters.get_subcommand_files();
+    let input = input_files.unwrap_or(&compile_parameters.input);
+    let command = compile_parameters.commands.as_ref().filter(|_| input_files.is_none());
+    let project = if let Some(command) = command {
         //Build with subcommand
         let config = command
             .get_build_configuration()
@@ -362,15 +537,14 @@ fn get_project(compile_parameters: &CompileParameters) -> Result<Project<PathBuf
         Project::from_config(&config)
     } else {
         //Build with parameters
-        let name = compile_parameters
-            .input
+        let name = input
             .first()
             .and_then(|it| it.get_location())
             .and_then(|it| it.file_name())
             .and_then(|it| it.to_str())
             .unwrap_or(DEFAULT_OUTPUT_NAME);
         let project = Project::new(name.to_string())
-            .with_file_pathes(compile_parameters.input.iter().map(PathBuf::from).collect())
+            .with_file_pathes(input.iter().map(PathBuf::from).collect())
             .with_include_pathes(compile_parameters.includes.iter().map(PathBuf::from).collect())
             .with_libraries(compile_parameters.libraries.clone());
         Ok(project)
@@ -388,6 +562,37 @@ fn get_project(compile_parameters: &CompileParameters) -> Result<Project<PathBuf
         .map(|proj| proj.with_output_name(compile_parameters.output.clone()))
 }
 
+/// Reads the layout recorded by the previous build, returns `None` if there was no previous build
+fn read_layout_record(location: &Path) -> Result<Option<LayoutRecord>, Diagnostic> {
+    if !location.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(location).map_err(|it| {
+        Diagnostic::error(it.to_string()).with_internal_error(it.into()).with_error_code("E002")
+    })?;
+    plc::online_change::parse_layout_record(&content).map(Some)
+}
+
+fn read_io_mapping((location, format): (&str, ConfigFormat)) -> Result<IoMapping, Diagnostic> {
+    let content = fs::read_to_string(location).map_err(|it| {
+        Diagnostic::error(format!("Cannot read the I/O mapping {location}: {it}"))
+            .with_internal_error(it.into())
+            .with_error_code("E002")
+    })?;
+    plc::hardware_binding::parse_io_mapping(&content, format)
+}
+
+fn write_layout_record(record: &LayoutRecord, location: &Path) -> Result<(), Diagnostic> {
+    let generated_record = plc::online_change::generate_layout_record(record)?;
+    location
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(location, generated_record))
+        .map_err(|it| {
+            Diagnostic::error(it.to_string()).with_internal_error(it.into()).with_error_code("E002")
+        })
+}
+
 fn get_config(root: &Path) -> Option<PathBuf> {
     Some(root.join("plc.json"))
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::HashSet,
     env,
     fs::{self, File},
     io::Write,
@@ -13,12 +14,21 @@ use ast::{
 use indexmap::IndexSet;
 use plc::{
     codegen::{CodegenContext, GeneratedModule},
+    datalayout::DataLayout,
+    hardware_binding::IoMapping,
     index::Index,
+    online_change::LayoutRecord,
     output::FormatOption,
     parser::parse_file,
-    resolver::{AnnotationMapImpl, AstAnnotations, Dependency, StringLiterals, TypeAnnotator},
+    process_image::MappedAddress,
+    resolver::{
+        const_evaluator::{evaluate_constants_with_functions, ConstFunctions},
+        generics::{implement_generic_functions, instantiate_generic_pous},
+        AnnotationMapImpl, AstAnnotations, Dependency, StringLiterals, TypeAnnotator,
+    },
+    stack_report::FunctionStackUsage,
     validation::Validator,
-    ConfigFormat, Target,
+    ConfigFormat, DebugLevel, Target,
 };
 use plc_diagnostics::{
     diagnostician::Diagnostician,
@@ -32,10 +42,71 @@ use project::{
 use rayon::prelude::*;
 use source_code::{source_location::SourceLocation, SourceContainer};
 
+/// The name of the module holding the embedded symbol table
+const SYMBOL_TABLE_MODULE: &str = "__symbols";
+/// The name of the module holding the table of all RETAIN and PERSISTENT variables
+const RETAIN_MODULE: &str = "__retain";
+/// The name of the module holding the online change migration functions
+const MIGRATION_MODULE: &str = "__migration";
+
 ///Represents a parsed project
 ///For this struct to be built, the project would have been parsed correctly and an AST would have
 ///been generated
-pub struct ParsedProject(Vec<CompilationUnit>);
+pub struct ParsedProject {
+    units: Vec<CompilationUnit>,
+    /// the highest severity of the syntax errors reported while parsing
+    severity: Severity,
+}
+
+/// Parses the project's own source files, without its includes and libraries. Every unit is
+/// returned with the highest severity of its syntax errors
+fn parse_sources<T: SourceContainer>(
+    ctxt: &GlobalContext,
+    project: &Project<T>,
+    diagnostician: &mut Diagnostician,
+) -> Result<Vec<(CompilationUnit, Severity)>, Diagnostic> {
+    project
+        .get_sources()
+        .iter()
+        .map(|it| {
+            let source = ctxt.get(it.get_location_str()).expect("All sources should've been read");
+
+            let parse_func = match source.get_type() {
+                source_code::SourceType::Text => parse_file,
+                source_code::SourceType::Xml => cfc::xml_parser::parse_file,
+                source_code::SourceType::Unknown => unreachable!(),
+            };
+            Ok(parse_func(source, LinkageType::Internal, ctxt.provider(), diagnostician))
+        })
+        .collect()
+}
+
+/// Exports the project's sources as a PLCopen TC6 XML project to the given location
+pub fn export_xml<T: SourceContainer>(
+    ctxt: &GlobalContext,
+    project: &Project<T>,
+    diagnostician: &mut Diagnostician,
+    location: &Path,
+) -> Result<(), Diagnostic> {
+    let units =
+        parse_sources(ctxt, project, diagnostician)?.into_iter().map(|(unit, _)| unit).collect::<Vec<_>>();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .and_then(|it| chrono::NaiveDateTime::from_timestamp_opt(it.as_secs() as i64, 0))
+        .unwrap_or_default();
+    let (content, diagnostics) = cfc::exporter::export(
+        project.get_name(),
+        &now.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        &units,
+        |file| ctxt.get(file).map(|it| it.source.as_str()),
+    );
+    diagnostician.handle(&diagnostics);
+
+    fs::write(location, content).map_err(|it| {
+        Diagnostic::error(it.to_string()).with_internal_error(it.into()).with_error_code("E002")
+    })
+}
 
 impl ParsedProject {
     /// Parses a giving project, transforming it to a `ParsedProject`
@@ -47,23 +118,7 @@ impl ParsedProject {
     ) -> Result<Self, Diagnostic> {
         //TODO in parallel
         //Parse the source files
-        let mut units = vec![];
-
-        let sources = project
-            .get_sources()
-            .iter()
-            .map(|it| {
-                let source = ctxt.get(it.get_location_str()).expect("All sources should've been read");
-
-                let parse_func = match source.get_type() {
-                    source_code::SourceType::Text => parse_file,
-                    source_code::SourceType::Xml => cfc::xml_parser::parse_file,
-                    source_code::SourceType::Unknown => unreachable!(),
-                };
-                Ok(parse_func(source, LinkageType::Internal, ctxt.provider(), diagnostician))
-            })
-            .collect::<Result<Vec<_>, Diagnostic>>()?;
-        units.extend(sources);
+        let mut units = parse_sources(ctxt, project, diagnostician)?;
 
         //Parse the includes
         let includes = project
@@ -88,13 +143,24 @@ impl ParsedProject {
             .collect::<Result<Vec<_>, Diagnostic>>()?;
         units.extend(lib_includes);
 
-        Ok(ParsedProject(units))
+        let severity = units.iter().map(|(_, severity)| *severity).max().unwrap_or_default();
+        let mut units = units.into_iter().map(|(unit, _)| unit).collect::<Vec<_>>();
+        let diagnostics = instantiate_generic_pous(&mut units, ctxt.provider());
+        diagnostician.handle(&diagnostics);
+
+        Ok(ParsedProject { units, severity })
+    }
+
+    /// returns true if a syntax error was reported while parsing the project. The project can
+    /// still be indexed and validated to find further problems
+    pub fn has_syntax_errors(&self) -> bool {
+        self.severity == Severity::Error
     }
 
     /// Creates an index out of a pased project. The index could then be used to query datatypes
     pub fn index(self, id_provider: IdProvider) -> IndexedProject {
         let indexed_units = self
-            .0
+            .units
             .into_par_iter()
             .map(|mut unit| {
                 //Preprocess
@@ -127,17 +193,41 @@ impl ParsedProject {
 
 ///A project that has also been indexed
 /// Units inside an index project could be resolved and annotated
+#[derive(Clone)]
 pub struct IndexedProject {
     units: Vec<CompilationUnit>,
     index: Index,
 }
 
 impl IndexedProject {
+    /// Lays out all types the way the given target expects them, constants (e.g. `SIZEOF`) are
+    /// evaluated with this layout when the project is annotated
+    pub fn set_target(&mut self, target: &Target) -> Result<(), Diagnostic> {
+        self.index.set_type_layout(DataLayout::for_target(target)?);
+        Ok(())
+    }
+
+    /// Moves the members of all structs and POUs back to their position in the given layout of a
+    /// previous build. Returns a warning for every type whose layout cannot be kept.
+    /// This happens before the project is annotated, so that constants (e.g. `SIZEOF`) are evaluated
+    /// with the stable layout
+    pub fn stabilize_layout(&mut self, previous: &LayoutRecord) -> Vec<Diagnostic> {
+        // members are compared by their size and type, which may depend on constants (e.g. array bounds)
+        let functions = ConstFunctions::new(&self.units);
+        let (evaluated, _) = evaluate_constants_with_functions(self.index.clone(), &functions);
+        let (reorderings, diagnostics) = plc::online_change::get_stable_orders(&evaluated, previous);
+        for (type_name, order) in reorderings {
+            self.index.reorder_members(&type_name, &order);
+        }
+        diagnostics
+    }
+
     /// Creates annotations on the project in order to facilitate codegen and validation
     pub fn annotate(self, mut id_provider: IdProvider) -> AnnotatedProject {
         //Resolve constants
         //TODO: Not sure what we are currently doing with unresolvables
-        let (mut full_index, _unresolvables) = plc::resolver::const_evaluator::evaluate_constants(self.index);
+        let functions = ConstFunctions::new(&self.units);
+        let (mut full_index, _unresolvables) = evaluate_constants_with_functions(self.index, &functions);
         //Create and call the annotator
         let mut annotated_units = Vec::new();
         let mut all_annotations = AnnotationMapImpl::default();
@@ -158,6 +248,12 @@ impl IndexedProject {
         }
 
         full_index.import(std::mem::take(&mut all_annotations.new_index));
+        implement_generic_functions(
+            &mut full_index,
+            &mut all_annotations,
+            &mut annotated_units,
+            id_provider.clone(),
+        );
 
         let annotations = AstAnnotations::new(all_annotations, id_provider.next_id());
 
@@ -179,20 +275,34 @@ impl AnnotatedProject {
         ctxt: &GlobalContext,
         diagnostician: &mut Diagnostician,
     ) -> Result<(), Diagnostic> {
-        // perform global validation
-        let mut validator = Validator::new(ctxt);
-        validator.perform_global_validation(&self.index);
-        let diagnostics = validator.diagnostics();
-        let mut severity = diagnostician.handle(&diagnostics);
-
-        //Perform per unit validation
-        self.units.iter().for_each(|(unit, _, _)| {
-            // validate unit
-            validator.visit_unit(&self.annotations, &self.index, unit);
-            // log errors
+        Self::validate_all([self], ctxt, diagnostician)
+    }
+
+    /// Validates the projects annotated for the different targets, a problem found for several
+    /// targets is only reported once
+    pub fn validate_all<'a>(
+        projects: impl IntoIterator<Item = &'a AnnotatedProject>,
+        ctxt: &GlobalContext,
+        diagnostician: &mut Diagnostician,
+    ) -> Result<(), Diagnostic> {
+        let mut reported = HashSet::new();
+        let mut severity = Severity::default();
+        for project in projects {
+            // perform global validation
+            let mut validator = Validator::new(ctxt);
+            validator.perform_global_validation(&project.index);
             let diagnostics = validator.diagnostics();
-            severity = severity.max(diagnostician.handle(&diagnostics));
-        });
+            severity = severity.max(report_once(diagnostician, &mut reported, diagnostics));
+
+            //Perform per unit validation
+            project.units.iter().for_each(|(unit, _, _)| {
+                // validate unit
+                validator.visit_unit(&project.annotations, &project.index, unit);
+                // log errors
+                let diagnostics = validator.diagnostics();
+                severity = severity.max(report_once(diagnostician, &mut reported, diagnostics));
+            });
+        }
         if severity == Severity::Error {
             Err(Diagnostic::error("Compilation aborted due to critical errors"))
         } else {
@@ -201,14 +311,20 @@ impl AnnotatedProject {
     }
 
     pub fn codegen_to_string(&self, compile_options: &CompileOptions) -> Result<Vec<String>, Diagnostic> {
-        self.units
+        let mut result = self
+            .units
             .iter()
             .map(|(unit, dependencies, literals)| {
                 let context = CodegenContext::create();
                 self.generate_module(&context, compile_options, unit, dependencies, literals)
                     .map(|it| it.persist_to_string())
             })
-            .collect()
+            .collect::<Result<Vec<_>, _>>()?;
+        let context = CodegenContext::create();
+        for (_, module) in self.generate_project_modules(&context, compile_options)? {
+            result.push(module.persist_to_string());
+        }
+        Ok(result)
     }
 
     pub fn generate_single_module<'ctx>(
@@ -230,7 +346,45 @@ impl AnnotatedProject {
         else {
             return Ok(None);
         };
-        module.map(Some)
+        self.generate_project_modules(context, compile_options)?
+            .into_iter()
+            .try_fold(module?, |module, (_, it)| module.merge(it))
+            .map(Some)
+    }
+
+    /// Generates the modules that do not belong to a single compilation unit: the table of all
+    /// instances and the functions to look them up as well as the functions migrating the state
+    /// of a previous build, if requested by the compile options, and the table of all RETAIN and
+    /// PERSISTENT variables, if there are any
+    fn generate_project_modules<'ctx>(
+        &self,
+        context: &'ctx CodegenContext,
+        compile_options: &CompileOptions,
+    ) -> Result<Vec<(&'static str, GeneratedModule<'ctx>)>, Diagnostic> {
+        let code_generator = |name| {
+            plc::codegen::CodeGen::new(
+                context,
+                compile_options.root.as_deref(),
+                name,
+                compile_options.optimization,
+                DebugLevel::None,
+            )
+        };
+        let mut modules = vec![];
+        if compile_options.embed_symbols {
+            let module = code_generator(SYMBOL_TABLE_MODULE).generate_symbol_table(context, &self.index)?;
+            modules.push((SYMBOL_TABLE_MODULE, module));
+        }
+        if !self.index.get_retain_variables().is_empty() {
+            let module = code_generator(RETAIN_MODULE).generate_retain_table(context, &self.index)?;
+            modules.push((RETAIN_MODULE, module));
+        }
+        if let Some(previous) = &compile_options.previous_layout {
+            let module =
+                code_generator(MIGRATION_MODULE).generate_migration(context, &self.index, previous)?;
+            modules.push((MIGRATION_MODULE, module));
+        }
+        Ok(modules)
     }
 
     fn generate_module<'ctx>(
@@ -247,7 +401,8 @@ impl AnnotatedProject {
             &unit.file_name,
             compile_options.optimization,
             compile_options.debug_level,
-        );
+        )
+        .with_coverage(compile_options.coverage);
         //Create a types codegen, this contains all the type declarations
         //Associate the index type with LLVM types
         let llvm_index = code_generator.generate_llvm_index(
@@ -260,102 +415,122 @@ impl AnnotatedProject {
         code_generator.generate(context, unit, &self.annotations, &self.index, &llvm_index)
     }
 
-    pub fn codegen_single_module<'ctx>(
-        &'ctx self,
-        compile_options: CompileOptions,
-        targets: &'ctx [Target],
-    ) -> Result<Vec<GeneratedProject>, Diagnostic> {
+    pub fn codegen_single_module(
+        &self,
+        compile_options: &CompileOptions,
+        target: &Target,
+    ) -> Result<GeneratedProject, Diagnostic> {
         let compile_directory = compile_options.build_location.clone().unwrap_or_else(|| {
             let tempdir = tempfile::tempdir().unwrap();
             tempdir.into_path()
         });
-        ensure_compile_dirs(targets, &compile_directory)?;
+        ensure_compile_dirs(std::slice::from_ref(target), &compile_directory)?;
         let context = CodegenContext::create(); //Create a build location for the generated object files
-        let targets = if targets.is_empty() { &[Target::System] } else { targets };
-        let module = self.generate_single_module(&context, &compile_options)?.unwrap();
-        let mut result = vec![];
-        for target in targets {
-            let obj: Object = module
-                .persist(
-                    Some(&compile_directory),
-                    &compile_options.output,
-                    compile_options.output_format,
-                    target,
-                    compile_options.optimization,
-                )
-                .map(Into::into)?;
-
-            result.push(GeneratedProject { target: target.clone(), objects: vec![obj] });
-        }
-
-        Ok(result)
+        let module = self.generate_single_module(&context, compile_options)?.unwrap();
+        let stack_usage = if compile_options.stack_report {
+            module.collect_stack_usage(target, compile_options.optimization)?
+        } else {
+            vec![]
+        };
+        let obj: Object = module
+            .persist(
+                Some(&compile_directory),
+                &compile_options.output,
+                compile_options.output_format,
+                target,
+                compile_options.optimization,
+            )
+            .map(Into::into)?;
+
+        Ok(GeneratedProject { target: target.clone(), objects: vec![obj], stack_usage })
     }
 
-    pub fn codegen<'ctx>(
-        &'ctx self,
-        compile_options: CompileOptions,
-        targets: &'ctx [Target],
-    ) -> Result<Vec<GeneratedProject>, Diagnostic> {
+    /// Generates the objects of every unit for the given target, the project has to be annotated
+    /// for this target (see [`IndexedProject::set_target`])
+    pub fn codegen(
+        &self,
+        compile_options: &CompileOptions,
+        target: &Target,
+    ) -> Result<GeneratedProject, Diagnostic> {
         let compile_directory = compile_options.build_location.clone().unwrap_or_else(|| {
             let tempdir = tempfile::tempdir().unwrap();
             tempdir.into_path()
         });
-        ensure_compile_dirs(targets, &compile_directory)?;
-        let targets = if targets.is_empty() { &[Target::System] } else { targets };
-        let res = targets
+        ensure_compile_dirs(std::slice::from_ref(target), &compile_directory)?;
+        let (mut objects, stack_usage): (Vec<_>, Vec<_>) = self
+            .units
             .par_iter()
-            .map(|target| {
-                let objects = self
-                    .units
-                    .par_iter()
-                    .map(|(unit, dependencies, literals)| {
-                        let current_dir = env::current_dir()?;
-                        let current_dir = compile_options.root.as_deref().unwrap_or(&current_dir);
-                        let unit_location = PathBuf::from(&unit.file_name);
-                        let unit_location = fs::canonicalize(unit_location)?;
-                        let output_name = if unit_location.starts_with(current_dir) {
-                            unit_location.strip_prefix(current_dir).map_err(|it| {
-                                Diagnostic::error(format!(
-                                    "Could not strip prefix for {}",
-                                    current_dir.to_string_lossy()
-                                ))
-                                .with_internal_error(it.into())
-                            })?
-                        } else if unit_location.has_root() {
-                            let root = Path::new("/").canonicalize()?;
-                            unit_location.strip_prefix(root).expect("Name has root")
-                        } else {
-                            unit_location.as_path()
-                        };
-
-                        let output_name = match compile_options.output_format {
-                            FormatOption::IR => format!("{}.ll", output_name.to_string_lossy()),
-                            FormatOption::Bitcode => format!("{}.bc", output_name.to_string_lossy()),
-                            _ => format!("{}.o", output_name.to_string_lossy()),
-                        };
-
-                        let context = CodegenContext::create(); //Create a build location for the generated object files
-                        let module =
-                            self.generate_module(&context, &compile_options, unit, dependencies, literals)?;
-                        module
-                            .persist(
-                                Some(&compile_directory),
-                                &output_name,
-                                compile_options.output_format,
-                                target,
-                                compile_options.optimization,
-                            )
-                            .map(Into::into)
-                            // Not needed here but might be a good idea for consistency
-                            .map(|it: Object| it.with_target(target))
-                    })
-                    .collect::<Result<Vec<_>, Diagnostic>>()?;
+            .map(|(unit, dependencies, literals)| {
+                let current_dir = env::current_dir()?;
+                let current_dir = compile_options.root.as_deref().unwrap_or(&current_dir);
+                let unit_location = PathBuf::from(&unit.file_name);
+                let unit_location = fs::canonicalize(unit_location)?;
+                let output_name = if unit_location.starts_with(current_dir) {
+                    unit_location.strip_prefix(current_dir).map_err(|it| {
+                        Diagnostic::error(format!(
+                            "Could not strip prefix for {}",
+                            current_dir.to_string_lossy()
+                        ))
+                        .with_internal_error(it.into())
+                    })?
+                } else if unit_location.has_root() {
+                    let root = Path::new("/").canonicalize()?;
+                    unit_location.strip_prefix(root).expect("Name has root")
+                } else {
+                    unit_location.as_path()
+                };
 
-                Ok(GeneratedProject { target: target.clone(), objects })
-            })
-            .collect::<Result<Vec<_>, Diagnostic>>()?;
+                let output_name = match compile_options.output_format {
+                    FormatOption::IR => format!("{}.ll", output_name.to_string_lossy()),
+                    FormatOption::Bitcode => format!("{}.bc", output_name.to_string_lossy()),
+                    _ => format!("{}.o", output_name.to_string_lossy()),
+                };
 
-        Ok(res)
+                let context = CodegenContext::create(); //Create a build location for the generated object files
+                let module = self.generate_module(&context, compile_options, unit, dependencies, literals)?;
+                let stack_usage = if compile_options.stack_report {
+                    module.collect_stack_usage(target, compile_options.optimization)?
+                } else {
+                    vec![]
+                };
+                module
+                    .persist(
+                        Some(&compile_directory),
+                        &output_name,
+                        compile_options.output_format,
+                        target,
+                        compile_options.optimization,
+                    )
+                    .map(Into::into)
+                    // Not needed here but might be a good idea for consistency
+                    .map(|it: Object| (it.with_target(target), stack_usage))
+            })
+            .collect::<Result<Vec<_>, Diagnostic>>()?
+            .into_iter()
+            .unzip();
+        let mut stack_usage = stack_usage.into_iter().flatten().collect::<Vec<_>>();
+        let context = CodegenContext::create();
+        for (name, module) in self.generate_project_modules(&context, compile_options)? {
+            let output_name = match compile_options.output_format {
+                FormatOption::IR => format!("{name}.ll"),
+                FormatOption::Bitcode => format!("{name}.bc"),
+                _ => format!("{name}.o"),
+            };
+            if compile_options.stack_report {
+                stack_usage.extend(module.collect_stack_usage(target, compile_options.optimization)?);
+            }
+            let object: Object = module
+                .persist(
+                    Some(&compile_directory),
+                    &output_name,
+                    compile_options.output_format,
+                    target,
+                    compile_options.optimization,
+                )
+                .map(Into::into)?;
+            objects.push(object.with_target(target));
+        }
+        Ok(GeneratedProject { target: target.clone(), objects, stack_usage })
     }
 
     pub fn generate_hardware_information(
@@ -370,6 +545,84 @@ impl AnnotatedProject {
         })?;
         Ok(())
     }
+
+    /// Writes the stack usage of the given generated project next to the given location, one report per
+    /// target. Returns an error for every program exceeding the given stack limit
+    pub fn generate_stack_report(
+        &self,
+        project: &GeneratedProject,
+        location: Option<&Path>,
+        limit: Option<u64>,
+    ) -> Result<Vec<Diagnostic>, Diagnostic> {
+        let report = plc::stack_report::collect_stack_report(
+            &self.index,
+            &self.annotations,
+            project.stack_usage.clone(),
+        );
+        if let Some(location) = location {
+            let location = location
+                .parent()
+                .map(|it| project.target.append_to(it))
+                .unwrap_or_default()
+                .join(location.file_name().unwrap_or_default());
+            let generated_report = plc::stack_report::generate_stack_report(&report)?;
+            File::create(location).and_then(|mut it| it.write_all(generated_report.as_bytes())).map_err(
+                |it| Diagnostic::error(it.to_string()).with_internal_error(it.into()).with_error_code("E002"),
+            )?;
+        }
+        Ok(limit.map(|limit| plc::stack_report::validate_stack_limit(&report, limit)).unwrap_or_default())
+    }
+
+    pub fn generate_symbol_map(&self, ctxt: &GlobalContext, location: &str) -> Result<(), Diagnostic> {
+        let symbol_map = plc::symbol_map::collect_symbol_map(&self.index, ctxt)?;
+        let generated_map = plc::symbol_map::generate_symbol_map(&symbol_map)?;
+        File::create(location).and_then(|mut it| it.write_all(generated_map.as_bytes())).map_err(|it| {
+            Diagnostic::error(it.to_string()).with_internal_error(it.into()).with_error_code("E002")
+        })?;
+        Ok(())
+    }
+
+    /// Assigns the instances declared at wildcard addresses to their location in the process image
+    /// and grows the images to cover them. Returns the problems found in the mapping
+    pub fn apply_io_mapping(&mut self, mapping: &IoMapping) -> Result<Vec<Diagnostic>, Diagnostic> {
+        let (instances, diagnostics) = plc::hardware_binding::resolve_io_mapping(&self.index, mapping)?;
+        let mapped = instances
+            .iter()
+            .map(|it| {
+                let address = MappedAddress {
+                    root: it.root.get_name().to_string(),
+                    offset: it.offset,
+                    direction: it.direction,
+                    address: it.address,
+                };
+                (it.variable.get_qualified_name().to_string(), it.get_end(), address)
+            })
+            .collect::<Vec<_>>();
+        for (variable, end, address) in mapped {
+            self.index.reserve_process_image(address.direction, end);
+            self.index.register_mapped_address(&variable, address);
+        }
+        Ok(diagnostics)
+    }
+
+    /// Records the memory layout of the project to keep it stable in the next build
+    pub fn collect_layout_record(&self) -> Result<LayoutRecord, Diagnostic> {
+        plc::online_change::collect_layout_record(&self.index)
+    }
+}
+
+/// Reports the diagnostics that have not been reported yet, e.g. because they were already found
+/// for another target. Returns the highest severity of the reported diagnostics
+pub fn report_once(
+    diagnostician: &mut Diagnostician,
+    reported: &mut HashSet<(String, SourceLocation, &'static str)>,
+    diagnostics: Vec<Diagnostic>,
+) -> Severity {
+    let diagnostics = diagnostics
+        .into_iter()
+        .filter(|it| reported.insert((it.get_message().to_string(), it.get_location(), it.get_type())))
+        .collect::<Vec<_>>();
+    diagnostician.handle(&diagnostics)
 }
 
 /// Ensures the directores for the various targets have been created
@@ -389,9 +642,15 @@ fn ensure_compile_dirs(targets: &[Target], compile_directory: &Path) -> Result<(
 pub struct GeneratedProject {
     target: Target,
     objects: Vec<Object>,
+    /// the stack and code size of every generated function, if requested
+    stack_usage: Vec<FunctionStackUsage>,
 }
 
 impl GeneratedProject {
+    pub fn get_target(&self) -> &Target {
+        &self.target
+    }
+
     pub fn link(
         &self,
         objects: &[Object],
@@ -478,9 +737,24 @@ impl GeneratedProject {
                 if let Some(loc) = lib_location {
                     linker.add_lib_path(&loc.to_string_lossy());
                 }
+                if let Some(script) = &link_options.linker_script {
+                    linker.add_linker_script(&script.to_string_lossy());
+                }
+                if self.target.is_freestanding() {
+                    linker.set_freestanding();
+                }
 
                 match link_options.format {
                     FormatOption::Static => linker.build_exectuable(output_location).map_err(Into::into),
+                    FormatOption::Shared | FormatOption::PIC | FormatOption::NoPIC
+                        if self.target.is_freestanding() =>
+                    {
+                        Err(Diagnostic::error(format!(
+                            "Cannot build a shared library for the freestanding target '{}'",
+                            target_triple.as_str().to_string_lossy()
+                        ))
+                        .with_error_code("E077"))
+                    }
                     FormatOption::Shared | FormatOption::PIC | FormatOption::NoPIC => {
                         linker.build_shared_obj(output_location).map_err(Into::into)
                     }
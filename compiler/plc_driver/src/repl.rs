@@ -0,0 +1,524 @@
+//! An interactive session evaluating ST declarations and statements in the JIT
+//!
+//! Every entry is compiled together with the declarations of the previous entries. Declarations
+//! (POUs, types and global variables) are kept for the rest of the session, `VAR` blocks declare
+//! global variables of the session. Statements are wrapped into a function that is run once, if
+//! the last statement is an expression, its value is printed with the type it was resolved to.
+//! The values of all global variables and program instances are carried over from one entry to
+//! the next.
+
+use std::{
+    io::{self, BufRead, Write},
+    iter,
+    path::Path,
+};
+
+use ast::{
+    ast::{AstNode, AstStatement, LinkageType},
+    provider::IdProvider,
+};
+use plc::{
+    codegen::{CodegenContext, GeneratedModule},
+    datalayout,
+    index::{Index, VariableIndexEntry},
+    lexer::{self, Token},
+    resolver::AnnotationMap,
+    typesystem::{DataType, DataTypeInformation, StringEncoding},
+    DebugLevel, OptimizationLevel,
+};
+use plc_diagnostics::{diagnostician::Diagnostician, diagnostics::Diagnostic};
+use plc_index::GlobalContext;
+use project::project::Project;
+use source_code::{source_location::SourceLocationFactory, SourceCode, SourceContainer};
+
+use crate::{
+    pipelines::{AnnotatedProject, ParsedProject},
+    simulation::{self, ValueFormat},
+    CompileOptions,
+};
+
+/// the function the statements of an entry are wrapped into, its return value is ignored
+const ENTRY_FUNCTION: &str = "__repl_entry";
+/// the global variable the value of a trailing expression is assigned to
+const RESULT_VARIABLE: &str = "__repl_result";
+
+const PROMPT: &str = "st> ";
+const CONTINUATION_PROMPT: &str = "..> ";
+const HELP: &str = "\
+Enter declarations (PROGRAM, FUNCTION, FUNCTION_BLOCK, TYPE, VAR, VAR_GLOBAL, ...) or statements.
+An entry ends with the first line closing all of its blocks, the value of a trailing expression is printed.
+:reset  forgets all declarations and values
+:help   prints this help
+:quit   ends the session";
+
+/// The value of a global variable or program instance after an entry ran
+struct SavedValue {
+    name: String,
+    type_name: String,
+    bytes: Vec<u8>,
+}
+
+/// The value of an expression entry and where to assign it to a variable
+struct ExpressionResult {
+    /// the position of the expression in the wrapped entry
+    range: std::ops::Range<usize>,
+    /// the name to declare the type of the expression with
+    type_name: String,
+    format: ValueFormat,
+}
+
+pub struct Repl {
+    ctxt: GlobalContext,
+    includes: Vec<SourceCode>,
+    declarations: Vec<SourceCode>,
+    values: Vec<SavedValue>,
+    entries: usize,
+    diagnostician: Diagnostician,
+}
+
+impl Repl {
+    /// creates a session compiling every entry with the given includes, which must be part of the
+    /// given context
+    pub fn new(ctxt: GlobalContext, includes: Vec<SourceCode>, diagnostician: Diagnostician) -> Self {
+        Repl { ctxt, includes, declarations: vec![], values: vec![], entries: 0, diagnostician }
+    }
+
+    /// forgets all declarations and values of the session
+    pub fn reset(&mut self) {
+        for declaration in self.declarations.drain(..) {
+            if let Some(name) = declaration.path.as_deref().and_then(|it| it.to_str()) {
+                self.ctxt.remove(name);
+            }
+        }
+        self.values.clear();
+    }
+
+    /// reports the given diagnostic
+    pub fn report(&mut self, diagnostic: &Diagnostic) {
+        self.diagnostician.handle(std::slice::from_ref(diagnostic));
+    }
+
+    /// Evaluates the given entry, returns the value of a trailing expression formatted with its
+    /// type. An entry that fails to compile does not change the session.
+    pub fn evaluate(&mut self, entry: &str) -> Result<Option<String>, Diagnostic> {
+        let name = format!("<repl:{}>", self.entries + 1);
+        let result = self.evaluate_entry(&name, entry);
+        // only declarations are compiled again with the following entries
+        let is_declaration =
+            self.declarations.last().is_some_and(|it| it.path.as_deref() == Some(Path::new(&name)));
+        if !is_declaration {
+            self.ctxt.remove(&name);
+        }
+        result
+    }
+
+    fn evaluate_entry(&mut self, name: &str, entry: &str) -> Result<Option<String>, Diagnostic> {
+        let Some((source, prefix)) = wrap_entry(entry) else {
+            return Ok(None);
+        };
+        // diagnostics point into the entry instead of the code wrapped around it
+        self.diagnostician.register_wrapped_file(name.to_string(), entry.to_string(), prefix);
+        let is_statement = source.starts_with(&format!("FUNCTION {ENTRY_FUNCTION} :"));
+        let mut entry_source = SourceCode::new(source, name);
+        let mut project = self.compile(&entry_source)?;
+
+        let result = is_statement.then(|| find_result(&project, &entry_source)).flatten();
+        if let Some(result) = &result {
+            // assign the expression to a global variable, its value can be read after the run
+            let source = &entry_source.source;
+            let (start, end) = (result.range.start, result.range.end);
+            let source = format!(
+                "{}{RESULT_VARIABLE} := {}{}\nVAR_GLOBAL {RESULT_VARIABLE} : {}; END_VAR\n",
+                &source[..start],
+                &source[start..end],
+                &source[end..],
+                result.type_name
+            );
+            entry_source = SourceCode::new(source, name);
+            // the diagnostics of the entry were reported already
+            let diagnostician =
+                std::mem::replace(&mut self.diagnostician, Diagnostician::null_diagnostician());
+            let compiled = self.compile(&entry_source);
+            self.diagnostician = diagnostician;
+            project = compiled?;
+        }
+
+        let context = CodegenContext::create();
+        let options = CompileOptions {
+            optimization: OptimizationLevel::None,
+            debug_level: DebugLevel::None,
+            ..Default::default()
+        };
+        let Some(module) = project.generate_single_module(&context, &options)? else {
+            return Ok(None);
+        };
+        self.entries += 1;
+        if !is_statement {
+            self.declarations.push(entry_source);
+            return Ok(None);
+        }
+        simulation::ensure_symbols_resolve(&module)?;
+        self.restore_values(&project.index, &module);
+        module.run_no_param::<i32>(ENTRY_FUNCTION);
+        self.values = save_values(&project.index, &module);
+        Ok(result.and_then(|result| {
+            let address = module.get_global_address(RESULT_VARIABLE)?;
+            // SAFETY: the variable was declared with the type of the format
+            let value = unsafe { simulation::read_value(address, result.format) };
+            let value = match result.format {
+                ValueFormat::String(_) => format!("'{value}'"),
+                ValueFormat::WString(_) => format!("\"{value}\""),
+                _ => value,
+            };
+            Some(format!("{value} : {}", result.type_name))
+        }))
+    }
+
+    /// parses, indexes, annotates and validates the declarations of the session with the given entry
+    fn compile(&mut self, entry: &SourceCode) -> Result<AnnotatedProject, Diagnostic> {
+        self.ctxt.insert(entry, None)?;
+        let project = Project::new("repl".to_string())
+            .with_sources(self.declarations.iter().chain(iter::once(entry)).cloned())
+            .with_source_includes(self.includes.iter().cloned());
+        let parsed_project = ParsedProject::parse(&self.ctxt, &project, &mut self.diagnostician)?;
+        if parsed_project.has_syntax_errors() {
+            return Err(Diagnostic::error("The entry is not valid ST").with_error_code("E001"));
+        }
+        let project = parsed_project.index(self.ctxt.provider()).annotate(self.ctxt.provider());
+        project.validate(&self.ctxt, &mut self.diagnostician)?;
+        Ok(project)
+    }
+
+    /// writes the values of the previous entry into the variables that were not redeclared with
+    /// a different type
+    fn restore_values(&self, index: &Index, module: &GeneratedModule) {
+        for variable in get_saved_variables(index) {
+            let Some(saved) = self.values.iter().find(|it| it.name.eq_ignore_ascii_case(variable.get_name()))
+            else {
+                continue;
+            };
+            if saved.type_name != variable.get_type_name()
+                || get_size(index, variable) != Some(saved.bytes.len())
+            {
+                continue;
+            }
+            if let Some(address) = module.get_global_address(variable.get_name()) {
+                // SAFETY: the variable is as big as the saved value
+                unsafe {
+                    std::ptr::copy_nonoverlapping(saved.bytes.as_ptr(), address as *mut u8, saved.bytes.len())
+                };
+            }
+        }
+    }
+}
+
+/// returns the source to compile for the given entry together with the number of bytes generated in
+/// front of the entry, `None` if it is empty
+fn wrap_entry(entry: &str) -> Option<(String, usize)> {
+    let lexer = lexer::lex_with_ids(entry, IdProvider::default(), SourceLocationFactory::internal(entry));
+    let wrapped = match lexer.token {
+        Token::End => return None,
+        Token::KeywordProgram
+        | Token::KeywordFunction
+        | Token::KeywordFunctionBlock
+        | Token::KeywordClass
+        | Token::KeywordType
+        | Token::KeywordVarGlobal
+        | Token::KeywordActions
+        | Token::KeywordAction
+        | Token::PropertyExternal => (entry.to_string(), 0),
+        // the variables of the session are global variables
+        Token::KeywordVar => {
+            let range = lexer.range();
+            let keyword = "VAR_GLOBAL";
+            (
+                format!("{}{keyword}{}", &entry[..range.start], &entry[range.end..]),
+                keyword.len() - range.len(),
+            )
+        }
+        _ => {
+            // the empty VAR block keeps a leading `(` from being parsed as part of the return type
+            let header = format!("FUNCTION {ENTRY_FUNCTION} : DINT VAR END_VAR\n");
+            let separator = if entry.trim_end().ends_with(';') { "" } else { ";" };
+            (format!("{header}{}{separator}\nEND_FUNCTION\n", entry.trim_end()), header.len())
+        }
+    };
+    Some(wrapped)
+}
+
+/// returns the trailing expression of the given statement entry, if its value can be printed
+fn find_result(project: &AnnotatedProject, entry: &SourceCode) -> Option<ExpressionResult> {
+    let implementation = project
+        .units
+        .iter()
+        .filter(|(unit, ..)| unit.file_name == entry.get_location_str())
+        .flat_map(|(unit, ..)| &unit.implementations)
+        .find(|it| it.name == ENTRY_FUNCTION)?;
+    let statement = implementation.statements.last().filter(|it| is_expression(it))?;
+    let data_type = project.annotations.get_type(statement, &project.index)?;
+    let format = simulation::get_value_format(&project.index, data_type.get_name())?;
+    let range = statement.get_location().to_range()?;
+    Some(ExpressionResult { range, type_name: get_declared_type_name(&project.index, data_type)?, format })
+}
+
+fn is_expression(statement: &AstNode) -> bool {
+    matches!(
+        statement.get_stmt(),
+        AstStatement::Literal(..)
+            | AstStatement::CastStatement(..)
+            | AstStatement::ReferenceExpr(..)
+            | AstStatement::Identifier(..)
+            | AstStatement::DirectAccess(..)
+            | AstStatement::BinaryExpression(..)
+            | AstStatement::UnaryExpression(..)
+            | AstStatement::ParenExpression(..)
+            | AstStatement::CallStatement(..)
+    )
+}
+
+/// returns the name to declare a variable of the given type with, the types of string literals
+/// are internal
+fn get_declared_type_name(index: &Index, data_type: &DataType) -> Option<String> {
+    let name = match data_type.get_type_information() {
+        DataTypeInformation::String { size, encoding } => {
+            let length = size.as_int_value(index).ok()? - 1;
+            match encoding {
+                StringEncoding::Utf8 => format!("STRING[{length}]"),
+                StringEncoding::Utf16 => format!("WSTRING[{length}]"),
+            }
+        }
+        _ => data_type.get_name().to_string(),
+    };
+    Some(name)
+}
+
+/// returns the global variables and program instances whose values are carried over to the next
+/// entry, values holding pointers would point into the memory of the previous entry
+fn get_saved_variables(index: &Index) -> impl Iterator<Item = &VariableIndexEntry> {
+    index
+        .get_globals()
+        .values()
+        .chain(index.get_program_instances())
+        .filter(|it| !it.is_constant() && it.get_linkage() == LinkageType::Internal)
+        .filter(|it| it.get_name() != RESULT_VARIABLE)
+        .filter(|it| !contains_pointer(index, index.get_type_information_or_void(it.get_type_name())))
+}
+
+fn save_values(index: &Index, module: &GeneratedModule) -> Vec<SavedValue> {
+    get_saved_variables(index)
+        .filter_map(|variable| {
+            let size = get_size(index, variable)?;
+            let address = module.get_global_address(variable.get_name())?;
+            // SAFETY: the variable is `size` bytes big
+            let bytes = unsafe { std::slice::from_raw_parts(address as *const u8, size) }.to_vec();
+            Some(SavedValue {
+                name: variable.get_name().to_string(),
+                type_name: variable.get_type_name().to_string(),
+                bytes,
+            })
+        })
+        .collect()
+}
+
+fn get_size(index: &Index, variable: &VariableIndexEntry) -> Option<usize> {
+    let type_info = index.get_type_information_or_void(variable.get_type_name());
+    datalayout::get_allocated_size(type_info, index).map(|it| it as usize)
+}
+
+fn contains_pointer(index: &Index, type_info: &DataTypeInformation) -> bool {
+    match type_info {
+        DataTypeInformation::Pointer { .. } => true,
+        DataTypeInformation::Struct { members, .. } | DataTypeInformation::Union { members, .. } => members
+            .iter()
+            .any(|it| contains_pointer(index, index.get_type_information_or_void(it.get_type_name()))),
+        DataTypeInformation::Array { inner_type_name: referenced_type, .. }
+        | DataTypeInformation::Alias { referenced_type, .. }
+        | DataTypeInformation::SubRange { referenced_type, .. } => {
+            contains_pointer(index, index.get_type_information_or_void(referenced_type))
+        }
+        _ => false,
+    }
+}
+
+/// returns true if the given text closes every block it opens, e.g. `IF` ... `END_IF`
+pub fn is_complete(text: &str) -> bool {
+    let mut lexer = lexer::lex_with_ids(text, IdProvider::default(), SourceLocationFactory::internal(text));
+    let mut depth = 0;
+    while lexer.token != Token::End {
+        match lexer.token {
+            Token::KeywordProgram
+            | Token::KeywordClass
+            | Token::KeywordMethod
+            | Token::KeywordFunction
+            | Token::KeywordFunctionBlock
+            | Token::KeywordType
+            | Token::KeywordStruct
+            | Token::KeywordUnion
+            | Token::KeywordActions
+            | Token::KeywordAction
+            | Token::KeywordVar
+            | Token::KeywordVarInput
+            | Token::KeywordVarOutput
+            | Token::KeywordVarInOut
+            | Token::KeywordVarTemp
+            | Token::KeywordVarGlobal
+            | Token::KeywordIf
+            | Token::KeywordFor
+            | Token::KeywordWhile
+            | Token::KeywordRepeat
+            | Token::KeywordCase => depth += 1,
+            Token::KeywordEndProgram
+            | Token::KeywordEndClass
+            | Token::KeywordEndMethod
+            | Token::KeywordEndFunction
+            | Token::KeywordEndFunctionBlock
+            | Token::KeywordEndType
+            | Token::KeywordEndStruct
+            | Token::KeywordEndUnion
+            | Token::KeywordEndActions
+            | Token::KeywordEndAction
+            | Token::KeywordEndVar
+            | Token::KeywordEndIf
+            | Token::KeywordEndFor
+            | Token::KeywordEndWhile
+            | Token::KeywordEndRepeat
+            | Token::KeywordEndCase => depth -= 1,
+            _ => {}
+        }
+        lexer.advance();
+    }
+    depth <= 0
+}
+
+/// Reads entries from the given input until it ends or the session is quit and writes the
+/// results to the given output. An entry ends with the first line closing all of its blocks.
+pub fn run(repl: &mut Repl, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut lines = input.lines();
+    let mut entry = String::new();
+    loop {
+        write!(output, "{}", if entry.is_empty() { PROMPT } else { CONTINUATION_PROMPT })?;
+        output.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        if entry.is_empty() {
+            match line.trim() {
+                ":quit" | ":q" => break,
+                ":reset" => {
+                    repl.reset();
+                    continue;
+                }
+                ":help" => {
+                    writeln!(output, "{HELP}")?;
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        entry.push_str(&line);
+        entry.push('\n');
+        if !is_complete(&entry) {
+            continue;
+        }
+        match repl.evaluate(&entry) {
+            Ok(Some(result)) => writeln!(output, "{result}")?,
+            Ok(None) => {}
+            Err(diagnostic) => repl.report(&diagnostic),
+        }
+        entry.clear();
+    }
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use plc_diagnostics::{diagnostician::Diagnostician, reporter::DiagnosticReporter};
+    use plc_index::GlobalContext;
+
+    use super::{is_complete, Repl, ENTRY_FUNCTION};
+
+    fn evaluate(repl: &mut Repl, entry: &str) -> Option<String> {
+        repl.evaluate(entry).unwrap()
+    }
+
+    #[test]
+    fn expressions_are_printed_with_their_type() {
+        let mut repl = Repl::new(GlobalContext::new(), vec![], Diagnostician::null_diagnostician());
+        assert_eq!(evaluate(&mut repl, "1 + 2"), Some("3 : DINT".to_string()));
+        assert_eq!(evaluate(&mut repl, "REAL#1.5 * 2;"), Some("3 : REAL".to_string()));
+        assert_eq!(evaluate(&mut repl, "5 > 3"), Some("TRUE : BOOL".to_string()));
+        assert_eq!(evaluate(&mut repl, "'hello'"), Some("'hello' : STRING[5]".to_string()));
+        assert_eq!(evaluate(&mut repl, "\"wide\""), Some("\"wide\" : WSTRING[4]".to_string()));
+    }
+
+    #[test]
+    fn declarations_and_values_are_kept_for_the_session() {
+        let mut repl = Repl::new(GlobalContext::new(), vec![], Diagnostician::null_diagnostician());
+        assert_eq!(evaluate(&mut repl, "VAR x : INT := 5; END_VAR"), None);
+        assert_eq!(
+            evaluate(
+                &mut repl,
+                "FUNCTION double : INT VAR_INPUT i : INT; END_VAR double := i * 2; END_FUNCTION"
+            ),
+            None
+        );
+        assert_eq!(evaluate(&mut repl, "x := double(x);"), None);
+        assert_eq!(evaluate(&mut repl, "x"), Some("10 : INT".to_string()));
+        assert_eq!(evaluate(&mut repl, "(x + 1) * 2"), Some("22 : DINT".to_string()));
+
+        // program instances keep their state as well
+        evaluate(
+            &mut repl,
+            "PROGRAM counter VAR_OUTPUT count : DINT; END_VAR count := count + 1; END_PROGRAM",
+        );
+        evaluate(&mut repl, "counter();");
+        assert_eq!(evaluate(&mut repl, "counter(); counter.count"), Some("2 : DINT".to_string()));
+
+        // invalid entries do not change the session
+        assert!(repl.evaluate("x := undefined;").is_err());
+        assert!(repl.evaluate("VAR x : BOOL; END_VAR").is_err());
+        assert_eq!(evaluate(&mut repl, "x"), Some("10 : INT".to_string()));
+
+        repl.reset();
+        assert!(repl.evaluate("x").is_err());
+    }
+
+    #[test]
+    fn diagnostics_point_into_the_entry() {
+        let mut repl = Repl::new(GlobalContext::new(), vec![], Diagnostician::buffered());
+        assert!(repl.evaluate("x := undefined;").is_err());
+        assert!(repl.evaluate("VAR x : INT := 1 +; END_VAR").is_err());
+        assert!(repl.evaluate("  y := 1 +;").is_err());
+
+        let output = repl.diagnostician.buffer().unwrap();
+        assert!(output.contains("┌─ <repl:1>:1:1"), "{output}");
+        assert!(output.contains("┌─ <repl:1>:1:6"), "{output}");
+        assert!(output.contains("┌─ <repl:1>:1:19"), "{output}");
+        assert!(output.contains("┌─ <repl:1>:1:11"), "{output}");
+        assert!(!output.contains(ENTRY_FUNCTION), "{output}");
+    }
+
+    #[test]
+    fn only_declarations_are_kept_in_the_context() {
+        let mut repl = Repl::new(GlobalContext::new(), vec![], Diagnostician::null_diagnostician());
+        assert_eq!(evaluate(&mut repl, "VAR x : INT := 5; END_VAR"), None);
+        assert!(repl.evaluate("x := undefined;").is_err());
+        assert_eq!(evaluate(&mut repl, "x + 1"), Some("6 : DINT".to_string()));
+        assert!(repl.ctxt.get("<repl:1>").is_some());
+        assert!(repl.ctxt.get("<repl:2>").is_none());
+
+        repl.reset();
+        assert!(repl.ctxt.get("<repl:1>").is_none());
+    }
+
+    #[test]
+    fn entries_are_complete_once_every_block_is_closed() {
+        assert!(is_complete("x := 1;"));
+        assert!(is_complete("1 + 2"));
+        assert!(!is_complete("IF x THEN\n  y := 1;\n"));
+        assert!(is_complete("IF x THEN\n  y := 1;\nEND_IF"));
+        assert!(!is_complete("TYPE Point : STRUCT\n  x : INT;\nEND_STRUCT\n"));
+        assert!(is_complete("TYPE Point : STRUCT\n  x : INT;\nEND_STRUCT\nEND_TYPE"));
+        assert!(!is_complete("FUNCTION_BLOCK fb\nVAR_INPUT a : INT; END_VAR\n"));
+    }
+}
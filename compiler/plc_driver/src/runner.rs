@@ -1,7 +1,10 @@
 use crate::{pipelines::ParsedProject, CompileOptions};
 
 use plc::codegen::{CodegenContext, GeneratedModule};
-use plc_diagnostics::diagnostician::Diagnostician;
+use plc_diagnostics::{
+    diagnostician::Diagnostician,
+    diagnostics::{Diagnostic, Severity},
+};
 use plc_index::GlobalContext;
 use project::project::Project;
 use source_code::Compilable;
@@ -24,20 +27,42 @@ impl Default for MainType {
 /// An implementation is also provided for `Vec<SourceContainer>`
 ///
 pub fn compile<T: Compilable>(context: &CodegenContext, source: T) -> GeneratedModule<'_> {
-    let source = source.containers();
-    let project = Project::new("TestProject".to_string()).with_sources(source);
-    let ctxt = GlobalContext::new().with_source(project.get_sources(), None).unwrap();
-    let mut diagnostician = Diagnostician::null_diagnostician();
-    let parsed_project = ParsedProject::parse(&ctxt, &project, &mut diagnostician).unwrap();
-    let indexed_project = parsed_project.index(ctxt.provider());
-    let annotated_project = indexed_project.annotate(ctxt.provider());
     let compile_options = CompileOptions {
         optimization: plc::OptimizationLevel::None,
         debug_level: plc::DebugLevel::None,
         ..Default::default()
     };
+    compile_with_options(context, source, compile_options).unwrap()
+}
+
+///
+/// Compiles the given sources with the given options
+/// Returns the first error if the options cannot be applied, e.g. an invalid I/O mapping
+///
+pub fn compile_with_options<T: Compilable>(
+    context: &CodegenContext,
+    source: T,
+    compile_options: CompileOptions,
+) -> Result<GeneratedModule<'_>, Diagnostic> {
+    let source = source.containers();
+    let project = Project::new("TestProject".to_string()).with_sources(source);
+    let ctxt = GlobalContext::new().with_source(project.get_sources(), None)?;
+    let mut diagnostician = Diagnostician::null_diagnostician();
+    let parsed_project = ParsedProject::parse(&ctxt, &project, &mut diagnostician)?;
+    let mut indexed_project = parsed_project.index(ctxt.provider());
+    if let Some(previous_layout) = &compile_options.previous_layout {
+        indexed_project.stabilize_layout(previous_layout);
+    }
+    let mut annotated_project = indexed_project.annotate(ctxt.provider());
+    if let Some(io_mapping) = &compile_options.io_mapping {
+        let diagnostics = annotated_project.apply_io_mapping(io_mapping)?;
+        if let Some(error) = diagnostics.into_iter().find(|it| it.get_severity() == Severity::Error) {
+            return Err(error);
+        }
+    }
 
-    annotated_project.generate_single_module(context, &compile_options).unwrap().unwrap()
+    let module = annotated_project.generate_single_module(context, &compile_options)?;
+    Ok(module.expect("the project contains at least one unit"))
 }
 
 ///
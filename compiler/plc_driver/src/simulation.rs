@@ -0,0 +1,319 @@
+//! Runs a program of the project in the JIT, a quick simulation of a PLC on the desktop
+//!
+//! The program is called once per cycle, either for a given number of cycles or until the user
+//! interrupts the simulation (Ctrl-C). The watched variables are printed after every cycle or
+//! logged to a CSV file. The traced variables are recorded to a Value Change Dump (see [`vcd`]).
+
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use plc::{
+    codegen::{CodegenContext, GeneratedModule},
+    index::{Index, PouIndexEntry},
+    symbol_map,
+    typesystem::{DataTypeInformation, StringEncoding},
+};
+use plc_diagnostics::diagnostics::Diagnostic;
+
+use crate::{pipelines::AnnotatedProject, CompileOptions};
+
+mod vcd;
+
+/// How to run the program, see the `run` subcommand
+#[derive(Debug, PartialEq)]
+pub struct RunOptions {
+    /// the PROGRAM to call every cycle
+    pub program: String,
+    /// the number of cycles to run, runs until interrupted if `None`
+    pub cycles: Option<u64>,
+    /// the time from the start of one cycle to the next, the cycles run back to back if `None`
+    pub cycle_time: Option<Duration>,
+    /// the variables to report after every cycle
+    pub watch: Vec<String>,
+    /// the CSV file to log the watched variables to instead of printing them
+    pub csv: Option<PathBuf>,
+    /// the VCD file to record the traced variables to
+    pub vcd: Option<PathBuf>,
+    /// the variables and instances to record, the whole program if empty
+    pub trace: Vec<String>,
+}
+
+/// How the value of a watched variable is read from memory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ValueFormat {
+    Bool,
+    Signed(u32),
+    Unsigned(u32),
+    Float(u32),
+    String(u32),
+    WString(u32),
+}
+
+/// A variable read after every cycle
+#[derive(Debug, PartialEq)]
+struct WatchedVariable {
+    name: String,
+    address: usize,
+    format: ValueFormat,
+}
+
+/// set by the SIGINT handler to stop the simulation after the current cycle
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn interrupt(_: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Generates the project into a single module and runs the given program in the JIT. The given
+/// libraries are loaded into the process first, so the program can call the functions they export
+/// (e.g. the standard library).
+pub fn run(
+    project: &AnnotatedProject,
+    compile_options: &CompileOptions,
+    libraries: &[PathBuf],
+    options: &RunOptions,
+) -> Result<(), Diagnostic> {
+    for library in libraries {
+        GeneratedModule::load_library(library)?;
+    }
+    let context = CodegenContext::create();
+    let Some(module) = project.generate_single_module(&context, compile_options)? else {
+        return Err(Diagnostic::error("There is nothing to run").with_error_code("E104"));
+    };
+    ensure_symbols_resolve(&module)?;
+    let index = &project.index;
+    let Some(PouIndexEntry::Program { name: program, instance_variable, .. }) =
+        index.find_pou(&options.program)
+    else {
+        return Err(
+            Diagnostic::error(format!("`{}` is not a PROGRAM", options.program)).with_error_code("E104")
+        );
+    };
+    let instance = get_address(&module, instance_variable.get_name())?;
+    let watched = options
+        .watch
+        .iter()
+        .map(|name| resolve_watched_variable(index, &module, program, name))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut trace = match &options.vcd {
+        Some(path) => {
+            let paths =
+                if options.trace.is_empty() { vec![program.to_string()] } else { options.trace.clone() };
+            let traced = vcd::resolve_traced_variables(index, &module, program, &paths)?;
+            Some(vcd::VcdWriter::new(BufWriter::new(create_file(path)?), traced, options.cycle_time)?)
+        }
+        None => None,
+    };
+    let mut writer: Box<dyn Write> = match &options.csv {
+        Some(path) => {
+            let mut writer = BufWriter::new(create_file(path)?);
+            let header = watched.iter().map(|it| escape_csv(&it.name)).collect::<Vec<_>>();
+            writeln!(writer, "cycle,{}", header.join(","))?;
+            Box::new(writer)
+        }
+        None => Box::new(io::stdout()),
+    };
+
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    let previous_handler = unsafe { libc::signal(libc::SIGINT, interrupt as libc::sighandler_t) };
+    let mut cycle = 0;
+    if let Some(trace) = &mut trace {
+        trace.sample(cycle)?;
+    }
+    while options.cycles.map_or(true, |cycles| cycle < cycles) && !INTERRUPTED.load(Ordering::SeqCst) {
+        let start = Instant::now();
+        // SAFETY: the instance is the global the program was generated for
+        module.run::<u8, ()>(program, unsafe { &mut *(instance as *mut u8) });
+        cycle += 1;
+        if let Some(trace) = &mut trace {
+            trace.sample(cycle)?;
+        }
+
+        let values = watched.iter().map(|it| unsafe { read_value(it.address, it.format) });
+        if options.csv.is_some() {
+            writeln!(writer, "{cycle},{}", values.map(|it| escape_csv(&it)).collect::<Vec<_>>().join(","))?;
+        } else if !watched.is_empty() {
+            let values = watched.iter().zip(values).map(|(it, value)| format!("{} = {value}", it.name));
+            writeln!(writer, "cycle {cycle}: {}", values.collect::<Vec<_>>().join(", "))?;
+        }
+        if let Some(remaining) = options.cycle_time.and_then(|it| it.checked_sub(start.elapsed())) {
+            thread::sleep(remaining);
+        }
+    }
+    unsafe { libc::signal(libc::SIGINT, previous_handler) };
+    writer.flush()?;
+    if let Some(trace) = trace {
+        trace.finish()?;
+    }
+    if compile_options.coverage {
+        module.dump_coverage();
+    }
+    Ok(())
+}
+
+/// Finds the shared library with the given name (e.g. `iec61131std`) in the given directories,
+/// a library that cannot be found is left to the dynamic loader's search path
+pub fn find_library(name: &str, directories: &[PathBuf]) -> PathBuf {
+    let file_name = format!("{DLL_PREFIX}{name}{DLL_SUFFIX}");
+    directories
+        .iter()
+        .map(|it| it.join(&file_name))
+        .find(|it| it.is_file())
+        .unwrap_or_else(|| PathBuf::from(file_name))
+}
+
+/// Reports the external functions and variables that no loaded library provides, calling them
+/// would crash the process
+pub(crate) fn ensure_symbols_resolve(module: &GeneratedModule) -> Result<(), Diagnostic> {
+    let unresolved = module.find_unresolved_symbols();
+    if unresolved.is_empty() {
+        Ok(())
+    } else {
+        Err(Diagnostic::error(format!(
+            "No loaded library provides {}, pass the library with `-l`",
+            unresolved.iter().map(|it| format!("`{it}`")).collect::<Vec<_>>().join(", ")
+        ))
+        .with_error_code("E104"))
+    }
+}
+
+fn create_file(path: &Path) -> Result<File, Diagnostic> {
+    File::create(path).map_err(|err| {
+        Diagnostic::error(format!("Cannot write {}: {err}", path.to_string_lossy())).with_error_code("E104")
+    })
+}
+
+fn get_address(module: &GeneratedModule, name: &str) -> Result<usize, Diagnostic> {
+    module.get_global_address(name).ok_or_else(|| {
+        Diagnostic::error(format!("Cannot find the address of `{name}`")).with_error_code("E104")
+    })
+}
+
+/// returns the given name qualified with the program, unless it starts with a global variable or
+/// a program
+fn qualify_name(index: &Index, program: &str, name: &str) -> String {
+    let root = name.split(['.', '[']).next().unwrap_or_default().trim();
+    let is_qualified = index.find_global_variable(root).is_some()
+        || matches!(index.find_pou(root), Some(PouIndexEntry::Program { .. }));
+    if is_qualified {
+        name.to_string()
+    } else {
+        format!("{program}.{name}")
+    }
+}
+
+/// resolves the watched variable with the given name, a name that is neither a global variable
+/// nor qualified with a program is looked up in the running program
+fn resolve_watched_variable(
+    index: &Index,
+    module: &GeneratedModule,
+    program: &str,
+    name: &str,
+) -> Result<WatchedVariable, Diagnostic> {
+    let location = symbol_map::find_instance(index, &qualify_name(index, program, name))
+        .map_err(|err| Diagnostic::error(format!("Cannot watch `{name}`: {err}")).with_error_code("E104"))?;
+    let format = get_value_format(index, location.type_name).ok_or_else(|| {
+        Diagnostic::error(format!(
+            "Cannot watch `{name}`: values of type `{}` cannot be printed",
+            location.type_name
+        ))
+        .with_error_code("E104")
+    })?;
+    let address = get_address(module, location.root.get_name())? + location.offset as usize;
+    Ok(WatchedVariable { name: name.to_string(), address, format })
+}
+
+/// returns how values of the given type are read, `None` if they cannot be printed
+pub(crate) fn get_value_format(index: &Index, type_name: &str) -> Option<ValueFormat> {
+    let data_type = index.get_intrinsic_type_by_name(type_name);
+    let size = data_type.get_type_information().get_size(index).value();
+    let format = match data_type.get_type_information() {
+        it if it.is_bool() => ValueFormat::Bool,
+        DataTypeInformation::Integer { signed: true, .. } => ValueFormat::Signed(size),
+        DataTypeInformation::Integer { signed: false, .. } => ValueFormat::Unsigned(size),
+        DataTypeInformation::Float { .. } => ValueFormat::Float(size),
+        DataTypeInformation::String { size, encoding } => {
+            let length = size.as_int_value(index).ok()? as u32;
+            match encoding {
+                StringEncoding::Utf8 => ValueFormat::String(length),
+                StringEncoding::Utf16 => ValueFormat::WString(length),
+            }
+        }
+        _ => return None,
+    };
+    Some(format)
+}
+
+/// reads the value at the given address
+///
+/// # Safety
+/// the address must point to a live value of the given format
+pub(crate) unsafe fn read_value(address: usize, format: ValueFormat) -> String {
+    let pointer = address as *const u8;
+    match format {
+        ValueFormat::Bool => if *pointer != 0 { "TRUE" } else { "FALSE" }.to_string(),
+        ValueFormat::Signed(1) => (*(pointer as *const i8)).to_string(),
+        ValueFormat::Signed(2) => (pointer as *const i16).read_unaligned().to_string(),
+        ValueFormat::Signed(4) => (pointer as *const i32).read_unaligned().to_string(),
+        ValueFormat::Signed(_) => (pointer as *const i64).read_unaligned().to_string(),
+        ValueFormat::Unsigned(1) => (*pointer).to_string(),
+        ValueFormat::Unsigned(2) => (pointer as *const u16).read_unaligned().to_string(),
+        ValueFormat::Unsigned(4) => (pointer as *const u32).read_unaligned().to_string(),
+        ValueFormat::Unsigned(_) => (pointer as *const u64).read_unaligned().to_string(),
+        ValueFormat::Float(4) => (pointer as *const f32).read_unaligned().to_string(),
+        ValueFormat::Float(_) => (pointer as *const f64).read_unaligned().to_string(),
+        ValueFormat::String(length) => {
+            let bytes = std::slice::from_raw_parts(pointer, length as usize);
+            let end = bytes.iter().position(|it| *it == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        }
+        ValueFormat::WString(length) => {
+            let characters = (0..length as usize).map(|it| (pointer as *const u16).add(it).read_unaligned());
+            String::from_utf16_lossy(&characters.take_while(|it| *it != 0).collect::<Vec<_>>())
+        }
+    }
+}
+
+/// quotes the given field if it contains a separator, a quote or a line break
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{escape_csv, find_library};
+
+    #[test]
+    fn csv_fields_are_quoted_when_necessary() {
+        assert_eq!(escape_csv("main.x"), "main.x");
+        assert_eq!(escape_csv("main.a[1,2]"), "\"main.a[1,2]\"");
+        assert_eq!(escape_csv("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn libraries_are_found_in_the_library_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let name = format!("{}mylib{}", std::env::consts::DLL_PREFIX, std::env::consts::DLL_SUFFIX);
+        std::fs::write(dir.path().join(&name), "").unwrap();
+        let directories = vec![Path::new("does/not/exist").to_path_buf(), dir.path().to_path_buf()];
+        assert_eq!(find_library("mylib", &directories), dir.path().join(&name));
+        assert_eq!(
+            find_library("otherlib", &directories).to_string_lossy(),
+            name.replace("mylib", "otherlib")
+        );
+    }
+}
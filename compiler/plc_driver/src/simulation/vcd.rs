@@ -0,0 +1,294 @@
+//! Records variables of the running program to a Value Change Dump (IEEE 1364), which can be
+//! viewed in waveform viewers like GTKWave
+//!
+//! The variables are sampled after every cycle, only the values that changed since the previous
+//! cycle are written. Every segment of a variable's name but the last one becomes a scope, e.g.
+//! `main.motor.speed` is `speed` in the scope `motor` inside the scope `main`.
+//! - `BOOL` is a `wire` of 1 bit
+//! - signed integers are `integer`s, unsigned integers (including bit strings and enums of an
+//!   unsigned type) are `reg`s of their size
+//! - `REAL` and `LREAL` are `real`s
+//!
+//! The time of a cycle is its number times the cycle time (1ms if no cycle time is given).
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    time::Duration,
+};
+
+use plc::{codegen::GeneratedModule, index::Index, symbol_map};
+use plc_diagnostics::diagnostics::Diagnostic;
+
+use super::{get_address, get_value_format, qualify_name, ValueFormat, WatchedVariable};
+
+/// Resolves the variables to record. A path naming an instance records all of its variables of
+/// elementary types, as listed by [`symbol_map::collect_instance_layouts`]. The elements of arrays
+/// of elementary types have to be named explicitly.
+pub(super) fn resolve_traced_variables(
+    index: &Index,
+    module: &GeneratedModule,
+    program: &str,
+    paths: &[String],
+) -> Result<Vec<WatchedVariable>, Diagnostic> {
+    let layouts = symbol_map::collect_instance_layouts(index)?;
+    let mut names = HashSet::new();
+    let mut variables = vec![];
+    for path in paths {
+        let qualified_name = qualify_name(index, program, path);
+        let location = symbol_map::find_instance(index, &qualified_name).map_err(|err| {
+            Diagnostic::error(format!("Cannot trace `{path}`: {err}")).with_error_code("E104")
+        })?;
+        let mut found = vec![];
+        if let Some(format) = get_value_format(index, location.type_name).filter(is_traceable) {
+            let address = get_address(module, location.root.get_name())? + location.offset as usize;
+            found.push(WatchedVariable { name: qualified_name, address, format });
+        } else {
+            let prefix = qualified_name.to_lowercase();
+            for layout in layouts.iter().filter(|it| {
+                let name = it.name.to_lowercase();
+                name.strip_prefix(&prefix).is_some_and(|rest| rest.starts_with(['.', '[']))
+            }) {
+                let Some(format) =
+                    get_value_format(index, layout.variable.get_type_name()).filter(is_traceable)
+                else {
+                    continue;
+                };
+                let address = get_address(module, layout.root.get_name())? + layout.offset as usize;
+                found.push(WatchedVariable { name: layout.name.clone(), address, format });
+            }
+        }
+        if found.is_empty() {
+            return Err(Diagnostic::error(format!(
+                "Cannot trace `{path}`: it has no variables of type BOOL, an integer, REAL or an enum"
+            ))
+            .with_error_code("E104"));
+        }
+        variables.extend(found.into_iter().filter(|it| names.insert(it.name.to_lowercase())));
+    }
+    Ok(variables)
+}
+
+fn is_traceable(format: &ValueFormat) -> bool {
+    !matches!(format, ValueFormat::String(_) | ValueFormat::WString(_))
+}
+
+/// Writes the samples of the traced variables in the VCD format
+pub(super) struct VcdWriter<W: Write> {
+    writer: W,
+    /// the variables with their identifier code and the last written value
+    variables: Vec<(WatchedVariable, String, Option<String>)>,
+    /// the time of a cycle in milliseconds
+    cycle_time: u128,
+}
+
+impl<W: Write> VcdWriter<W> {
+    /// writes the declarations of the given variables
+    pub fn new(
+        mut writer: W,
+        mut variables: Vec<WatchedVariable>,
+        cycle_time: Option<Duration>,
+    ) -> io::Result<Self> {
+        writeln!(writer, "$version RuSTy plc run $end")?;
+        writeln!(writer, "$timescale 1ms $end")?;
+        // the variables of a scope are declared together
+        variables.sort_by_key(|it| get_scopes(&it.name).to_lowercase());
+        let mut open_scopes: Vec<&str> = vec![];
+        for (position, variable) in variables.iter().enumerate() {
+            let scopes = get_scopes(&variable.name);
+            let segments = if scopes.is_empty() { vec![] } else { scopes.split('.').collect::<Vec<_>>() };
+            let common =
+                open_scopes.iter().zip(&segments).take_while(|(a, b)| a.eq_ignore_ascii_case(b)).count();
+            for _ in common..open_scopes.len() {
+                writeln!(writer, "$upscope $end")?;
+            }
+            for scope in &segments[common..] {
+                writeln!(writer, "$scope module {scope} $end")?;
+            }
+            open_scopes = segments;
+            let (kind, size) = match variable.format {
+                ValueFormat::Bool => ("wire", 1),
+                ValueFormat::Signed(bytes) => ("integer", bytes * 8),
+                ValueFormat::Unsigned(bytes) => ("reg", bytes * 8),
+                ValueFormat::Float(_) => ("real", 64),
+                ValueFormat::String(_) | ValueFormat::WString(_) => unreachable!("strings are not traced"),
+            };
+            let name = &variable.name[variable.name.len() - get_local_name(&variable.name).len()..];
+            writeln!(writer, "$var {kind} {size} {} {name} $end", get_identifier(position))?;
+        }
+        for _ in &open_scopes {
+            writeln!(writer, "$upscope $end")?;
+        }
+        writeln!(writer, "$enddefinitions $end")?;
+
+        let variables = variables
+            .into_iter()
+            .enumerate()
+            .map(|(position, it)| (it, get_identifier(position), None))
+            .collect();
+        let cycle_time = cycle_time.map_or(1, |it| it.as_millis());
+        Ok(VcdWriter { writer, variables, cycle_time })
+    }
+
+    /// writes the values of the given cycle that changed since the previous one
+    pub fn sample(&mut self, cycle: u64) -> io::Result<()> {
+        let mut changes = vec![];
+        for (variable, identifier, last) in &mut self.variables {
+            // SAFETY: the address belongs to a variable of the given format
+            let value = unsafe { read_sample(variable.address, variable.format) };
+            if last.as_ref() != Some(&value) {
+                let separator = if variable.format == ValueFormat::Bool { "" } else { " " };
+                changes.push(format!("{value}{separator}{identifier}"));
+                *last = Some(value);
+            }
+        }
+        if cycle == 0 {
+            writeln!(self.writer, "#0")?;
+            writeln!(self.writer, "$dumpvars")?;
+            for change in changes {
+                writeln!(self.writer, "{change}")?;
+            }
+            writeln!(self.writer, "$end")?;
+        } else if !changes.is_empty() {
+            writeln!(self.writer, "#{}", cycle as u128 * self.cycle_time)?;
+            for change in changes {
+                writeln!(self.writer, "{change}")?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// returns the scopes of the given name, e.g. `main.motors[1]` for `main.motors[1].speed`
+fn get_scopes(name: &str) -> &str {
+    name[..name.len() - get_local_name(name).len()].trim_end_matches('.')
+}
+
+/// returns the last segment of the given name, e.g. `speed` for `main.motors[1].speed`
+fn get_local_name(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// returns the identifier code of the variable at the given position, made of the printable
+/// ASCII characters
+fn get_identifier(mut position: usize) -> String {
+    const FIRST: u8 = b'!';
+    const COUNT: usize = (b'~' - FIRST + 1) as usize;
+    let mut identifier = String::new();
+    loop {
+        identifier.push((FIRST + (position % COUNT) as u8) as char);
+        position /= COUNT;
+        if position == 0 {
+            return identifier;
+        }
+        position -= 1;
+    }
+}
+
+/// reads the value at the given address in the VCD notation
+///
+/// # Safety
+/// the address must point to a live value of the given format
+unsafe fn read_sample(address: usize, format: ValueFormat) -> String {
+    let pointer = address as *const u8;
+    let bits = match format {
+        ValueFormat::Bool => return if *pointer != 0 { "1" } else { "0" }.to_string(),
+        ValueFormat::Float(4) => return format!("r{}", (pointer as *const f32).read_unaligned()),
+        ValueFormat::Float(_) => return format!("r{}", (pointer as *const f64).read_unaligned()),
+        ValueFormat::Signed(1) | ValueFormat::Unsigned(1) => *pointer as u64,
+        ValueFormat::Signed(2) | ValueFormat::Unsigned(2) => (pointer as *const u16).read_unaligned() as u64,
+        ValueFormat::Signed(4) | ValueFormat::Unsigned(4) => (pointer as *const u32).read_unaligned() as u64,
+        ValueFormat::Signed(_) | ValueFormat::Unsigned(_) => (pointer as *const u64).read_unaligned(),
+        ValueFormat::String(_) | ValueFormat::WString(_) => unreachable!("strings are not traced"),
+    };
+    format!("b{bits:b}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::{get_identifier, get_scopes, VcdWriter};
+    use crate::simulation::{ValueFormat, WatchedVariable};
+
+    #[test]
+    fn identifiers_are_unique() {
+        assert_eq!(get_identifier(0), "!");
+        assert_eq!(get_identifier(93), "~");
+        assert_eq!(get_identifier(94), "!!");
+        assert_eq!(get_identifier(95), "\"!");
+        let identifiers = (0..20_000).map(get_identifier).collect::<std::collections::HashSet<_>>();
+        assert_eq!(identifiers.len(), 20_000);
+    }
+
+    #[test]
+    fn scopes_are_the_leading_segments() {
+        assert_eq!(get_scopes("ticks"), "");
+        assert_eq!(get_scopes("main.count"), "main");
+        assert_eq!(get_scopes("main.motors[1].speed"), "main.motors[1]");
+    }
+
+    #[test]
+    fn changed_values_are_written_every_cycle() {
+        let (count, on, speed) = (Cell::new(-1_i16), Cell::new(0_u8), Cell::new(0.5_f64));
+        let mut output = vec![];
+        let variables = vec![
+            WatchedVariable {
+                name: "main.motor.speed".to_string(),
+                address: speed.as_ptr() as usize,
+                format: ValueFormat::Float(8),
+            },
+            WatchedVariable {
+                name: "main.count".to_string(),
+                address: count.as_ptr() as usize,
+                format: ValueFormat::Signed(2),
+            },
+            WatchedVariable {
+                name: "on".to_string(),
+                address: on.as_ptr() as usize,
+                format: ValueFormat::Bool,
+            },
+        ];
+        let mut writer =
+            VcdWriter::new(&mut output, variables, Some(std::time::Duration::from_millis(10))).unwrap();
+        writer.sample(0).unwrap();
+        count.set(2);
+        on.set(1);
+        writer.sample(1).unwrap();
+        writer.sample(2).unwrap();
+        speed.set(1.25);
+        writer.sample(3).unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\
+$version RuSTy plc run $end
+$timescale 1ms $end
+$var wire 1 ! on $end
+$scope module main $end
+$var integer 16 \" count $end
+$scope module motor $end
+$var real 64 # speed $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+b1111111111111111 \"
+r0.5 #
+$end
+#10
+1!
+b10 \"
+#30
+r1.25 #
+"
+        );
+    }
+}
@@ -64,6 +64,11 @@ impl GlobalContext {
         self.sources.get(key)
     }
 
+    /// Removes the [`SourceCode`] with the given key, e.g. a source that is not compiled anymore
+    pub fn remove(&mut self, key: &str) -> Option<SourceCode> {
+        self.sources.remove(key)
+    }
+
     /// Returns a cloned [`IdProvider`]
     pub fn provider(&self) -> IdProvider {
         self.provider.clone()
@@ -53,6 +53,9 @@ pub struct ProjectConfig {
     pub package_commands: Vec<String>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub linker_script: Option<PathBuf>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -259,7 +262,8 @@ mod tests {
             "file.st"
             ],
         "compile_type" : "Shared",
-        "output": "proj.so"
+        "output": "proj.so",
+        "linker_script": "memory.ld"
     }
 "#;
 
@@ -301,6 +305,7 @@ mod tests {
                 },
             ],
             package_commands: vec![],
+            linker_script: None,
             version: None,
             format_version: None,
         };
@@ -11,7 +11,7 @@ use crate::{
     object::Object,
 };
 
-use plc::output::FormatOption;
+use plc::{output::FormatOption, Target};
 use source_code::{SourceContainer, SourceType};
 
 #[derive(Debug)]
@@ -81,6 +81,8 @@ pub struct Project<T: SourceContainer> {
     format: FormatOption,
     /// Output Name
     output: Option<String>,
+    /// Linker script laying out the linked output
+    linker_script: Option<PathBuf>,
 }
 
 impl<T: SourceContainer> LibraryInformation<T> {
@@ -160,6 +162,9 @@ impl Project<PathBuf> {
         let current_dir = env::current_dir()?;
         let location = config.parent().map(Path::to_path_buf).or(Some(current_dir));
         let sources = resolve_file_paths(location.as_deref(), project_config.files)?;
+        let linker_script = project_config
+            .linker_script
+            .map(|it| location.as_deref().map(|location| location.join(&it)).unwrap_or(it));
         Ok(Project {
             name: project_config.name,
             location,
@@ -167,6 +172,7 @@ impl Project<PathBuf> {
             libraries,
             format: project_config.compile_type,
             output: project_config.output,
+            linker_script,
             includes: vec![],
             objects: vec![],
         })
@@ -216,6 +222,7 @@ impl<S: SourceContainer> Project<S> {
             libraries: vec![],
             format: FormatOption::default(),
             output: None,
+            linker_script: None,
         }
     }
 
@@ -278,9 +285,26 @@ impl<S: SourceContainer> Project<S> {
         })
     }
 
+    /// returns the name of the output linked for the given target, a WebAssembly module is named
+    /// `.wasm` unless an output name is given
+    pub fn get_output_name_for(&self, target: &Target) -> String {
+        match self.format {
+            FormatOption::Static | FormatOption::Shared | FormatOption::PIC | FormatOption::NoPIC
+                if self.output.is_none() && target.is_wasm() =>
+            {
+                format!("{}.wasm", self.get_name())
+            }
+            _ => self.get_output_name(),
+        }
+    }
+
     pub fn get_output_format(&self) -> FormatOption {
         self.format
     }
+
+    pub fn get_linker_script(&self) -> Option<&Path> {
+        self.linker_script.as_deref()
+    }
 }
 
 fn resolve_file_paths(location: Option<&Path>, inputs: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
@@ -1,6 +1,8 @@
 use std::{
+    collections::HashMap,
     fmt::{Debug, Display, Formatter},
     ops::Range,
+    sync::Arc,
 };
 
 use crate::{SourceCode, SourceContainer};
@@ -9,12 +11,17 @@ use crate::{SourceCode, SourceContainer};
 pub struct SourceLocationFactory {
     file: Option<&'static str>,
     newlines: NewLines,
+    /// the start of every segment of embedded code with its offset in the source, sorted by their start
+    /// in the embedded code. An offset in the embedded code is mapped through the segment containing it
+    segments: Arc<Vec<(usize, usize)>>,
+    /// the graphical origins of the blocks of a diagram, indexed by their local id
+    origins: Arc<HashMap<usize, Arc<BlockOrigin>>>,
 }
 
 impl SourceLocationFactory {
     /// constructs a SourceRangeFactory used for internally generated code (e.g. builtins)
     pub fn internal(src: &str) -> Self {
-        SourceLocationFactory { file: None, newlines: NewLines::build(src) }
+        SourceLocationFactory { file: None, newlines: NewLines::build(src), ..Default::default() }
     }
 
     /// constructs a SourceRangeFactory used to construct SourceRanes that point into the given source
@@ -22,19 +29,49 @@ impl SourceLocationFactory {
         SourceLocationFactory {
             file: Some(source_code.get_location_str()),
             newlines: NewLines::build(&source_code.source),
+            ..Default::default()
         }
     }
 
+    /// returns a factory for code embedded in this factory's source (e.g. an ST body inside an XML file),
+    /// so ranges created for the embedded code point into the surrounding source. The embedded code is
+    /// given as segments of (start in the embedded code, offset in the source), as it may differ from its
+    /// source, e.g. by escaped characters
+    pub fn with_segments(&self, segments: Vec<(usize, usize)>) -> Self {
+        SourceLocationFactory { segments: Arc::new(segments), ..self.clone() }
+    }
+
+    /// returns the offset in the source of the given offset in the embedded code
+    fn get_source_offset(&self, offset: usize) -> usize {
+        let segment = self.segments.partition_point(|(start, _)| *start <= offset);
+        match segment.checked_sub(1).map(|it| self.segments[it]) {
+            Some((start, source_offset)) => source_offset + offset - start,
+            None => offset,
+        }
+    }
+
+    /// returns a factory that attaches the given origins to the block locations it creates, so code
+    /// generated from a diagram can be traced back to the block's local id and position
+    pub fn with_block_origins(&self, origins: impl IntoIterator<Item = (usize, BlockOrigin)>) -> Self {
+        let origins = origins.into_iter().map(|(id, origin)| (id, Arc::new(origin))).collect();
+        SourceLocationFactory { origins: Arc::new(origins), ..self.clone() }
+    }
+
     /// creates a new SourceRange using the factory's file_name
     pub fn create_range(&self, range: core::ops::Range<usize>) -> SourceLocation {
-        let start = TextLocation::from_offset(range.start, &self.newlines);
-        let end = TextLocation::from_offset(range.end, &self.newlines);
+        let start = TextLocation::from_offset(self.get_source_offset(range.start), &self.newlines);
+        let end = TextLocation::from_offset(self.get_source_offset(range.end), &self.newlines);
         SourceLocation { span: CodeSpan::Range(start..end), file: self.file }
     }
 
     pub fn create_block_location(&self, local_id: usize, execution_order: Option<usize>) -> SourceLocation {
         SourceLocation {
-            span: CodeSpan::Block { local_id, execution_order, inner_range: None },
+            span: CodeSpan::Block {
+                local_id,
+                execution_order,
+                inner_range: None,
+                origin: self.origins.get(&local_id).cloned(),
+            },
             file: self.file,
         }
     }
@@ -74,11 +111,39 @@ impl TextLocation {
     }
 }
 
+/// Where a block of a graphical diagram (e.g. CFC) was drawn and declared
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlockOrigin {
+    /// The type name of a block, or the element name of any other node (e.g. `inVariable`)
+    pub name: String,
+    /// The `<position x y>` of the node in the diagram, if the file declares one
+    pub position: Option<(i32, i32)>,
+    /// The range of the node's element in the source file
+    pub range: Range<usize>,
+    /// The zero-based line the node's element starts at in the source file
+    pub line: usize,
+}
+
+impl BlockOrigin {
+    /// Describes the origin of the block with the given local id, e.g. `block 12 (ADD) at (120, 40)`
+    pub fn describe(&self, local_id: usize) -> String {
+        match self.position {
+            Some((x, y)) => format!("block {local_id} ({}) at ({x}, {y})", self.name),
+            None => format!("block {local_id} ({})", self.name),
+        }
+    }
+}
+
 /// Represents the location of a code element in a source code
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum CodeSpan {
     /// The location of a block in a diagram
-    Block { local_id: usize, execution_order: Option<usize>, inner_range: Option<Range<usize>> },
+    Block {
+        local_id: usize,
+        execution_order: Option<usize>,
+        inner_range: Option<Range<usize>>,
+        origin: Option<Arc<BlockOrigin>>,
+    },
     /// An element spanning multiple IDs
     Combined(Vec<CodeSpan>),
     /// A location inside a text
@@ -90,6 +155,9 @@ pub enum CodeSpan {
 impl Display for CodeSpan {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            CodeSpan::Block { local_id, origin: Some(origin), .. } => {
+                write!(f, "{}", origin.describe(*local_id))
+            }
             CodeSpan::Block { .. } => write!(f, "Block {}", self.get_line()),
             CodeSpan::Combined(spans) => {
                 write!(f, "{}", spans.iter().map(|it| it.to_string()).collect::<String>())
@@ -117,10 +185,11 @@ impl CodeSpan {
 
     /// Gets the line representation for a source location
     /// If the location does not represent a line, the closest equivalent is returned
-    // That is 0 for None and the ID for id/inner spans
+    // That is 0 for None, the line of the element for blocks of a diagram and the ID for other id/inner spans
     pub fn get_line(&self) -> usize {
         match self {
             Self::Range(range) => range.start.line,
+            Self::Block { origin: Some(origin), .. } => origin.line,
             Self::Block { local_id, .. } => *local_id,
             _ => 0,
         }
@@ -129,16 +198,19 @@ impl CodeSpan {
     pub fn get_line_plus_one(&self) -> usize {
         match self {
             Self::Range(range) => range.start.line + 1,
+            Self::Block { origin: Some(origin), .. } => origin.line + 1,
             Self::Block { local_id, .. } => *local_id,
             _ => 0,
         }
     }
 
     /// Gets the colmumn representation for a source location
+    /// Blocks of a diagram return their ID, so a debugger can map the element's line back to the block.
     /// If the location does not represent a line, 0 is returned
     pub fn get_column(&self) -> usize {
         match self {
             Self::Range(range) => range.start.column,
+            Self::Block { local_id, origin: Some(_), .. } => *local_id,
             _ => 0,
         }
     }
@@ -146,10 +218,20 @@ impl CodeSpan {
     pub fn to_range(&self) -> Option<Range<usize>> {
         match self {
             CodeSpan::Range(range) => Some(range.start.offset..range.end.offset),
-            CodeSpan::Block { inner_range, .. } => inner_range.clone(),
+            CodeSpan::Block { inner_range: Some(range), .. } => Some(range.clone()),
+            CodeSpan::Block { origin, .. } => origin.as_ref().map(|it| it.range.clone()),
             _ => None,
         }
     }
+
+    /// Describes the diagram blocks this span originates from, e.g. `block 12 (ADD) at (120, 40)`
+    pub fn get_block_descriptions(&self) -> Vec<String> {
+        match self {
+            CodeSpan::Block { local_id, origin: Some(origin), .. } => vec![origin.describe(*local_id)],
+            CodeSpan::Combined(spans) => spans.iter().flat_map(|it| it.get_block_descriptions()).collect(),
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -211,7 +293,7 @@ impl SourceLocation {
         let span = match (&self.span, &other.span) {
             //ID -> ID = Combile
             (
-                CodeSpan::Block { local_id, execution_order, inner_range },
+                CodeSpan::Block { local_id, execution_order, inner_range, origin },
                 CodeSpan::Block { local_id: other, inner_range: other_range, .. },
             ) if local_id == other => {
                 let inner_range = match (inner_range, other_range) {
@@ -220,7 +302,12 @@ impl SourceLocation {
                     (Some(start), Some(end)) => Some(start.start..end.end),
                 };
 
-                CodeSpan::Block { local_id: *local_id, execution_order: *execution_order, inner_range }
+                CodeSpan::Block {
+                    local_id: *local_id,
+                    execution_order: *execution_order,
+                    inner_range,
+                    origin: origin.clone(),
+                }
             }
             (CodeSpan::Block { .. }, CodeSpan::Block { .. }) => {
                 CodeSpan::Combined(vec![self.span.clone(), other.span.clone()])
@@ -228,8 +315,8 @@ impl SourceLocation {
             //Range -> Range = Range
             (CodeSpan::Range(start), CodeSpan::Range(end)) => CodeSpan::Range(start.start..end.end),
             //ID -> Range = InnerRange
-            (CodeSpan::Block { local_id, execution_order, inner_range }, CodeSpan::Range(range))
-            | (CodeSpan::Range(range), CodeSpan::Block { local_id, execution_order, inner_range }) => {
+            (CodeSpan::Block { local_id, execution_order, inner_range, origin }, CodeSpan::Range(range))
+            | (CodeSpan::Range(range), CodeSpan::Block { local_id, execution_order, inner_range, origin }) => {
                 CodeSpan::Block {
                     local_id: *local_id,
                     execution_order: *execution_order,
@@ -237,6 +324,7 @@ impl SourceLocation {
                         .as_ref()
                         .map(|it| it.start..range.end.offset)
                         .or_else(|| Some(range.start.offset..range.end.offset)),
+                    origin: origin.clone(),
                 }
             }
             //None any -> None (unsupported)
@@ -352,10 +440,12 @@ impl NewLines {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use crate::source_location::{NewLines, TextLocation};
     use insta::assert_debug_snapshot;
 
-    use super::{CodeSpan, SourceLocation};
+    use super::{BlockOrigin, CodeSpan, SourceLocation, SourceLocationFactory};
 
     #[test]
     fn new_lines_test_empty_string() {
@@ -416,11 +506,11 @@ mod tests {
     fn span_two_blocks() {
         let loc1 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None, origin: None },
         };
         let loc2 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 2, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 2, execution_order: Some(1), inner_range: None, origin: None },
         };
         assert_debug_snapshot!(loc1.span(&loc2));
     }
@@ -429,11 +519,11 @@ mod tests {
     fn span_two_blocks_with_same_id() {
         let loc1 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None, origin: None },
         };
         let loc2 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None, origin: None },
         };
         assert_debug_snapshot!(loc1.span(&loc2));
     }
@@ -442,7 +532,7 @@ mod tests {
     fn span_id_and_range() {
         let loc1 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None, origin: None },
         };
         let loc2 = SourceLocation {
             file: None,
@@ -468,19 +558,19 @@ mod tests {
     fn span_combined() {
         let loc1 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None, origin: None },
         };
         let loc2 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 2, execution_order: Some(2), inner_range: None },
+            span: CodeSpan::Block { local_id: 2, execution_order: Some(2), inner_range: None, origin: None },
         };
         let loc3 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 3, execution_order: Some(3), inner_range: None },
+            span: CodeSpan::Block { local_id: 3, execution_order: Some(3), inner_range: None, origin: None },
         };
         let loc4 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 4, execution_order: Some(4), inner_range: None },
+            span: CodeSpan::Block { local_id: 4, execution_order: Some(4), inner_range: None, origin: None },
         };
         assert_debug_snapshot!(loc1.span(&loc2).span(&loc3.span(&loc4)));
     }
@@ -489,9 +579,32 @@ mod tests {
     fn span_none() {
         let loc1 = SourceLocation {
             file: None,
-            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None },
+            span: CodeSpan::Block { local_id: 1, execution_order: Some(1), inner_range: None, origin: None },
         };
         let loc2 = SourceLocation { file: None, span: CodeSpan::None };
         assert_debug_snapshot!(loc1.span(&loc2));
     }
+
+    #[test]
+    fn block_locations_carry_their_diagram_origin() {
+        let source = "<block localId=\"12\" typeName=\"ADD\"/>";
+        let factory = SourceLocationFactory::internal(source).with_block_origins(HashMap::from([(
+            12,
+            BlockOrigin { name: "ADD".into(), position: Some((120, 40)), range: 0..source.len(), line: 0 },
+        )]));
+
+        let block = factory.create_block_location(12, Some(1));
+        let other = factory.create_block_location(13, None);
+
+        assert_eq!(block.to_range(), Some(0..source.len()));
+        assert_eq!(block.get_line_plus_one(), 1);
+        assert_eq!(block.get_column(), 12);
+        assert_eq!(block.get_span().to_string(), "block 12 (ADD) at (120, 40)");
+        assert_eq!(
+            block.span(&block).get_span().get_block_descriptions(),
+            vec!["block 12 (ADD) at (120, 40)"]
+        );
+        assert_eq!(other.get_span().to_string(), "Block 13");
+        assert!(other.get_span().get_block_descriptions().is_empty());
+    }
 }
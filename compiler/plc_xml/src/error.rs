@@ -1,5 +1,8 @@
 use std::{num::ParseIntError, str::Utf8Error};
 
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::SourceLocation;
+
 pub enum Error {
     UnexpectedEndOfFile(Vec<&'static [u8]>),
 
@@ -32,6 +35,19 @@ impl From<ParseIntError> for Error {
     }
 }
 
+impl Error {
+    /// Converts the error into a diagnostic reported at the given location of the XML file
+    pub(crate) fn into_diagnostic(self, location: SourceLocation) -> Diagnostic {
+        let error_code = match self {
+            Error::UnexpectedEndOfFile(_) | Error::Encoding(_) | Error::ReadEvent(_) => "E095",
+            Error::MissingAttribute(_) | Error::Parse(_) => "E096",
+            Error::UnexpectedElement(_) => "E097",
+        };
+
+        Diagnostic::error(self.to_string()).with_error_code(error_code).with_location(location)
+    }
+}
+
 impl std::error::Error for Error {}
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
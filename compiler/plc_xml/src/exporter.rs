@@ -0,0 +1,497 @@
+//! Exports parsed ST code as a PLCopen TC6 XML project, so code compiled with RuSTy can be moved back into
+//! vendor IDEs.
+//!
+//! The structure of the project (POUs, their interfaces, data types and global variables) is taken from the
+//! AST, while expressions such as initial values and the bodies of the POUs are copied from the original
+//! source code. The project is built with the [`Node`] builder of the [`serializer`](crate::serializer).
+
+use ast::ast::{
+    flatten_expression_list, Assignment, AstNode, AstStatement, CompilationUnit, DataType,
+    DataTypeDeclaration, Implementation, Pou, PouType, RangeStatement, UserTypeDeclaration, Variable,
+    VariableBlock, VariableBlockType,
+};
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::{CodeSpan, SourceLocation};
+use quick_xml::escape::escape;
+
+use crate::serializer::{IntoNode, Node};
+
+const TC6_NAMESPACE: &str = "http://www.plcopen.org/xml/tc6_0201";
+const XHTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+/// The elementary types of TC6, every other type is exported as a `<derived>` type
+const ELEMENTARY_TYPES: &[&str] = &[
+    "BOOL", "BYTE", "WORD", "DWORD", "LWORD", "SINT", "INT", "DINT", "LINT", "USINT", "UINT", "UDINT",
+    "ULINT", "REAL", "LREAL", "TIME", "DATE", "DT", "TOD",
+];
+
+/// Exports the given units as a TC6 XML project named `name`
+///
+/// `source_of` returns the source code of a file, it is used to copy expressions and bodies into the
+/// project. Elements which cannot be represented in TC6 (e.g. classes and methods) are skipped and reported
+/// as warnings.
+pub fn export<'src>(
+    name: &str,
+    creation_date_time: &str,
+    units: &[CompilationUnit],
+    source_of: impl Fn(&str) -> Option<&'src str>,
+) -> (String, Vec<Diagnostic>) {
+    let mut exporter = Exporter { source_of: &source_of, diagnostics: vec![] };
+    let project = exporter.project(name, creation_date_time, units);
+    let content = format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{}", project.serialize(0));
+    (content, exporter.diagnostics)
+}
+
+struct Exporter<'exp, 'src> {
+    source_of: &'exp dyn Fn(&str) -> Option<&'src str>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'exp, 'src> Exporter<'exp, 'src> {
+    fn project(&mut self, name: &str, creation_date_time: &str, units: &[CompilationUnit]) -> Node {
+        let file_header = Node::new("fileHeader")
+            .attribute("companyName", "")
+            .attribute("productName", "RuSTy")
+            .attribute("productVersion", env!("CARGO_PKG_VERSION"))
+            .attribute("creationDateTime", text(creation_date_time))
+            .close();
+
+        let data_types =
+            units.iter().flat_map(|it| &it.user_types).map(|it| self.user_type(it)).collect::<Vec<_>>();
+        let implementations = units.iter().flat_map(|it| &it.implementations).collect::<Vec<_>>();
+        let pous = units
+            .iter()
+            .flat_map(|it| &it.units)
+            .filter_map(|it| self.pou(it, &implementations))
+            .collect::<Vec<_>>();
+        let types = Node::new("types")
+            .child(&Node::new("dataTypes").children(nodes(&data_types)))
+            .child(&Node::new("pous").children(nodes(&pous)));
+
+        let global_vars =
+            units.iter().flat_map(|it| &it.global_vars).map(|it| self.variable_block(it)).collect::<Vec<_>>();
+        let mut configurations = Node::new("configurations");
+        if !global_vars.is_empty() {
+            // TC6 only knows global variables declared in configurations
+            configurations = configurations
+                .child(&Node::new("configuration").attribute("name", "config").children(nodes(&global_vars)));
+        }
+
+        Node::new("project")
+            .attribute("xmlns", TC6_NAMESPACE)
+            .child(&file_header)
+            .child(&content_header(name))
+            .child(&types)
+            .child(&Node::new("instances").child(&configurations))
+    }
+
+    fn user_type(&mut self, user_type: &UserTypeDeclaration) -> Node {
+        let name = user_type.data_type.get_name().unwrap_or_default();
+        let base_type = self.data_type(&user_type.data_type, &user_type.location);
+        let node = Node::new("dataType").attribute("name", text(name)).child(&base(base_type));
+        match user_type.initializer.as_ref().and_then(|it| self.initial_value(it)) {
+            Some(initial_value) => node.child(&initial_value),
+            None => node,
+        }
+    }
+
+    fn pou(&mut self, pou: &Pou, implementations: &[&Implementation]) -> Option<Node> {
+        let pou_type = match pou.pou_type {
+            PouType::Program => "program",
+            PouType::Function => "function",
+            PouType::FunctionBlock => "functionBlock",
+            _ => {
+                self.diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "`{}` cannot be exported to PLCopen XML, only programs, functions and function blocks are supported",
+                        pou.name
+                    ))
+                    .with_location(pou.name_location.clone()),
+                );
+                return None;
+            }
+        };
+
+        let mut interface = Node::new("interface");
+        if let Some(return_type) = &pou.return_type {
+            interface = interface.child(&Node::new("returnType").child(&self.type_declaration(return_type)));
+        }
+        for block in &pou.variable_blocks {
+            interface = interface.child(&self.variable_block(block));
+        }
+        let mut node = Node::new("pou")
+            .attribute("name", text(&pou.name))
+            .attribute("pouType", pou_type)
+            .child(&interface);
+
+        let actions = implementations
+            .iter()
+            .filter(|it| it.pou_type == PouType::Action && it.type_name == pou.name)
+            .map(|action| {
+                let name = action.name.rsplit('.').next().unwrap_or_default();
+                let node = Node::new("action").attribute("name", text(name));
+                match self.body(action) {
+                    Some(body) => node.child(&body),
+                    None => node,
+                }
+            })
+            .collect::<Vec<_>>();
+        if !actions.is_empty() {
+            node = node.child(&Node::new("actions").children(nodes(&actions)));
+        }
+
+        if let Some(body) = implementations
+            .iter()
+            .find(|it| it.pou_type == pou.pou_type && it.name == pou.name)
+            .and_then(|it| self.body(it))
+        {
+            node = node.child(&body);
+        }
+        Some(node)
+    }
+
+    fn body(&mut self, implementation: &Implementation) -> Option<Node> {
+        let Some(code) = self.get_body_text(implementation) else {
+            self.diagnostics.push(
+                Diagnostic::warning(format!(
+                    "The body of `{}` cannot be exported to PLCopen XML, its source code is not available",
+                    implementation.name
+                ))
+                .with_location(implementation.name_location.clone()),
+            );
+            return None;
+        };
+
+        let xhtml = Node::new("xhtml").attribute("xmlns", XHTML_NAMESPACE).content(text(&code));
+        Some(Node::new("body").child(&Node::new("ST").child(&xhtml)))
+    }
+
+    fn variable_block(&mut self, block: &VariableBlock) -> Node {
+        let name = match block.variable_block_type {
+            VariableBlockType::Local => "localVars",
+            VariableBlockType::Temp => "tempVars",
+            VariableBlockType::Input(_) => "inputVars",
+            VariableBlockType::Output => "outputVars",
+            VariableBlockType::InOut => "inOutVars",
+            VariableBlockType::Global => "globalVars",
+        };
+        let mut node = Node::new(name);
+        if block.constant {
+            node = node.attribute("constant", "true");
+        }
+        if block.retain {
+            node = node.attribute("retain", "true");
+        }
+        if block.persistent {
+            node = node.attribute("persistent", "true");
+        }
+
+        let variables = block.variables.iter().map(|it| self.variable(it)).collect::<Vec<_>>();
+        node.children(nodes(&variables))
+    }
+
+    fn variable(&mut self, variable: &Variable) -> Node {
+        // the address' location includes the leading `AT` keyword
+        let address = variable
+            .address
+            .as_ref()
+            .and_then(|it| self.get_text(&it.get_location()))
+            .map(|it| it.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace()));
+        let mut node = Node::new("variable").attribute("name", text(&variable.name));
+        if let Some(address) = address {
+            node = node.attribute("address", text(address));
+        }
+
+        node = node.child(&Node::new("type").child(&self.type_declaration(&variable.data_type_declaration)));
+        match variable.initializer.as_ref().and_then(|it| self.initial_value(it)) {
+            Some(initial_value) => node.child(&initial_value),
+            None => node,
+        }
+    }
+
+    fn type_declaration(&mut self, declaration: &DataTypeDeclaration) -> Node {
+        match declaration {
+            DataTypeDeclaration::DataTypeReference { referenced_type, .. } => type_reference(referenced_type),
+            DataTypeDeclaration::DataTypeDefinition { data_type, location, .. } => {
+                self.data_type(data_type, location)
+            }
+        }
+    }
+
+    fn data_type(&mut self, data_type: &DataType, location: &SourceLocation) -> Node {
+        match data_type {
+            DataType::StructType { variables, .. } => {
+                let variables = variables.iter().map(|it| self.variable(it)).collect::<Vec<_>>();
+                Node::new("struct").children(nodes(&variables))
+            }
+            DataType::UnionType { variables, .. } => {
+                let variables = variables.iter().map(|it| self.variable(it)).collect::<Vec<_>>();
+                Node::new("union").children(nodes(&variables))
+            }
+            DataType::EnumType { elements, numeric_type, .. } => {
+                let values = flatten_expression_list(elements)
+                    .into_iter()
+                    .map(|element| {
+                        let (name, value) = match &element.stmt {
+                            AstStatement::Assignment(Assignment { left, right }) => {
+                                (left.get_flat_reference_name(), self.get_text(&right.get_location()))
+                            }
+                            _ => (element.get_flat_reference_name(), None),
+                        };
+                        let node = Node::new("value").attribute("name", text(name.unwrap_or_default()));
+                        match value {
+                            Some(value) => node.attribute("value", text(value)).close(),
+                            None => node.close(),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                Node::new("enum")
+                    .child(&Node::new("values").children(nodes(&values)))
+                    .child(&base(type_reference(numeric_type)))
+            }
+            DataType::SubRangeType { referenced_type, bounds: None, .. } => type_reference(referenced_type),
+            DataType::SubRangeType { referenced_type, bounds: Some(bounds), .. } => {
+                let name = if referenced_type.to_uppercase().starts_with('U') {
+                    "subrangeUnsigned"
+                } else {
+                    "subrangeSigned"
+                };
+                let mut node = Node::new(name);
+                if let Some(range) = self.range("range", bounds) {
+                    node = node.child(&range);
+                }
+                node.child(&base(type_reference(referenced_type)))
+            }
+            DataType::ArrayType { bounds, referenced_type, .. } => {
+                let dimensions = flatten_expression_list(bounds)
+                    .into_iter()
+                    .filter_map(|it| self.range("dimension", it))
+                    .collect::<Vec<_>>();
+                Node::new("array")
+                    .children(nodes(&dimensions))
+                    .child(&base(self.type_declaration(referenced_type)))
+            }
+            DataType::PointerType { referenced_type, .. } => {
+                Node::new("pointer").child(&base(self.type_declaration(referenced_type)))
+            }
+            DataType::StringType { is_wide, size, .. } => {
+                let node = Node::new(if *is_wide { "wstring" } else { "string" });
+                match size.as_ref().and_then(|it| self.get_text(&it.get_location())) {
+                    Some(length) => node.attribute("length", text(length)).close(),
+                    None => node.close(),
+                }
+            }
+            DataType::VarArgs { .. } | DataType::GenericType { .. } => {
+                self.diagnostics.push(
+                    Diagnostic::warning("Variadic and generic types cannot be exported to PLCopen XML")
+                        .with_location(location.clone()),
+                );
+                Node::new("derived").attribute("name", text(data_type.get_name().unwrap_or_default())).close()
+            }
+        }
+    }
+
+    /// returns a range like `0..10` as `<name lower="0" upper="10"/>`
+    fn range(&mut self, name: &'static str, range: &AstNode) -> Option<Node> {
+        let AstStatement::RangeStatement(RangeStatement { start, end }) = &range.stmt else {
+            self.diagnostics.push(
+                Diagnostic::warning(
+                    "Only ranges with a lower and upper bound can be exported to PLCopen XML",
+                )
+                .with_location(range.get_location()),
+            );
+            return None;
+        };
+        let lower = self.get_text(&start.get_location()).unwrap_or_default();
+        let upper = self.get_text(&end.get_location()).unwrap_or_default();
+        Some(Node::new(name).attribute("lower", text(lower)).attribute("upper", text(upper)).close())
+    }
+
+    fn initial_value(&self, initializer: &AstNode) -> Option<Node> {
+        // structured values can be expressed in ST syntax as well, so every value is exported as simple value
+        let value = self.get_text(&initializer.get_location())?;
+        Some(
+            Node::new("initialValue")
+                .child(&Node::new("simpleValue").attribute("value", text(value)).close()),
+        )
+    }
+
+    /// returns the source code of the given location
+    fn get_text(&self, location: &SourceLocation) -> Option<&'src str> {
+        // locations of graphical elements do not point into the source code
+        if !matches!(location.get_span(), CodeSpan::Range(..)) {
+            return None;
+        }
+        let source = (self.source_of)(location.get_file_name()?)?;
+        source.get(location.to_range()?)
+    }
+
+    /// returns the statements of the given implementation as written in the source code, without the
+    /// indentation they share
+    fn get_body_text(&self, implementation: &Implementation) -> Option<String> {
+        let location = &implementation.location;
+        if !matches!(location.get_span(), CodeSpan::Range(..)) {
+            return None;
+        }
+        let source = (self.source_of)(location.get_file_name()?)?;
+        let range = location.to_range()?;
+        // start at the beginning of the first line to keep its indentation
+        let start = source[..range.start].rfind('\n').map(|it| it + 1).unwrap_or_default();
+        let body = source.get(start..range.end)?.trim_end();
+        // the location of an implementation includes the closing `END_...` keyword, upper-casing only ASCII
+        // characters keeps the byte offsets of the body
+        let end_keyword = body.to_ascii_uppercase().rfind("END_")?;
+        let lines =
+            body[..end_keyword].trim_end().lines().skip_while(|it| it.trim().is_empty()).collect::<Vec<_>>();
+
+        let indentation = lines
+            .iter()
+            .filter(|it| !it.trim().is_empty())
+            .map(|it| it.len() - it.trim_start().len())
+            .min()
+            .unwrap_or_default();
+        let lines = lines.iter().map(|it| it.get(indentation..).unwrap_or_default()).collect::<Vec<_>>();
+        Some(lines.join("\n"))
+    }
+}
+
+fn content_header(name: &str) -> Node {
+    let coordinate_info =
+        ["fbd", "ld", "sfc"].into_iter().fold(Node::new("coordinateInfo"), |node, language| {
+            node.child(
+                &Node::new(language)
+                    .child(&Node::new("scaling").attribute("x", "1").attribute("y", "1").close()),
+            )
+        });
+    Node::new("contentHeader").attribute("name", text(name)).child(&coordinate_info)
+}
+
+fn type_reference(name: &str) -> Node {
+    let upper_case = name.to_uppercase();
+    let elementary = match upper_case.as_str() {
+        "STRING" => "string",
+        "WSTRING" => "wstring",
+        "DATE_AND_TIME" => "DT",
+        "TIME_OF_DAY" => "TOD",
+        it => match ELEMENTARY_TYPES.iter().find(|elementary| **elementary == it) {
+            Some(elementary) => elementary,
+            None => return Node::new("derived").attribute("name", text(name)).close(),
+        },
+    };
+    Node::new(elementary).close()
+}
+
+/// wraps the given type in a `<baseType>`
+fn base(node: Node) -> Node {
+    Node::new("baseType").child(&node)
+}
+
+fn nodes(nodes: &[Node]) -> Vec<&dyn IntoNode> {
+    nodes.iter().map(|it| it as &dyn IntoNode).collect()
+}
+
+/// escapes the given text to be written as attribute value or element content
+fn text(value: &str) -> String {
+    escape(value).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::{ast::LinkageType, provider::IdProvider};
+    use insta::assert_snapshot;
+    use plc::lexer;
+    use plc_source::{source_location::SourceLocationFactory, SourceCode};
+
+    fn export(content: &str) -> (String, Vec<plc_diagnostics::diagnostics::Diagnostic>) {
+        let source = SourceCode::new(content, "test.st");
+        let (unit, _) = plc::parser::parse(
+            lexer::lex_with_ids(content, IdProvider::default(), SourceLocationFactory::for_source(&source)),
+            LinkageType::Internal,
+            "test.st",
+        );
+        super::export("test", "2023-01-01T00:00:00", &[unit], |_| Some(content))
+    }
+
+    #[test]
+    fn pous_with_interfaces_and_bodies_are_exported() {
+        let (content, diagnostics) = export(
+            "
+            FUNCTION_BLOCK fb
+            VAR_INPUT
+                a : ARRAY[1..2, 0..3] OF STRING[20];
+            END_VAR
+            VAR_OUTPUT RETAIN
+                b AT %QX1.2 : BOOL := TRUE;
+            END_VAR
+            VAR_IN_OUT
+                c : REF_TO DATE_AND_TIME;
+            END_VAR
+                IF a[1, 0] = 'x' THEN
+                    b := FALSE;
+                END_IF
+            END_FUNCTION_BLOCK
+
+            ACTIONS fb
+            ACTION act
+                b := TRUE;
+            END_ACTION
+            END_ACTIONS
+            ",
+        );
+
+        assert!(diagnostics.is_empty());
+        assert_snapshot!(content);
+    }
+
+    #[test]
+    fn data_types_and_global_variables_are_exported() {
+        let (content, diagnostics) = export(
+            "
+            TYPE State : BYTE (idle := 1, busy := 2); END_TYPE
+            TYPE Range : INT(-10..10) := 5; END_TYPE
+            TYPE Pair : STRUCT first, second : WSTRING; END_STRUCT END_TYPE
+            VAR_GLOBAL CONSTANT
+                limit : LINT := 16#FF;
+            END_VAR
+            ",
+        );
+
+        assert!(diagnostics.is_empty());
+        assert_snapshot!(content);
+    }
+
+    #[test]
+    fn bodies_with_non_ascii_text_are_exported() {
+        // upper-casing `ΐ` and `ΰ` changes their length in bytes
+        let (content, diagnostics) = export(
+            "
+            PROGRAM main
+            VAR
+                s : STRING;
+            END_VAR
+                s := 'Straße';
+                s := 'ΐΰ';
+            END_PROGRAM
+            ",
+        );
+
+        assert!(diagnostics.is_empty());
+        assert!(content.contains(
+            "<xhtml xmlns=\"http://www.w3.org/1999/xhtml\">s := &apos;Straße&apos;;\ns := &apos;ΐΰ&apos;;</xhtml>"
+        ));
+    }
+
+    #[test]
+    fn classes_and_methods_are_reported() {
+        let (_, diagnostics) = export(
+            "
+            CLASS cls
+            METHOD foo
+            END_METHOD
+            END_CLASS
+            ",
+        );
+
+        assert_eq!(diagnostics.len(), 2);
+    }
+}
@@ -2,18 +2,22 @@
 #![allow(dead_code)]
 
 pub mod error;
+pub mod exporter;
 mod extensions;
 pub mod xml_parser;
 pub(crate) mod model {
     pub mod action;
     pub mod block;
     pub mod body;
+    pub mod configuration;
     pub mod connector;
     pub mod control;
+    pub mod data_types;
     pub mod fbd;
     pub mod interface;
     pub mod pou;
     pub mod project;
+    pub mod var_list;
     pub mod variables;
 }
 mod reader;
@@ -4,6 +4,7 @@ use quick_xml::events::Event;
 
 use crate::{
     error::Error,
+    extensions::GetOrErr,
     reader::Reader,
     xml_parser::{get_attributes, Parseable},
 };
@@ -52,8 +53,7 @@ impl Parseable for Action<'_> {
         let Some(tag) = tag else { unreachable!() };
 
         let attributes = get_attributes(tag.attributes())?;
-        let Some(name) = attributes.get("name") else { todo!() };
-        let mut action = Action::new(name);
+        let mut action = Action::new(&attributes.get_or_err("name")?);
         loop {
             match reader.read_event()? {
                 Event::Start(tag) if tag.name().as_ref() == b"body" => {
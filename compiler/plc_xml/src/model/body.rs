@@ -6,15 +6,58 @@ use crate::{error::Error, reader::Reader, xml_parser::Parseable};
 #[derive(Debug, Default)]
 pub(crate) struct Body<'xml> {
     pub function_block_diagram: FunctionBlockDiagram<'xml>,
+    pub structured_text: Option<StructuredText>,
+    /// the name of the body's language if it is neither FBD nor ST, e.g. `IL`
+    pub unsupported_language: Option<String>,
+}
+
+/// The content of an `<ST>` body
+#[derive(Debug, Default)]
+pub(crate) struct StructuredText {
+    pub content: String,
+    /// the start of every piece of the content with its offset in the xml file, the content differs
+    /// from the xml text where characters are escaped (e.g. `&lt;`)
+    pub segments: Vec<(usize, usize)>,
+}
+
+impl StructuredText {
+    /// appends the given escaped text found at the given offset of the xml file
+    fn push_escaped(&mut self, text: &str, offset: usize) -> Result<(), Error> {
+        let mut position = 0;
+        while let Some(start) = text[position..].find('&').map(|it| position + it) {
+            self.push(&text[position..start], offset + position);
+            let end = text[start..].find(';').map_or(text.len(), |it| start + it + 1);
+            let character = quick_xml::escape::unescape(&text[start..end])
+                .map_err(|it| Error::ReadEvent(quick_xml::Error::EscapeError(it)))?;
+            self.push(&character, offset + start);
+            position = end;
+        }
+        self.push(&text[position..], offset + position);
+        Ok(())
+    }
+
+    /// separates the following text from the previous one, the reader trims the whitespace between them
+    fn separate(&mut self, offset: usize) {
+        if !self.content.is_empty() {
+            self.push("\n", offset);
+        }
+    }
+
+    fn push(&mut self, text: &str, offset: usize) {
+        if !text.is_empty() {
+            self.segments.push((self.content.len(), offset));
+            self.content.push_str(text);
+        }
+    }
 }
 
 impl<'xml> Body<'xml> {
     fn new(fbd: FunctionBlockDiagram<'xml>) -> Result<Self, Error> {
-        Ok(Self { function_block_diagram: fbd })
+        Ok(Self { function_block_diagram: fbd, ..Default::default() })
     }
 
     fn empty() -> Result<Self, Error> {
-        Ok(Self { function_block_diagram: FunctionBlockDiagram::default() })
+        Ok(Self::default())
     }
 }
 
@@ -26,6 +69,14 @@ impl<'xml> Parseable for Body<'xml> {
                 Event::Start(tag) if tag.name().as_ref() == b"FBD" => {
                     body.function_block_diagram = FunctionBlockDiagram::visit(reader, Some(tag))?
                 }
+                Event::Start(tag) if tag.name().as_ref() == b"ST" => {
+                    body.structured_text = Some(StructuredText::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if matches!(tag.name().as_ref(), b"IL" | b"LD" | b"SFC") => {
+                    body.unsupported_language =
+                        Some(String::from_utf8_lossy(tag.name().as_ref()).to_string());
+                    reader.skip_element(&tag)?
+                }
                 Event::End(tag) if tag.name().as_ref() == b"body" => break,
                 Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"body"])),
                 _ => {}
@@ -36,6 +87,33 @@ impl<'xml> Parseable for Body<'xml> {
     }
 }
 
+impl Parseable for StructuredText {
+    fn visit(reader: &mut Reader, _tag: Option<BytesStart>) -> Result<Self, Error> {
+        let mut structured_text = StructuredText::default();
+        loop {
+            match reader.read_event().map_err(Error::ReadEvent)? {
+                // the code is usually wrapped in an `<xhtml>` element, which we do not need to track
+                // the code may be split into several texts, e.g. around a CDATA section or a comment
+                Event::Text(text) => {
+                    let offset = reader.text_offset(&text);
+                    structured_text.separate(offset);
+                    structured_text.push_escaped(&String::from_utf8_lossy(&text), offset)?;
+                }
+                Event::CData(text) => {
+                    let offset = reader.text_offset(&text);
+                    structured_text.separate(offset);
+                    structured_text.push(&String::from_utf8_lossy(&text), offset);
+                }
+                Event::End(tag) if tag.name().as_ref() == b"ST" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"ST"])),
+                _ => {}
+            }
+        }
+
+        Ok(structured_text)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
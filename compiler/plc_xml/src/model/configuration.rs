@@ -0,0 +1,138 @@
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{
+    error::Error,
+    extensions::GetOrErr,
+    reader::Reader,
+    xml_parser::{get_attributes, Parseable},
+};
+
+use super::var_list::VarList;
+
+/// A configuration declared in the `<instances>` element of a project
+///
+/// Tasks and program instances are kept in the model, but scheduling them is left to the runtime.
+/// Only the global variables of configurations and resources are compiled.
+#[derive(Debug, Default)]
+pub(crate) struct Configuration {
+    pub name: String,
+    pub resources: Vec<Resource>,
+    pub global_vars: Vec<VarList>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Resource {
+    pub name: String,
+    pub tasks: Vec<Task>,
+    pub pou_instances: Vec<PouInstance>,
+    pub global_vars: Vec<VarList>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Task {
+    pub name: String,
+    pub interval: Option<String>,
+    pub priority: Option<String>,
+    pub pou_instances: Vec<PouInstance>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PouInstance {
+    pub name: String,
+    pub type_name: String,
+}
+
+impl Configuration {
+    /// returns all global variable lists of this configuration and its resources
+    pub fn all_global_vars(&self) -> impl Iterator<Item = &VarList> {
+        self.global_vars.iter().chain(self.resources.iter().flat_map(|it| it.global_vars.iter()))
+    }
+}
+
+impl Parseable for Configuration {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let mut configuration = Configuration {
+            name: get_attributes(tag.attributes())?.get_or_err("name")?,
+            ..Default::default()
+        };
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"resource" => {
+                    configuration.resources.push(Resource::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"globalVars" => {
+                    configuration.global_vars.push(VarList::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == b"configuration" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"configuration"])),
+                _ => {}
+            }
+        }
+        Ok(configuration)
+    }
+}
+
+impl Parseable for Resource {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let mut resource =
+            Resource { name: get_attributes(tag.attributes())?.get_or_err("name")?, ..Default::default() };
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"task" => {
+                    resource.tasks.push(Task::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"pouInstance" => {
+                    resource.pou_instances.push(PouInstance::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"globalVars" => {
+                    resource.global_vars.push(VarList::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == b"resource" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"resource"])),
+                _ => {}
+            }
+        }
+        Ok(resource)
+    }
+}
+
+impl Parseable for Task {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let mut attributes = get_attributes(tag.attributes())?;
+        let mut task = Task {
+            name: attributes.get_or_err("name")?,
+            interval: attributes.remove("interval"),
+            priority: attributes.remove("priority"),
+            pou_instances: vec![],
+        };
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"pouInstance" => {
+                    task.pou_instances.push(PouInstance::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == b"task" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"task"])),
+                _ => {}
+            }
+        }
+        Ok(task)
+    }
+}
+
+impl Parseable for PouInstance {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let attributes = get_attributes(tag.attributes())?;
+        reader.skip_element(&tag)?;
+        Ok(PouInstance {
+            name: attributes.get_or_err("name")?,
+            type_name: attributes.get_or_err("typeName")?,
+        })
+    }
+}
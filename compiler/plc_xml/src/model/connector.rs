@@ -53,6 +53,7 @@ impl<'xml> Parseable for Connector<'xml> {
                     attributes.extend(get_attributes(tag.attributes())?)
                 }
                 Event::End(tag) if matches!(tag.name().as_ref(), b"connector" | b"continuation") => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"connector", b"continuation"])),
                 _ => {}
             }
         }
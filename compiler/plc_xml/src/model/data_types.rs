@@ -0,0 +1,304 @@
+use std::ops::Range;
+
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{
+    error::Error,
+    extensions::{GetOrErr, TryToString},
+    reader::Reader,
+    xml_parser::{get_attributes, Parseable},
+};
+
+use super::var_list::VarDeclaration;
+
+/// A user defined type, declared in the `<dataTypes>` element of a project
+#[derive(Debug)]
+pub(crate) struct UserType {
+    pub name: String,
+    pub base_type: TypeDefinition,
+    pub initial_value: Option<Value>,
+    pub range: Range<usize>,
+}
+
+impl Parseable for UserType {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let range = reader.tag_range(&tag);
+        let name = get_attributes(tag.attributes())?.get_or_err("name")?;
+
+        let mut base_type = None;
+        let mut initial_value = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"baseType" => {
+                    base_type = Some(TypeDefinition::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"initialValue" => {
+                    initial_value = Some(Value::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == b"dataType" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"dataType"])),
+                _ => {}
+            }
+        }
+
+        let base_type = base_type.ok_or_else(|| Error::MissingAttribute("baseType".to_string()))?;
+        Ok(UserType { name, base_type, initial_value, range })
+    }
+}
+
+/// The type of a variable or user type, e.g. `<type><INT/></type>` or `<baseType><array>...</array></baseType>`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TypeDefinition {
+    /// an elementary type like `INT` or a reference to another type
+    Reference(String),
+    String {
+        is_wide: bool,
+        length: Option<String>,
+    },
+    Array {
+        /// the lower and upper bound of each dimension
+        dimensions: Vec<(String, String)>,
+        base_type: Box<TypeDefinition>,
+    },
+    Pointer(Box<TypeDefinition>),
+    Struct(Vec<VarDeclaration>),
+    Union(Vec<VarDeclaration>),
+    Enum {
+        /// the name and optional value of each element
+        values: Vec<(String, Option<String>)>,
+        base_type: Option<Box<TypeDefinition>>,
+    },
+    SubRange {
+        lower: String,
+        upper: String,
+        base_type: Box<TypeDefinition>,
+    },
+}
+
+impl Parseable for TypeDefinition {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let end = tag.name().as_ref().to_vec();
+
+        let mut definition = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if definition.is_none() => {
+                    definition = Some(TypeDefinition::visit_definition(reader, tag)?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == end => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"type"])),
+                _ => {}
+            }
+        }
+
+        definition.ok_or_else(|| Error::UnexpectedElement(String::from_utf8_lossy(&end).to_string()))
+    }
+}
+
+impl TypeDefinition {
+    /// reads the element defining the type, e.g. `<derived name="MyStruct"/>`
+    fn visit_definition(reader: &mut Reader, tag: BytesStart) -> Result<Self, Error> {
+        let attributes = get_attributes(tag.attributes())?;
+        let definition = match tag.name().as_ref() {
+            b"derived" => TypeDefinition::Reference(attributes.get_or_err("name")?),
+            b"string" | b"wstring" => TypeDefinition::String {
+                is_wide: tag.name().as_ref() == b"wstring",
+                length: attributes.get("length").cloned(),
+            },
+            b"array" => {
+                let mut dimensions = vec![];
+                let mut base_type = None;
+                loop {
+                    match reader.read_event()? {
+                        Event::Start(tag) if tag.name().as_ref() == b"dimension" => {
+                            let attributes = get_attributes(tag.attributes())?;
+                            dimensions
+                                .push((attributes.get_or_err("lower")?, attributes.get_or_err("upper")?));
+                        }
+                        Event::Start(tag) if tag.name().as_ref() == b"baseType" => {
+                            base_type = Some(TypeDefinition::visit(reader, Some(tag))?)
+                        }
+                        Event::End(tag) if tag.name().as_ref() == b"array" => break,
+                        Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"array"])),
+                        _ => {}
+                    }
+                }
+                let base_type = base_type.ok_or_else(|| Error::MissingAttribute("baseType".to_string()))?;
+                TypeDefinition::Array { dimensions, base_type: Box::new(base_type) }
+            }
+            b"pointer" => {
+                let mut base_type = None;
+                loop {
+                    match reader.read_event()? {
+                        Event::Start(tag) if tag.name().as_ref() == b"baseType" => {
+                            base_type = Some(TypeDefinition::visit(reader, Some(tag))?)
+                        }
+                        Event::End(tag) if tag.name().as_ref() == b"pointer" => break,
+                        Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"pointer"])),
+                        _ => {}
+                    }
+                }
+                let base_type = base_type.ok_or_else(|| Error::MissingAttribute("baseType".to_string()))?;
+                TypeDefinition::Pointer(Box::new(base_type))
+            }
+            b"struct" | b"union" => {
+                let mut variables = vec![];
+                loop {
+                    match reader.read_event()? {
+                        Event::Start(tag) if tag.name().as_ref() == b"variable" => {
+                            variables.push(VarDeclaration::visit(reader, Some(tag))?)
+                        }
+                        Event::Start(tag) => reader.skip_element(&tag)?,
+                        Event::End(end) if end.name() == tag.name() => break,
+                        Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"struct", b"union"])),
+                        _ => {}
+                    }
+                }
+                if tag.name().as_ref() == b"union" {
+                    TypeDefinition::Union(variables)
+                } else {
+                    TypeDefinition::Struct(variables)
+                }
+            }
+            b"enum" => {
+                let mut values = vec![];
+                let mut base_type = None;
+                loop {
+                    match reader.read_event()? {
+                        Event::Start(tag) if tag.name().as_ref() == b"value" => {
+                            let mut attributes = get_attributes(tag.attributes())?;
+                            values.push((attributes.get_or_err("name")?, attributes.remove("value")));
+                        }
+                        Event::Start(tag) if tag.name().as_ref() == b"baseType" => {
+                            base_type = Some(Box::new(TypeDefinition::visit(reader, Some(tag))?))
+                        }
+                        Event::End(tag) if tag.name().as_ref() == b"enum" => break,
+                        Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"enum"])),
+                        _ => {}
+                    }
+                }
+                TypeDefinition::Enum { values, base_type }
+            }
+            b"subrangeSigned" | b"subrangeUnsigned" => {
+                let end = tag.name().as_ref().to_vec();
+                let mut range = None;
+                let mut base_type = None;
+                loop {
+                    match reader.read_event()? {
+                        Event::Start(tag) if tag.name().as_ref() == b"range" => {
+                            let attributes = get_attributes(tag.attributes())?;
+                            range = Some((attributes.get_or_err("lower")?, attributes.get_or_err("upper")?));
+                        }
+                        Event::Start(tag) if tag.name().as_ref() == b"baseType" => {
+                            base_type = Some(TypeDefinition::visit(reader, Some(tag))?)
+                        }
+                        Event::End(tag) if tag.name().as_ref() == end => break,
+                        Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"subrangeSigned"])),
+                        _ => {}
+                    }
+                }
+                let (lower, upper) = range.ok_or_else(|| Error::MissingAttribute("range".to_string()))?;
+                let base_type = base_type.ok_or_else(|| Error::MissingAttribute("baseType".to_string()))?;
+                TypeDefinition::SubRange { lower, upper, base_type: Box::new(base_type) }
+            }
+            // elementary types like `<INT/>`
+            _ => {
+                reader.skip_element(&tag)?;
+                TypeDefinition::Reference(tag.name().try_to_string()?)
+            }
+        };
+        Ok(definition)
+    }
+}
+
+/// The initial value of a variable or user type
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    /// a value in ST syntax, e.g. `<simpleValue value="16#FF"/>`
+    Simple(String),
+    /// the elements of an array with their optional repetition count
+    Array(Vec<(Option<String>, Value)>),
+    /// the values of the struct's members
+    Struct(Vec<(String, Value)>),
+}
+
+impl Parseable for Value {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let end = tag.name().as_ref().to_vec();
+
+        let mut value = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"simpleValue" => {
+                    value = Some(Value::Simple(get_attributes(tag.attributes())?.get_or_err("value")?));
+                    reader.skip_element(&tag)?;
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"arrayValue" => {
+                    let mut elements = vec![];
+                    loop {
+                        match reader.read_event()? {
+                            Event::Start(tag) if tag.name().as_ref() == b"value" => {
+                                let repetition = get_attributes(tag.attributes())?.remove("repetitionValue");
+                                elements.push((repetition, Value::visit(reader, Some(tag))?));
+                            }
+                            Event::End(tag) if tag.name().as_ref() == b"arrayValue" => break,
+                            Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"arrayValue"])),
+                            _ => {}
+                        }
+                    }
+                    value = Some(Value::Array(elements));
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"structValue" => {
+                    let mut members = vec![];
+                    loop {
+                        match reader.read_event()? {
+                            Event::Start(tag) if tag.name().as_ref() == b"value" => {
+                                let member = get_attributes(tag.attributes())?.get_or_err("member")?;
+                                members.push((member, Value::visit(reader, Some(tag))?));
+                            }
+                            Event::End(tag) if tag.name().as_ref() == b"structValue" => break,
+                            Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"structValue"])),
+                            _ => {}
+                        }
+                    }
+                    value = Some(Value::Struct(members));
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == end => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"initialValue"])),
+                _ => {}
+            }
+        }
+
+        value.ok_or_else(|| Error::MissingAttribute("value".to_string()))
+    }
+}
+
+impl std::fmt::Display for Value {
+    /// formats the value as an ST expression
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Simple(value) => write!(f, "{value}"),
+            Value::Array(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|(repetition, value)| match repetition {
+                        Some(repetition) => format!("{repetition}({value})"),
+                        None => value.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Struct(members) => {
+                let members =
+                    members.iter().map(|(member, value)| format!("{member} := {value}")).collect::<Vec<_>>();
+                write!(f, "({})", members.join(", "))
+            }
+        }
+    }
+}
@@ -1,8 +1,13 @@
 use indexmap::{IndexMap, IndexSet};
 use plc_diagnostics::diagnostics::Diagnostic;
-use plc_source::source_location::SourceLocationFactory;
+use plc_source::source_location::{BlockOrigin, SourceLocationFactory};
 use quick_xml::events::{BytesStart, Event};
-use std::{cmp::Ordering, collections::HashMap, hash::Hash};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    hash::Hash,
+};
 
 use crate::{error::Error, reader::Reader, xml_parser::Parseable};
 
@@ -10,7 +15,7 @@ use super::{
     block::Block,
     connector::{Connector, ConnectorKind},
     control::Control,
-    variables::FunctionBlockVariable,
+    variables::{FunctionBlockVariable, VariableKind},
 };
 
 /// Represent either a `localId` or `refLocalId`
@@ -20,15 +25,65 @@ pub(crate) type NodeIndex<'xml> = IndexMap<NodeId, Node<'xml>>;
 #[derive(Debug, Default)]
 pub(crate) struct FunctionBlockDiagram<'xml> {
     pub nodes: NodeIndex<'xml>,
+    pub feedback_variables: Vec<FeedbackVariable>,
+    /// the name, position and xml range of each node, used to map generated code back to the diagram
+    pub origins: BTreeMap<NodeId, BlockOrigin>,
+}
+
+/// An implicit state variable holding the output of a block which is part of a feedback loop, i.e. whose output is
+/// (indirectly) wired back into one of its inputs. Nodes executed before the block read the previous cycle's output.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct FeedbackVariable {
+    pub name: String,
+    /// the block whose output is stored in the variable
+    pub local_id: NodeId,
+    /// the function called by the block, its return type is the variable's type
+    pub type_name: String,
 }
 
 impl<'xml> FunctionBlockDiagram<'xml> {
+    /// Validates the execution order of the diagram and resolves its connection points and feedback connections.
+    /// The names of implicit feedback variables are prefixed with the given scope, i.e. the name of the pou or
+    /// action the diagram belongs to.
     pub(crate) fn desugar(
         &mut self,
+        scope: &str,
         source_location_factory: &SourceLocationFactory,
     ) -> Result<(), Vec<Diagnostic>> {
-        self.nodes.desugar_connection_points(source_location_factory)?;
-        Ok(())
+        let source_location_factory = &self.get_source_location_factory(source_location_factory);
+        let mut diagnostics = self.nodes.validate_execution_order(source_location_factory);
+        if let Err(errors) = self.nodes.desugar_connection_points(source_location_factory) {
+            diagnostics.extend(errors);
+        }
+
+        let (feedback_variables, errors) =
+            self.nodes.desugar_feedback_connections(scope, source_location_factory);
+        // the implicit nodes of a feedback variable originate from the block it stores
+        for (id, node) in &self.nodes {
+            let Node::FunctionBlockVariable(var) = node else { continue };
+            let Some(source) = feedback_variables.iter().find(|it| it.name == var.expression) else {
+                continue;
+            };
+            if let Some(origin) = self.origins.get(&source.local_id).cloned() {
+                self.origins.insert(*id, origin);
+            }
+        }
+        self.feedback_variables = feedback_variables;
+        diagnostics.extend(errors);
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    /// returns a factory whose block locations carry the origins of this diagram's nodes
+    pub(crate) fn get_source_location_factory(
+        &self,
+        factory: &SourceLocationFactory,
+    ) -> SourceLocationFactory {
+        factory.with_block_origins(self.origins.clone())
     }
 }
 
@@ -109,44 +164,75 @@ impl<'xml> Node<'xml> {
     fn is_connector(&self) -> bool {
         matches!(self, Node::Connector(_))
     }
+
+    /// Returns true for nodes which are only executed if they have an execution order, i.e. nodes whose
+    /// result is not consumed by other nodes
+    fn is_sink(&self) -> bool {
+        match self {
+            Node::FunctionBlockVariable(var) => var.kind != VariableKind::Input,
+            Node::Control(_) => true,
+            _ => false,
+        }
+    }
+
+    fn get_kind_name(&self) -> &'static str {
+        match self {
+            Node::Block(_) => "Block",
+            Node::FunctionBlockVariable(_) => "Variable",
+            Node::Control(_) => "Control",
+            Node::Connector(_) => "Connector",
+        }
+    }
 }
 
 impl<'xml> Parseable for FunctionBlockDiagram<'xml> {
     fn visit(reader: &mut Reader, _tag: Option<BytesStart>) -> Result<Self, Error> {
         let mut nodes = IndexMap::new();
+        let mut origins = BTreeMap::new();
 
         loop {
             match reader.read_event().map_err(Error::ReadEvent)? {
-                Event::Start(tag) => match tag.name().as_ref() {
-                    b"block" => {
-                        let node = Block::visit(reader, Some(tag))?;
-                        nodes.insert(node.local_id, Node::Block(node));
-                    }
-                    b"jump" | b"label" | b"return" => {
-                        let node = Control::visit(reader, Some(tag))?;
-                        nodes.insert(node.local_id, Node::Control(node));
-                    }
-                    b"inVariable" | b"outVariable" => {
-                        let node = FunctionBlockVariable::visit(reader, Some(tag))?;
-                        nodes.insert(node.local_id, Node::FunctionBlockVariable(node));
-                    }
-                    b"continuation" | b"connector" => {
-                        let node = Connector::visit(reader, Some(tag))?;
-                        nodes.insert(node.local_id, Node::Connector(node));
-                    }
-                    _ => {}
-                },
+                Event::Start(tag) => {
+                    let start = reader.tag_range(&tag).start;
+                    let element = String::from_utf8_lossy(tag.name().as_ref()).to_string();
+                    let node = match tag.name().as_ref() {
+                        b"block" => Node::Block(Block::visit(reader, Some(tag))?),
+                        b"jump" | b"label" | b"return" => Node::Control(Control::visit(reader, Some(tag))?),
+                        b"inVariable" | b"outVariable" => {
+                            Node::FunctionBlockVariable(FunctionBlockVariable::visit(reader, Some(tag))?)
+                        }
+                        b"continuation" | b"connector" => {
+                            Node::Connector(Connector::visit(reader, Some(tag))?)
+                        }
+                        _ => continue,
+                    };
+
+                    let range = start..reader.buffer_position();
+                    let name = match &node {
+                        Node::Block(block) => block.type_name.to_string(),
+                        _ => element,
+                    };
+                    let origin = BlockOrigin {
+                        name,
+                        position: reader.get_position(range.clone()),
+                        line: reader.get_line(range.start),
+                        range,
+                    };
+                    origins.insert(node.get_id(), origin);
+                    nodes.insert(node.get_id(), node);
+                }
 
                 Event::End(tag) if tag.name().as_ref() == b"FBD" => {
                     break;
                 }
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"FBD"])),
                 _ => {}
             }
         }
 
         nodes.sort_by(|_, b, _, d| b.partial_cmp(d).unwrap()); // This _shouldn't_ panic because our `partial_cmp` method covers all cases
 
-        Ok(FunctionBlockDiagram { nodes })
+        Ok(FunctionBlockDiagram { nodes, origins, ..Default::default() })
     }
 }
 
@@ -292,6 +378,172 @@ impl<'xml> ConnectionResolver<'xml> for NodeIndex<'xml> {
     }
 }
 
+// IndexMap<NodeId, Node> interface for execution order validation and feedback connection desugaring
+trait ExecutionOrder<'xml> {
+    fn validate_execution_order(&self, source_location_factory: &SourceLocationFactory) -> Vec<Diagnostic>;
+    fn desugar_feedback_connections(
+        &mut self,
+        scope: &str,
+        source_location_factory: &SourceLocationFactory,
+    ) -> (Vec<FeedbackVariable>, Vec<Diagnostic>);
+    fn is_part_of_cycle(&self, id: NodeId) -> bool;
+}
+
+impl<'xml> ExecutionOrder<'xml> for NodeIndex<'xml> {
+    /// Reports nodes sharing the same execution order and sinks without one, since the latter are never executed
+    fn validate_execution_order(&self, source_location_factory: &SourceLocationFactory) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut execution_order: HashMap<NodeId, NodeId> = HashMap::new();
+        for (id, node) in self.iter() {
+            let location = source_location_factory.create_block_location(*id, node.get_exec_id());
+            match node.get_exec_id() {
+                Some(exec_id) => {
+                    if let Some(other) = execution_order.insert(exec_id, *id) {
+                        diagnostics.push(
+                            Diagnostic::error(format!(
+                                "{} {id} has the same execution order {exec_id} as node {other}",
+                                node.get_kind_name()
+                            ))
+                            .with_error_code("E098")
+                            .with_location(location)
+                            .with_secondary_location(
+                                source_location_factory.create_block_location(other, Some(exec_id)),
+                            ),
+                        );
+                    }
+                }
+                None if node.is_sink() => diagnostics.push(
+                    Diagnostic::warning(format!(
+                        "{} {id} has no execution order and will not be executed",
+                        node.get_kind_name()
+                    ))
+                    .with_error_code("E098")
+                    .with_location(location),
+                ),
+                None => {}
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Breaks up all cycles in the diagram by storing the output of each block of a cycle in an implicit feedback
+    /// variable right after the block was executed. All nodes consuming the block's output read the variable
+    /// instead, so nodes executed before the block see the output of the previous cycle.
+    ///
+    /// ```st
+    /// // the output of B is fed back into A, which is executed first
+    /// ┏━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━┓
+    /// ┗━━> A(0) ━━━━> B(1) ━┳━━> OUT   ┃
+    ///                       ┗━━━━━━━━━━┛
+    /// // resolves to
+    /// FEEDBACK_B ━━━━> A(0) ━━━━> FEEDBACK_A
+    /// FEEDBACK_A ━━━━> B(1) ━━━━> FEEDBACK_B
+    /// FEEDBACK_B ━━━━> OUT
+    /// ```
+    fn desugar_feedback_connections(
+        &mut self,
+        scope: &str,
+        source_location_factory: &SourceLocationFactory,
+    ) -> (Vec<FeedbackVariable>, Vec<Diagnostic>) {
+        let mut feedback_variables = vec![];
+        let mut diagnostics = vec![];
+        let mut next_id = self.keys().max().copied().unwrap_or_default();
+
+        let cycles = self.keys().copied().filter(|id| self.is_part_of_cycle(*id)).collect::<Vec<_>>();
+        for source in cycles {
+            let (type_name, execution_order_id) = match self.get(&source) {
+                Some(Node::Block(block)) if block.execution_order_id.is_some() => {
+                    (block.type_name.to_string(), block.execution_order_id)
+                }
+                Some(node) => {
+                    // the cycle is cut without storing the value to avoid endless recursions
+                    diagnostics.push(
+                        Diagnostic::error(format!(
+                            "{} {source} is part of a feedback loop, only blocks with an execution order can be fed back",
+                            node.get_kind_name()
+                        ))
+                        .with_error_code("E099")
+                        .with_location(source_location_factory.create_block_location(source, node.get_exec_id())),
+                    );
+                    (String::new(), None)
+                }
+                None => continue,
+            };
+
+            next_id += 1;
+            let input_id = next_id;
+            let name = format!("__{scope}_feedback_{source}");
+
+            // all consumers read the feedback variable instead of the block's output
+            for node in self.values_mut() {
+                for (position, ref_id) in node.get_ref_ids().into_iter().enumerate() {
+                    if ref_id != Some(source) {
+                        continue;
+                    }
+
+                    node.set_ref_id(position, execution_order_id.map(|_| input_id));
+                }
+            }
+
+            if execution_order_id.is_none() {
+                continue;
+            }
+
+            self.insert(
+                input_id,
+                Node::FunctionBlockVariable(FunctionBlockVariable {
+                    kind: VariableKind::Input,
+                    local_id: input_id,
+                    negated: false,
+                    expression: Cow::from(name.clone()),
+                    execution_order_id: None,
+                    ref_local_id: None,
+                }),
+            );
+
+            // store the block's output right after it was executed
+            next_id += 1;
+            self.insert(
+                next_id,
+                Node::FunctionBlockVariable(FunctionBlockVariable {
+                    kind: VariableKind::Output,
+                    local_id: next_id,
+                    negated: false,
+                    expression: Cow::from(name.clone()),
+                    execution_order_id,
+                    ref_local_id: Some(source),
+                }),
+            );
+
+            feedback_variables.push(FeedbackVariable { name, local_id: source, type_name });
+        }
+
+        // the stable sort keeps the assignments of the feedback variables right after their blocks
+        self.sort_by(|_, b, _, d| b.partial_cmp(d).unwrap());
+
+        (feedback_variables, diagnostics)
+    }
+
+    /// Returns true if the given node (indirectly) consumes its own output
+    fn is_part_of_cycle(&self, id: NodeId) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = self.get(&id).map(Node::get_ref_ids).unwrap_or_default();
+        while let Some(current) = pending.pop() {
+            let Some(current) = current else { continue };
+            if current == id {
+                return true;
+            }
+
+            if visited.insert(current) {
+                pending.extend(self.get(&current).map(Node::get_ref_ids).unwrap_or_default());
+            }
+        }
+
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::serializer::{SBlock, SInVariable, SOutVariable, SVariable, YFbd};
@@ -361,6 +613,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -420,6 +673,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -500,6 +754,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -579,6 +834,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -628,6 +884,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -677,6 +934,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -758,6 +1016,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -818,6 +1077,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
@@ -921,6 +1181,7 @@ mod tests {
                 ),
             ]
             .into(),
+            ..Default::default()
         };
         pou.body.function_block_diagram = fbd;
         model.pous.push(pou);
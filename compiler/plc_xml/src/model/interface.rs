@@ -6,14 +6,18 @@ use crate::error::Error;
 use crate::reader::Reader;
 use crate::xml_parser::Parseable;
 
-use super::pou::PouType;
+use super::{
+    data_types::TypeDefinition,
+    pou::PouType,
+    var_list::{VarList, VarListKind},
+};
 
 #[derive(Debug)]
 pub(crate) struct Interface {
-    //TODO: return type, variables, etc..
-    // we only care about the addData field for now, as it contains our text-declaration
-
-    // the addData field is be application-specific and can be implemented/extended as needed
+    pub return_type: Option<TypeDefinition>,
+    pub var_lists: Vec<VarList>,
+    // the addData field is be application-specific and can be implemented/extended as needed.
+    // if present, its text-declaration takes precedence over the declared variables
     add_data: Option<Data>,
 }
 
@@ -21,7 +25,7 @@ pub(crate) struct Interface {
 // so it can be easily modified/extended for other purposes
 impl Interface {
     pub fn new(content: &str) -> Self {
-        Interface { add_data: Some(Data::new_implementation(content)) }
+        Interface { return_type: None, var_lists: vec![], add_data: Some(Data::new_implementation(content)) }
     }
 
     pub fn get_data_content(&self) -> Option<&str> {
@@ -39,16 +43,25 @@ impl Interface {
 
         Interface {
             add_data: Some(Data::new_implementation(&format!("{}\nEND_{}", old_data.content, pou_type))),
+            ..self
         }
     }
 }
 
 impl Parseable for Interface {
     fn visit(reader: &mut Reader, _tag: Option<BytesStart>) -> Result<Self, Error> {
-        let mut interface = Interface { add_data: None };
+        let mut interface = Interface { return_type: None, var_lists: vec![], add_data: None };
         loop {
             match reader.read_event().map_err(Error::ReadEvent)? {
+                Event::Start(tag) if tag.name().as_ref() == b"returnType" => {
+                    interface.return_type = Some(TypeDefinition::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if VarListKind::try_from(tag.name().as_ref()).is_ok() => {
+                    interface.var_lists.push(VarList::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"documentation" => reader.skip_element(&tag)?,
                 Event::End(tag) if tag.name().as_ref() == b"interface" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"interface"])),
                 Event::Text(text) => {
                     interface.add_data =
                         Some(Data::new_implementation(text.unescape().map_err(Error::ReadEvent)?.borrow()))
@@ -36,7 +36,25 @@ impl<'xml> Pou<'xml> {
         &mut self,
         source_location_factory: &plc_source::source_location::SourceLocationFactory,
     ) -> Result<(), Vec<Diagnostic>> {
-        self.body.function_block_diagram.desugar(source_location_factory)
+        let mut diagnostics = vec![];
+        let _ = self
+            .body
+            .function_block_diagram
+            .desugar(&self.name, source_location_factory)
+            .map_err(|e| diagnostics.extend(e));
+        for action in self.actions.iter_mut() {
+            let _ = action
+                .body
+                .function_block_diagram
+                .desugar(&action.name, source_location_factory)
+                .map_err(|e| diagnostics.extend(e));
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
     }
 }
 
@@ -1,30 +1,45 @@
 use plc_diagnostics::diagnostics::Diagnostic;
+use quick_xml::events::{BytesStart, Event};
 
-use crate::{reader::Reader, xml_parser::Parseable};
+use crate::{error::Error, reader::Reader, xml_parser::Parseable};
 
-use super::pou::Pou;
+use super::{configuration::Configuration, data_types::UserType, pou::Pou};
 
 /// The Project root as specified in the official XSD
 #[derive(Debug, Default)]
 pub(crate) struct Project<'xml> {
     pub pous: Vec<Pou<'xml>>,
+    pub data_types: Vec<UserType>,
+    pub configurations: Vec<Configuration>,
     /*
     attributes,
-    dataTypes,
     fileHeader,
     contentHeader,
-    instances,
     addData,
     documentation
     */
 }
 
 impl Parseable for Project<'_> {
-    fn visit(
-        _reader: &mut Reader,
-        _tag: Option<quick_xml::events::BytesStart>,
-    ) -> Result<Self, crate::error::Error> {
-        unimplemented!()
+    fn visit(reader: &mut Reader, _tag: Option<BytesStart>) -> Result<Self, Error> {
+        let mut project = Project::default();
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"dataType" => {
+                    project.data_types.push(UserType::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"pou" => {
+                    project.pous.push(Pou::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"configuration" => {
+                    project.configurations.push(Configuration::visit(reader, Some(tag))?)
+                }
+                Event::End(tag) if tag.name().as_ref() == b"project" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"project"])),
+                _ => {}
+            }
+        }
+        Ok(project)
     }
 }
 
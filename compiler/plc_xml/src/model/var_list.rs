@@ -0,0 +1,126 @@
+use std::ops::Range;
+
+use quick_xml::events::{BytesStart, Event};
+
+use crate::{
+    error::Error,
+    extensions::{GetOrErr, TryToString},
+    reader::Reader,
+    xml_parser::{get_attributes, Parseable},
+};
+
+use super::data_types::{TypeDefinition, Value};
+
+/// A list of variable declarations, e.g. `<globalVars>` or `<inputVars>`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VarList {
+    pub kind: VarListKind,
+    pub constant: bool,
+    pub retain: bool,
+    pub persistent: bool,
+    pub variables: Vec<VarDeclaration>,
+    pub range: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VarListKind {
+    Global,
+    Input,
+    Output,
+    InOut,
+    Local,
+    Temp,
+    External,
+}
+
+impl TryFrom<&[u8]> for VarListKind {
+    type Error = Error;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        match value {
+            b"globalVars" => Ok(VarListKind::Global),
+            b"inputVars" => Ok(VarListKind::Input),
+            b"outputVars" => Ok(VarListKind::Output),
+            b"inOutVars" => Ok(VarListKind::InOut),
+            b"localVars" => Ok(VarListKind::Local),
+            b"tempVars" => Ok(VarListKind::Temp),
+            b"externalVars" => Ok(VarListKind::External),
+            _ => Err(Error::UnexpectedElement(value.try_to_string()?)),
+        }
+    }
+}
+
+impl Parseable for VarList {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let range = reader.tag_range(&tag);
+        let kind = VarListKind::try_from(tag.name().as_ref())?;
+        let attributes = get_attributes(tag.attributes())?;
+        let is_set = |key: &str| attributes.get(key).is_some_and(|it| it == "true");
+
+        let mut variables = vec![];
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"variable" => {
+                    variables.push(VarDeclaration::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(end) if end.name() == tag.name() => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"variable"])),
+                _ => {}
+            }
+        }
+
+        Ok(VarList {
+            kind,
+            constant: is_set("constant"),
+            retain: is_set("retain"),
+            persistent: is_set("persistent"),
+            variables,
+            range,
+        })
+    }
+}
+
+/// A single variable, e.g. `<variable name="x" address="%IX1.0"><type><BOOL/></type></variable>`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VarDeclaration {
+    pub name: String,
+    pub address: Option<String>,
+    pub data_type: TypeDefinition,
+    pub initial_value: Option<Value>,
+    pub range: Range<usize>,
+}
+
+impl Parseable for VarDeclaration {
+    fn visit(reader: &mut Reader, tag: Option<BytesStart>) -> Result<Self, Error> {
+        let Some(tag) = tag else { unreachable!() };
+        let range = reader.tag_range(&tag);
+        let mut attributes = get_attributes(tag.attributes())?;
+
+        let mut data_type = None;
+        let mut initial_value = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == b"type" => {
+                    data_type = Some(TypeDefinition::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) if tag.name().as_ref() == b"initialValue" => {
+                    initial_value = Some(Value::visit(reader, Some(tag))?)
+                }
+                Event::Start(tag) => reader.skip_element(&tag)?,
+                Event::End(tag) if tag.name().as_ref() == b"variable" => break,
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"variable"])),
+                _ => {}
+            }
+        }
+
+        Ok(VarDeclaration {
+            name: attributes.get_or_err("name")?,
+            address: attributes.remove("address"),
+            data_type: data_type.ok_or_else(|| Error::MissingAttribute("type".to_string()))?,
+            initial_value,
+            range,
+        })
+    }
+}
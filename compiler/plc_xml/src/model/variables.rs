@@ -154,6 +154,8 @@ impl<'xml> Parseable for FunctionBlockVariable<'xml> {
                     _ => {}
                 },
 
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"inVariable", b"outVariable"])),
+
                 _ => {}
             }
         }
@@ -211,6 +213,8 @@ impl Parseable for BlockVariable {
                     break;
                 }
 
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![b"variable"])),
+
                 _ => {}
             }
         }
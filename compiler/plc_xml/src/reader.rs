@@ -1,6 +1,12 @@
-use std::ops::{Deref, DerefMut};
+use std::ops::{Deref, DerefMut, Range};
 
-pub struct Reader<'xml>(quick_xml::Reader<&'xml [u8]>);
+use quick_xml::events::{BytesStart, Event};
+
+use crate::error::Error;
+
+/// Wrapper around [`quick_xml::Reader`], keeping track of the source content and the position of the last
+/// event read, so that errors can be located within the XML file
+pub struct Reader<'xml>(quick_xml::Reader<&'xml [u8]>, &'xml str, usize);
 impl<'xml> Deref for Reader<'xml> {
     type Target = quick_xml::Reader<&'xml [u8]>;
     fn deref(&self) -> &Self::Target {
@@ -18,7 +24,89 @@ impl<'xml> Reader<'xml> {
     pub fn new(content: &'xml str) -> Self {
         let mut reader = quick_xml::Reader::from_str(content);
         reader.expand_empty_elements(true).trim_text(true);
-        Reader(reader)
+        Reader(reader, content, 0)
+    }
+
+    /// reads the next event, remembering where it started
+    pub fn read_event(&mut self) -> quick_xml::Result<Event<'xml>> {
+        self.2 = self.buffer_position();
+        self.0.read_event()
+    }
+
+    /// returns the range of the last event read (or of the content that failed to be read), without the
+    /// whitespace preceding it
+    pub fn last_event_range(&self) -> Range<usize> {
+        let end = self.buffer_position().min(self.1.len()).max(self.2);
+        let whitespace =
+            self.1.as_bytes()[self.2..end].iter().take_while(|it| it.is_ascii_whitespace()).count();
+        (self.2 + whitespace)..end
+    }
+
+    /// returns the range of the given start tag within the xml content, the tag has to be the last
+    /// element read
+    pub fn tag_range(&self, tag: &BytesStart) -> Range<usize> {
+        let end = self.buffer_position();
+        // the tag's content does not include the surrounding `<` and `>`
+        end.saturating_sub(tag.len() + 2)..end
+    }
+
+    /// returns the offset of the given raw text (e.g. of a text or CDATA event) within the xml content, the
+    /// text has to be the last element read
+    pub fn text_offset(&self, text: &[u8]) -> usize {
+        let end = self.buffer_position();
+        // the reader trims whitespace around texts, so the text ends somewhere before the current position
+        self.1.as_bytes()[..end]
+            .windows(text.len().max(1))
+            .rposition(|it| it == text)
+            .unwrap_or_else(|| end.saturating_sub(text.len()))
+    }
+
+    /// returns the `<position x y>` of the element spanning the given range, coordinates are rounded to
+    /// whole numbers. only direct children of the element are considered, e.g. not the positions of its
+    /// connection points
+    pub fn get_position(&self, range: Range<usize>) -> Option<(i32, i32)> {
+        let mut reader = quick_xml::Reader::from_str(self.1.get(range)?);
+        reader.expand_empty_elements(true).trim_text(true);
+        let mut depth = 0;
+        loop {
+            match reader.read_event().ok()? {
+                Event::Start(tag) if depth == 1 && tag.name().as_ref() == b"position" => {
+                    let coordinate = |name: &[u8]| -> Option<i32> {
+                        let value = tag.try_get_attribute(name).ok()??.unescape_value().ok()?;
+                        value.trim().parse::<f64>().ok().map(|it| it.round() as i32)
+                    };
+                    return coordinate(b"x").zip(coordinate(b"y"));
+                }
+                Event::Start(_) => depth += 1,
+                Event::End(_) => depth -= 1,
+                Event::Eof => return None,
+                _ => {}
+            }
+        }
+    }
+
+    /// returns the zero-based line of the given offset within the xml content
+    pub fn get_line(&self, offset: usize) -> usize {
+        self.1.as_bytes()[..offset.min(self.1.len())].iter().filter(|it| **it == b'\n').count()
+    }
+
+    /// skips all content of the given element up to and including its end tag
+    pub fn skip_element(&mut self, tag: &BytesStart) -> Result<(), Error> {
+        let name = tag.name().as_ref().to_vec();
+        let mut depth = 0;
+        loop {
+            match self.read_event()? {
+                Event::Start(tag) if tag.name().as_ref() == name => depth += 1,
+                Event::End(tag) if tag.name().as_ref() == name => {
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    depth -= 1;
+                }
+                Event::Eof => return Err(Error::UnexpectedEndOfFile(vec![])),
+                _ => {}
+            }
+        }
     }
 }
 
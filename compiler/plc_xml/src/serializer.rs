@@ -1,6 +1,6 @@
 #![allow(clippy::new_without_default)]
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 
 #[derive(Clone)]
 pub struct Node {
@@ -9,73 +9,82 @@ pub struct Node {
 
     /// XML attributes, e.g. `<position x="1">` where `x` is the attribute
     ///
-    /// Design Note: We use a HashMap here to avoid duplicates but also update existing values in case of
+    /// Design Note: We use a map here to avoid duplicates but also update existing values in case of
     /// repeated function calls, e.g. `with_attribute("x", 1)` and `with_attribute("x", 2)` where the value of
-    /// x has been updated from 1 to 2.
-    attributes: HashMap<&'static str, &'static str>,
+    /// x has been updated from 1 to 2. The map keeps the insertion order, so serialized nodes are stable.
+    attributes: IndexMap<&'static str, String>,
 
     /// Indicates if an element has a closed form, e.g. `<position x="1" y="2"/>`
     closed: bool,
 
     /// Indicates if an element has some text wrapped inside itself, e.g. `<expression>a + b</expression>`
-    content: Option<&'static str>,
+    content: Option<String>,
 }
 
 pub trait IntoNode {
     fn inner(&self) -> Node;
 }
 
+impl IntoNode for Node {
+    fn inner(&self) -> Node {
+        self.clone()
+    }
+}
+
 impl Node {
-    fn new(name: &'static str) -> Self {
-        Self { name, attributes: HashMap::new(), children: Vec::new(), closed: false, content: None }
+    pub(crate) fn new(name: &'static str) -> Self {
+        Self { name, attributes: IndexMap::new(), children: Vec::new(), closed: false, content: None }
     }
 
-    fn attribute(mut self, key: &'static str, value: &'static str) -> Self {
-        self.attributes.insert(key, value);
+    /// Adds the given attribute, the value is written as is and has to be escaped already
+    pub(crate) fn attribute(mut self, key: &'static str, value: impl Into<String>) -> Self {
+        self.attributes.insert(key, value.into());
         self
     }
 
-    fn child(mut self, node: &dyn IntoNode) -> Self {
+    pub(crate) fn child(mut self, node: &dyn IntoNode) -> Self {
         self.children.push(node.inner());
         self
     }
 
-    fn children(mut self, nodes: Vec<&dyn IntoNode>) -> Self {
+    pub(crate) fn children(mut self, nodes: Vec<&dyn IntoNode>) -> Self {
         self.children.extend(nodes.into_iter().map(IntoNode::inner));
         self
     }
 
-    fn close(mut self) -> Self {
+    pub(crate) fn close(mut self) -> Self {
         self.closed = true;
         self
     }
 
-    fn indent(level: usize) -> String {
-        " ".repeat(level * 4)
+    /// Wraps the given text inside the element, the text is written as is and has to be escaped already
+    pub(crate) fn content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
     }
 
-    fn serialize_content(indent: &str, name: &'static str, content: &'static str) -> String {
-        format!("{indent}<{name}>{content}</{name}>\n")
+    fn indent(level: usize) -> String {
+        " ".repeat(level * 4)
     }
 
-    #[allow(unused_assignments)]
     pub fn serialize(&self, level: usize) -> String {
         let (name, indent) = (self.name, Node::indent(level));
-        let attributes = self.attributes.iter().map(|(key, value)| format!("{key}=\"{value}\""));
-        let attributes_str = attributes.collect::<Vec<_>>().join(" ");
-        let mut result = String::new();
+        let attributes = self.attributes.iter().fold(String::new(), |mut attributes, (key, value)| {
+            attributes.push_str(&format!(" {key}=\"{value}\""));
+            attributes
+        });
 
         if self.closed {
-            return format!("{indent}<{name} {attributes_str}/>\n");
+            return format!("{indent}<{name}{attributes}/>\n");
         }
 
-        if let Some(content) = self.content {
-            return Node::serialize_content(&indent, name, content);
+        if let Some(content) = &self.content {
+            return format!("{indent}<{name}{attributes}>{content}</{name}>\n");
         }
 
-        result = format!("{indent}<{name} {attributes_str}>\n");
-        self.children.iter().for_each(|child| result = format!("{result}{}", child.serialize(level + 1)));
-        result = format!("{result}{indent}</{name}>\n");
+        let mut result = format!("{indent}<{name}{attributes}>\n");
+        self.children.iter().for_each(|child| result.push_str(&child.serialize(level + 1)));
+        result.push_str(&format!("{indent}</{name}>\n"));
 
         result
     }
@@ -123,6 +132,10 @@ macro_rules! newtype_impl {
                 Self(self.inner().children(nodes))
             }
 
+            pub fn with_position(self, x: i32, y: i32) -> Self {
+                self.child(&SPosition::at(x, y))
+            }
+
             pub fn serialize(self) -> String {
                 self.inner().serialize(0)
             }
@@ -233,9 +246,8 @@ impl SReturn {
 }
 
 impl SContent {
-    pub fn with_declaration(mut self, content: &'static str) -> Self {
-        self.0.content = Some(content);
-        self
+    pub fn with_declaration(self, content: &'static str) -> Self {
+        Self(self.0.content(content))
     }
 }
 
@@ -291,6 +303,15 @@ impl SBlock {
     }
 }
 
+impl SPosition {
+    pub fn at(x: i32, y: i32) -> Self {
+        Self::new()
+            .attribute("x", Box::leak(x.to_string().into_boxed_str()))
+            .attribute("y", Box::leak(y.to_string().into_boxed_str()))
+            .close()
+    }
+}
+
 impl SBody {
     pub fn with_fbd(self, children: Vec<&dyn IntoNode>) -> Self {
         Self::new().child(&YFbd::new().children(children))
@@ -325,9 +346,7 @@ impl SVariable {
 
 impl SExpression {
     pub fn expression(expression: &'static str) -> Self {
-        let mut node = Self::new();
-        node.0.content = Some(expression);
-        node
+        Self(Self::new().0.content(expression))
     }
 }
 
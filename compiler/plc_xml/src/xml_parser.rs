@@ -1,11 +1,17 @@
 use std::collections::HashMap;
 
 use ast::{
-    ast::{AstId, AstNode, CompilationUnit, Implementation, LinkageType, PouType as AstPouType},
+    ast::{
+        AccessModifier, AstId, AstNode, CompilationUnit, DataTypeDeclaration, Implementation, LinkageType,
+        PouType as AstPouType, Variable, VariableBlock, VariableBlockType,
+    },
     provider::IdProvider,
 };
 use plc::{lexer, parser::expressions_parser::parse_expression};
-use plc_diagnostics::{diagnostician::Diagnostician, diagnostics::Diagnostic};
+use plc_diagnostics::{
+    diagnostician::Diagnostician,
+    diagnostics::{Diagnostic, Severity},
+};
 
 use plc_source::{
     source_location::{SourceLocation, SourceLocationFactory},
@@ -17,6 +23,7 @@ use crate::{
     error::Error,
     extensions::TryToString,
     model::{
+        fbd::FeedbackVariable,
         pou::{Pou, PouType},
         project::Project,
     },
@@ -25,11 +32,14 @@ use crate::{
 
 mod action;
 mod block;
+mod body;
 mod control;
+mod data_types;
 mod fbd;
 mod pou;
 #[cfg(test)]
 mod tests;
+mod var_list;
 mod variables;
 
 pub(crate) fn get_attributes(attributes: Attributes) -> Result<HashMap<String, String>, Error> {
@@ -47,19 +57,20 @@ where
 }
 
 pub(crate) fn visit(content: &str) -> Result<Project, Error> {
-    let mut reader = Reader::new(content);
-    reader.trim_text(true).expand_empty_elements(true);
+    read_project(&mut Reader::new(content))
+}
+
+fn read_project<'xml>(reader: &mut Reader) -> Result<Project<'xml>, Error> {
     let mut project = Project::default();
 
     loop {
         match reader.read_event()? {
             Event::Start(tag) if tag.name().as_ref() == b"pou" => {
-                project.pous.push(Pou::visit(&mut reader, Some(tag))?)
+                project.pous.push(Pou::visit(reader, Some(tag))?)
             }
-            Event::Start(tag) if tag.name().as_ref() == b"project" || tag.name().as_ref() == b"pous" => {
-                todo!("Project support comming in #977")
+            Event::Start(tag) if tag.name().as_ref() == b"project" => {
+                return Project::visit(reader, Some(tag))
             }
-            Event::End(tag) if tag.name().as_ref() == b"project" || tag.name().as_ref() == b"pous" => break,
             Event::Eof => break,
             _ => {}
         }
@@ -67,17 +78,19 @@ pub(crate) fn visit(content: &str) -> Result<Project, Error> {
     Ok(project)
 }
 
+/// parses the given source and reports its syntax errors, returns the unit together with the
+/// highest severity of the reported errors
 pub fn parse_file(
     source: &SourceCode,
     linkage: LinkageType,
     id_provider: IdProvider,
     diagnostician: &mut Diagnostician,
-) -> CompilationUnit {
+) -> (CompilationUnit, Severity) {
     let (unit, errors) = parse(source, linkage, id_provider);
     //Register the source file with the diagnostician
     diagnostician.register_file(source.get_location_str().to_string(), source.source.clone()); // TODO: Remove clone here, generally passing the GlobalContext instead of the actual source here or in the handle method should be sufficient
-    diagnostician.handle(&errors);
-    unit
+    let severity = diagnostician.handle(&errors);
+    (unit, severity)
 }
 
 fn parse(
@@ -88,9 +101,14 @@ fn parse(
     let source_location_factory = SourceLocationFactory::for_source(source);
     // Transform the xml file to a data model.
     // XXX: consecutive call-statements are nested in a single ast-statement. this will be broken up with temporary variables in the future
-    let mut project = match visit(&source.source) {
+    let mut reader = Reader::new(&source.source);
+    let mut project = match read_project(&mut reader) {
         Ok(project) => project,
-        Err(why) => todo!("cfc errors need to be transformed into diagnostics; {why:?}"),
+        Err(why) => {
+            // the model is incomplete, report the error at the element the reader stopped at
+            let location = source_location_factory.create_range(reader.last_event_range());
+            return (CompilationUnit::new(source.get_location_str()), vec![why.into_diagnostic(location)]);
+        }
     };
 
     let mut diagnostics = vec![];
@@ -99,11 +117,10 @@ fn parse(
     // Create a new parse session
     let parser =
         ParseSession::new(&project, source.get_location_str(), id_provider, linkage, source_location_factory);
-    // Parse the declaration data field
-    let Some((unit, declaration_diagnostics)) = parser.try_parse_declaration() else {
-        unimplemented!("XML schemas without text declarations are not yet supported")
-    };
+    // Parse the declarations of all pous, data types and configurations
+    let (mut unit, declaration_diagnostics) = parser.parse_declarations();
     diagnostics.extend(declaration_diagnostics);
+    diagnostics.extend(parser.declare_feedback_variables(&mut unit));
 
     // Transform the data-model into an AST
     let (implementations, parser_diagnostics) = parser.parse_model();
@@ -132,24 +149,124 @@ impl<'parse, 'xml> ParseSession<'parse, 'xml> {
         ParseSession { project, id_provider, linkage, file_name, range_factory, diagnostics: Vec::new() }
     }
 
-    /// parse the compilation unit from the addData field
-    fn try_parse_declaration(&self) -> Option<(CompilationUnit, Vec<Diagnostic>)> {
-        let Some(content) = self
-            .project
-            .pous
-            .first()
-            .and_then(|it| it.interface.as_ref().and_then(|it| it.get_data_content()))
-        else {
-            return None;
-        };
-
-        //TODO: if our ST parser returns a diagnostic here, we might not have a text declaration and need to rely on the XML to provide us with
-        // the necessary data. for now, we will assume to always have a text declaration
-        Some(plc::parser::parse(
-            lexer::lex_with_ids(content, self.id_provider.clone(), self.range_factory.clone()),
-            self.linkage,
-            self.file_name,
-        ))
+    /// parse the compilation unit from the addData fields of all pous and add the project's data types
+    /// and the global variables of its configurations
+    fn parse_declarations(&self) -> (CompilationUnit, Vec<Diagnostic>) {
+        let mut unit = CompilationUnit::new(self.file_name);
+        let mut diagnostics = vec![];
+        for pou in &self.project.pous {
+            // a text declaration takes precedence, otherwise the declaration is built from the interface's elements
+            let Some(content) = pou.interface.as_ref().and_then(|it| it.get_data_content()) else {
+                unit.units.push(pou.build_declaration(self));
+                continue;
+            };
+            let (pou_unit, pou_diagnostics) = plc::parser::parse(
+                lexer::lex_with_ids(content, self.id_provider.clone(), self.range_factory.clone()),
+                self.linkage,
+                self.file_name,
+            );
+            // the body still needs a declaration if the text does not declare the pou
+            if !pou_unit.units.iter().any(|it| it.name.eq_ignore_ascii_case(&pou.name)) {
+                diagnostics.push(
+                    Diagnostic::error(format!(
+                        "The textual declaration of `{}` does not declare it",
+                        pou.name
+                    ))
+                    .with_error_code("E105")
+                    .with_location(self.create_file_only_location()),
+                );
+                unit.units.push(pou.build_declaration(self));
+            }
+            unit.import(pou_unit);
+            diagnostics.extend(pou_diagnostics);
+        }
+
+        unit.user_types.extend(self.project.data_types.iter().map(|it| it.transform(self)));
+        unit.global_vars.extend(
+            self.project
+                .configurations
+                .iter()
+                .flat_map(|it| it.all_global_vars())
+                .filter_map(|it| it.transform(self)),
+        );
+
+        (unit, diagnostics)
+    }
+
+    /// declares the implicit variables of all feedback connections in the pous they belong to. A variable's type
+    /// is the return type of the block's function, which has to be declared in the same file. Functions cannot
+    /// have feedback connections since their local variables do not keep their value between calls.
+    fn declare_feedback_variables(&self, unit: &mut CompilationUnit) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for pou in &self.project.pous {
+            let mut variables = vec![];
+            let diagrams = std::iter::once(&pou.body)
+                .chain(pou.actions.iter().map(|it| &it.body))
+                .map(|it| &it.function_block_diagram);
+            for diagram in diagrams {
+                let range_factory = diagram.get_source_location_factory(&self.range_factory);
+                for feedback in &diagram.feedback_variables {
+                    let location = range_factory.create_block_location(feedback.local_id, None);
+                    if matches!(pou.pou_type, PouType::Function) {
+                        diagnostics.push(
+                            Diagnostic::error(format!(
+                                "Block {} is part of a feedback loop, functions do not keep the value of the previous call",
+                                feedback.local_id
+                            ))
+                            .with_error_code("E099")
+                            .with_location(location),
+                        );
+                        continue;
+                    }
+                    let Some(data_type_declaration) = get_feedback_type(unit, feedback) else {
+                        diagnostics.push(
+                            Diagnostic::error(format!(
+                                "Cannot infer the type of the feedback connection of block {}, `{}` is not a function with a return type declared in this file",
+                                feedback.local_id, feedback.type_name
+                            ))
+                            .with_error_code("E099")
+                            .with_location(location),
+                        );
+                        continue;
+                    };
+
+                    variables.push(Variable {
+                        name: feedback.name.clone(),
+                        data_type_declaration,
+                        initializer: None,
+                        address: None,
+                        location,
+                    });
+                }
+            }
+
+            let Some(declaration) = unit.units.iter_mut().find(|it| it.name == pou.name) else { continue };
+            if !variables.is_empty() {
+                declaration.variable_blocks.push(VariableBlock {
+                    access: AccessModifier::Protected,
+                    constant: false,
+                    retain: false,
+                    persistent: false,
+                    variables,
+                    variable_block_type: VariableBlockType::Local,
+                    linkage: self.linkage,
+                    location: self.create_file_only_location(),
+                });
+            }
+        }
+
+        diagnostics
+    }
+
+    /// parses an expression found in a declaration, e.g. an initial value or array bounds
+    fn parse_declaration_expression(&self, expr: &str, location: &SourceLocation) -> AstNode {
+        let mut exp = parse_expression(&mut lexer::lex_with_ids(
+            html_escape::decode_html_entities_to_string(expr, &mut String::new()),
+            self.id_provider.clone(),
+            self.range_factory.clone(),
+        ));
+        exp.set_location(location.clone());
+        exp
     }
 
     fn parse_expression(&self, expr: &str, local_id: usize, execution_order: Option<usize>) -> AstNode {
@@ -194,6 +311,16 @@ impl<'parse, 'xml> ParseSession<'parse, 'xml> {
     }
 }
 
+/// returns the type of the given feedback variable, see [`ParseSession::declare_feedback_variables`]
+fn get_feedback_type(unit: &CompilationUnit, feedback: &FeedbackVariable) -> Option<DataTypeDeclaration> {
+    unit.units
+        .iter()
+        .find(|it| it.name.eq_ignore_ascii_case(&feedback.type_name))
+        .and_then(|it| it.return_type.as_ref())
+        .filter(|it| matches!(it, DataTypeDeclaration::DataTypeReference { .. }))
+        .cloned()
+}
+
 impl From<PouType> for AstPouType {
     fn from(value: PouType) -> Self {
         match value {
@@ -6,16 +6,14 @@ use super::ParseSession;
 
 impl<'xml> Action<'xml> {
     pub(crate) fn transform(&self, session: &mut ParseSession) -> Vec<AstNode> {
-        let fbd = &self.body.function_block_diagram;
-
         if cfg!(feature = "debug") {
-            let statements = fbd.transform(session);
+            let statements = self.body.transform(session);
             println!("{statements:#?}");
 
             return statements;
         }
 
-        fbd.transform(session)
+        self.body.transform(session)
     }
 
     pub(crate) fn build_implementation(&self, session: &mut ParseSession) -> Implementation {
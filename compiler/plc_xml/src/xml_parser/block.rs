@@ -1,26 +1,24 @@
 use ast::ast::{AstFactory, AstNode};
+use plc_diagnostics::diagnostics::Diagnostic;
 
 use crate::model::{block::Block, fbd::NodeIndex};
 
 use super::ParseSession;
 
 impl<'xml> Block<'xml> {
-    pub(crate) fn transform(&self, session: &ParseSession, index: &NodeIndex) -> AstNode {
-        let parameters = self
-            .variables
-            .iter()
-            .filter_map(|var| {
-                // try to transform the element this block variable points to
-                var.transform(session, index)
-            })
-            .collect();
+    pub(crate) fn transform(&self, session: &ParseSession, index: &NodeIndex) -> Result<AstNode, Diagnostic> {
+        let mut parameters = vec![];
+        for var in &self.variables {
+            // try to transform the element this block variable points to
+            parameters.extend(var.transform(session, index, self.local_id)?);
+        }
 
-        AstFactory::create_call_to(
+        Ok(AstFactory::create_call_to(
             self.instance_name.as_ref().unwrap_or(&self.type_name).to_string(),
             parameters,
             session.next_id(),
             session.next_id(),
             &session.create_block_location(self.local_id, self.execution_order_id),
-        )
+        ))
     }
 }
@@ -0,0 +1,38 @@
+use ast::ast::AstNode;
+use plc::lexer;
+use plc_diagnostics::diagnostics::Diagnostic;
+
+use crate::model::body::{Body, StructuredText};
+
+use super::ParseSession;
+
+impl<'xml> Body<'xml> {
+    pub(crate) fn transform(&self, session: &mut ParseSession) -> Vec<AstNode> {
+        if let Some(language) = &self.unsupported_language {
+            session.diagnostics.push(
+                Diagnostic::error(format!("Bodies written in {language} are not supported"))
+                    .with_error_code("E094")
+                    .with_location(session.create_file_only_location()),
+            );
+            return vec![];
+        }
+
+        match &self.structured_text {
+            Some(structured_text) => structured_text.transform(session),
+            None => self.function_block_diagram.transform(session),
+        }
+    }
+}
+
+impl StructuredText {
+    /// parses the statements with the ST parser, their locations point into the xml file
+    fn transform(&self, session: &mut ParseSession) -> Vec<AstNode> {
+        let (statements, diagnostics) = plc::parser::parse_statements(lexer::lex_with_ids(
+            &self.content,
+            session.id_provider.clone(),
+            session.range_factory.with_segments(self.segments.clone()),
+        ));
+        session.diagnostics.extend(diagnostics);
+        statements
+    }
+}
@@ -43,7 +43,7 @@ fn get_connection(
 
     match node {
         Node::FunctionBlockVariable(variable) => Ok(variable.transform(session)),
-        Node::Block(block) => Ok(block.transform(session, index)),
+        Node::Block(block) => block.transform(session, index),
 
         _ => {
             let location_control =
@@ -0,0 +1,111 @@
+use ast::ast::{DataType, DataTypeDeclaration, UserTypeDeclaration};
+use plc::typesystem::{DINT_TYPE, STRING_TYPE, WSTRING_TYPE};
+use plc_source::source_location::SourceLocation;
+
+use crate::model::data_types::{TypeDefinition, UserType};
+
+use super::ParseSession;
+
+impl UserType {
+    pub(crate) fn transform(&self, session: &ParseSession) -> UserTypeDeclaration {
+        let location = session.create_range(self.range.clone());
+        let data_type = match self.base_type.transform(session, &location) {
+            DataTypeDeclaration::DataTypeDefinition { mut data_type, .. } => {
+                data_type.set_name(self.name.clone());
+                data_type
+            }
+            // aliases are declared like sub-ranges without bounds, e.g. `TYPE MyInt : INT; END_TYPE`
+            DataTypeDeclaration::DataTypeReference { referenced_type, .. } => {
+                DataType::SubRangeType { name: Some(self.name.clone()), referenced_type, bounds: None }
+            }
+        };
+
+        UserTypeDeclaration {
+            data_type,
+            initializer: self
+                .initial_value
+                .as_ref()
+                .map(|it| session.parse_declaration_expression(&it.to_string(), &location)),
+            location,
+            scope: None,
+        }
+    }
+}
+
+impl TypeDefinition {
+    pub(crate) fn transform(&self, session: &ParseSession, location: &SourceLocation) -> DataTypeDeclaration {
+        let data_type = match self {
+            TypeDefinition::Reference(name) => {
+                return DataTypeDeclaration::DataTypeReference {
+                    referenced_type: name.clone(),
+                    location: location.clone(),
+                }
+            }
+            TypeDefinition::String { is_wide, length: None } => {
+                return DataTypeDeclaration::DataTypeReference {
+                    referenced_type: if *is_wide { WSTRING_TYPE } else { STRING_TYPE }.to_string(),
+                    location: location.clone(),
+                }
+            }
+            TypeDefinition::String { is_wide, length: Some(length) } => DataType::StringType {
+                name: None,
+                is_wide: *is_wide,
+                size: Some(session.parse_declaration_expression(length, location)),
+            },
+            TypeDefinition::Array { dimensions, base_type } => {
+                let bounds = dimensions
+                    .iter()
+                    .map(|(lower, upper)| format!("{lower}..{upper}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                DataType::ArrayType {
+                    name: None,
+                    bounds: session.parse_declaration_expression(&bounds, location),
+                    referenced_type: Box::new(base_type.transform(session, location)),
+                    is_variable_length: false,
+                }
+            }
+            TypeDefinition::Pointer(base_type) => DataType::PointerType {
+                name: None,
+                referenced_type: Box::new(base_type.transform(session, location)),
+            },
+            TypeDefinition::Struct(variables) => DataType::StructType {
+                name: None,
+                variables: variables.iter().map(|it| it.transform(session)).collect(),
+            },
+            TypeDefinition::Union(variables) => DataType::UnionType {
+                name: None,
+                variables: variables.iter().map(|it| it.transform(session)).collect(),
+            },
+            TypeDefinition::Enum { values, base_type } => {
+                let elements = values
+                    .iter()
+                    .map(|(name, value)| match value {
+                        Some(value) => format!("{name} := {value}"),
+                        None => name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                DataType::EnumType {
+                    name: None,
+                    numeric_type: base_type
+                        .as_ref()
+                        .and_then(|it| it.transform(session, location).get_name().map(str::to_string))
+                        .unwrap_or_else(|| DINT_TYPE.to_string()),
+                    elements: session.parse_declaration_expression(&elements, location),
+                }
+            }
+            TypeDefinition::SubRange { lower, upper, base_type } => DataType::SubRangeType {
+                name: None,
+                referenced_type: base_type
+                    .transform(session, location)
+                    .get_name()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| DINT_TYPE.to_string()),
+                bounds: Some(session.parse_declaration_expression(&format!("{lower}..{upper}"), location)),
+            },
+        };
+
+        DataTypeDeclaration::DataTypeDefinition { data_type, location: location.clone(), scope: None }
+    }
+}
@@ -1,5 +1,6 @@
 use ast::ast::{AstFactory, AstNode, AstStatement};
 use indexmap::IndexMap;
+use plc_diagnostics::diagnostics::Diagnostic;
 use plc_source::source_location::SourceLocation;
 
 use crate::model::fbd::{FunctionBlockDiagram, Node, NodeId};
@@ -10,6 +11,15 @@ impl<'xml> FunctionBlockDiagram<'xml> {
     /// Transforms the body of a function block diagram to their AST-equivalent, in order of execution.
     /// Only statements that are necessary for execution logic will be selected.
     pub(crate) fn transform(&self, session: &mut ParseSession) -> Vec<AstNode> {
+        // all locations created while transforming the diagram point to the nodes they originate from
+        let range_factory = self.get_source_location_factory(&session.range_factory);
+        let range_factory = std::mem::replace(&mut session.range_factory, range_factory);
+        let statements = self.transform_nodes(session);
+        session.range_factory = range_factory;
+        statements
+    }
+
+    fn transform_nodes(&self, session: &mut ParseSession) -> Vec<AstNode> {
         let mut ast_association = IndexMap::new();
 
         // transform each node to an ast-statement. since we might see and transform a node multiple times, we use an
@@ -38,10 +48,25 @@ impl<'xml> FunctionBlockDiagram<'xml> {
         session: &mut ParseSession,
         ast_association: &IndexMap<usize, AstNode>,
     ) -> (AstNode, Option<NodeId>) {
-        let Some(current_node) = self.nodes.get(&id) else { unreachable!() };
+        let Some(current_node) = self.nodes.get(&id) else {
+            let location = session.create_block_location(id, None);
+            report(
+                session,
+                Diagnostic::error(format!("Found a reference to a non-existing element with ID {id}"))
+                    .with_error_code("E082")
+                    .with_location(location.clone()),
+            );
+            return (AstFactory::create_empty_statement(location, session.next_id()), None);
+        };
 
         match current_node {
-            Node::Block(block) => (block.transform(session, &self.nodes), None),
+            Node::Block(block) => match block.transform(session, &self.nodes) {
+                Ok(value) => (value, None),
+                Err(why) => {
+                    report(session, why);
+                    (AstFactory::create_empty_statement(SourceLocation::undefined(), session.next_id()), None)
+                }
+            },
             Node::FunctionBlockVariable(var) => {
                 let lhs = var.transform(session);
 
@@ -50,16 +75,12 @@ impl<'xml> FunctionBlockDiagram<'xml> {
                     return (lhs, None);
                 };
 
-                let (rhs, remove_id) = ast_association
-                    .get(&ref_id)
-                    .map(|stmt| {
-                        if matches!(stmt.get_stmt(), AstStatement::CallStatement(..)) {
-                            (stmt.clone(), Some(ref_id))
-                        } else {
-                            self.transform_node(ref_id, session, ast_association)
-                        }
-                    })
-                    .expect("Expected AST statement, found None");
+                let (rhs, remove_id) = match ast_association.get(&ref_id) {
+                    Some(stmt) if matches!(stmt.get_stmt(), AstStatement::CallStatement(..)) => {
+                        (stmt.clone(), Some(ref_id))
+                    }
+                    _ => self.transform_node(ref_id, session, ast_association),
+                };
 
                 (AstFactory::create_assignment(lhs, rhs, session.next_id()), remove_id)
             }
@@ -67,11 +88,28 @@ impl<'xml> FunctionBlockDiagram<'xml> {
             Node::Control(control) => match control.transform(session, &self.nodes) {
                 Ok(value) => (value, None),
                 Err(why) => {
-                    session.diagnostics.push(why);
+                    report(session, why);
                     (AstFactory::create_empty_statement(SourceLocation::undefined(), session.next_id()), None)
                 }
             },
-            Node::Connector(_) => todo!(),
+            // connectors are resolved when desugaring the model, any leftover one is not connected to anything
+            Node::Connector(connector) => {
+                let location = session.create_block_location(connector.local_id, None);
+                report(
+                    session,
+                    Diagnostic::error(format!("Unexpected connection point '{}'", connector.name))
+                        .with_error_code("E083")
+                        .with_location(location.clone()),
+                );
+                (AstFactory::create_empty_statement(location, session.next_id()), None)
+            }
         }
     }
 }
+
+/// Nodes which are connected to other nodes are transformed more than once, report their errors only once
+fn report(session: &mut ParseSession, diagnostic: Diagnostic) {
+    if !session.diagnostics.contains(&diagnostic) {
+        session.diagnostics.push(diagnostic);
+    }
+}
@@ -1,4 +1,4 @@
-use ast::ast::{AstNode, Implementation};
+use ast::ast::{AstNode, Implementation, PolymorphismMode, Pou as AstPou, PouType as AstPouType};
 
 use crate::model::pou::Pou;
 
@@ -6,16 +6,39 @@ use super::ParseSession;
 
 impl<'xml> Pou<'xml> {
     fn transform(&self, session: &mut ParseSession) -> Vec<AstNode> {
-        let fbd = &self.body.function_block_diagram;
-
         if cfg!(feature = "debug") {
-            let statements = fbd.transform(session);
+            let statements = self.body.transform(session);
             println!("{statements:#?}");
 
             return statements;
         }
 
-        fbd.transform(session)
+        self.body.transform(session)
+    }
+
+    /// builds the pou's declaration from the variables and return type declared in its `<interface>`
+    pub fn build_declaration(&self, session: &ParseSession) -> AstPou {
+        let pou_type: AstPouType = self.pou_type.into();
+        let interface = self.interface.as_ref();
+        let location = session.create_file_only_location();
+
+        AstPou {
+            name: self.name.to_string(),
+            variable_blocks: interface
+                .map(|it| it.var_lists.iter().filter_map(|it| it.transform(session)).collect())
+                .unwrap_or_default(),
+            return_type: interface
+                .and_then(|it| it.return_type.as_ref())
+                .map(|it| it.transform(session, &location)),
+            poly_mode: matches!(pou_type, AstPouType::FunctionBlock).then_some(PolymorphismMode::None),
+            pou_type,
+            location: location.clone(),
+            name_location: location,
+            generics: vec![],
+            type_arguments: vec![],
+            linkage: session.linkage,
+            super_class: None,
+        }
     }
 
     pub fn build_implementation(&self, session: &mut ParseSession) -> Implementation {
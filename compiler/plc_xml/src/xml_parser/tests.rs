@@ -10,7 +10,8 @@ use plc_diagnostics::diagnostics::Diagnostic;
 use plc_source::{source_location::SourceLocationFactory, SourceCode, SourceCodeFactory};
 
 use crate::serializer::{
-    SBlock, SConnector, SContinuation, SInOutVariable, SInVariable, SOutVariable, SPou, SReturn, SVariable,
+    SBlock, SConnector, SContinuation, SInOutVariable, SInVariable, SLabel, SOutVariable, SPou, SReturn,
+    SVariable,
 };
 use crate::{model::project::Project, xml_parser};
 
@@ -19,6 +20,19 @@ fn parse(content: &str) -> (CompilationUnit, Vec<Diagnostic>) {
     xml_parser::parse(&source_code, LinkageType::Internal, IdProvider::default())
 }
 
+/// wraps the given pous into a project, e.g. to declare the functions called by the blocks of a diagram
+fn with_pous(pous: Vec<SPou>) -> String {
+    let pous = pous.into_iter().map(SPou::serialize).collect::<String>();
+    format!(
+        r#"<project xmlns="http://www.plcopen.org/xml/tc6_0201"><types><pous>{pous}</pous></types></project>"#
+    )
+}
+
+/// a function declared next to the diagrams of the feedback tests
+fn my_add() -> SPou {
+    SPou::init("myAdd", "function", "FUNCTION myAdd : DINT VAR_INPUT a, b : DINT; END_VAR")
+}
+
 fn visit(content: &str) -> Result<Project, crate::error::Error> {
     xml_parser::visit(content)
 }
@@ -349,6 +363,49 @@ fn ast_generates_locations() {
     assert!(diagnostics.is_empty());
 }
 
+#[test]
+fn generated_code_is_located_at_the_originating_block() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR x : DINT; END_VAR").with_fbd(vec![
+        &SInVariable::id(1).with_expression("x").with_position(20, 40),
+        &SBlock::init("ADD", 12, 0)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(1),
+                &SVariable::new().with_name("b").connect(1),
+            ])
+            .with_output(vec![&SVariable::new().with_name("ADD")])
+            .with_position(120, 40),
+        &SOutVariable::id(3).with_expression("x").with_execution_id(1).connect(12),
+    ]);
+
+    let source_code = SourceCode::new(content.serialize(), "<internal>.cfc");
+    let (units, diagnostics) = xml_parser::parse(&source_code, LinkageType::Internal, IdProvider::default());
+    assert!(diagnostics.is_empty());
+
+    let AstStatement::Assignment(Assignment { left, right, .. }) =
+        &units.implementations[0].statements[0].get_stmt()
+    else {
+        panic!("Not an assignment");
+    };
+    // the call and its arguments point to the block and the variable they were drawn with
+    assert_eq!(right.get_location().to_string(), "<internal>.cfc:block 12 (ADD) at (120, 40)");
+    let range = right.get_location().to_range().unwrap();
+    assert!(source_code.source[range.clone()].starts_with("<block"));
+    assert!(source_code.source[range.clone()].ends_with("</block>"));
+    // debuggers find the block at the line of its element, with its local id as column
+    let line = source_code.source[..range.start].matches('\n').count() + 1;
+    assert_eq!(right.get_location().get_line_plus_one(), line);
+    assert_eq!(right.get_location().get_column(), 12);
+
+    let AstStatement::CallStatement(CallStatement { parameters, .. }) = right.get_stmt() else {
+        panic!("Not a call statement");
+    };
+    let parameters = flatten_expression_list(parameters.as_deref().unwrap());
+    assert_eq!(parameters[0].get_location().to_string(), "<internal>.cfc:block 1 (inVariable) at (20, 40)");
+
+    // the output variable is declared without a position
+    assert_eq!(left.get_location().to_string(), "<internal>.cfc:block 3 (outVariable)");
+}
+
 #[test]
 fn actions_generated_correctly() {
     let source = SourceCode::new(content::ACTION_TEST, "<internal>.cfc");
@@ -587,3 +644,408 @@ END_VAR</content>
 </pou>
     "###;
 }
+
+const PROJECT: &str = r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://www.plcopen.org/xml/tc6_0201">
+    <types>
+        <dataTypes>
+            <dataType name="Color">
+                <baseType>
+                    <enum>
+                        <values>
+                            <value name="red"/>
+                            <value name="green" value="5"/>
+                        </values>
+                    </enum>
+                </baseType>
+            </dataType>
+            <dataType name="Point">
+                <baseType>
+                    <struct>
+                        <variable name="x">
+                            <type><INT/></type>
+                            <initialValue><simpleValue value="3"/></initialValue>
+                        </variable>
+                        <variable name="name">
+                            <type><string length="10"/></type>
+                        </variable>
+                    </struct>
+                </baseType>
+            </dataType>
+            <dataType name="Points">
+                <baseType>
+                    <array>
+                        <dimension lower="0" upper="2"/>
+                        <dimension lower="1" upper="4"/>
+                        <baseType><pointer><baseType><derived name="Point"/></baseType></pointer></baseType>
+                    </array>
+                </baseType>
+            </dataType>
+            <dataType name="Percent">
+                <baseType>
+                    <subrangeUnsigned>
+                        <range lower="0" upper="100"/>
+                        <baseType><USINT/></baseType>
+                    </subrangeUnsigned>
+                </baseType>
+            </dataType>
+        </dataTypes>
+        <pous/>
+    </types>
+    <instances>
+        <configurations>
+            <configuration name="config">
+                <globalVars retain="true">
+                    <variable name="points">
+                        <type><derived name="Points"/></type>
+                    </variable>
+                </globalVars>
+                <resource name="resource">
+                    <task name="cyclic" interval="T#10ms" priority="1">
+                        <pouInstance name="instance" typeName="prg"/>
+                    </task>
+                    <globalVars constant="true">
+                        <variable name="limit" address="%MD1">
+                            <type><DINT/></type>
+                            <initialValue>
+                                <arrayValue>
+                                    <value repetitionValue="2"><simpleValue value="1"/></value>
+                                    <value><simpleValue value="16#FF"/></value>
+                                </arrayValue>
+                            </initialValue>
+                        </variable>
+                        <variable name="origin">
+                            <type><derived name="Point"/></type>
+                            <initialValue>
+                                <structValue>
+                                    <value member="x"><simpleValue value="0"/></value>
+                                </structValue>
+                            </initialValue>
+                        </variable>
+                    </globalVars>
+                    <externalVars>
+                        <variable name="points">
+                            <type><derived name="Points"/></type>
+                        </variable>
+                    </externalVars>
+                </resource>
+            </configuration>
+        </configurations>
+    </instances>
+</project>
+"#;
+
+#[test]
+fn project_with_data_types_and_configurations_is_visited() {
+    let project = visit(PROJECT.trim()).unwrap();
+    assert_debug_snapshot!(project);
+}
+
+#[test]
+fn project_data_types_and_global_variables_are_transformed() {
+    let (unit, diagnostics) = parse(PROJECT.trim());
+
+    assert!(diagnostics.is_empty());
+    assert_debug_snapshot!(unit.user_types);
+    assert_debug_snapshot!(unit.global_vars);
+}
+
+#[test]
+fn pou_declaration_is_built_from_interface_without_text_declaration() {
+    let content = r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<pou xmlns="http://www.plcopen.org/xml/tc6_0201" name="counter" pouType="functionBlock">
+    <interface>
+        <inputVars>
+            <variable name="reset">
+                <type><BOOL/></type>
+            </variable>
+        </inputVars>
+        <outputVars retain="true">
+            <variable name="count">
+                <type><derived name="Counter"/></type>
+                <initialValue><simpleValue value="10"/></initialValue>
+            </variable>
+        </outputVars>
+        <inOutVars>
+            <variable name="history">
+                <type>
+                    <array>
+                        <dimension lower="1" upper="5"/>
+                        <baseType><INT/></baseType>
+                    </array>
+                </type>
+            </variable>
+        </inOutVars>
+        <tempVars>
+            <variable name="message">
+                <type><wstring/></type>
+            </variable>
+        </tempVars>
+    </interface>
+    <body>
+        <FBD/>
+    </body>
+</pou>
+"#;
+
+    let (unit, diagnostics) = parse(content.trim());
+
+    assert!(diagnostics.is_empty());
+    assert_debug_snapshot!(unit.units);
+}
+
+#[test]
+fn structured_text_body_locations_point_into_xml_file() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<pou xmlns="http://www.plcopen.org/xml/tc6_0201" name="foo" pouType="program">
+    <interface>
+        <localVars>
+            <variable name="a"><type><DINT/></type></variable>
+        </localVars>
+    </interface>
+    <body>
+        <ST>
+            <xhtml xmlns="http://www.w3.org/1999/xhtml">
+a := 1;
+IF a &lt; 2 AND a &gt;= 0 THEN a := a + ; END_IF
+a := a + ;
+            </xhtml>
+        </ST>
+    </body>
+</pou>
+"#;
+
+    let (unit, diagnostics) = parse(content);
+
+    assert_eq!(unit.implementations[0].statements.len(), 3);
+    let first_statement = unit.implementations[0].statements[0].get_location();
+    assert_eq!(first_statement.get_line(), 10);
+    assert_eq!(first_statement.get_column(), 0);
+    // the escaped characters do not shift the locations following them
+    let columns = diagnostics.iter().map(|it| (it.get_location().get_line(), it.get_location().get_column()));
+    assert_eq!(columns.collect::<Vec<_>>(), vec![(11, 40), (12, 9)]);
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn instruction_list_body_is_reported_as_unsupported() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<pou xmlns="http://www.plcopen.org/xml/tc6_0201" name="foo" pouType="program">
+    <interface/>
+    <body>
+        <IL>
+            <xhtml xmlns="http://www.w3.org/1999/xhtml">LD 1</xhtml>
+        </IL>
+    </body>
+</pou>
+"#;
+
+    let (unit, diagnostics) = parse(content);
+
+    assert!(unit.implementations[0].statements.is_empty());
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn truncated_file_is_reported_as_malformed() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<pou xmlns="http://www.plcopen.org/xml/tc6_0201" name="foo" pouType="program">
+    <body>
+        <FBD>
+            <inVariable localId="1">
+"#;
+
+    let (unit, diagnostics) = parse(content);
+
+    assert!(unit.units.is_empty());
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn missing_attributes_are_reported_at_their_element() {
+    let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<pou xmlns="http://www.plcopen.org/xml/tc6_0201" name="foo" pouType="program">
+    <actions>
+        <action>
+            <body/>
+        </action>
+    </actions>
+</pou>
+"#;
+
+    let (_, diagnostics) = parse(content);
+
+    assert_eq!(diagnostics.len(), 1);
+    let start = content.find("<action>").unwrap();
+    assert_eq!(diagnostics[0].get_location().to_range(), Some(start..start + "<action>".len()));
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn text_declaration_without_the_pou_is_reported() {
+    let content =
+        SPou::init("foo", "program", "PROGRAM bar VAR a : DINT; END_VAR").with_fbd(vec![]).serialize();
+
+    let (unit, diagnostics) = parse(&content);
+
+    assert_eq!(diagnostics.iter().map(|it| it.get_type()).collect::<Vec<_>>(), vec!["E105"]);
+    // the body is declared from the interface instead
+    assert_eq!(unit.units.iter().map(|it| it.name.as_str()).collect::<Vec<_>>(), vec!["foo", "bar"]);
+    assert_eq!(unit.implementations[0].name, "foo");
+}
+
+#[test]
+fn invalid_attribute_values_are_reported() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR a : DINT; END_VAR")
+        .with_fbd(vec![&SInVariable::id(1).with_expression("a").with_execution_id("first")])
+        .serialize();
+
+    let (_, diagnostics) = parse(&content);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].get_type(), "E096");
+}
+
+#[test]
+fn block_parameter_connected_to_non_existing_node_is_reported() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR a : DINT; END_VAR").with_fbd(vec![
+        &SOutVariable::id(1).with_expression("a").with_execution_id(1).connect(2),
+        &SBlock::init("ADD", 2, 0)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(7),
+                &SVariable::new().with_name("b").connect(3),
+            ])
+            .with_output(vec![&SVariable::new().with_name("ADD")]),
+        &SInVariable::id(3).with_expression("1"),
+    ]);
+
+    let (_, diagnostics) = parse(&content.serialize());
+
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn block_parameter_connected_to_control_is_reported() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR a : DINT; END_VAR").with_fbd(vec![
+        &SLabel::id(1).with_name("lbl").with_execution_id(0),
+        &SBlock::init("ADD", 2, 1)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(1),
+                &SVariable::new().with_name("b").connect(3),
+            ])
+            .with_output(vec![&SVariable::new().with_name("ADD")]),
+        &SInVariable::id(3).with_expression("1"),
+    ]);
+
+    let (_, diagnostics) = parse(&content.serialize());
+
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn block_fed_back_into_itself_is_stored_in_feedback_variable() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR x : DINT; END_VAR").with_fbd(vec![
+        &SInVariable::id(1).with_expression("1"),
+        &SBlock::init("myAdd", 2, 0)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(2),
+                &SVariable::new().with_name("b").connect(1),
+            ])
+            .with_output(vec![&SVariable::new().with_name("myAdd")]),
+        &SOutVariable::id(3).with_expression("x").with_execution_id(1).connect(2),
+    ]);
+
+    let (unit, diagnostics) = parse(&with_pous(vec![my_add(), content]));
+
+    assert!(diagnostics.is_empty());
+    let feedback = unit.units[1].variable_blocks.last().unwrap();
+    assert_eq!(feedback.variables[0].name, "__foo_feedback_2");
+    assert_eq!(feedback.variables[0].data_type_declaration.get_name(), Some("DINT"));
+    assert_debug_snapshot!(unit.implementations[1].statements);
+}
+
+#[test]
+fn feedback_loop_is_cut_before_the_first_executed_block() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR x, y : DINT; END_VAR").with_fbd(vec![
+        &SInVariable::id(1).with_expression("1"),
+        &SBlock::init("myAdd", 2, 0)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(1),
+                &SVariable::new().with_name("b").connect(3),
+            ])
+            .with_output(vec![&SVariable::new().with_name("myAdd")]),
+        &SBlock::init("myAdd", 3, 1)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(2),
+                &SVariable::new().with_name("b").connect(1),
+            ])
+            .with_output(vec![&SVariable::new().with_name("myAdd")]),
+        &SOutVariable::id(4).with_expression("x").with_execution_id(2).connect(2),
+        &SOutVariable::id(5).with_expression("y").with_execution_id(3).connect(3),
+    ]);
+
+    let (unit, diagnostics) = parse(&with_pous(vec![my_add(), content]));
+
+    assert!(diagnostics.is_empty());
+    assert_debug_snapshot!(unit.implementations[1].statements);
+}
+
+#[test]
+fn feedback_variable_without_inferable_type_is_reported() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR x : DINT; END_VAR").with_fbd(vec![
+        &SInVariable::id(1).with_expression("1"),
+        &SBlock::init("ADD", 2, 0)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(2),
+                &SVariable::new().with_name("b").connect(1),
+            ])
+            .with_output(vec![&SVariable::new().with_name("ADD")]),
+    ]);
+
+    let (_, diagnostics) = parse(&content.serialize());
+
+    assert_debug_snapshot!(diagnostics);
+}
+
+#[test]
+fn feedback_loop_in_function_is_reported() {
+    let content = SPou::init("foo", "function", "FUNCTION foo : DINT").with_fbd(vec![
+        &SInVariable::id(1).with_expression("1"),
+        &SBlock::init("myAdd", 2, 0)
+            .with_input(vec![
+                &SVariable::new().with_name("a").connect(2),
+                &SVariable::new().with_name("b").connect(1),
+            ])
+            .with_output(vec![&SVariable::new().with_name("myAdd")]),
+        &SOutVariable::id(3).with_expression("foo").with_execution_id(1).connect(2),
+    ]);
+
+    let (unit, diagnostics) = parse(&with_pous(vec![my_add(), content]));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].get_message(),
+        "Block 2 is part of a feedback loop, functions do not keep the value of the previous call"
+    );
+    // no state is declared in the function
+    assert!(unit.units[1]
+        .variable_blocks
+        .iter()
+        .all(|it| it.variables.iter().all(|it| it.name != "__foo_feedback_2")));
+}
+
+#[test]
+fn duplicate_and_missing_execution_order_is_reported() {
+    let content = SPou::init("foo", "program", "PROGRAM foo VAR x, y, z : DINT; END_VAR").with_fbd(vec![
+        &SInVariable::id(1).with_expression("1"),
+        &SOutVariable::id(2).with_expression("x").with_execution_id(0).connect(1),
+        &SOutVariable::id(3).with_expression("y").with_execution_id(0).connect(1),
+        &SOutVariable::id(4).with_expression("z").connect(1),
+    ]);
+
+    let (_, diagnostics) = parse(&content.serialize());
+
+    assert_debug_snapshot!(diagnostics);
+}
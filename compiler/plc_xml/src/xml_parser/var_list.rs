@@ -0,0 +1,57 @@
+use ast::ast::{AccessModifier, ArgumentProperty, AstFactory, Variable, VariableBlock, VariableBlockType};
+
+use crate::model::var_list::{VarDeclaration, VarList, VarListKind};
+
+use super::ParseSession;
+
+impl VarList {
+    /// transforms the variable list into a variable block. `VAR_EXTERNAL` lists return `None`, since
+    /// global variables can be accessed without declaring them
+    pub(crate) fn transform(&self, session: &ParseSession) -> Option<VariableBlock> {
+        let variable_block_type = match self.kind {
+            VarListKind::Global => VariableBlockType::Global,
+            VarListKind::Input => VariableBlockType::Input(ArgumentProperty::ByVal),
+            VarListKind::Output => VariableBlockType::Output,
+            VarListKind::InOut => VariableBlockType::InOut,
+            VarListKind::Local => VariableBlockType::Local,
+            VarListKind::Temp => VariableBlockType::Temp,
+            VarListKind::External => return None,
+        };
+
+        let mut variables = self.variables.iter().map(|it| it.transform(session)).collect::<Vec<_>>();
+        if self.constant {
+            // like in ST, constants without an initial value are initialized with their default value
+            variables.iter_mut().filter(|it| it.initializer.is_none()).for_each(|it| {
+                it.initializer =
+                    Some(AstFactory::create_default_value(it.location.clone(), session.next_id()))
+            });
+        }
+
+        Some(VariableBlock {
+            access: AccessModifier::Protected,
+            constant: self.constant,
+            retain: self.retain,
+            persistent: self.persistent,
+            variables,
+            variable_block_type,
+            linkage: session.linkage,
+            location: session.create_range(self.range.clone()),
+        })
+    }
+}
+
+impl VarDeclaration {
+    pub(crate) fn transform(&self, session: &ParseSession) -> Variable {
+        let location = session.create_range(self.range.clone());
+        Variable {
+            name: self.name.clone(),
+            data_type_declaration: self.data_type.transform(session, &location),
+            initializer: self
+                .initial_value
+                .as_ref()
+                .map(|it| session.parse_declaration_expression(&it.to_string(), &location)),
+            address: self.address.as_ref().map(|it| session.parse_declaration_expression(it, &location)),
+            location,
+        }
+    }
+}
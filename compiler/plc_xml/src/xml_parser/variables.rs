@@ -1,4 +1,5 @@
 use ast::ast::{AstFactory, AstNode, Operator};
+use plc_diagnostics::diagnostics::Diagnostic;
 
 use crate::model::{
     fbd::{Node, NodeIndex},
@@ -8,19 +9,38 @@ use crate::model::{
 use super::ParseSession;
 
 impl BlockVariable {
-    pub(crate) fn transform(&self, session: &ParseSession, index: &NodeIndex) -> Option<AstNode> {
-        let Some(ref_id) = &self.ref_local_id else {
+    pub(crate) fn transform(
+        &self,
+        session: &ParseSession,
+        index: &NodeIndex,
+        block_id: usize,
+    ) -> Result<Option<AstNode>, Diagnostic> {
+        let Some(ref_id) = self.ref_local_id else {
             // param not provided/passed
-            return None;
+            return Ok(None);
         };
 
         // XXX: data-recursion?
-        match index.get(ref_id) {
-            Some(Node::Block(block)) => Some(block.transform(session, index)),
-            Some(Node::FunctionBlockVariable(var)) => Some(var.transform(session)),
-            Some(Node::Control(_)) => todo!(),
-            Some(Node::Connector(_)) => unreachable!(),
-            None => unreachable!(),
+        match index.get(&ref_id) {
+            Some(Node::Block(block)) => block.transform(session, index).map(Some),
+            Some(Node::FunctionBlockVariable(var)) => Ok(Some(var.transform(session))),
+            // controls have no outputs and connectors are resolved when desugaring the model
+            Some(node @ (Node::Control(_) | Node::Connector(_))) => {
+                let location_block = session.create_block_location(block_id, None);
+                let location_other = session.create_block_location(ref_id, node.get_exec_id());
+
+                Err(Diagnostic::error(format!(
+                    "Parameter `{}` of block {block_id} cannot be connected to node {ref_id}",
+                    self.formal_parameter
+                ))
+                .with_error_code("E083")
+                .with_location(location_block.span(&location_other)))
+            }
+            None => Err(Diagnostic::error(format!(
+                "Node {block_id} is referencing a non-existing element with ID {ref_id}"
+            ))
+            .with_error_code("E082")
+            .with_location(session.create_block_location(block_id, None))),
         }
     }
 }
@@ -2,15 +2,33 @@ use std::env;
 use std::path::Path;
 use std::process::Command;
 
+/// The ST sources which do not depend on functions implemented with the rust standard library.
+/// Sources in `freestanding/` declare functions that only the `no_std` build implements
+const FREESTANDING_SOURCES: &[&str] = &[
+    "bistable_functionblocks.st",
+    "bit_conversion.st",
+    "bit_num_conversion.st",
+    "bit_shift_functions.st",
+    "counters.st",
+    "endianness_conversion_functions.st",
+    "flanks.st",
+    "freestanding/cycle_clock.st",
+    "num_conversion.st",
+    "numerical_functions.st",
+    "selectors.st",
+    "timers.st",
+    "validation_functions.st",
+];
+
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
-    let mut args = vec![
-        "plc".to_owned(),
-        "iec61131-st/*.st".to_owned(),
-        "-c".to_owned(),
-        "-o".to_owned(),
-        format!("{out_dir}/st.o"),
-    ];
+    let mut args = vec!["plc".to_owned()];
+    if env::var("CARGO_FEATURE_STD").is_ok() {
+        args.push("iec61131-st/*.st".to_owned());
+    } else {
+        args.extend(FREESTANDING_SOURCES.iter().map(|it| format!("iec61131-st/{it}")));
+    }
+    args.extend(["-c".to_owned(), "-o".to_owned(), format!("{out_dir}/st.o")]);
     if let Ok(target) = env::var("TARGET") {
         args.push("--target".to_owned());
         args.push(target);
@@ -27,8 +45,16 @@ fn main() {
     args.push("--single-module".to_owned());
 
     plc_driver::compile(&args).unwrap();
+    // the system `ar` cannot index WebAssembly objects, which the wasm linker requires
+    #[cfg(not(target_os = "windows"))]
+    let archiver =
+        if env::var("CARGO_CFG_TARGET_FAMILY").is_ok_and(|it| it == "wasm") { "llvm-ar" } else { "ar" };
     #[cfg(not(target_os = "windows"))]
-    Command::new("ar").args(["crs", "libst.a", "st.o"]).current_dir(Path::new(&out_dir)).status().unwrap();
+    Command::new(archiver)
+        .args(["crs", "libst.a", "st.o"])
+        .current_dir(Path::new(&out_dir))
+        .status()
+        .unwrap();
     #[cfg(target_os = "windows")]
     Command::new("lld-link").args(["/LIB", "st.o"]).current_dir(Path::new(&out_dir)).status().unwrap();
 
@@ -1,20 +1,39 @@
 // Definitions of the core standard function modules for IEC61131-3
+// Without the `std` feature only the modules that do not depend on the rust standard library are
+// available, the library can then be linked into freestanding (bare-metal) applications.
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "std")]
 pub mod arithmetic_functions;
 pub mod bistable_functionblocks;
 pub mod bit_num_conversion;
 pub mod bit_shift_functions;
 pub mod counters;
+#[cfg(feature = "std")]
 pub mod date_time_conversion;
+#[cfg(feature = "std")]
 pub mod date_time_extra_functions;
+#[cfg(feature = "std")]
 pub mod date_time_numeric_functions;
 pub mod endianness_conversion_functions;
+#[cfg(feature = "std")]
 pub mod extra_functions;
 pub mod flanks;
 pub mod numerical_functions;
+#[cfg(feature = "std")]
 pub mod string_conversion;
+#[cfg(feature = "std")]
 pub mod string_functions;
 pub mod timers;
 pub mod types;
 pub mod utils;
 pub mod validation_functions;
+
+/// Freestanding applications have no unwinding runtime, a panic stops the application
+#[cfg(all(not(feature = "std"), not(test)))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {
+        core::hint::spin_loop();
+    }
+}
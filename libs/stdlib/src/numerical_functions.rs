@@ -4,7 +4,10 @@
 #[allow(non_snake_case)]
 #[no_mangle]
 pub extern "C" fn ROUND__REAL(input: f32) -> f32 {
-    input.round()
+    #[cfg(feature = "std")]
+    return input.round();
+    #[cfg(not(feature = "std"))]
+    return round_f32(input);
 }
 
 /// .
@@ -13,5 +16,50 @@ pub extern "C" fn ROUND__REAL(input: f32) -> f32 {
 #[allow(non_snake_case)]
 #[no_mangle]
 pub extern "C" fn ROUND__LREAL(input: f64) -> f64 {
-    input.round()
+    #[cfg(feature = "std")]
+    return input.round();
+    #[cfg(not(feature = "std"))]
+    return round_f64(input);
+}
+
+/// Rounds half-way cases away from zero like `round` of the standard library, which is not
+/// available without it. Values beyond the given limit have no fractional digits.
+macro_rules! round_without_std {
+    ($name:ident, $float:ty, $int:ty, $limit:expr) => {
+        #[cfg(any(test, not(feature = "std")))]
+        fn $name(input: $float) -> $float {
+            // also returns infinities and NaN unchanged
+            if !(-$limit < input && input < $limit) {
+                return input;
+            }
+            let truncated = input as $int as $float;
+            let fraction = input - truncated;
+            if fraction >= 0.5 {
+                truncated + 1.0
+            } else if fraction <= -0.5 {
+                truncated - 1.0
+            } else {
+                truncated
+            }
+        }
+    };
+}
+
+round_without_std!(round_f32, f32, i32, 8_388_608.0);
+round_without_std!(round_f64, f64, i64, 4_503_599_627_370_496.0);
+
+#[cfg(test)]
+mod tests {
+    use super::{round_f32, round_f64};
+
+    #[test]
+    fn rounding_without_std_matches_std() {
+        for value in [0.0, 0.4, 0.5, 1.5, 2.5, -0.5, -1.49, -2.5, 3.7, -3.7, 8_388_607.5, 1e10, -1e10] {
+            assert_eq!(round_f32(value as f32), (value as f32).round(), "{value}");
+            assert_eq!(round_f64(value), value.round(), "{value}");
+        }
+        assert_eq!(round_f64(4_503_599_627_370_495.5), 4_503_599_627_370_496.0);
+        assert!(round_f32(f32::NAN).is_nan());
+        assert_eq!(round_f64(f64::INFINITY), f64::INFINITY);
+    }
 }
@@ -0,0 +1,37 @@
+// Freestanding targets have no operating system clock to measure the timers against.
+// Instead the runtime advances this clock by the duration of every cycle it executes.
+
+use core::time::Duration;
+
+use super::Time;
+
+static mut ELAPSED_NANOS: u64 = 0;
+
+/// .
+/// Advances the clock of the timers by the given cycle time
+/// The runtime calls this once per cycle, before executing the tasks of the cycle
+///
+#[allow(non_snake_case)]
+#[no_mangle]
+pub extern "C" fn ADVANCE_CLOCK(cycle_time: Time) {
+    // the clock is only advanced from the cycle executing the timers
+    unsafe { ELAPSED_NANOS = ELAPSED_NANOS.wrapping_add(cycle_time.max(0) as u64) }
+}
+
+fn elapsed_nanos() -> u64 {
+    unsafe { ELAPSED_NANOS }
+}
+
+/// A point in time of the cycle clock
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Instant(u64);
+
+impl Instant {
+    pub fn now() -> Instant {
+        Instant(elapsed_nanos())
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(elapsed_nanos().wrapping_sub(self.0))
+    }
+}
@@ -1,4 +1,4 @@
-use std::{
+use core::{
     fmt::Debug,
     ops::{BitAnd, Shr},
 };
@@ -30,7 +30,7 @@ where
     T: Shr<usize, Output = T> + BitAnd<Output = T> + Copy + From<u8> + TryInto<u8, Error = U>,
     U: Debug,
 {
-    let iterations = std::mem::size_of::<T>() * u8::BITS as usize / BITS_PER_BCD_DIGIT;
+    let iterations = core::mem::size_of::<T>() * u8::BITS as usize / BITS_PER_BCD_DIGIT;
     for i in 0..iterations {
         if ((input >> (BITS_PER_BCD_DIGIT * i)) & 0b1111.into())
             .try_into()
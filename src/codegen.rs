@@ -1,6 +1,7 @@
 // Copyright (c) 2020 Ghaith Hachem and Mathias Rieder
 use std::{
     cell::RefCell,
+    ffi::CString,
     ops::Deref,
     path::{Path, PathBuf},
 };
@@ -9,16 +10,21 @@ use std::{
 use self::{
     debug::{Debug, DebugBuilderEnum},
     generators::{
+        coverage_generator::CoverageCounters,
         data_type_generator,
         llvm::{GlobalValueExt, Llvm},
+        migration_generator,
         pou_generator::{self, PouGenerator},
+        symbol_table_generator,
         variable_generator::VariableGenerator,
     },
     llvm_index::LlvmTypedIndex,
 };
 use crate::{
+    online_change::LayoutRecord,
     output::FormatOption,
     resolver::{AstAnnotations, Dependency, StringLiterals},
+    stack_report::FunctionStackUsage,
     DebugLevel, OptimizationLevel, Target,
 };
 
@@ -29,11 +35,16 @@ use inkwell::{
     execution_engine::{ExecutionEngine, JitFunction},
     memory_buffer::MemoryBuffer,
     types::BasicType,
+    values::BasicValue,
 };
 use inkwell::{
     module::Module,
     passes::PassBuilderOptions,
-    targets::{CodeModel, FileType, InitializationConfig, RelocMode},
+    targets::{FileType, RelocMode},
+};
+use llvm_sys::{
+    execution_engine::LLVMGetGlobalValueAddress,
+    support::{LLVMLoadLibraryPermanently, LLVMSearchForAddressOfSymbol},
 };
 use plc_ast::ast::{CompilationUnit, LinkageType};
 use plc_diagnostics::diagnostics::Diagnostic;
@@ -43,6 +54,7 @@ mod debug;
 pub(crate) mod generators;
 mod llvm_index;
 mod llvm_typesystem;
+mod stack_usage;
 #[cfg(test)]
 mod tests;
 
@@ -72,6 +84,9 @@ pub struct CodeGen<'ink> {
     pub debug: DebugBuilderEnum<'ink>,
 
     pub module_location: String,
+
+    /// instruments the generated statements with coverage counters
+    coverage: bool,
 }
 
 pub struct GeneratedModule<'ink> {
@@ -94,7 +109,14 @@ impl<'ink> CodeGen<'ink> {
         let module = context.create_module(module_location);
         module.set_source_file_name(module_location);
         let debug = debug::DebugBuilderEnum::new(context, &module, root, optimization_level, debug_level);
-        CodeGen { module, debug, module_location: module_location.to_string() }
+        CodeGen { module, debug, module_location: module_location.to_string(), coverage: false }
+    }
+
+    /// instruments the generated statements with statement and branch coverage counters, see
+    /// [`generators::coverage_generator`]
+    pub fn with_coverage(mut self, coverage: bool) -> Self {
+        self.coverage = coverage;
+        self
     }
 
     pub fn generate_llvm_index(
@@ -123,7 +145,10 @@ impl<'ink> CodeGen<'ink> {
         //Generate global variables
         let llvm_gv_index =
             variable_generator.generate_global_variables(dependencies, &self.module_location)?;
+        //Generate the process images behind direct addresses
+        let llvm_pi_index = variable_generator.generate_process_images()?;
         index.merge(llvm_gv_index);
+        index.merge(llvm_pi_index);
 
         //Generate opaque functions for implementations and associate them with their types
         let llvm = Llvm::new(context, context.create_builder());
@@ -198,16 +223,20 @@ impl<'ink> CodeGen<'ink> {
         //generate all pous
         let llvm = Llvm::new(context, context.create_builder());
         let pou_generator = PouGenerator::new(llvm, global_index, annotations, llvm_index);
+        let coverage = self.coverage.then(|| CoverageCounters::new(&self.module));
 
         //Generate the POU stubs in the first go to make sure they can be referenced.
         for implementation in &unit.implementations {
             //Don't generate external or generic functions
             if let Some(entry) = global_index.find_pou(implementation.name.as_str()) {
                 if !entry.is_generic() && entry.get_linkage() != &LinkageType::External {
-                    pou_generator.generate_implementation(implementation, &self.debug)?;
+                    pou_generator.generate_implementation(implementation, &self.debug, coverage.as_ref())?;
                 }
             }
         }
+        if let Some(coverage) = coverage {
+            coverage.generate_dump(&self.module, &Llvm::new(context, context.create_builder()));
+        }
 
         self.debug.finalize();
         log::debug!("{}", self.module.to_string());
@@ -223,6 +252,46 @@ impl<'ink> CodeGen<'ink> {
         #[cfg(not(feature = "verify"))]
         Ok(GeneratedModule { module: self.module, engine: RefCell::new(None) })
     }
+
+    /// generates a table describing all instances of the project together with the functions
+    /// to look them up at runtime
+    pub fn generate_symbol_table(
+        self,
+        context: &'ink CodegenContext,
+        global_index: &Index,
+    ) -> Result<GeneratedModule<'ink>, Diagnostic> {
+        let llvm = Llvm::new(context, context.create_builder());
+        symbol_table_generator::generate_symbol_table(&self.module, &llvm, global_index)?;
+        self.debug.finalize();
+        Ok(GeneratedModule { module: self.module, engine: RefCell::new(None) })
+    }
+
+    /// generates a table describing all `RETAIN` and `PERSISTENT` global variables together with
+    /// the functions to look them up at runtime
+    pub fn generate_retain_table(
+        self,
+        context: &'ink CodegenContext,
+        global_index: &Index,
+    ) -> Result<GeneratedModule<'ink>, Diagnostic> {
+        let llvm = Llvm::new(context, context.create_builder());
+        symbol_table_generator::generate_retain_table(&self.module, &llvm, global_index)?;
+        self.debug.finalize();
+        Ok(GeneratedModule { module: self.module, engine: RefCell::new(None) })
+    }
+
+    /// generates the functions migrating the instances of a previous build with the given layout
+    /// into the instances of this build
+    pub fn generate_migration(
+        self,
+        context: &'ink CodegenContext,
+        global_index: &Index,
+        previous: &LayoutRecord,
+    ) -> Result<GeneratedModule<'ink>, Diagnostic> {
+        let llvm = Llvm::new(context, context.create_builder());
+        migration_generator::generate_migration_functions(&self.module, &llvm, global_index, previous)?;
+        self.debug.finalize();
+        Ok(GeneratedModule { module: self.module, engine: RefCell::new(None) })
+    }
 }
 
 impl<'ink> GeneratedModule<'ink> {
@@ -291,6 +360,15 @@ impl<'ink> GeneratedModule<'ink> {
         output
     }
 
+    /// Measures the stack frame and the code size of every function generated for the given target
+    pub fn collect_stack_usage(
+        &self,
+        target: &Target,
+        optimization_level: OptimizationLevel,
+    ) -> Result<Vec<FunctionStackUsage>, Diagnostic> {
+        stack_usage::collect_stack_usage(&self.module, target, optimization_level)
+    }
+
     ///
     /// Compiles the given source into an object file and saves it in output
     ///
@@ -301,30 +379,7 @@ impl<'ink> GeneratedModule<'ink> {
         target: &Target,
         optimization_level: OptimizationLevel,
     ) -> Result<PathBuf, Diagnostic> {
-        let initialization_config = &InitializationConfig::default();
-        inkwell::targets::Target::initialize_all(initialization_config);
-
-        let triple = target.get_target_triple();
-
-        let target = inkwell::targets::Target::from_triple(&triple).map_err(|it| {
-            Diagnostic::codegen_error(
-                format!("Invalid target-tripple '{triple}' - {it:?}"),
-                SourceLocation::undefined(),
-            )
-        })?;
-        let machine = target
-            .create_target_machine(
-                &triple,
-                //TODO : Add cpu features as optionals
-                "generic", //TargetMachine::get_host_cpu_name().to_string().as_str(),
-                "",        //TargetMachine::get_host_cpu_features().to_string().as_str(),
-                optimization_level.into(),
-                reloc,
-                CodeModel::Default,
-            )
-            .ok_or_else(|| {
-                Diagnostic::codegen_error("Cannot create target machine.", SourceLocation::undefined())
-            });
+        let machine = target.create_target_machine(reloc, optimization_level);
 
         //Make sure all parents exist
         if let Some(parent) = output.parent() {
@@ -333,6 +388,9 @@ impl<'ink> GeneratedModule<'ink> {
         ////Run the passes
         machine
             .and_then(|it| {
+                // lay out the module the way the target expects it before optimizing it
+                self.module.set_triple(&it.get_triple());
+                self.module.set_data_layout(&it.get_target_data().get_data_layout());
                 self.module
                     .run_passes(optimization_level.opt_params(), &it, PassBuilderOptions::create())
                     .map_err(|it| {
@@ -477,6 +535,60 @@ impl<'ink> GeneratedModule<'ink> {
         }
     }
 
+    ///
+    /// Writes the coverage counters of a module generated with coverage, as an executable does at
+    /// exit (see [`generators::coverage_generator`])
+    ///
+    pub fn dump_coverage(&self) {
+        self.get_execution_engine().run_static_destructors();
+    }
+
+    ///
+    /// Returns the address of the global variable given by `name` inside the compiled module
+    ///
+    pub fn get_global_address(&self, name: &str) -> Option<usize> {
+        let engine = self.get_execution_engine();
+        let name = CString::new(name).ok()?;
+        let address = unsafe { LLVMGetGlobalValueAddress(engine.as_mut_ptr(), name.as_ptr()) };
+        (address != 0).then_some(address as usize)
+    }
+
+    ///
+    /// Loads the given shared library into the process, the functions of all modules running in
+    /// the JIT can call the symbols it exports
+    ///
+    pub fn load_library(path: &Path) -> Result<(), Diagnostic> {
+        let name = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|it| Diagnostic::error(it.to_string()).with_error_code("E104"))?;
+        if unsafe { LLVMLoadLibraryPermanently(name.as_ptr()) } != 0 {
+            return Err(Diagnostic::error(format!("Cannot load library {}", path.to_string_lossy()))
+                .with_error_code("E104"));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Returns the names of the functions and globals the module uses but does not define, that
+    /// neither the process nor a loaded library provides. Running code that refers to them in the
+    /// JIT would crash
+    ///
+    pub fn find_unresolved_symbols(&self) -> Vec<String> {
+        // creating the engine makes the symbols of the process itself searchable
+        let _ = self.get_execution_engine();
+        self.module
+            .get_functions()
+            .map(|it| it.as_global_value())
+            .chain(self.module.get_globals())
+            .filter(|it| it.is_declaration() && it.as_pointer_value().get_first_use().is_some())
+            .filter_map(|it| it.get_name().to_str().ok().map(str::to_string))
+            .filter(|name| !name.starts_with("llvm."))
+            .filter(|name| {
+                CString::new(name.as_str())
+                    .map_or(true, |it| unsafe { LLVMSearchForAddressOfSymbol(it.as_ptr()) }.is_null())
+            })
+            .collect()
+    }
+
     pub fn add_global_function_mapping(&self, function_name: &str, local_function: usize) {
         let engine = self.get_execution_engine();
         if let Some(function) = self.module.get_function(function_name) {
@@ -16,7 +16,7 @@ use plc_diagnostics::diagnostics::Diagnostic;
 use plc_source::source_location::SourceLocation;
 
 use crate::{
-    datalayout::{Bytes, DataLayout, MemoryLocation},
+    datalayout::{self, Bytes, MemoryLocation},
     index::{ImplementationType, Index, PouIndexEntry, VariableIndexEntry},
     typesystem::{DataType, DataTypeInformation, Dimension, StringEncoding, CHAR_TYPE, WCHAR_TYPE},
     DebugLevel, OptimizationLevel,
@@ -262,6 +262,7 @@ impl<'ink> DebugBuilder<'ink> {
         //Create each type
         let index_types = members
             .iter()
+            .filter(|it| !it.is_in_process_image())
             .map(|it| (it.get_name(), it.get_type_name(), &it.source_location))
             .map(|(name, type_name, location)| {
                 index.get_type(type_name.as_ref()).map(|dt| (name, dt, location))
@@ -322,6 +323,60 @@ impl<'ink> DebugBuilder<'ink> {
         Ok(())
     }
 
+    fn create_union_type(
+        &mut self,
+        name: &str,
+        members: &[VariableIndexEntry],
+        index: &Index,
+        location: &SourceLocation,
+    ) -> Result<(), Diagnostic> {
+        let file = location
+            .get_file_name()
+            .map(|it| self.get_or_create_debug_file(it))
+            .unwrap_or_else(|| self.compile_unit.get_file());
+
+        //All members start at offset 0
+        let mut types = vec![];
+        for member in members {
+            let dt = index.get_type(member.get_type_name())?;
+            let di_type = self.get_or_create_debug_type(dt, index)?;
+            let type_info = dt.get_type_information();
+            types.push(
+                self.debug_info
+                    .create_member_type(
+                        file.as_debug_info_scope(),
+                        member.get_name(),
+                        file,
+                        member.source_location.get_line_plus_one() as u32,
+                        type_info.get_size(index).bits().into(),
+                        type_info.get_alignment(index).bits(),
+                        0,
+                        DIFlags::PUBLIC,
+                        di_type.into(),
+                    )
+                    .as_type(),
+            );
+        }
+
+        let union_dt = index.get_type_information_or_void(name);
+        let size = datalayout::get_allocated_size(union_dt, index).unwrap_or_default();
+        let union_type = self.debug_info.create_union_type(
+            file.as_debug_info_scope(),
+            name,
+            file,
+            location.get_line_plus_one() as u32,
+            Bytes::new(size).bits().into(),
+            datalayout::get_allocated_alignment(union_dt, index).bits(),
+            DIFlags::PUBLIC,
+            types.as_slice(),
+            0,
+            name,
+        );
+
+        self.register_concrete_type(name, DebugType::Struct(union_type));
+        Ok(())
+    }
+
     fn create_array_type(
         &mut self,
         name: &str,
@@ -522,7 +577,7 @@ impl<'ink> DebugBuilder<'ink> {
             //If the variable is an aggregate return type, register it as first parameter, and
             //increase the param count
             if variable.is_return() && var_type.is_aggregate_type() {
-                self.register_aggregate_return(variable, var_type, func);
+                self.register_aggregate_return(variable, var_type, func, index);
                 param_offset += 1;
             } else {
                 self.register_local_variable(variable, alignment, func);
@@ -549,6 +604,7 @@ impl<'ink> DebugBuilder<'ink> {
         variable: &VariableIndexEntry,
         var_type: &DataType,
         scope: FunctionValue<'ink>,
+        index: &Index,
     ) {
         let original_type = self
             .types
@@ -556,12 +612,12 @@ impl<'ink> DebugBuilder<'ink> {
             .copied()
             .unwrap_or_else(|| panic!("Cannot find type {} in debug types", variable.get_name()))
             .into();
-        let data_layout = DataLayout::default();
+        let data_layout = index.get_type_layout();
         let debug_type = self.debug_info.create_pointer_type(
             &format!("__ref_to_{}", variable.get_type_name()), // TODO: Naming convention (see plc_util/src/convention.rs)
             original_type,
-            data_layout.p64.bits().into(),
-            data_layout.p64.bits(),
+            data_layout.pointer_size.bits().into(),
+            data_layout.pointer.bits(),
             inkwell::AddressSpace::from(ADDRESS_SPACE_GLOBAL),
         );
         let location = &variable.source_location;
@@ -643,6 +699,9 @@ impl<'ink> Debug<'ink> for DebugBuilder<'ink> {
                 DataTypeInformation::Struct { members, .. } => {
                     self.create_struct_type(name, members.as_slice(), index, location)
                 }
+                DataTypeInformation::Union { members, .. } => {
+                    self.create_union_type(name, members.as_slice(), index, location)
+                }
                 DataTypeInformation::Array { name, inner_type_name, dimensions, .. } => {
                     self.create_array_type(name, inner_type_name, dimensions, size, alignment, index)
                 }
@@ -1,8 +1,11 @@
+pub mod coverage_generator;
 pub mod data_type_generator;
 pub mod expression_generator;
 pub mod llvm;
+pub mod migration_generator;
 pub mod pou_generator;
 pub mod statement_generator;
+pub mod symbol_table_generator;
 pub mod variable_generator;
 
 // See
@@ -0,0 +1,270 @@
+//! Instruments the generated statements with execution counters for statement and branch coverage
+//! and generates the function writing them as an lcov tracefile.
+//!
+//! Every line holding a statement gets a counter, incremented before the first statement of the
+//! line executes. Every decision gets a counter per branch:
+//! - `IF`: one branch per condition and one for the (possibly empty) `ELSE`
+//! - `CASE`: one branch per case and one for the (possibly empty) `ELSE`
+//! - `FOR`, `WHILE`, `REPEAT`: the loop body and leaving the loop
+//! - conditional `RETURN`: returning and continuing
+//!
+//! The counters are written by an internal function registered in `llvm.global_dtors`, so it runs
+//! when the program exits (or the shared object is unloaded). The records are appended to the file
+//! named by the `PLC_COVERAGE_FILE` environment variable, or `coverage.info` if it is not set.
+//! Every module writes its own `SF:` ... `end_of_record` records; `lcov` sums up the counters
+//! of the same file when the tracefile is read.
+use std::cell::{Cell, RefCell};
+
+use indexmap::{IndexMap, IndexSet};
+use inkwell::{
+    module::{Linkage, Module},
+    types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, PointerType},
+    values::{BasicMetadataValueEnum, BasicValueEnum, FunctionValue, GlobalValue, PointerValue},
+    AddressSpace,
+};
+use plc_diagnostics::diagnostics::INTERNAL_LLVM_ERROR;
+use plc_source::source_location::{CodeSpan, SourceLocation};
+
+use super::{llvm::Llvm, ADDRESS_SPACE_GENERIC};
+
+/// the environment variable naming the file the coverage records are appended to
+pub const COVERAGE_FILE_VARIABLE: &str = "PLC_COVERAGE_FILE";
+/// the file the coverage records are appended to if `PLC_COVERAGE_FILE` is not set
+pub const DEFAULT_COVERAGE_FILE: &str = "coverage.info";
+
+const COUNTERS_NAME: &str = "__coverage_counters";
+const DUMP_FUNCTION: &str = "__coverage_dump";
+
+/// A counted location in the source. Lines are 1-based as in lcov.
+enum CoveragePoint {
+    Line { line: usize },
+    Branch { line: usize, block: usize, branch: usize },
+}
+
+/// A decision whose branches are counted separately
+pub struct Decision {
+    file: &'static str,
+    line: usize,
+    block: usize,
+}
+
+/// The counters of a module, grouped by source file. The counters are stored in a single array,
+/// which is generated once the number of counters is known. Until then, a placeholder stands in
+/// for the array.
+pub struct CoverageCounters<'ink> {
+    placeholder: GlobalValue<'ink>,
+    points: RefCell<IndexMap<&'static str, Vec<(CoveragePoint, u64)>>>,
+    lines: RefCell<IndexSet<(&'static str, usize)>>,
+    counters: Cell<u64>,
+    decisions: Cell<usize>,
+}
+
+impl<'ink> CoverageCounters<'ink> {
+    /// creates the counters of the given module
+    pub fn new(module: &Module<'ink>) -> Self {
+        let placeholder = module.add_global(module.get_context().i64_type(), None, COUNTERS_NAME);
+        CoverageCounters {
+            placeholder,
+            points: Default::default(),
+            lines: Default::default(),
+            counters: Default::default(),
+            decisions: Default::default(),
+        }
+    }
+
+    /// counts the execution of the line of the given statement at the current insert position,
+    /// unless an earlier statement on the same line is counted already
+    pub fn count_statement(&self, llvm: &Llvm<'ink>, location: &SourceLocation) {
+        let Some((file, line)) = get_source_line(location) else { return };
+        if self.lines.borrow_mut().insert((file, line)) {
+            self.add_counter(llvm, file, CoveragePoint::Line { line });
+        }
+    }
+
+    /// starts a new decision at the given location, `None` if the location is not in a source file
+    pub fn create_decision(&self, location: &SourceLocation) -> Option<Decision> {
+        let (file, line) = get_source_line(location)?;
+        let block = self.decisions.replace(self.decisions.get() + 1);
+        Some(Decision { file, line, block })
+    }
+
+    /// counts taking the given branch of the decision at the current insert position
+    pub fn count_branch(&self, llvm: &Llvm<'ink>, decision: &Decision, branch: usize) {
+        let point = CoveragePoint::Branch { line: decision.line, block: decision.block, branch };
+        self.add_counter(llvm, decision.file, point);
+    }
+
+    fn add_counter(&self, llvm: &Llvm<'ink>, file: &'static str, point: CoveragePoint) {
+        let index = self.counters.replace(self.counters.get() + 1);
+        let i64_type = llvm.context.i64_type();
+        let counter =
+            unsafe { self.placeholder.as_pointer_value().const_gep(&[i64_type.const_int(index, false)]) };
+
+        let builder = &llvm.builder;
+        let value = builder.build_load(counter, "").into_int_value();
+        let value = builder.build_int_add(value, i64_type.const_int(1, false), "");
+        builder.build_store(counter, value);
+        self.points.borrow_mut().entry(file).or_default().push((point, index));
+    }
+
+    /// generates the array of counters and the function writing them, which is registered to run
+    /// at exit
+    pub fn generate_dump(self, module: &Module<'ink>, llvm: &Llvm<'ink>) {
+        if self.counters.get() == 0 {
+            unsafe { self.placeholder.delete() };
+            return;
+        }
+        let i64_type = llvm.context.i64_type();
+        let array_type = i64_type.array_type(self.counters.get() as u32);
+        let counters = module.add_global(array_type, None, "");
+        counters.set_linkage(Linkage::Internal);
+        counters.set_initializer(&array_type.const_zero());
+        let pointer_type = i64_type.ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC));
+        self.placeholder
+            .as_pointer_value()
+            .replace_all_uses_with(counters.as_pointer_value().const_cast(pointer_type));
+        unsafe { self.placeholder.delete() };
+        counters.as_pointer_value().set_name(COUNTERS_NAME);
+
+        let generator = DumpGenerator { module, llvm };
+        let function = generator.generate_dump_function(counters, &self.points.into_inner());
+        generator.register_destructor(function);
+    }
+}
+
+/// returns the source file and the 1-based line of the given location
+fn get_source_line(location: &SourceLocation) -> Option<(&'static str, usize)> {
+    match location.get_span() {
+        CodeSpan::Range(_) if !location.is_internal() => {
+            location.get_file_name().map(|file| (file, location.get_line_plus_one()))
+        }
+        _ => None,
+    }
+}
+
+struct DumpGenerator<'a, 'ink> {
+    module: &'a Module<'ink>,
+    llvm: &'a Llvm<'ink>,
+}
+
+impl<'a, 'ink> DumpGenerator<'a, 'ink> {
+    fn i8_ptr_type(&self) -> PointerType<'ink> {
+        self.llvm.context.i8_type().ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC))
+    }
+
+    /// returns the C library function with the given name, declaring it if necessary
+    fn get_library_function(
+        &self,
+        name: &str,
+        return_type: BasicTypeEnum<'ink>,
+        parameters: &[BasicMetadataTypeEnum<'ink>],
+        variadic: bool,
+    ) -> FunctionValue<'ink> {
+        self.module.get_function(name).unwrap_or_else(|| {
+            let function_type = return_type.fn_type(parameters, variadic);
+            self.module.add_function(name, function_type, Some(Linkage::External))
+        })
+    }
+
+    fn create_string(&self, value: &str) -> PointerValue<'ink> {
+        self.llvm.builder.build_global_string_ptr(value, "").as_pointer_value()
+    }
+
+    /// generates `void __coverage_dump(void)` appending the counters to the coverage file
+    fn generate_dump_function(
+        &self,
+        counters: GlobalValue<'ink>,
+        points: &IndexMap<&'static str, Vec<(CoveragePoint, u64)>>,
+    ) -> FunctionValue<'ink> {
+        let (context, builder) = (self.llvm.context, &self.llvm.builder);
+        let (i8_ptr, i32_type) = (self.i8_ptr_type(), context.i32_type());
+        let getenv = self.get_library_function("getenv", i8_ptr.into(), &[i8_ptr.into()], false);
+        let fopen = self.get_library_function("fopen", i8_ptr.into(), &[i8_ptr.into(), i8_ptr.into()], false);
+        let fprintf =
+            self.get_library_function("fprintf", i32_type.into(), &[i8_ptr.into(), i8_ptr.into()], true);
+        let fclose = self.get_library_function("fclose", i32_type.into(), &[i8_ptr.into()], false);
+
+        let function = self.module.add_function(DUMP_FUNCTION, context.void_type().fn_type(&[], false), None);
+        function.set_linkage(Linkage::Internal);
+        let entry = context.append_basic_block(function, "entry");
+        let write = context.append_basic_block(function, "write");
+        let exit = context.append_basic_block(function, "exit");
+
+        builder.position_at_end(entry);
+        let variable = self.create_string(COVERAGE_FILE_VARIABLE);
+        let path = self.call(getenv, &[variable.into()]).into_pointer_value();
+        let is_unset = builder.build_is_null(path, "");
+        let path = builder.build_select(is_unset, self.create_string(DEFAULT_COVERAGE_FILE), path, "");
+        let file = self.call(fopen, &[path.into(), self.create_string("a").into()]).into_pointer_value();
+        let is_closed = builder.build_is_null(file, "");
+        builder.build_conditional_branch(is_closed, exit, write);
+
+        builder.position_at_end(write);
+        let line_format = self.create_string("DA:%u,%llu\n");
+        let branch_format = self.create_string("BRDA:%u,%u,%u,%llu\n");
+        let int = |value: usize| i32_type.const_int(value as u64, false).into();
+        let i64_type = context.i64_type();
+        for (source, points) in points {
+            let source_format = self.create_string("SF:%s\n");
+            self.call(fprintf, &[file.into(), source_format.into(), self.create_string(source).into()]);
+            let lines = points.iter().filter(|(it, _)| matches!(it, CoveragePoint::Line { .. }));
+            let branches = points.iter().filter(|(it, _)| matches!(it, CoveragePoint::Branch { .. }));
+            for (point, index) in lines.chain(branches) {
+                let index = [i64_type.const_zero(), i64_type.const_int(*index, false)];
+                let counter = unsafe { counters.as_pointer_value().const_in_bounds_gep(&index) };
+                let count = builder.build_load(counter, "").into();
+                match point {
+                    CoveragePoint::Line { line } => {
+                        self.call(fprintf, &[file.into(), line_format.into(), int(*line), count])
+                    }
+                    CoveragePoint::Branch { line, block, branch } => self.call(
+                        fprintf,
+                        &[file.into(), branch_format.into(), int(*line), int(*block), int(*branch), count],
+                    ),
+                };
+            }
+            self.call(fprintf, &[file.into(), self.create_string("end_of_record\n").into()]);
+        }
+        self.call(fclose, &[file.into()]);
+        builder.build_unconditional_branch(exit);
+
+        builder.position_at_end(exit);
+        builder.build_return(None);
+        function
+    }
+
+    fn call(
+        &self,
+        function: FunctionValue<'ink>,
+        arguments: &[BasicMetadataValueEnum<'ink>],
+    ) -> BasicValueEnum<'ink> {
+        self.llvm
+            .builder
+            .build_call(function, arguments, "")
+            .try_as_basic_value()
+            .left()
+            .expect(INTERNAL_LLVM_ERROR)
+    }
+
+    /// adds the given function to `llvm.global_dtors`, the entries of all modules are appended when
+    /// the modules are linked
+    fn register_destructor(&self, function: FunctionValue<'ink>) {
+        let context = self.llvm.context;
+        let entry_type = context.struct_type(
+            &[
+                context.i32_type().into(),
+                function.get_type().ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC)).into(),
+                self.i8_ptr_type().into(),
+            ],
+            false,
+        );
+        let entry = entry_type.const_named_struct(&[
+            context.i32_type().const_int(65535, false).into(),
+            function.as_global_value().as_pointer_value().into(),
+            self.i8_ptr_type().const_null().into(),
+        ]);
+        let destructors = self.module.add_global(entry_type.array_type(1), None, "llvm.global_dtors");
+        destructors.set_linkage(Linkage::Appending);
+        destructors.set_initializer(&entry_type.const_array(&[entry]));
+    }
+}
@@ -1,5 +1,6 @@
 // Copyright (c) 2020 Ghaith Hachem and Mathias Rieder
 use crate::codegen::debug::Debug;
+use crate::datalayout::{self, Bytes};
 use crate::index::{Index, VariableIndexEntry, VariableType};
 use crate::resolver::{AstAnnotations, Dependency};
 use crate::typesystem::{self, DataTypeInformation, Dimension, StringEncoding, StructSource};
@@ -23,6 +24,7 @@ use plc_diagnostics::diagnostics::Diagnostic;
 use plc_source::source_location::SourceLocation;
 /// the data_type_generator generates user defined data-types
 /// - Structures
+/// - Unions
 /// - Enum types
 /// - SubRange types
 /// - Alias types
@@ -43,6 +45,7 @@ pub struct DataTypeGenerator<'ink, 'b> {
 /// generates the llvm-type for the given data-type and registers it at the index
 /// this function may create and register a ...
 /// - Struct type for a STRUCT
+/// - Struct type overlaying all members of a UNION
 /// - global variables for enum-elements
 /// - an alias index entry for sub-range types
 /// - Array type for arrays
@@ -83,7 +86,9 @@ pub fn generate_data_types<'ink>(
     // first create all STUBs for struct types (empty structs)
     // and associate them in the llvm index
     for (name, user_type) in &types {
-        if let DataTypeInformation::Struct { name: struct_name, .. } = user_type.get_type_information() {
+        if let DataTypeInformation::Struct { name: struct_name, .. }
+        | DataTypeInformation::Union { name: struct_name, .. } = user_type.get_type_information()
+        {
             generator.types_index.associate_type(name, llvm.create_struct_stub(struct_name).into())?;
         }
     }
@@ -174,7 +179,7 @@ impl<'ink, 'b> DataTypeGenerator<'ink, 'b> {
             let members = members
                 .iter()
                 .filter(|it| !it.is_temp() && !it.is_return())
-                .map(|m| self.types_index.get_associated_type(m.get_type_name()))
+                .map(|m| self.get_member_type(m))
                 .collect::<Result<Vec<BasicTypeEnum>, Diagnostic>>()?;
 
             let struct_type = match source {
@@ -187,9 +192,52 @@ impl<'ink, 'b> DataTypeGenerator<'ink, 'b> {
 
             struct_type.set_body(members.as_slice(), false);
         }
+
+        if let DataTypeInformation::Union { members, .. } = information {
+            let body = self.create_union_body(data_type, members)?;
+            let union_type = self.types_index.get_associated_type(data_type.get_name())?.into_struct_type();
+            union_type.set_body(body.as_slice(), false);
+        }
         Ok(())
     }
 
+    /// A union is represented as a struct holding its most strictly aligned member, padded with bytes up to
+    /// the size of the biggest member. All members are accessed through a pointer to the union.
+    fn create_union_body(
+        &self,
+        data_type: &DataType,
+        members: &[VariableIndexEntry],
+    ) -> Result<Vec<BasicTypeEnum<'ink>>, Diagnostic> {
+        let size_of = |type_info: &DataTypeInformation| {
+            datalayout::get_allocated_size(type_info, self.index).ok_or_else(|| {
+                Diagnostic::codegen_error(
+                    format!("Cannot determine the size of union {}", data_type.get_name()),
+                    data_type.location.clone(),
+                )
+            })
+        };
+
+        let mut dominant: Option<(&VariableIndexEntry, Bytes, u32)> = None;
+        for member in members {
+            let type_info = self.index.get_type_information_or_void(member.get_type_name());
+            let alignment = datalayout::get_allocated_alignment(type_info, self.index);
+            let size = size_of(type_info)?;
+            if dominant
+                .map_or(true, |(_, max_alignment, max_size)| (alignment, size) > (max_alignment, max_size))
+            {
+                dominant = Some((member, alignment, size));
+            }
+        }
+
+        let Some((member, _, member_size)) = dominant else { return Ok(vec![]) };
+        let mut body = vec![self.types_index.get_associated_type(member.get_type_name())?];
+        let padding = size_of(data_type.get_type_information())? - member_size;
+        if padding > 0 {
+            body.push(self.llvm.context.i8_type().array_type(padding).into());
+        }
+        Ok(body)
+    }
+
     /// Creates an llvm type to be associated with the given data type.
     /// Generates only an opaque type for structs.
     /// Eagerly generates but does not associate nested array and referenced aliased types
@@ -204,6 +252,7 @@ impl<'ink, 'b> DataTypeGenerator<'ink, 'b> {
                 }
                 StructSource::Internal(_) => self.types_index.get_associated_type(data_type.get_name()),
             },
+            DataTypeInformation::Union { .. } => self.types_index.get_associated_type(data_type.get_name()),
 
             // We distinguish between two types of arrays, normal and variable length ones.
             // Variable Length Arrays are defined by having unknown sizes at compile time, thus we handle
@@ -266,6 +315,17 @@ impl<'ink, 'b> DataTypeGenerator<'ink, 'b> {
         }
     }
 
+    /// returns the type of the given member inside its struct. Members declared at a direct address
+    /// keep their position but occupy no memory, they are accessed in the process image
+    fn get_member_type(&self, member: &VariableIndexEntry) -> Result<BasicTypeEnum<'ink>, Diagnostic> {
+        let member_type = self.types_index.get_associated_type(member.get_type_name())?;
+        if member.is_in_process_image() {
+            Ok(member_type.array_type(0).into())
+        } else {
+            Ok(member_type)
+        }
+    }
+
     fn generate_initial_value(
         &mut self,
         data_type: &DataType,
@@ -277,6 +337,11 @@ impl<'ink, 'b> DataTypeGenerator<'ink, 'b> {
                     .iter()
                     .filter(|it| it.get_variable_type() != VariableType::Temp)
                     .map(|it| {
+                        if it.is_in_process_image() {
+                            return self
+                                .get_member_type(it)
+                                .map(|t| (it.get_qualified_name(), get_default_for(t)));
+                        }
                         self.generate_initial_value_for_variable(it).and_then(|v| match v {
                             Some(v) => Ok((it.get_qualified_name(), v)),
                             None => self
@@ -9,6 +9,7 @@ use crate::{
         const_expressions::ConstId, ArgumentType, ImplementationIndexEntry, Index, PouIndexEntry,
         VariableIndexEntry, VariableType,
     },
+    process_image::{self, ImageAddress},
     resolver::{AnnotationMap, AstAnnotations, StatementAnnotation},
     typesystem::{
         is_same_type_class, DataType, DataTypeInformation, DataTypeInformationProvider, Dimension,
@@ -26,8 +27,8 @@ use inkwell::{
 };
 use plc_ast::{
     ast::{
-        flatten_expression_list, AstFactory, AstNode, AstStatement, DirectAccessType, Operator,
-        ReferenceAccess, ReferenceExpr,
+        flatten_expression_list, AstFactory, AstNode, AstStatement, DirectAccessType, HardwareAccessType,
+        Operator, ReferenceAccess, ReferenceExpr,
     },
     literals::AstLiteral,
 };
@@ -98,6 +99,18 @@ impl<'ink> ExpressionValue<'ink> {
     }
 }
 
+/// A location inside one of the process images
+#[derive(Debug)]
+pub struct ProcessImageLocation<'ink> {
+    /// points to the first addressed byte, casted to the accessed type
+    pub pointer: PointerValue<'ink>,
+    /// the addressed bit inside the byte for bit-accesses like `%IX1.2`
+    pub bit: Option<IntValue<'ink>>,
+    /// whether the bit or the whole byte is addressed, for variables declared at a wildcard address
+    /// whose instances are mapped to single bits as well as to bytes. `None` if the bit is always addressed
+    pub is_bit: Option<IntValue<'ink>>,
+}
+
 impl<'ink, 'b> ExpressionCodeGenerator<'ink, 'b> {
     /// creates a new expression generator
     ///
@@ -204,6 +217,10 @@ impl<'ink, 'b> ExpressionCodeGenerator<'ink, 'b> {
                 return self.generate_constant_expression(qualified_name, expression);
             }
         }
+        // direct addresses and variables declared `AT` them live in the process image
+        if let Some(location) = self.find_process_image_location(expression)? {
+            return Ok(self.generate_process_image_value(location));
+        }
         // generate the expression
         match expression.get_stmt() {
             AstStatement::ReferenceExpr(data) => {
@@ -234,16 +251,168 @@ impl<'ink, 'b> ExpressionCodeGenerator<'ink, 'b> {
             AstStatement::UnaryExpression(data) => {
                 self.generate_unary_expression(&data.operator, &data.value).map(ExpressionValue::RValue)
             }
-            // TODO: Hardware access needs to be evaluated, see #648
-            AstStatement::HardwareAccess { .. } => {
-                Ok(ExpressionValue::RValue(self.llvm.i32_type().const_zero().into()))
-            }
+            AstStatement::HardwareAccess { .. } => Err(Diagnostic::codegen_error(
+                "Direct address is not part of a process image",
+                expression.get_location(),
+            )),
             AstStatement::ParenExpression(expr) => self.generate_expression_value(expr),
             //fallback
             _ => self.generate_literal(expression),
         }
     }
 
+    /// returns the process image location behind a direct address like `%IX1.2` or behind a
+    /// reference to a variable declared `AT` such an address. Returns `None` for all other expressions
+    /// and for hierarchical addresses that are not mapped into a process image
+    pub fn find_process_image_location(
+        &self,
+        expression: &AstNode,
+    ) -> Result<Option<ProcessImageLocation<'ink>>, Diagnostic> {
+        let (direction, address, type_name) = match (expression.get_stmt(), self.annotations.get(expression))
+        {
+            (AstStatement::HardwareAccess(data), _) => {
+                let address = ImageAddress::from_statements(data.access, &data.address);
+                (
+                    data.direction,
+                    address,
+                    self.annotations.get_type_or_void(expression, self.index).get_name(),
+                )
+            }
+            (
+                AstStatement::ReferenceExpr(data),
+                Some(StatementAnnotation::Variable { qualified_name, .. }),
+            ) => {
+                let Some((variable, binding)) = self
+                    .index
+                    .find_fully_qualified_variable(qualified_name)
+                    .and_then(|it| it.get_hardware_binding().map(|binding| (it, binding)))
+                else {
+                    return Ok(None);
+                };
+                if binding.access == DirectAccessType::Template {
+                    return self.find_mapped_location(expression, data, variable);
+                }
+                (binding.direction, binding.image_address, variable.get_type_name())
+            }
+            _ => return Ok(None),
+        };
+
+        let Some(address) = address else {
+            return Ok(None);
+        };
+        let Some(pointer) = self.get_process_image_pointer(direction, address, type_name)? else {
+            return Ok(None);
+        };
+        let bit = address.bit.map(|it| self.llvm.context.i8_type().const_int(it as u64, false));
+        Ok(Some(ProcessImageLocation { pointer, bit, is_bit: None }))
+    }
+
+    /// returns the location of a reference to a variable declared at a wildcard address like `%I*`.
+    /// The referenced instance is compared with the instances mapped by the I/O mapping to address its
+    /// location in the process image, instances that are not mapped use their own storage.
+    /// Returns `None` if no instance of the variable is mapped
+    fn find_mapped_location(
+        &self,
+        expression: &AstNode,
+        reference: &ReferenceExpr,
+        variable: &VariableIndexEntry,
+    ) -> Result<Option<ProcessImageLocation<'ink>>, Diagnostic> {
+        let mapped = self.index.get_mapped_addresses(variable.get_qualified_name());
+        if mapped.is_empty() {
+            return Ok(None);
+        }
+        let builder = &self.llvm.builder;
+        let i8_type = self.llvm.context.i8_type();
+        let bool_type = self.llvm.context.bool_type();
+        let own = self
+            .generate_reference_expression(&reference.access, reference.base.as_deref(), expression)?
+            .get_basic_value_enum()
+            .into_pointer_value();
+        let own_address =
+            builder.build_pointer_cast(own, i8_type.ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC)), "");
+
+        let (mut pointer, mut bit, mut is_bit) = (own, i8_type.const_zero(), bool_type.const_zero());
+        for instance in mapped {
+            let image = self.get_process_image_pointer(
+                instance.direction,
+                instance.address,
+                variable.get_type_name(),
+            )?;
+            let root = self.llvm_index.find_global_value(&instance.root);
+            let Some((image, root)) = image.zip(root) else {
+                continue;
+            };
+            let root = builder.build_pointer_cast(root.as_pointer_value(), own_address.get_type(), "");
+            let offset = self.llvm.context.i64_type().const_int(instance.offset as u64, false);
+            let instance_address = unsafe { builder.build_in_bounds_gep(root, &[offset], "") };
+            let is_instance = builder.build_int_compare(IntPredicate::EQ, own_address, instance_address, "");
+            let image = builder.build_pointer_cast(image, own.get_type(), "");
+            pointer = builder.build_select(is_instance, image, pointer, "").into_pointer_value();
+            let position = i8_type.const_int(instance.address.bit.unwrap_or_default() as u64, false);
+            bit = builder.build_select(is_instance, position, bit, "").into_int_value();
+            let addresses_bit = bool_type.const_int(instance.address.bit.is_some() as u64, false);
+            is_bit = builder.build_select(is_instance, addresses_bit, is_bit, "").into_int_value();
+        }
+        // only BOOLs can be mapped to single bits
+        let has_bits = mapped.iter().any(|it| it.address.bit.is_some());
+        Ok(Some(ProcessImageLocation {
+            pointer,
+            bit: has_bits.then_some(bit),
+            is_bit: has_bits.then_some(is_bit),
+        }))
+    }
+
+    /// returns a pointer to the given address in the process image of the given direction, casted
+    /// to the given type unless a single bit is addressed. Returns `None` if there is no such image
+    fn get_process_image_pointer(
+        &self,
+        direction: HardwareAccessType,
+        address: ImageAddress,
+        type_name: &str,
+    ) -> Result<Option<PointerValue<'ink>>, Diagnostic> {
+        let image =
+            process_image::get_image_name(direction).and_then(|it| self.llvm_index.find_global_value(it));
+        let Some(image) = image else {
+            return Ok(None);
+        };
+
+        let pointer = unsafe {
+            self.llvm.builder.build_in_bounds_gep(
+                image.as_pointer_value(),
+                &[
+                    self.llvm.i32_type().const_zero(),
+                    self.llvm.i32_type().const_int(address.byte as u64, false),
+                ],
+                "",
+            )
+        };
+        if address.bit.is_some() {
+            return Ok(Some(pointer));
+        }
+        let target_type = self.llvm_index.get_associated_type(type_name)?;
+        Ok(Some(self.llvm.builder.build_pointer_cast(
+            pointer,
+            target_type.ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC)),
+            "",
+        )))
+    }
+
+    /// generates the value at the given process image location, single bits are loaded and
+    /// extracted from their byte
+    fn generate_process_image_value(&self, location: ProcessImageLocation<'ink>) -> ExpressionValue<'ink> {
+        let Some(bit) = location.bit else {
+            return ExpressionValue::LValue(location.pointer);
+        };
+        let byte = self.llvm.load_pointer(&location.pointer, "").into_int_value();
+        let shift = self.llvm.builder.build_right_shift(byte, bit, false, "shift");
+        let value = self.llvm.builder.build_and(shift, byte.get_type().const_int(1, false), "");
+        let value = match location.is_bit {
+            Some(is_bit) => self.llvm.builder.build_select(is_bit, value, byte, ""),
+            None => value.as_basic_value_enum(),
+        };
+        ExpressionValue::RValue(value)
+    }
+
     /// Propagate the constant value of the constant reference to  `qualified_name`.
     /// - `qualified _name` the qualified name of the referenced constant variable we want to propagate
     /// - `expression` the original expression
@@ -569,6 +738,7 @@ impl<'ink, 'b> ExpressionCodeGenerator<'ink, 'b> {
                     //Special string handling
                     if (assigned_output_type.is_string() && output_value_type.is_string())
                         || (assigned_output_type.is_struct() && output_value_type.is_struct())
+                        || (assigned_output_type.is_union() && output_value_type.is_union())
                         || (assigned_output_type.is_array() && output_value_type.is_array())
                     {
                         self.generate_string_store(
@@ -1267,14 +1437,28 @@ impl<'ink, 'b> ExpressionCodeGenerator<'ink, 'b> {
                     }
                 }
                 Some(StatementAnnotation::Variable { qualified_name, .. }) => {
-                    let member_location = self
+                    let member = self
                         .index
                         .find_fully_qualified_variable(qualified_name)
-                        .map(VariableIndexEntry::get_location_in_parent)
                         .ok_or_else(|| Diagnostic::unresolved_reference(qualified_name, offset.clone()))?;
+
+                    // all members of a union share the union's address
+                    let container = qualified_name.rsplit_once('.').map(|(container, _)| container);
+                    if container
+                        .and_then(|it| self.index.find_effective_type_info(it))
+                        .is_some_and(DataTypeInformation::is_union)
+                    {
+                        let member_type = self.llvm_index.get_associated_type(member.get_type_name())?;
+                        return Ok(self.llvm.builder.build_pointer_cast(
+                            *qualifier,
+                            member_type.ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC)),
+                            name,
+                        ));
+                    }
+
                     let gep: PointerValue<'_> = self.llvm.get_member_pointer_from_struct(
                         *qualifier,
-                        member_location,
+                        member.get_location_in_parent(),
                         name,
                         offset,
                     )?;
@@ -2278,6 +2462,7 @@ impl<'ink, 'b> ExpressionCodeGenerator<'ink, 'b> {
                 right_statement.get_location(),
             )?;
         } else if (left_type.is_struct() && right_type.is_struct())
+            || (left_type.is_union() && right_type.is_union())
             || (left_type.is_array() && right_type.is_array())
         {
             //memcopy right_statement into left
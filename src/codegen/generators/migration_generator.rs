@@ -0,0 +1,304 @@
+// Copyright (c) 2020 Ghaith Hachem and Mathias Rieder
+//! Generates the functions migrating the state of a previous build into the current build
+//! (see [`crate::online_change`]).
+//!
+//! For every global variable and program instance that already existed with the same type in the
+//! previous build, the following function is exported:
+//! ```c
+//! void __migrate_<symbol>(const void* previous);
+//! ```
+//! It copies the state of the instance of the previous build at the given address into the
+//! instance of the current build, e.g. `__migrate_mainProg_instance`. Members are matched by
+//! their name. New members, members whose type changed and pointers (which would still point into
+//! the memory of the previous build) keep their current value.
+use std::collections::HashMap;
+
+use inkwell::{
+    module::{Linkage, Module},
+    types::PointerType,
+    values::{FunctionValue, PointerValue},
+    AddressSpace, IntPredicate,
+};
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::SourceLocation;
+
+use crate::{
+    index::{Index, VariableIndexEntry},
+    online_change::{get_stateful_instances, get_stateful_types, record_type, LayoutRecord},
+    symbol_map::{get_member_offsets, get_size, get_type_description, is_allocated},
+    typesystem::{DataTypeInformation, StructSource},
+};
+
+use super::{llvm::Llvm, ADDRESS_SPACE_GENERIC};
+
+pub const MIGRATION_FUNCTION_PREFIX: &str = "__migrate_";
+
+/// generates the functions migrating the instances of the given previous layout into the given module
+pub fn generate_migration_functions<'ink>(
+    module: &Module<'ink>,
+    llvm: &Llvm<'ink>,
+    index: &Index,
+    previous: &LayoutRecord,
+) -> Result<(), Diagnostic> {
+    let mut generator = MigrationGenerator { module, llvm, index, previous, functions: HashMap::new() };
+    generator.generate().map_err(|message| Diagnostic::codegen_error(message, SourceLocation::undefined()))
+}
+
+/// how an instance of a type is transferred from the previous into the current build
+enum Migration<'idx> {
+    /// the layout did not change, the memory is copied as is
+    Copy(u32),
+    /// the struct or POU changed, its members are migrated one by one
+    Members(&'idx str),
+    /// the elements of an array are migrated one by one
+    Elements {
+        inner_type_name: &'idx str,
+        length: u32,
+        previous_size: u32,
+        size: u32,
+    },
+    Skip,
+}
+
+struct MigrationGenerator<'a, 'ink> {
+    module: &'a Module<'ink>,
+    llvm: &'a Llvm<'ink>,
+    index: &'a Index,
+    previous: &'a LayoutRecord,
+    /// the migration functions of all changed types, by their lower-case name
+    functions: HashMap<String, FunctionValue<'ink>>,
+}
+
+impl<'a, 'ink> MigrationGenerator<'a, 'ink> {
+    fn i8_ptr_type(&self) -> PointerType<'ink> {
+        self.llvm.context.i8_type().ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC))
+    }
+
+    fn generate(&mut self) -> Result<(), String> {
+        let i8_ptr = self.i8_ptr_type();
+        let function_type = self.llvm.context.void_type().fn_type(&[i8_ptr.into(), i8_ptr.into()], false);
+        // declare the functions of all changed types first, so they can call each other
+        let mut changed_types = vec![];
+        for data_type in get_stateful_types(self.index) {
+            if self.previous.find_type(data_type.get_name()).is_some()
+                && !self.can_copy(data_type.get_name())?
+            {
+                let function = self.module.add_function(
+                    &format!("{MIGRATION_FUNCTION_PREFIX}type_{}", data_type.get_name()),
+                    function_type,
+                    Some(Linkage::Private),
+                );
+                self.functions.insert(data_type.get_name().to_lowercase(), function);
+                changed_types.push(data_type.get_name());
+            }
+        }
+        for type_name in changed_types {
+            self.generate_type_migration(type_name)?;
+        }
+        for instance in get_stateful_instances(self.index) {
+            self.generate_instance_migration(instance)?;
+        }
+        Ok(())
+    }
+
+    /// generates the exported function migrating the given global variable or program instance
+    fn generate_instance_migration(&self, instance: &'a VariableIndexEntry) -> Result<(), String> {
+        let Some(record) = self.previous.find_instance(instance.get_name()) else {
+            return Ok(());
+        };
+        if record.type_name != get_type_description(self.index, instance.get_type_name()) {
+            return Ok(());
+        }
+        let context = self.llvm.context;
+        let function = self.module.add_function(
+            &format!("{MIGRATION_FUNCTION_PREFIX}{}", instance.get_name()),
+            context.void_type().fn_type(&[self.i8_ptr_type().into()], false),
+            None,
+        );
+        let entry = context.append_basic_block(function, "entry");
+        self.llvm.builder.position_at_end(entry);
+        let previous = function.get_nth_param(0).expect("parameter").into_pointer_value();
+        let current = self
+            .module
+            .get_global(instance.get_name())
+            .unwrap_or_else(|| {
+                let global = self.llvm.create_global_variable(
+                    self.module,
+                    instance.get_name(),
+                    context.i8_type().into(),
+                );
+                global.set_linkage(Linkage::External);
+                global
+            })
+            .as_pointer_value()
+            .const_cast(self.i8_ptr_type());
+        self.generate_migration(function, instance.get_type_name(), previous, current)?;
+        self.llvm.builder.build_return(None);
+        Ok(())
+    }
+
+    /// generates the body of the function migrating the given struct or POU member by member
+    fn generate_type_migration(&self, type_name: &'a str) -> Result<(), String> {
+        let function = self.functions[&type_name.to_lowercase()];
+        let record =
+            self.previous.find_type(type_name).expect("changed types are part of the previous layout");
+        let entry = self.llvm.context.append_basic_block(function, "entry");
+        self.llvm.builder.position_at_end(entry);
+        let previous = function.get_nth_param(0).expect("parameter").into_pointer_value();
+        let current = function.get_nth_param(1).expect("parameter").into_pointer_value();
+        for (member, offset) in get_member_offsets(self.index, type_name)? {
+            let Some(previous_member) = record
+                .find_member(member.get_name())
+                .filter(|it| it.type_name == get_type_description(self.index, member.get_type_name()))
+            else {
+                continue;
+            };
+            let previous = self.offset(previous, previous_member.offset);
+            let current = self.offset(current, offset);
+            self.generate_migration(function, member.get_type_name(), previous, current)?;
+        }
+        self.llvm.builder.build_return(None);
+        Ok(())
+    }
+
+    /// generates the migration of a single instance of the given type at the current position of the builder
+    fn generate_migration(
+        &self,
+        function: FunctionValue<'ink>,
+        type_name: &'a str,
+        previous: PointerValue<'ink>,
+        current: PointerValue<'ink>,
+    ) -> Result<(), String> {
+        let builder = &self.llvm.builder;
+        let i32_type = self.llvm.context.i32_type();
+        match self.get_migration(type_name)? {
+            Migration::Copy(size) => {
+                builder.build_memcpy(current, 1, previous, 1, i32_type.const_int(size as u64, false))?;
+            }
+            Migration::Members(type_name) => {
+                let migration = self.functions[&type_name.to_lowercase()];
+                builder.build_call(migration, &[previous.into(), current.into()], "");
+            }
+            Migration::Elements { inner_type_name, length, previous_size, size } => {
+                let context = self.llvm.context;
+                let entry = builder.get_insert_block().expect("builder is positioned");
+                let body = context.append_basic_block(function, "element");
+                let continue_block = context.append_basic_block(function, "continue");
+                builder.build_unconditional_branch(body);
+
+                builder.position_at_end(body);
+                let position = builder.build_phi(i32_type, "position");
+                let position_value = position.as_basic_value().into_int_value();
+                let previous_element = unsafe {
+                    builder.build_in_bounds_gep(
+                        previous,
+                        &[builder.build_int_mul(
+                            position_value,
+                            i32_type.const_int(previous_size as u64, false),
+                            "",
+                        )],
+                        "previous_element",
+                    )
+                };
+                let current_element = unsafe {
+                    builder.build_in_bounds_gep(
+                        current,
+                        &[builder.build_int_mul(position_value, i32_type.const_int(size as u64, false), "")],
+                        "current_element",
+                    )
+                };
+                self.generate_migration(function, inner_type_name, previous_element, current_element)?;
+                let next = builder.build_int_add(position_value, i32_type.const_int(1, false), "next");
+                let done = builder.build_int_compare(
+                    IntPredicate::EQ,
+                    next,
+                    i32_type.const_int(length as u64, false),
+                    "",
+                );
+                let latch = builder.get_insert_block().expect("builder is positioned");
+                builder.build_conditional_branch(done, continue_block, body);
+                position.add_incoming(&[(&i32_type.const_zero(), entry), (&next, latch)]);
+
+                builder.position_at_end(continue_block);
+            }
+            Migration::Skip => {}
+        }
+        Ok(())
+    }
+
+    fn offset(&self, pointer: PointerValue<'ink>, offset: u32) -> PointerValue<'ink> {
+        let offset = self.llvm.context.i32_type().const_int(offset as u64, false);
+        unsafe { self.llvm.builder.build_in_bounds_gep(pointer, &[offset], "") }
+    }
+
+    fn get_migration(&self, type_name: &'a str) -> Result<Migration<'a>, String> {
+        if self.can_copy(type_name)? {
+            return get_size(self.index, type_name).map(Migration::Copy);
+        }
+        let migration = match self.index.get_effective_type_or_void_by_name(type_name).get_type_information()
+        {
+            DataTypeInformation::Struct { name, .. } if self.functions.contains_key(&name.to_lowercase()) => {
+                Migration::Members(name)
+            }
+            DataTypeInformation::Array { inner_type_name, dimensions, .. } => {
+                let mut length = 1;
+                for dimension in dimensions {
+                    length *= dimension.get_length(self.index)?;
+                }
+                Migration::Elements {
+                    inner_type_name,
+                    length,
+                    previous_size: self.get_previous_size(inner_type_name)?,
+                    size: get_size(self.index, inner_type_name)?,
+                }
+            }
+            _ => Migration::Skip,
+        };
+        Ok(migration)
+    }
+
+    /// returns true if instances of the given type can be copied as they are, i.e. their layout
+    /// did not change and they do not contain pointers
+    fn can_copy(&self, type_name: &str) -> Result<bool, String> {
+        let data_type = self.index.get_effective_type_or_void_by_name(type_name);
+        match data_type.get_type_information() {
+            DataTypeInformation::Pointer { .. } => Ok(false),
+            DataTypeInformation::Array { inner_type_name, .. } => self.can_copy(inner_type_name),
+            DataTypeInformation::Struct { source, members, .. } => {
+                if let (StructSource::OriginalDeclaration | StructSource::Pou(..), Some(record)) =
+                    (source, self.previous.find_type(data_type.get_name()))
+                {
+                    if *record != record_type(self.index, data_type)? {
+                        return Ok(false);
+                    }
+                }
+                for member in members.iter().filter(|it| is_allocated(it)) {
+                    if !self.can_copy(member.get_type_name())? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            _ => Ok(true),
+        }
+    }
+
+    /// returns the size an instance of the given type had in the previous build
+    fn get_previous_size(&self, type_name: &str) -> Result<u32, String> {
+        let data_type = self.index.get_effective_type_or_void_by_name(type_name);
+        match data_type.get_type_information() {
+            DataTypeInformation::Struct { .. } => match self.previous.find_type(data_type.get_name()) {
+                Some(record) => Ok(record.size),
+                None => get_size(self.index, type_name),
+            },
+            DataTypeInformation::Array { inner_type_name, dimensions, .. } => {
+                let mut size = self.get_previous_size(inner_type_name)?;
+                for dimension in dimensions {
+                    size *= dimension.get_length(self.index)?;
+                }
+                Ok(size)
+            }
+            _ => get_size(self.index, type_name),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) 2020 Ghaith Hachem and Mathias Rieder
 
 use super::{
+    coverage_generator::CoverageCounters,
     data_type_generator::get_default_for,
     expression_generator::ExpressionCodeGenerator,
     llvm::{GlobalValueExt, Llvm},
@@ -281,6 +282,7 @@ impl<'ink, 'cg> PouGenerator<'ink, 'cg> {
         &self,
         implementation: &Implementation,
         debug: &DebugBuilderEnum<'ink>,
+        coverage: Option<&CoverageCounters<'ink>>,
     ) -> Result<(), Diagnostic> {
         let context = self.llvm.context;
         let mut local_index = LlvmTypedIndex::create_child(self.llvm_index);
@@ -386,6 +388,7 @@ impl<'ink, 'cg> PouGenerator<'ink, 'cg> {
                 &local_index,
                 &function_context,
                 debug,
+                coverage,
             );
             statement_gen.generate_body(&implementation.statements)?;
             statement_gen.generate_return_statement()?;
@@ -657,8 +660,8 @@ impl<'ink, 'cg> PouGenerator<'ink, 'cg> {
         // 4th try, see if the datatype has a default initializer
         } else if let Some(initial_value) = self.llvm_index.find_associated_initial_value(variable.get_type_name()) {
             (initial_value, DEFAULT_ALIGNMENT)
-        // no inital value defined + array type - so we use a 0 byte the memset the array to 0
-        }else if v_type_info.is_array() || v_type_info.is_string() {
+        // no inital value defined + array or union type - so we use a 0 byte the memset the memory to 0
+        }else if v_type_info.is_array() || v_type_info.is_string() || v_type_info.is_union() {
             (self.llvm.context.i8_type().const_zero().as_basic_value_enum(), DEFAULT_ALIGNMENT)
         // no initial value defined + no-array
         } else {
@@ -2,7 +2,8 @@ use std::collections::HashMap;
 
 // Copyright (c) 2020 Ghaith Hachem and Mathias Rieder
 use super::{
-    expression_generator::{to_i1, ExpressionCodeGenerator},
+    coverage_generator::{CoverageCounters, Decision},
+    expression_generator::{to_i1, ExpressionCodeGenerator, ProcessImageLocation},
     llvm::Llvm,
 };
 use crate::{
@@ -16,7 +17,7 @@ use inkwell::{
     basic_block::BasicBlock,
     builder::Builder,
     context::Context,
-    values::{BasicValueEnum, FunctionValue, PointerValue},
+    values::{BasicValue, BasicValueEnum, FunctionValue, PointerValue},
 };
 use plc_ast::{
     ast::{
@@ -55,6 +56,9 @@ pub struct StatementCodeGenerator<'a, 'b> {
     pub current_loop_continue: Option<BasicBlock<'a>>,
 
     pub debug: &'b DebugBuilderEnum<'a>,
+
+    /// the coverage counters to instrument the statements with, if coverage is enabled
+    coverage: Option<&'b CoverageCounters<'a>>,
 }
 
 impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
@@ -66,6 +70,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
         llvm_index: &'b LlvmTypedIndex<'a>,
         linking_context: &'b FunctionContext<'a, 'b>,
         debug: &'b DebugBuilderEnum<'a>,
+        coverage: Option<&'b CoverageCounters<'a>>,
     ) -> StatementCodeGenerator<'a, 'b> {
         StatementCodeGenerator {
             llvm,
@@ -78,6 +83,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
             current_loop_exit: None,
             current_loop_continue: None,
             debug,
+            coverage,
         }
     }
 
@@ -116,6 +122,12 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
     ///
     /// - `statement` the statement to be generated
     pub fn generate_statement(&self, statement: &AstNode) -> Result<(), Diagnostic> {
+        if !matches!(
+            statement.get_stmt(),
+            AstStatement::EmptyStatement(..) | AstStatement::LabelStatement(..)
+        ) {
+            self.count_statement(statement);
+        }
         match statement.get_stmt() {
             AstStatement::EmptyStatement(..) => {
                 //nothing to generate
@@ -247,16 +259,19 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
         if left_statement.has_direct_access() {
             return self.generate_direct_access_assignment(left_statement, right_statement);
         }
-        //TODO: Also hacky but for now we cannot generate assignments for hardware access
-        if matches!(left_statement.get_stmt(), AstStatement::HardwareAccess { .. }) {
-            return Ok(());
-        }
         let exp_gen = self.create_expr_generator();
-        let left: PointerValue = exp_gen.generate_expression_value(left_statement).and_then(|it| {
-            it.get_basic_value_enum().try_into().map_err(|err| {
-                Diagnostic::codegen_error(format!("{err:?}").as_str(), left_statement.get_location())
-            })
-        })?;
+        let left: PointerValue = match exp_gen.find_process_image_location(left_statement)? {
+            // single bits in the process image need to be merged into their byte
+            Some(location @ ProcessImageLocation { bit: Some(_), .. }) => {
+                return self.generate_process_image_bit_assignment(location, right_statement);
+            }
+            Some(location) => location.pointer,
+            None => exp_gen.generate_expression_value(left_statement).and_then(|it| {
+                it.get_basic_value_enum().try_into().map_err(|err| {
+                    Diagnostic::codegen_error(format!("{err:?}").as_str(), left_statement.get_location())
+                })
+            })?,
+        };
 
         let left_type = exp_gen.get_type_hint_info_for(left_statement)?;
         // if the lhs-type is a subrange type we may need to generate a check-call
@@ -372,6 +387,42 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
         Ok(())
     }
 
+    /// generates an assignment to a single bit in the process image like `%QX1.2 := TRUE`
+    ///
+    /// - `location` the byte holding the bit and the position of the bit inside the byte
+    /// - `right_statement` the value to assign
+    fn generate_process_image_bit_assignment(
+        &self,
+        location: ProcessImageLocation<'a>,
+        right_statement: &AstNode,
+    ) -> Result<(), Diagnostic> {
+        let ProcessImageLocation { pointer, bit: Some(bit), is_bit } = location else {
+            unreachable!("only single bits are merged into their byte")
+        };
+        let exp_gen = self.create_expr_generator();
+        let byte = self.llvm.load_pointer(&pointer, "").into_int_value();
+        let byte_type = byte.get_type();
+        //Erase the target bit
+        let mask = self
+            .llvm
+            .builder
+            .build_not(self.llvm.builder.build_left_shift(byte_type.const_int(1, false), bit, ""), "");
+        let erased = self.llvm.builder.build_and(byte, mask, "erase");
+        //Move the new value to the target bit
+        let right = to_i1(exp_gen.generate_expression(right_statement)?.into_int_value(), &self.llvm.builder);
+        let right = self.llvm.builder.build_int_z_extend(right, byte_type, "");
+        let value = self.llvm.builder.build_left_shift(right, bit, "value");
+        //OR the result and store it in the byte
+        let or_value = self.llvm.builder.build_or(erased, value, "or");
+        //Instances that are not mapped to a single bit store the whole byte
+        let or_value = match is_bit {
+            Some(is_bit) => self.llvm.builder.build_select(is_bit, or_value, right, ""),
+            None => or_value.as_basic_value_enum(),
+        };
+        self.llvm.builder.build_store(pointer, or_value);
+        Ok(())
+    }
+
     /// generates a for-loop statement
     ///
     /// FOR `counter` := `start` TO `end` BY `by_step` DO
@@ -413,6 +464,8 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
 
         //Enter the for loop
         builder.position_at_end(for_body);
+        let decision = self.create_decision(counter);
+        self.count_branch(&decision, 0);
         let body_generator = StatementCodeGenerator {
             current_loop_exit: Some(continue_block),
             current_loop_continue: Some(increment_block),
@@ -454,6 +507,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
 
         //Continue
         builder.position_at_end(continue_block);
+        self.count_branch(&decision, 1);
 
         Ok(())
     }
@@ -525,12 +579,13 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
         let exp_gen = self.create_expr_generator();
         self.register_debug_location(selector);
         let selector_statement = exp_gen.generate_expression(selector)?;
+        let decision = self.create_decision(selector);
 
         let mut cases = Vec::new();
         let else_block = context.append_basic_block(current_function, "else");
         let mut current_else_block = else_block;
 
-        for conditional_block in conditional_blocks {
+        for (branch, conditional_block) in conditional_blocks.iter().enumerate() {
             //craete a block for the case's body
             let case_block = context.prepend_basic_block(else_block, "case");
 
@@ -557,12 +612,14 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
             }
             //generate the case's body
             builder.position_at_end(case_block);
+            self.count_branch(&decision, branch);
             self.generate_body(&conditional_block.body)?;
             // skiop all other case-bodies
             builder.build_unconditional_branch(continue_block);
         }
         // current-else is the last else-block generated by the range-expressions
         builder.position_at_end(current_else_block);
+        self.count_branch(&decision, conditional_blocks.len());
         self.generate_body(else_body)?;
         builder.build_unconditional_branch(continue_block);
         continue_block.move_after(current_else_block).expect(INTERNAL_LLVM_ERROR);
@@ -696,6 +753,8 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
 
         //Enter the for loop
         builder.position_at_end(while_body);
+        let decision = self.create_decision(condition);
+        self.count_branch(&decision, 0);
         let body_generator = StatementCodeGenerator {
             current_loop_exit: Some(continue_block),
             current_loop_continue: Some(condition_check),
@@ -709,6 +768,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
 
         //Continue
         builder.position_at_end(continue_block);
+        self.count_branch(&decision, 1);
         Ok((condition_check, while_body))
     }
 
@@ -727,7 +787,8 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
             blocks.push(context.append_basic_block(current_function, "branch"));
         }
 
-        let else_block = if !else_body.is_empty() {
+        // the coverage of an empty else-block is counted as well
+        let else_block = if !else_body.is_empty() || self.coverage.is_some() {
             let result = context.append_basic_block(current_function, "else");
             blocks.push(result);
             Some(result)
@@ -738,6 +799,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
         let continue_block = context.append_basic_block(current_function, "continue");
         blocks.push(continue_block);
 
+        let decision = conditional_blocks.first().and_then(|it| self.create_decision(&it.condition));
         for (i, block) in conditional_blocks.iter().enumerate() {
             let then_block = blocks[i];
             let else_block = blocks[i + 1];
@@ -758,6 +820,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
             //Generate if statement content
 
             builder.position_at_end(conditional_block);
+            self.count_branch(&decision, i);
             self.generate_body(&block.body)?;
             builder.build_unconditional_branch(continue_block);
         }
@@ -765,6 +828,7 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
 
         if let Some(else_block) = else_block {
             builder.position_at_end(else_block);
+            self.count_branch(&decision, conditional_blocks.len());
             self.generate_body(else_body)?;
             builder.build_unconditional_branch(continue_block);
         }
@@ -830,14 +894,36 @@ impl<'a, 'b> StatementCodeGenerator<'a, 'b> {
             else_block,
         );
 
+        let decision = self.create_decision(statement);
         self.llvm.builder.position_at_end(then_block);
         self.register_debug_location(statement);
+        self.count_branch(&decision, 0);
         self.generate_return_statement()?;
         self.llvm.builder.position_at_end(else_block);
+        self.count_branch(&decision, 1);
 
         Ok(())
     }
 
+    /// counts the execution of the given statement's line if coverage is enabled
+    fn count_statement(&self, statement: &AstNode) {
+        if let Some(coverage) = self.coverage {
+            coverage.count_statement(self.llvm, &statement.get_location());
+        }
+    }
+
+    /// starts a new decision at the given statement if coverage is enabled
+    fn create_decision(&self, statement: &AstNode) -> Option<Decision> {
+        self.coverage.and_then(|it| it.create_decision(&statement.get_location()))
+    }
+
+    /// counts taking the given branch of the decision at the current insert position
+    fn count_branch(&self, decision: &Option<Decision>, branch: usize) {
+        if let Some((coverage, decision)) = self.coverage.zip(decision.as_ref()) {
+            coverage.count_branch(self.llvm, decision, branch);
+        }
+    }
+
     fn get_llvm_deps(&self) -> (&Builder, FunctionValue, &Context) {
         (&self.llvm.builder, self.function_context.function, self.llvm.context)
     }
@@ -0,0 +1,407 @@
+// Copyright (c) 2020 Ghaith Hachem and Mathias Rieder
+//! Generates a table describing every instance of the project together with functions to look
+//! up these entries at runtime. This allows a runtime to access variables by their qualified name.
+//!
+//! Every entry of the table has the following layout:
+//! ```c
+//! typedef struct {
+//!     const char* name;      // e.g. "mainProg.fbMotor.rSpeed"
+//!     const char* type_name; // e.g. "REAL"
+//!     void* address;
+//!     uint32_t size;         // in bytes
+//! } __plc_symbol;
+//! ```
+//! The following functions are exported:
+//! - `uint32_t __plc_symbol_count()` returns the number of entries in the table
+//! - `const __plc_symbol* __plc_symbol_at(uint32_t index)` returns the entry at the given index
+//!   or `NULL` if the index is out of bounds
+//! - `const __plc_symbol* __plc_find_symbol(const char* name)` returns the entry with the given
+//!   (case-insensitive) name or `NULL` if no such entry exists
+//!
+//! The same way, a table of all `RETAIN` and `PERSISTENT` global variables is generated, so a
+//! runtime can save and restore them:
+//! ```c
+//! typedef struct {
+//!     const char* name;
+//!     void* address;
+//!     uint32_t size;  // in bytes
+//!     uint32_t flags; // 1 for PERSISTENT variables
+//! } __plc_retain_variable;
+//! ```
+//! with the functions `uint32_t __plc_retain_count()` and
+//! `const __plc_retain_variable* __plc_retain_at(uint32_t index)`
+use inkwell::{
+    module::{Linkage, Module},
+    types::{BasicType, StructType},
+    values::{BasicValue, FunctionValue, GlobalValue, PointerValue, StructValue},
+    AddressSpace, IntPredicate,
+};
+use plc_diagnostics::diagnostics::Diagnostic;
+
+use crate::{
+    index::{Index, VariableIndexEntry},
+    symbol_map::{collect_instance_layouts, get_size, get_type_description, InstanceLayout},
+};
+
+use super::{llvm::Llvm, ADDRESS_SPACE_GENERIC};
+
+pub const SYMBOL_TYPE_NAME: &str = "__plc_symbol";
+pub const SYMBOL_TABLE_NAME: &str = "__plc_symbols";
+pub const SYMBOL_COUNT_FUNCTION: &str = "__plc_symbol_count";
+pub const SYMBOL_AT_FUNCTION: &str = "__plc_symbol_at";
+pub const FIND_SYMBOL_FUNCTION: &str = "__plc_find_symbol";
+
+pub const RETAIN_TYPE_NAME: &str = "__plc_retain_variable";
+pub const RETAIN_TABLE_NAME: &str = "__plc_retain_variables";
+pub const RETAIN_COUNT_FUNCTION: &str = "__plc_retain_count";
+pub const RETAIN_AT_FUNCTION: &str = "__plc_retain_at";
+/// marks an entry of the retain table as a `PERSISTENT` variable
+pub const RETAIN_FLAG_PERSISTENT: u32 = 1;
+
+/// generates the symbol table and its lookup functions for all instances in the index into the given module
+pub fn generate_symbol_table<'ink>(
+    module: &Module<'ink>,
+    llvm: &Llvm<'ink>,
+    index: &Index,
+) -> Result<(), Diagnostic> {
+    let instances = collect_instance_layouts(index)?;
+    let generator = SymbolTableGenerator { module, llvm, index };
+    let symbol_type = generator.create_symbol_type();
+    let table = generator.generate_table(symbol_type, &instances);
+    let count = instances.len() as u32;
+    generator.generate_count_function(SYMBOL_COUNT_FUNCTION, count);
+    generator.generate_entry_at_function(SYMBOL_AT_FUNCTION, symbol_type, table, count);
+    let compare = generator.generate_name_compare_function();
+    generator.generate_find_symbol_function(symbol_type, table, count, compare);
+    Ok(())
+}
+
+/// generates the table of all `RETAIN` and `PERSISTENT` global variables and its lookup functions
+/// into the given module
+pub fn generate_retain_table<'ink>(
+    module: &Module<'ink>,
+    llvm: &Llvm<'ink>,
+    index: &Index,
+) -> Result<(), Diagnostic> {
+    let variables = index.get_retain_variables();
+    let generator = SymbolTableGenerator { module, llvm, index };
+    let retain_type = generator.create_retain_type();
+    let table = generator
+        .generate_retain_table(retain_type, &variables)
+        .map_err(|message| Diagnostic::error(message).with_error_code("E002"))?;
+    let count = variables.len() as u32;
+    generator.generate_count_function(RETAIN_COUNT_FUNCTION, count);
+    generator.generate_entry_at_function(RETAIN_AT_FUNCTION, retain_type, table, count);
+    Ok(())
+}
+
+struct SymbolTableGenerator<'a, 'ink> {
+    module: &'a Module<'ink>,
+    llvm: &'a Llvm<'ink>,
+    index: &'a Index,
+}
+
+impl<'a, 'ink> SymbolTableGenerator<'a, 'ink> {
+    fn i8_ptr_type(&self) -> inkwell::types::PointerType<'ink> {
+        self.llvm.context.i8_type().ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC))
+    }
+
+    fn create_symbol_type(&self) -> StructType<'ink> {
+        let symbol_type = self.llvm.context.opaque_struct_type(SYMBOL_TYPE_NAME);
+        let i8_ptr = self.i8_ptr_type().as_basic_type_enum();
+        symbol_type.set_body(&[i8_ptr, i8_ptr, i8_ptr, self.llvm.context.i32_type().into()], false);
+        symbol_type
+    }
+
+    fn generate_table(
+        &self,
+        symbol_type: StructType<'ink>,
+        instances: &[InstanceLayout],
+    ) -> GlobalValue<'ink> {
+        let i32_type = self.llvm.context.i32_type();
+        let entries = instances
+            .iter()
+            .enumerate()
+            .map(|(idx, instance)| {
+                let name = self.create_string(&format!("__plc_symbol_name_{idx}"), &instance.name);
+                let type_name = self.create_string(
+                    &format!("__plc_symbol_type_{idx}"),
+                    &get_type_description(self.index, instance.variable.get_type_name()),
+                );
+                let address = self.get_address(instance.root, instance.offset);
+                symbol_type.const_named_struct(&[
+                    name.into(),
+                    type_name.into(),
+                    address.into(),
+                    i32_type.const_int(instance.size as u64, false).into(),
+                ])
+            })
+            .collect::<Vec<_>>();
+        self.create_table(SYMBOL_TABLE_NAME, symbol_type, &entries)
+    }
+
+    fn create_retain_type(&self) -> StructType<'ink> {
+        let retain_type = self.llvm.context.opaque_struct_type(RETAIN_TYPE_NAME);
+        let i8_ptr = self.i8_ptr_type().as_basic_type_enum();
+        let i32_type = self.llvm.context.i32_type().as_basic_type_enum();
+        retain_type.set_body(&[i8_ptr, i8_ptr, i32_type, i32_type], false);
+        retain_type
+    }
+
+    fn generate_retain_table(
+        &self,
+        retain_type: StructType<'ink>,
+        variables: &[&VariableIndexEntry],
+    ) -> Result<GlobalValue<'ink>, String> {
+        let i32_type = self.llvm.context.i32_type();
+        let entries = variables
+            .iter()
+            .enumerate()
+            .map(|(idx, variable)| {
+                let name = self.create_string(&format!("__plc_retain_name_{idx}"), variable.get_name());
+                let size = get_size(self.index, variable.get_type_name())?;
+                let flags = if variable.is_persistent() { RETAIN_FLAG_PERSISTENT } else { 0 };
+                Ok(retain_type.const_named_struct(&[
+                    name.into(),
+                    self.get_address(variable, 0).into(),
+                    i32_type.const_int(size as u64, false).into(),
+                    i32_type.const_int(flags as u64, false).into(),
+                ]))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(self.create_table(RETAIN_TABLE_NAME, retain_type, &entries))
+    }
+
+    /// creates an internal constant array holding the given entries
+    fn create_table(
+        &self,
+        name: &str,
+        entry_type: StructType<'ink>,
+        entries: &[StructValue<'ink>],
+    ) -> GlobalValue<'ink> {
+        let table_type = entry_type.array_type(entries.len() as u32);
+        let table = self.llvm.create_global_variable(self.module, name, table_type.into());
+        table.set_constant(true);
+        table.set_linkage(Linkage::Internal);
+        table.set_initializer(&entry_type.const_array(entries));
+        table
+    }
+
+    /// creates a private null-terminated string constant and returns a pointer to its first character
+    fn create_string(&self, name: &str, value: &str) -> PointerValue<'ink> {
+        let string = self.llvm.context.const_string(value.as_bytes(), true);
+        let global = self.llvm.create_global_variable(self.module, name, string.get_type().into());
+        global.set_constant(true);
+        global.set_linkage(Linkage::Private);
+        global.set_unnamed_addr(true);
+        global.set_initializer(&string);
+        global.as_pointer_value().const_cast(self.i8_ptr_type())
+    }
+
+    /// returns the address of the given global variable or program instance plus the given offset
+    fn get_address(&self, root: &VariableIndexEntry, offset: u32) -> PointerValue<'ink> {
+        let root_name = root.get_name();
+        let root = self.module.get_global(root_name).unwrap_or_else(|| {
+            let global =
+                self.llvm.create_global_variable(self.module, root_name, self.llvm.context.i8_type().into());
+            global.set_linkage(Linkage::External);
+            global
+        });
+        let base = root.as_pointer_value().const_cast(self.i8_ptr_type());
+        let offset = self.llvm.context.i64_type().const_int(offset as u64, false);
+        unsafe { base.const_in_bounds_gep(&[offset]) }
+    }
+
+    fn generate_count_function(&self, name: &str, count: u32) {
+        let i32_type = self.llvm.context.i32_type();
+        let function = self.module.add_function(name, i32_type.fn_type(&[], false), None);
+        let entry = self.llvm.context.append_basic_block(function, "entry");
+        self.llvm.builder.position_at_end(entry);
+        self.llvm.builder.build_return(Some(&i32_type.const_int(count as u64, false)));
+    }
+
+    /// generates a function returning a pointer to the entry of the table at the given index
+    /// or `NULL` if the index is out of bounds
+    fn generate_entry_at_function(
+        &self,
+        name: &str,
+        entry_type: StructType<'ink>,
+        table: GlobalValue<'ink>,
+        count: u32,
+    ) {
+        let context = self.llvm.context;
+        let builder = &self.llvm.builder;
+        let i32_type = context.i32_type();
+        let entry_ptr = entry_type.ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC));
+        let function = self.module.add_function(name, entry_ptr.fn_type(&[i32_type.into()], false), None);
+        let entry = context.append_basic_block(function, "entry");
+        let found = context.append_basic_block(function, "found");
+        let not_found = context.append_basic_block(function, "not_found");
+
+        builder.position_at_end(entry);
+        let position = function.get_nth_param(0).expect("parameter").into_int_value();
+        let in_range = builder.build_int_compare(
+            IntPredicate::ULT,
+            position,
+            i32_type.const_int(count as u64, false),
+            "in_range",
+        );
+        builder.build_conditional_branch(in_range, found, not_found);
+
+        builder.position_at_end(found);
+        let symbol = unsafe {
+            builder.build_in_bounds_gep(
+                table.as_pointer_value(),
+                &[i32_type.const_zero(), position],
+                "symbol",
+            )
+        };
+        builder.build_return(Some(&symbol));
+
+        builder.position_at_end(not_found);
+        builder.build_return(Some(&entry_ptr.const_null()));
+    }
+
+    /// generates a function comparing two null-terminated strings ignoring the case of ASCII letters
+    fn generate_name_compare_function(&self) -> FunctionValue<'ink> {
+        let context = self.llvm.context;
+        let builder = &self.llvm.builder;
+        let i8_type = context.i8_type();
+        let i64_type = context.i64_type();
+        let i8_ptr = self.i8_ptr_type();
+        let function = self.module.add_function(
+            "__plc_symbol_name_eq",
+            context.bool_type().fn_type(&[i8_ptr.into(), i8_ptr.into()], false),
+            Some(Linkage::Private),
+        );
+        let entry = context.append_basic_block(function, "entry");
+        let compare = context.append_basic_block(function, "compare");
+        let same = context.append_basic_block(function, "same");
+        let next = context.append_basic_block(function, "next");
+        let equal = context.append_basic_block(function, "equal");
+        let different = context.append_basic_block(function, "different");
+        let left = function.get_nth_param(0).expect("parameter").into_pointer_value();
+        let right = function.get_nth_param(1).expect("parameter").into_pointer_value();
+
+        builder.position_at_end(entry);
+        builder.build_unconditional_branch(compare);
+
+        builder.position_at_end(compare);
+        let position = builder.build_phi(i64_type, "position");
+        let position_value = position.as_basic_value().into_int_value();
+        let to_lower = |pointer: PointerValue<'ink>, name: &str| {
+            let character_ptr = unsafe { builder.build_in_bounds_gep(pointer, &[position_value], "") };
+            let character = builder.build_load(character_ptr, name).into_int_value();
+            let is_upper = builder.build_and(
+                builder.build_int_compare(
+                    IntPredicate::UGE,
+                    character,
+                    i8_type.const_int(b'A' as u64, false),
+                    "",
+                ),
+                builder.build_int_compare(
+                    IntPredicate::ULE,
+                    character,
+                    i8_type.const_int(b'Z' as u64, false),
+                    "",
+                ),
+                "",
+            );
+            let lower = builder.build_int_add(character, i8_type.const_int((b'a' - b'A') as u64, false), "");
+            let result = builder.build_select(is_upper, lower, character, "").into_int_value();
+            (character, result)
+        };
+        let (left_character, left_lower) = to_lower(left, "left");
+        let (_, right_lower) = to_lower(right, "right");
+        let is_same = builder.build_int_compare(IntPredicate::EQ, left_lower, right_lower, "is_same");
+        builder.build_conditional_branch(is_same, same, different);
+
+        builder.position_at_end(same);
+        let is_end =
+            builder.build_int_compare(IntPredicate::EQ, left_character, i8_type.const_zero(), "is_end");
+        builder.build_conditional_branch(is_end, equal, next);
+
+        builder.position_at_end(next);
+        let next_position =
+            builder.build_int_add(position_value, i64_type.const_int(1, false), "next_position");
+        builder.build_unconditional_branch(compare);
+        position.add_incoming(&[(&i64_type.const_zero(), entry), (&next_position, next)]);
+
+        builder.position_at_end(equal);
+        builder.build_return(Some(&context.bool_type().const_int(1, false)));
+
+        builder.position_at_end(different);
+        builder.build_return(Some(&context.bool_type().const_zero()));
+        function
+    }
+
+    fn generate_find_symbol_function(
+        &self,
+        symbol_type: StructType<'ink>,
+        table: GlobalValue<'ink>,
+        count: u32,
+        compare: FunctionValue<'ink>,
+    ) {
+        let context = self.llvm.context;
+        let builder = &self.llvm.builder;
+        let i32_type = context.i32_type();
+        let symbol_ptr = symbol_type.ptr_type(AddressSpace::from(ADDRESS_SPACE_GENERIC));
+        let function = self.module.add_function(
+            FIND_SYMBOL_FUNCTION,
+            symbol_ptr.fn_type(&[self.i8_ptr_type().into()], false),
+            None,
+        );
+        let entry = context.append_basic_block(function, "entry");
+        let condition = context.append_basic_block(function, "condition");
+        let check = context.append_basic_block(function, "check");
+        let next = context.append_basic_block(function, "next");
+        let found = context.append_basic_block(function, "found");
+        let not_found = context.append_basic_block(function, "not_found");
+        let name = function.get_nth_param(0).expect("parameter").into_pointer_value();
+
+        builder.position_at_end(entry);
+        let is_null = builder.build_is_null(name, "is_null");
+        builder.build_conditional_branch(is_null, not_found, condition);
+
+        builder.position_at_end(condition);
+        let position = builder.build_phi(i32_type, "position");
+        let position_value = position.as_basic_value().into_int_value();
+        let in_range = builder.build_int_compare(
+            IntPredicate::ULT,
+            position_value,
+            i32_type.const_int(count as u64, false),
+            "in_range",
+        );
+        builder.build_conditional_branch(in_range, check, not_found);
+
+        builder.position_at_end(check);
+        let symbol = unsafe {
+            builder.build_in_bounds_gep(
+                table.as_pointer_value(),
+                &[i32_type.const_zero(), position_value],
+                "symbol",
+            )
+        };
+        let symbol_name_ptr = builder.build_struct_gep(symbol, 0, "symbol_name_ptr").expect("symbol name");
+        let symbol_name = builder.build_load(symbol_name_ptr, "symbol_name");
+        let is_equal = builder
+            .build_call(compare, &[symbol_name.into(), name.into()], "is_equal")
+            .try_as_basic_value()
+            .left()
+            .expect("compare returns a value")
+            .into_int_value();
+        builder.build_conditional_branch(is_equal, found, next);
+
+        builder.position_at_end(next);
+        let next_position =
+            builder.build_int_add(position_value, i32_type.const_int(1, false), "next_position");
+        builder.build_unconditional_branch(condition);
+        position.add_incoming(&[(&i32_type.const_zero(), entry), (&next_position, next)]);
+
+        builder.position_at_end(found);
+        builder.build_return(Some(&symbol.as_basic_value_enum()));
+
+        builder.position_at_end(not_found);
+        builder.build_return(Some(&symbol_ptr.const_null()));
+    }
+}
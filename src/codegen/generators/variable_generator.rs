@@ -4,10 +4,14 @@
 use crate::{
     codegen::{debug::Debug, llvm_index::LlvmTypedIndex, llvm_typesystem::cast_if_needed},
     index::{get_initializer_name, Index, PouIndexEntry, VariableIndexEntry},
+    process_image,
     resolver::{AnnotationMap, AstAnnotations, Dependency},
 };
 use indexmap::IndexSet;
-use inkwell::{module::Module, values::GlobalValue};
+use inkwell::{
+    module::{Linkage, Module},
+    values::GlobalValue,
+};
 use plc_ast::ast::LinkageType;
 use plc_diagnostics::diagnostics::Diagnostic;
 
@@ -18,6 +22,11 @@ use super::{
 };
 use crate::codegen::debug::DebugBuilderEnum;
 
+/// the linker section holding all `RETAIN` global variables
+pub const RETAIN_SECTION: &str = ".retain";
+/// the linker section holding all `PERSISTENT` global variables
+pub const PERSISTENT_SECTION: &str = ".persistent";
+
 pub struct VariableGenerator<'ctx, 'b> {
     module: &'b Module<'ctx>,
     llvm: &'b Llvm<'ctx>,
@@ -75,6 +84,10 @@ impl<'ctx, 'b> VariableGenerator<'ctx, 'b> {
         });
 
         for (name, variable) in globals {
+            // variables declared at a direct address are accessed in the process image
+            if variable.is_in_process_image() {
+                continue;
+            }
             let linkage =
                 if !variable.is_in_unit(location) { LinkageType::External } else { variable.get_linkage() };
             let global_variable =
@@ -99,6 +112,42 @@ impl<'ctx, 'b> VariableGenerator<'ctx, 'b> {
         Ok(index)
     }
 
+    /// generates the input, output and marker images addressed by direct addresses like `%IX1.2`
+    /// together with a constant holding their size in bytes. The globals containing instances mapped
+    /// into the images by an I/O mapping are declared if they are not part of the module.
+    ///
+    /// Every module defines the images of the whole project, the linker merges them into one.
+    pub fn generate_process_images(&self) -> Result<LlvmTypedIndex<'ctx>, Diagnostic> {
+        let mut index = LlvmTypedIndex::default();
+        for (name, size) in self.global_index.get_process_image().get_used_areas() {
+            let image_type = self.llvm.context.i8_type().array_type(size);
+            let image = self.llvm.create_global_variable(self.module, name, image_type.into());
+            image.set_initializer(&image_type.const_zero());
+            image.set_linkage(Linkage::WeakODR);
+            image.set_alignment(self.global_index.get_type_layout().i64.value());
+            index.associate_global(name, image)?;
+
+            let size_type = self.llvm.context.i32_type();
+            let size_name = process_image::get_size_name(name);
+            let size_value = self.llvm.create_global_variable(self.module, &size_name, size_type.into());
+            size_value.set_initializer(&size_type.const_int(size as u64, false));
+            size_value.set_constant(true);
+            size_value.set_linkage(Linkage::WeakODR);
+            index.associate_global(&size_name, size_value)?;
+        }
+        // references to mapped instances compare their address with the instances' roots
+        for root in self.global_index.get_all_mapped_addresses().map(|it| it.root.as_str()) {
+            let global = self.module.get_global(root).unwrap_or_else(|| {
+                let global =
+                    self.llvm.create_global_variable(self.module, root, self.llvm.context.i8_type().into());
+                global.set_linkage(Linkage::External);
+                global
+            });
+            index.associate_global(root, global)?;
+        }
+        Ok(index)
+    }
+
     /// convenience function to generates a global variable for the given variable
     ///
     /// - `module` the module to generate the variable into
@@ -159,6 +208,10 @@ impl<'ctx, 'b> VariableGenerator<'ctx, 'b> {
                         global_variable.source_location.clone(),
                     ));
                 }
+            } else if global_variable.is_persistent() {
+                global_ir_variable.set_section(Some(PERSISTENT_SECTION));
+            } else if global_variable.is_retain() {
+                global_ir_variable.set_section(Some(RETAIN_SECTION));
             }
         }
 
@@ -0,0 +1,116 @@
+//! Measures the stack frame and the code size of every function of a generated module.
+//!
+//! LLVM only knows the final frame size of a function once the prologue and epilogue were inserted
+//! during code generation. Every function is given a `warn-stack-size` limit of `0`, so the
+//! prologue/epilogue inserter reports the frame size of every function with a non-empty frame
+//! through the diagnostic handler of the context. The code size is read from the symbol table of
+//! the object generated along the way.
+use std::{collections::HashMap, ffi::CStr};
+
+use inkwell::{
+    attributes::AttributeLoc,
+    module::Module,
+    passes::PassBuilderOptions,
+    targets::{FileType, RelocMode},
+};
+use llvm_sys::{
+    core::{
+        LLVMContextGetDiagnosticContext, LLVMContextGetDiagnosticHandler, LLVMContextSetDiagnosticHandler,
+        LLVMDisposeMessage, LLVMGetDiagInfoDescription, LLVMGetModuleContext,
+    },
+    prelude::LLVMDiagnosticInfoRef,
+};
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::SourceLocation;
+use regex::Regex;
+
+use crate::{stack_report::FunctionStackUsage, OptimizationLevel, Target};
+
+/// generates code for a copy of the given module and returns the stack usage of its functions
+pub(super) fn collect_stack_usage(
+    module: &Module,
+    target: &Target,
+    optimization_level: OptimizationLevel,
+) -> Result<Vec<FunctionStackUsage>, Diagnostic> {
+    let module = module.clone();
+    let context = module.get_context();
+    let limit = context.create_string_attribute("warn-stack-size", "0");
+    let functions = module.get_functions().filter(|it| it.count_basic_blocks() > 0).collect::<Vec<_>>();
+    for function in &functions {
+        function.add_attribute(AttributeLoc::Function, limit);
+    }
+
+    let machine = target.create_target_machine(RelocMode::Default, optimization_level)?;
+    module.set_triple(&machine.get_triple());
+    module.set_data_layout(&machine.get_target_data().get_data_layout());
+    let mut frame_sizes = FrameSizes::default();
+    let buffer = frame_sizes.collect(&module, || {
+        module
+            .run_passes(optimization_level.opt_params(), &machine, PassBuilderOptions::create())
+            .and_then(|_| machine.write_to_memory_buffer(&module, FileType::Object))
+            .map_err(|it| {
+                Diagnostic::llvm_error(
+                    module.get_source_file_name().to_str().unwrap_or_default(),
+                    &it.to_string_lossy(),
+                )
+            })
+    })?;
+
+    let object = buffer.create_object_file().map_err(|_| {
+        Diagnostic::codegen_error("Cannot read the generated object", SourceLocation::undefined())
+    })?;
+    let code_sizes = object
+        .get_symbols()
+        .filter_map(|it| it.get_name().map(|name| (name.to_string_lossy().to_string(), it.size())))
+        .collect::<HashMap<_, _>>();
+
+    Ok(functions
+        .iter()
+        .filter_map(|it| it.get_name().to_str().ok())
+        .map(|name| FunctionStackUsage {
+            name: name.to_string(),
+            frame_size: frame_sizes.sizes.get(name).copied().unwrap_or_default(),
+            code_size: code_sizes.get(name).copied().unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// the frame sizes reported by LLVM while generating code
+#[derive(Default)]
+struct FrameSizes {
+    sizes: HashMap<String, u64>,
+}
+
+impl FrameSizes {
+    /// collects the frame sizes reported while running the given code generation
+    fn collect<T>(&mut self, module: &Module, generate: impl FnOnce() -> T) -> T {
+        unsafe {
+            let context = LLVMGetModuleContext(module.as_mut_ptr());
+            let (handler, handler_context) =
+                (LLVMContextGetDiagnosticHandler(context), LLVMContextGetDiagnosticContext(context));
+            LLVMContextSetDiagnosticHandler(context, Some(Self::handle), self as *mut Self as *mut _);
+            let result = generate();
+            LLVMContextSetDiagnosticHandler(context, handler, handler_context);
+            result
+        }
+    }
+
+    extern "C" fn handle(info: LLVMDiagnosticInfoRef, frame_sizes: *mut std::ffi::c_void) {
+        let frame_sizes = unsafe { &mut *(frame_sizes as *mut FrameSizes) };
+        let description = unsafe { LLVMGetDiagInfoDescription(info) };
+        let message = unsafe { CStr::from_ptr(description) }.to_string_lossy().to_string();
+        unsafe { LLVMDisposeMessage(description) };
+
+        // e.g. `stack frame size (24) exceeds limit (0) in function 'main'`
+        let pattern =
+            Regex::new(r"stack frame size \((\d+)\) exceeds limit \(\d+\) in (?:function )?'([^']+)'")
+                .expect("valid pattern");
+        match pattern.captures(&message) {
+            Some(captures) => {
+                let size = captures[1].parse().unwrap_or_default();
+                frame_sizes.sizes.insert(captures[2].to_string(), size);
+            }
+            None => log::warn!("{message}"),
+        }
+    }
+}
@@ -68,6 +68,14 @@ fn external_global_variable_generates_as_external() {
     insta::assert_snapshot!(result);
 }
 
+#[test]
+fn retain_and_persistent_global_variables_are_generated_in_their_sections() {
+    let result = generate_with_empty_program(
+        "VAR_GLOBAL RETAIN gX : INT; END_VAR VAR_GLOBAL PERSISTENT gY : BOOL; END_VAR VAR_GLOBAL gZ : INT; END_VAR",
+    );
+    insta::assert_snapshot!(result);
+}
+
 #[test]
 fn two_global_variables_generates_in_separate_global_variables() {
     let result =
@@ -3314,3 +3322,29 @@ fn array_of_struct_as_member_of_another_struct_and_variable_declaration_is_initi
 
     insta::assert_snapshot!(res);
 }
+
+#[test]
+fn union_members_are_accessed_through_a_pointer_to_the_union() {
+    let result = codegen(
+        r#"
+        TYPE MyUnion : UNION
+            value : DWORD;
+            bytes : ARRAY[0..3] OF BYTE;
+            flag : BOOL;
+        END_UNION
+        END_TYPE
+
+        PROGRAM main
+        VAR
+            u : MyUnion;
+            b : BYTE;
+        END_VAR
+            u.value := 16#12345678;
+            b := u.bytes[1];
+            u.flag := TRUE;
+        END_PROGRAM
+        "#,
+    );
+
+    insta::assert_snapshot!(result);
+}
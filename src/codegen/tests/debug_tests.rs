@@ -132,6 +132,25 @@ fn test_global_var_struct_added_to_debug_info() {
     assert_snapshot!(codegen)
 }
 
+#[test]
+fn test_global_var_union_added_to_debug_info() {
+    let codegen = codegen(
+        r#"
+    TYPE myUnion : UNION
+        a : DWORD;
+        b : ARRAY[0..3] OF BYTE;
+        c : LREAL;
+    END_UNION
+    END_TYPE
+
+    VAR_GLOBAL
+        gUnion : myUnion;
+    END_VAR
+    "#,
+    );
+    assert_snapshot!(codegen)
+}
+
 #[test]
 fn test_global_var_nested_struct_added_to_debug_info() {
     let codegen = codegen(
@@ -512,12 +512,12 @@ fn hardware_access_codegen() {
         VAR
           x,y,z : BYTE;
         END_VAR
-          x := %IB1.2;
-          y := %MB1.2;
-          z := %GB1.2;
+          x := %IB1;
+          y := %MB1;
+          z := %QB2;
           x := %IX1.2;
-          y := %MD1.2;
-          z := %GW1.2;
+          y := %MD1;
+          z := %QW2;
         END_PROGRAM
         ",
     );
@@ -533,12 +533,35 @@ fn hardware_access_assign_codegen() {
         VAR
           x,y,z : BYTE;
         END_VAR
-          %IB1.2 := 1;
-          %MB1.2 := 1;
-          %GB1.2 := 1;
+          %IB1 := 1;
+          %MB1 := 1;
+          %QB2 := 1;
           %IX1.2 := 1;
-          %MD1.2 := 1;
-          %GW1.2 := 1;
+          %MD1 := 1;
+          %QW2 := 1;
+        END_PROGRAM
+        ",
+    );
+
+    insta::assert_snapshot!(result);
+}
+
+#[test]
+fn variables_at_direct_addresses_live_in_the_process_image() {
+    let result = codegen(
+        "
+        VAR_GLOBAL
+          start AT %IX0.1 : BOOL;
+        END_VAR
+        PROGRAM prg
+        VAR
+          speed AT %IW2 : INT;
+          running AT %QX1.0 : BOOL;
+          setpoint AT %QW1 : INT;
+          unmapped AT %IW4.1.2 : INT;
+        END_VAR
+          running := start;
+          setpoint := speed + unmapped;
         END_PROGRAM
         ",
     );
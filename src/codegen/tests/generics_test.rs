@@ -121,3 +121,64 @@ fn any_real_function_called_with_ints() {
     //Expecting to REAL/LREAL conversion for every call
     insta::assert_snapshot!(codegen(src));
 }
+
+#[test]
+fn generic_function_with_body_is_implemented_for_each_call() {
+    let prg = codegen(
+        r"
+        FUNCTION clamp<T : ANY_NUM> : T
+        VAR_INPUT
+            value, low, high : T;
+        END_VAR
+            IF value < low THEN
+                clamp := low;
+            ELSIF value > high THEN
+                clamp := high;
+            ELSE
+                clamp := value;
+            END_IF
+        END_FUNCTION
+
+        PROGRAM prg
+        VAR
+            i : INT;
+            r : REAL;
+        END_VAR
+            i := clamp(i, INT#0, INT#10);
+            r := clamp(r, REAL#0.0, REAL#1.0);
+        END_PROGRAM
+        ",
+    );
+
+    insta::assert_snapshot!(prg);
+}
+
+#[test]
+fn generic_function_block_is_instantiated_for_each_type_argument() {
+    let src = r"
+        FUNCTION_BLOCK Fifo<T : ANY>
+        VAR
+            buffer : ARRAY[0..3] OF T;
+            count : DINT;
+        END_VAR
+            METHOD push
+            VAR_INPUT
+                value : T;
+            END_VAR
+                buffer[count] := value;
+                count := count + 1;
+            END_METHOD
+        END_FUNCTION_BLOCK
+
+        PROGRAM main
+        VAR
+            ints : Fifo<INT>;
+            reals : Fifo<REAL>;
+        END_VAR
+            ints.push(1);
+            reals.push(2.5);
+        END_PROGRAM
+    ";
+
+    insta::assert_snapshot!(codegen(src));
+}
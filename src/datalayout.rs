@@ -1,6 +1,21 @@
 use std::ops::{Add, AddAssign};
 
-#[derive(Copy, Clone, Debug)]
+use inkwell::{
+    context::Context,
+    targets::{RelocMode, TargetData},
+    types::AnyType,
+    AddressSpace,
+};
+use plc_diagnostics::diagnostics::Diagnostic;
+
+use crate::{
+    index::{Index, VariableIndexEntry},
+    typesystem::DataTypeInformation,
+    OptimizationLevel, Target,
+};
+
+/// The alignment of the llvm types on the target, see <https://llvm.org/docs/LangRef.html#data-layout>
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct DataLayout {
     pub i1: Bytes,
     pub i8: Bytes,
@@ -9,27 +24,59 @@ pub struct DataLayout {
     pub i64: Bytes,
     pub f32: Bytes,
     pub f64: Bytes,
-    pub p64: Bytes,
+    pub pointer: Bytes,
     pub v64: Bytes,
     pub v128: Bytes,
     pub aggregate: Bytes,
+    /// the size of a pointer, `8` bytes on 64-bit and `4` bytes on 32-bit targets
+    pub pointer_size: Bytes,
 }
 
 impl Default for DataLayout {
+    /// the layout of a 64-bit target like `x86_64-linux-gnu`
     fn default() -> Self {
         Self {
             i1: Bytes::from_bits(8),
             i8: Bytes::from_bits(8),
             i16: Bytes::from_bits(16),
             i32: Bytes::from_bits(32),
-            i64: Bytes::from_bits(64), //Using 64bit default alignment, if we need to support 32bit
-            //this has to be adjusted
+            i64: Bytes::from_bits(64),
             f32: Bytes::from_bits(32),
             f64: Bytes::from_bits(64),
-            p64: Bytes::from_bits(64),
+            pointer: Bytes::from_bits(64),
             v64: Bytes::from_bits(64),
             v128: Bytes::from_bits(128),
             aggregate: Bytes::from_bits(64),
+            pointer_size: Bytes::from_bits(64),
+        }
+    }
+}
+
+impl DataLayout {
+    /// returns the layout llvm uses when generating code for the given target
+    pub fn for_target(target: &Target) -> Result<DataLayout, Diagnostic> {
+        let machine = target.create_target_machine(RelocMode::Default, OptimizationLevel::None)?;
+        Ok(DataLayout::from_target_data(&machine.get_target_data()))
+    }
+
+    fn from_target_data(data: &TargetData) -> DataLayout {
+        let context = Context::create();
+        let abi = |it: &dyn AnyType| Bytes::new(data.get_abi_alignment(it));
+        let i32_type = context.i32_type();
+        DataLayout {
+            i1: abi(&context.bool_type()),
+            i8: abi(&context.i8_type()),
+            i16: abi(&context.i16_type()),
+            i32: abi(&i32_type),
+            i64: abi(&context.i64_type()),
+            f32: abi(&context.f32_type()),
+            f64: abi(&context.f64_type()),
+            pointer: abi(&i32_type.ptr_type(AddressSpace::default())),
+            v64: abi(&i32_type.vec_type(2)),
+            v128: abi(&i32_type.vec_type(4)),
+            // aggregates have no minimum alignment, they are placed at their preferred alignment
+            aggregate: Bytes::new(data.get_preferred_alignment(&context.struct_type(&[], false))),
+            pointer_size: Bytes::new(data.get_pointer_byte_size(None)),
         }
     }
 }
@@ -105,6 +152,101 @@ impl AddAssign<Bytes> for MemoryLocation {
     }
 }
 
+/// returns the number of bytes allocated for the given type, including any padding.
+/// returns `None` if the size depends on constants that are not resolved yet
+pub fn get_allocated_size(type_info: &DataTypeInformation, index: &Index) -> Option<u32> {
+    let size = match type_info {
+        DataTypeInformation::Struct { .. } => {
+            let mut offset = MemoryLocation::new(0);
+            for member in get_allocated_members(type_info) {
+                let member_type = index.get_type_information_or_void(member.get_type_name());
+                let aligned = offset.align_to(get_allocated_alignment(member_type, index));
+                offset = MemoryLocation::new(aligned.value() + get_allocated_size(member_type, index)?);
+            }
+            offset.align_to(get_allocated_alignment(type_info, index)).value()
+        }
+        // all members of a union start at offset 0, the union is as big as its biggest member
+        DataTypeInformation::Union { .. } => {
+            let mut size = MemoryLocation::new(0);
+            for member in get_allocated_members(type_info) {
+                let member_size =
+                    get_allocated_size(index.get_type_information_or_void(member.get_type_name()), index)?;
+                size = MemoryLocation::new(size.value().max(member_size));
+            }
+            size.align_to(get_allocated_alignment(type_info, index)).value()
+        }
+        DataTypeInformation::Array { inner_type_name, dimensions, .. } => {
+            let inner_type = index.get_type_information_or_void(inner_type_name);
+            let element_count =
+                dimensions.iter().map(|dim| dim.get_length(index).ok()).product::<Option<u32>>()?;
+            get_allocated_size(inner_type, index)? * element_count
+        }
+        DataTypeInformation::String { size, encoding } => {
+            let length = size.as_int_value(index).ok()? as u32;
+            encoding.get_bytes_per_char() * length
+        }
+        DataTypeInformation::Alias { referenced_type, .. }
+        | DataTypeInformation::SubRange { referenced_type, .. }
+        | DataTypeInformation::Enum { referenced_type, .. } => {
+            get_allocated_size(index.get_type_information_or_void(referenced_type), index)?
+        }
+        _ => type_info.get_size(index).value(),
+    };
+    Some(size)
+}
+
+/// returns the alignment of the given type as it is layed out in memory. Other than
+/// [`DataTypeInformation::get_alignment`] aggregates are aligned to their biggest member.
+pub fn get_allocated_alignment(type_info: &DataTypeInformation, index: &Index) -> Bytes {
+    match type_info {
+        DataTypeInformation::Struct { .. } | DataTypeInformation::Union { .. } => {
+            get_allocated_members(type_info)
+                .map(|it| {
+                    get_allocated_alignment(index.get_type_information_or_void(it.get_type_name()), index)
+                })
+                .fold(index.get_type_layout().i8, |max, it| if it > max { it } else { max })
+        }
+        DataTypeInformation::Array { inner_type_name, .. } => {
+            get_allocated_alignment(index.get_type_information_or_void(inner_type_name), index)
+        }
+        DataTypeInformation::String { .. } => type_info.get_string_character_width(index),
+        _ => type_info.get_alignment(index),
+    }
+}
+
+/// returns the byte offset of every member that is allocated inside the given struct or union.
+/// Temporary variables, return values and variables declared at a direct address are not part of the
+/// instance and are skipped.
+/// returns `None` if the layout depends on constants that are not resolved yet
+pub fn get_member_offsets<'t>(
+    type_info: &'t DataTypeInformation,
+    index: &Index,
+) -> Option<Vec<(&'t VariableIndexEntry, MemoryLocation)>> {
+    if type_info.is_union() {
+        return Some(get_allocated_members(type_info).map(|it| (it, MemoryLocation::new(0))).collect());
+    }
+
+    let mut offset = MemoryLocation::new(0);
+    let mut result = vec![];
+    for member in get_allocated_members(type_info) {
+        let member_type = index.get_type_information_or_void(member.get_type_name());
+        let aligned = offset.align_to(get_allocated_alignment(member_type, index));
+        result.push((member, aligned));
+        offset = MemoryLocation::new(aligned.value() + get_allocated_size(member_type, index)?);
+    }
+    Some(result)
+}
+
+fn get_allocated_members(type_info: &DataTypeInformation) -> impl Iterator<Item = &VariableIndexEntry> {
+    let members = match type_info {
+        DataTypeInformation::Struct { members, .. } | DataTypeInformation::Union { members, .. } => {
+            members.as_slice()
+        }
+        _ => &[],
+    };
+    members.iter().filter(|it| !it.is_temp() && !it.is_return() && !it.is_in_process_image())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_utils::tests::index;
@@ -188,4 +330,73 @@ mod tests {
         assert_eq!(struct_type.get_size(&index).bits(), 192);
         assert_eq!(struct_type.get_alignment(&index), Bytes::new(8)) //Struct alignment is 64 by default
     }
+
+    #[test]
+    fn union_members_overlay_each_other() {
+        //Given a union with differently sized members
+        let (_, index) = index(
+            "
+        TYPE MyUnion : UNION
+            a : BYTE; //8bit - offset 0
+            b : DWORD; //32bit - offset 0
+            c : ARRAY[0..4] OF BYTE; //40bit - offset 0
+        END_UNION
+        END_TYPE
+        ",
+        );
+
+        let union_type = index.get_effective_type_by_name("MyUnion").unwrap().get_type_information();
+        // Then every member starts at offset 0
+        let offsets = get_member_offsets(union_type, &index).unwrap();
+        assert!(offsets.iter().all(|(_, offset)| offset.value() == 0));
+        // And the union is as big as its biggest member, padded to its strictest alignment
+        assert_eq!(get_allocated_alignment(union_type, &index), Bytes::new(4));
+        assert_eq!(get_allocated_size(union_type, &index), Some(8));
+    }
+
+    #[test]
+    fn x86_64_targets_use_the_default_layout() {
+        let layout = DataLayout::for_target(&Target::from("x86_64-unknown-linux-gnu")).unwrap();
+        assert_eq!(layout, DataLayout::default());
+    }
+
+    #[test]
+    fn pointers_follow_the_target() {
+        for triple in ["armv7-unknown-linux-gnueabihf", "powerpc-unknown-linux-gnu", "i686-unknown-linux-gnu"]
+        {
+            let layout = DataLayout::for_target(&Target::from(triple)).unwrap();
+            assert_eq!(layout.pointer_size, Bytes::new(4), "{triple}");
+            assert_eq!(layout.pointer, Bytes::new(4), "{triple}");
+        }
+        let layout = DataLayout::for_target(&Target::from("aarch64-unknown-linux-gnu")).unwrap();
+        assert_eq!(layout.pointer_size, Bytes::new(8));
+    }
+
+    #[test]
+    fn struct_members_are_aligned_for_the_target() {
+        //Given a struct with a 64bit member
+        let (_, mut index) = index(
+            "
+        TYPE MyStruct : STRUCT
+            a : BYTE; //8bit - offset 0
+            b : LWORD; //64bit - offset 4 on i686, 8 elsewhere
+        END_STRUCT
+        END_TYPE
+        ",
+        );
+        let layout = |index: &Index| {
+            let struct_type = index.get_effective_type_by_name("MyStruct").unwrap().get_type_information();
+            let offsets = get_member_offsets(struct_type, index).unwrap();
+            (offsets[1].1.value(), get_allocated_size(struct_type, index).unwrap())
+        };
+
+        assert_eq!(layout(&index), (8, 16));
+        // 32bit x86 only aligns 64bit values to 4 bytes
+        index.set_type_layout(DataLayout::for_target(&Target::from("i686-unknown-linux-gnu")).unwrap());
+        assert_eq!(layout(&index), (4, 12));
+        // while arm keeps aligning them to 8 bytes
+        index
+            .set_type_layout(DataLayout::for_target(&Target::from("armv7-unknown-linux-gnueabihf")).unwrap());
+        assert_eq!(layout(&index), (8, 16));
+    }
 }
@@ -43,6 +43,10 @@ impl<'idx> ExpressionPath<'idx> {
         res
     }
 
+    pub fn get_elements(&self) -> &[ExpressionPathElement<'idx>] {
+        &self.names
+    }
+
     /// Expands the given name to reference all underlying instances
     /// This implementation will create an element for every contained array
     pub fn expand(&self, index: &Index) -> Vec<String> {
@@ -1,11 +1,21 @@
+use std::collections::HashSet;
+
+use indexmap::IndexMap;
 use plc_ast::ast::{DirectAccessType, HardwareAccessType};
 use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::SourceLocation;
 use serde::{
     ser::{SerializeSeq, SerializeStruct},
-    Serialize, Serializer,
+    Deserialize, Serialize, Serializer,
 };
 
-use crate::{expression_path::ExpressionPath, index::Index, ConfigFormat};
+use crate::{
+    expression_path::ExpressionPath,
+    index::{Index, VariableIndexEntry},
+    process_image::ImageAddress,
+    symbol_map::{collect_instance_layouts, get_size, InstanceLayout},
+    ConfigFormat,
+};
 
 trait SerializeWithContext {
     fn serialize<S>(&self, ctx: &Index, serializer: S) -> Result<S::Ok, S::Error>
@@ -159,14 +169,271 @@ pub fn generate_hardware_configuration(
     }
 }
 
+/// An I/O mapping assigning concrete addresses to the instances of variables declared at wildcard
+/// addresses like `%I*` or `%Q*`. It has the same shape as the generated hardware configuration,
+/// with the `type` and `address` of every entry filled in.
+#[derive(Debug, Deserialize)]
+pub struct IoMapping {
+    #[serde(rename = "HardwareConfiguration")]
+    bindings: Vec<MappedBindings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MappedBindings {
+    Expanded(Vec<MappedBinding>),
+    Single(MappedBinding),
+}
+
+#[derive(Debug, Deserialize)]
+struct MappedBinding {
+    name: String,
+    #[serde(flatten)]
+    direction: HardwareAccessType,
+    #[serde(flatten)]
+    access_type: DirectAccessType,
+    address: Vec<String>,
+}
+
+impl IoMapping {
+    fn get_bindings(&self) -> impl Iterator<Item = &MappedBinding> {
+        self.bindings.iter().flat_map(|it| match it {
+            MappedBindings::Expanded(bindings) => bindings.iter().collect::<Vec<_>>(),
+            MappedBindings::Single(binding) => vec![binding],
+        })
+    }
+}
+
+pub fn parse_io_mapping(content: &str, format: ConfigFormat) -> Result<IoMapping, Diagnostic> {
+    let mapping = match format {
+        ConfigFormat::JSON => serde_json::from_str(content).map_err(anyhow::Error::from),
+        ConfigFormat::TOML => toml::from_str(content).map_err(anyhow::Error::from),
+    };
+    mapping.map_err(|e| {
+        Diagnostic::error(format!("Cannot read the I/O mapping: {e}"))
+            .with_error_code("E002")
+            .with_internal_error(e)
+    })
+}
+
+/// An instance of a variable declared at a wildcard address that was assigned its location in
+/// the process image by an I/O mapping
+#[derive(Debug)]
+pub struct MappedInstance<'idx> {
+    /// the expanded path of the instance, e.g. `motors[1].speed`
+    pub name: String,
+    /// the declaration of the instance
+    pub variable: &'idx VariableIndexEntry,
+    /// the global variable or program instance containing the instance
+    pub root: &'idx VariableIndexEntry,
+    /// the byte offset of the instance relative to its root
+    pub offset: u32,
+    /// the size of the instance in bytes
+    pub size: u32,
+    pub direction: HardwareAccessType,
+    pub address: ImageAddress,
+}
+
+impl MappedInstance<'_> {
+    /// returns the first byte behind the instance in the process image
+    pub fn get_end(&self) -> u32 {
+        self.address.byte + if self.address.bit.is_some() { 1 } else { self.size }
+    }
+}
+
+/// Resolves the entries of the given mapping to the instances they address.
+///
+/// Every entry has to name an instance declared at a wildcard address of the same direction and
+/// may only be mapped once. Mapped outputs must neither overlap each other nor outputs declared at
+/// a fixed address. Instances that are not mapped are reported as a warning and keep their own
+/// storage. Returns the mapped instances together with the problems found in the mapping.
+pub fn resolve_io_mapping<'idx>(
+    index: &'idx Index,
+    mapping: &IoMapping,
+) -> Result<(Vec<MappedInstance<'idx>>, Vec<Diagnostic>), Diagnostic> {
+    let layouts = collect_instance_layouts(index)?;
+    // the instances declared at a wildcard address by their lower-case name
+    let mut templates: IndexMap<String, &InstanceLayout> = IndexMap::new();
+    for layout in &layouts {
+        let Some(binding) = layout.variable.get_hardware_binding() else { continue };
+        if binding.access == DirectAccessType::Template {
+            templates.insert(layout.name.to_lowercase(), layout);
+        }
+    }
+    // the variables occupying a fixed location in the output image, they have no storage inside of
+    // their instances so every declaration is only listed once
+    let mut fixed_outputs = vec![];
+    let mut fixed_variables = HashSet::new();
+    for (_, variable) in index.find_instances() {
+        let Some(binding) = variable.get_hardware_binding() else { continue };
+        let Some(address) = binding.image_address.filter(|_| binding.direction == HardwareAccessType::Output)
+        else {
+            continue;
+        };
+        if fixed_variables.insert(variable.get_qualified_name().to_lowercase()) {
+            let size = get_size(index, variable.get_type_name())
+                .map_err(|message| Diagnostic::error(message).with_error_code("E002"))?;
+            let range = get_bit_range(address, size);
+            fixed_outputs.push((variable.get_qualified_name(), range, binding.location.clone()));
+        }
+    }
+
+    let mut diagnostics = vec![];
+    let mut instances: Vec<MappedInstance> = vec![];
+    let mut mapped = HashSet::new();
+    for entry in mapping.get_bindings() {
+        let name = entry.name.as_str();
+        let Some(layout) = templates.get(&name.to_lowercase()) else {
+            diagnostics.push(
+                Diagnostic::error(format!("Cannot map `{name}`, it is not declared at a wildcard address"))
+                    .with_error_code("E102"),
+            );
+            continue;
+        };
+        let Some(binding) = layout.variable.get_hardware_binding() else { continue };
+        let location = binding.location.clone();
+        if !mapped.insert(name.to_lowercase()) {
+            diagnostics.push(
+                Diagnostic::error(format!("`{name}` is mapped more than once"))
+                    .with_error_code("E102")
+                    .with_location(location),
+            );
+            continue;
+        }
+        if !matches!(binding.direction, HardwareAccessType::Input | HardwareAccessType::Output) {
+            diagnostics.push(
+                Diagnostic::error(format!("Cannot map `{name}`, only inputs and outputs can be mapped"))
+                    .with_error_code("E102")
+                    .with_location(location),
+            );
+            continue;
+        }
+        if entry.direction != binding.direction {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "`{name}` is declared as {:?} but mapped as {:?}",
+                    binding.direction, entry.direction
+                ))
+                .with_error_code("E102")
+                .with_location(location),
+            );
+            continue;
+        }
+        let address = entry
+            .address
+            .iter()
+            .map(|it| it.trim().parse::<u32>().ok())
+            .collect::<Option<Vec<_>>>()
+            .and_then(|it| ImageAddress::new(entry.access_type, &it));
+        let Some(address) = address else {
+            diagnostics.push(
+                Diagnostic::error(format!(
+                    "Invalid address {:?} of type {:?} for `{name}`",
+                    entry.address, entry.access_type
+                ))
+                .with_error_code("E102")
+                .with_location(location),
+            );
+            continue;
+        };
+        let is_bool =
+            index.find_effective_type_info(layout.variable.get_type_name()).is_some_and(|it| it.is_bool());
+        if address.bit.is_some() && !is_bool {
+            diagnostics.push(
+                Diagnostic::error(format!("`{name}` is mapped to a single bit but is not a BOOL"))
+                    .with_error_code("E102")
+                    .with_location(location),
+            );
+            continue;
+        }
+        instances.push(MappedInstance {
+            name: layout.name.clone(),
+            variable: layout.variable,
+            root: layout.root,
+            offset: layout.offset,
+            size: layout.size,
+            direction: binding.direction,
+            address,
+        });
+    }
+
+    for (name, layout) in &templates {
+        if !mapped.contains(name) {
+            let location = layout
+                .variable
+                .get_hardware_binding()
+                .map_or_else(SourceLocation::undefined, |it| it.location.clone());
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "`{}` is declared at a wildcard address but not mapped, it keeps its own storage",
+                    layout.name
+                ))
+                .with_error_code("E102")
+                .with_location(location),
+            );
+        }
+    }
+
+    // an output can only be written by one instance
+    let outputs = instances
+        .iter()
+        .filter(|it| it.direction == HardwareAccessType::Output)
+        .map(|it| {
+            let location = it
+                .variable
+                .get_hardware_binding()
+                .map_or_else(SourceLocation::undefined, |it| it.location.clone());
+            (it.name.as_str(), get_bit_range(it.address, it.size), location)
+        })
+        .collect::<Vec<_>>();
+    for (idx, (name, range, location)) in outputs.iter().enumerate() {
+        for (other, other_range, other_location) in outputs.iter().skip(idx + 1).chain(fixed_outputs.iter()) {
+            if range.start < other_range.end && other_range.start < range.end {
+                diagnostics.push(
+                    Diagnostic::error(format!("`{name}` overlaps `{other}` in the output image"))
+                        .with_error_code("E102")
+                        .with_location(location.clone())
+                        .with_secondary_location(other_location.clone()),
+                );
+            }
+        }
+    }
+
+    Ok((instances, diagnostics))
+}
+
+/// returns the bits of the process image occupied by an instance of the given size at the given address
+fn get_bit_range(address: ImageAddress, size: u32) -> std::ops::Range<u32> {
+    match address.bit {
+        Some(bit) => {
+            let start = address.byte * 8 + bit;
+            start..start + 1
+        }
+        None => address.byte * 8..(address.byte + size) * 8,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use plc_ast::ast::HardwareAccessType;
+
     use crate::{
-        hardware_binding::{collect_hardware_configuration, generate_hardware_configuration},
+        hardware_binding::{
+            collect_hardware_configuration, generate_hardware_configuration, parse_io_mapping,
+            resolve_io_mapping,
+        },
+        index::Index,
+        process_image::ImageAddress,
+        resolver::const_evaluator::evaluate_constants,
         test_utils::tests::index,
         ConfigFormat,
     };
 
+    fn evaluated_index(src: &str) -> Index {
+        let (_, index) = index(src);
+        evaluate_constants(index).0
+    }
+
     #[test]
     fn hardware_collected_gv() {
         let (_, index) = index(
@@ -281,4 +548,142 @@ mod tests {
         let res = generate_hardware_configuration(&config, ConfigFormat::TOML).unwrap();
         insta::assert_snapshot!(res);
     }
+
+    #[test]
+    fn io_mappings_are_read_in_the_shape_of_the_hardware_configuration() {
+        let json = parse_io_mapping(
+            r#"{ "HardwareConfiguration": [
+                [ { "name": "gFb.a", "direction": "Input", "type": "Bit", "address": ["1", "2"] } ],
+                { "name": "gFb.x", "direction": "Output", "type": "Word", "address": ["3"] }
+            ] }"#,
+            ConfigFormat::JSON,
+        )
+        .unwrap();
+        let toml = parse_io_mapping(
+            r#"
+            [[HardwareConfiguration]]
+            name = 'gFb.a'
+            direction = 'Input'
+            type = 'Bit'
+            address = ['1', '2']
+            [[HardwareConfiguration]]
+            name = 'gFb.x'
+            direction = 'Output'
+            type = 'Word'
+            address = ['3']
+            "#,
+            ConfigFormat::TOML,
+        )
+        .unwrap();
+
+        for mapping in [json, toml] {
+            let bindings =
+                mapping.get_bindings().map(|it| (it.name.as_str(), it.address.len())).collect::<Vec<_>>();
+            assert_eq!(bindings, vec![("gFb.a", 2), ("gFb.x", 1)]);
+        }
+    }
+
+    #[test]
+    fn invalid_io_mappings_cannot_be_read() {
+        let error =
+            parse_io_mapping(r#"{ "HardwareConfiguration": [ { "name": "a" } ] }"#, ConfigFormat::JSON)
+                .unwrap_err();
+        assert_eq!(error.get_type(), "E002");
+    }
+
+    #[test]
+    fn wildcard_addresses_are_resolved_through_the_mapping() {
+        let index = evaluated_index(
+            "
+        FUNCTION_BLOCK fb
+        VAR
+            a AT %I*: BOOL;
+            x AT %Q* : INT;
+        END_VAR
+        END_FUNCTION_BLOCK
+        VAR_GLOBAL
+            gFb : fb;
+            aFb : ARRAY[0..1] OF fb;
+        END_VAR",
+        );
+        let mapping = parse_io_mapping(
+            r#"{ "HardwareConfiguration": [
+                { "name": "gFb.a", "direction": "Input", "type": "Bit", "address": ["1", "2"] },
+                { "name": "gfb.x", "direction": "Output", "type": "Word", "address": ["3"] },
+                { "name": "aFb[0].a", "direction": "Input", "type": "Byte", "address": ["4"] },
+                { "name": "aFb[0].x", "direction": "Output", "type": "Word", "address": ["4"] },
+                { "name": "aFb[1].a", "direction": "Input", "type": "Bit", "address": ["4", "0"] },
+                { "name": "aFb[1].x", "direction": "Output", "type": "Word", "address": ["5"] }
+            ] }"#,
+            ConfigFormat::JSON,
+        )
+        .unwrap();
+
+        let (instances, diagnostics) = resolve_io_mapping(&index, &mapping).unwrap();
+        assert!(diagnostics.is_empty(), "{diagnostics:#?}");
+        let instances = instances
+            .iter()
+            .map(|it| (it.name.as_str(), it.root.get_name(), it.offset, it.size, it.direction, it.address))
+            .collect::<Vec<_>>();
+        let at = |byte, bit| ImageAddress { byte, bit };
+        assert_eq!(
+            instances,
+            vec![
+                ("gFb.a", "gFb", 0, 1, HardwareAccessType::Input, at(1, Some(2))),
+                ("gFb.x", "gFb", 2, 2, HardwareAccessType::Output, at(6, None)),
+                ("aFb[0].a", "aFb", 0, 1, HardwareAccessType::Input, at(4, None)),
+                ("aFb[0].x", "aFb", 2, 2, HardwareAccessType::Output, at(8, None)),
+                ("aFb[1].a", "aFb", 4, 1, HardwareAccessType::Input, at(4, Some(0))),
+                ("aFb[1].x", "aFb", 6, 2, HardwareAccessType::Output, at(10, None)),
+            ]
+        );
+    }
+
+    #[test]
+    fn conflicts_in_the_io_mapping_are_reported() {
+        let index = evaluated_index(
+            "
+        VAR_GLOBAL
+            a AT %I* : BOOL;
+            b AT %I* : INT;
+            c AT %Q* : DINT;
+            d AT %Q* : INT;
+            e AT %Q* : INT;
+            f AT %M* : INT;
+            g AT %Q* : INT;
+            h AT %QW10 : INT;
+            i AT %I* : INT;
+            unmapped AT %I* : INT;
+            fixed AT %IW2 : INT;
+        END_VAR",
+        );
+        let mapping = parse_io_mapping(
+            r#"{ "HardwareConfiguration": [
+                { "name": "a", "direction": "Input", "type": "Bit", "address": ["0", "8"] },
+                { "name": "b", "direction": "Output", "type": "Word", "address": ["1"] },
+                { "name": "b", "direction": "Input", "type": "Bit", "address": ["1", "1"] },
+                { "name": "c", "direction": "Output", "type": "DWord", "address": ["0"] },
+                { "name": "d", "direction": "Output", "type": "Word", "address": ["1"] },
+                { "name": "d", "direction": "Output", "type": "Word", "address": ["4"] },
+                { "name": "e", "direction": "Output", "type": "Word", "address": ["x"] },
+                { "name": "f", "direction": "Memory", "type": "Word", "address": ["1"] },
+                { "name": "g", "direction": "Output", "type": "Word", "address": ["10"] },
+                { "name": "i", "direction": "Input", "type": "Bit", "address": ["2", "0"] },
+                { "name": "fixed", "direction": "Input", "type": "Word", "address": ["3"] },
+                { "name": "unknown", "direction": "Input", "type": "Word", "address": ["3"] }
+            ] }"#,
+            ConfigFormat::JSON,
+        )
+        .unwrap();
+
+        let (_, diagnostics) = resolve_io_mapping(&index, &mapping).unwrap();
+        let messages = diagnostics.iter().map(|it| (it.get_severity(), it.get_message())).collect::<Vec<_>>();
+        insta::assert_debug_snapshot!(messages);
+
+        // overlaps point to the declarations of both outputs
+        let overlap = diagnostics.iter().find(|it| it.get_message().starts_with("`g` overlaps")).unwrap();
+        let lines =
+            overlap.get_secondary_locations().unwrap().iter().map(|it| it.get_line()).collect::<Vec<_>>();
+        assert_eq!((overlap.get_location().get_line(), lines), (8, vec![9]));
+    }
 }
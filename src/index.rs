@@ -2,6 +2,7 @@
 use crate::{
     builtins::{self, BuiltIn},
     datalayout::DataLayout,
+    process_image::{self, ImageAddress, MappedAddress, ProcessImage},
     typesystem::{self, *},
 };
 use indexmap::IndexMap;
@@ -58,6 +59,10 @@ pub struct VariableIndexEntry {
     pub argument_type: ArgumentType,
     /// true if this variable is a compile-time-constant
     is_constant: bool,
+    /// true if this variable keeps its value over a warm restart (`RETAIN`)
+    is_retain: bool,
+    /// true if this variable keeps its value over a cold restart (`PERSISTENT`)
+    is_persistent: bool,
     /// the variable's datatype
     pub data_type_name: String,
     /// the index of the member-variable in it's container (e.g. struct). defautls to 0 (Single variables)
@@ -82,11 +87,18 @@ pub struct HardwareBinding {
     pub entries: Vec<ConstId>,
     /// The location in the original source-file
     pub location: SourceLocation,
+    /// The position in the process image, `None` for wildcard and hierarchical addresses
+    pub image_address: Option<ImageAddress>,
 }
 
 impl HardwareBinding {
     fn from_statement(index: &mut Index, it: &AstNode, scope: Option<String>) -> Option<Self> {
         if let AstStatement::HardwareAccess(data) = it.get_stmt() {
+            let image_address = ImageAddress::from_statements(data.access, &data.address)
+                .filter(|_| process_image::get_image_name(data.direction).is_some());
+            if let Some(address) = image_address {
+                index.process_image.register(data.direction, data.access, address);
+            }
             Some(HardwareBinding {
                 access: data.access,
                 direction: data.direction,
@@ -102,6 +114,7 @@ impl HardwareBinding {
                     })
                     .collect(),
                 location: it.get_location(),
+                image_address,
             })
         } else {
             None
@@ -135,6 +148,8 @@ impl VariableIndexEntry {
             initial_value: None,
             argument_type,
             is_constant: false,
+            is_retain: false,
+            is_persistent: false,
             data_type_name: data_type_name.to_string(),
             location_in_parent,
             linkage: LinkageType::Internal,
@@ -156,6 +171,8 @@ impl VariableIndexEntry {
             initial_value: None,
             argument_type: ArgumentType::ByVal(VariableType::Global),
             is_constant: false,
+            is_retain: false,
+            is_persistent: false,
             data_type_name: data_type_name.to_string(),
             location_in_parent: 0,
             linkage: LinkageType::Internal,
@@ -180,6 +197,16 @@ impl VariableIndexEntry {
         self
     }
 
+    pub fn set_retain(mut self, is_retain: bool) -> Self {
+        self.is_retain = is_retain;
+        self
+    }
+
+    pub fn set_persistent(mut self, is_persistent: bool) -> Self {
+        self.is_persistent = is_persistent;
+        self
+    }
+
     pub fn set_hardware_binding(mut self, binding: Option<HardwareBinding>) -> Self {
         self.binding = binding;
         self
@@ -236,6 +263,14 @@ impl VariableIndexEntry {
         self.is_constant
     }
 
+    pub fn is_retain(&self) -> bool {
+        self.is_retain
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.is_persistent
+    }
+
     pub fn is_external(&self) -> bool {
         self.linkage == LinkageType::External
     }
@@ -264,6 +299,12 @@ impl VariableIndexEntry {
         self.binding.as_ref()
     }
 
+    /// returns true if the variable is declared at a direct address like `%IX1.2`, it is read and
+    /// written in the process image and has no storage of its own
+    pub fn is_in_process_image(&self) -> bool {
+        self.binding.as_ref().is_some_and(|it| it.image_address.is_some())
+    }
+
     pub fn is_parameter(&self) -> bool {
         matches!(self.get_variable_type(), VariableType::Input | VariableType::Output | VariableType::InOut)
     }
@@ -405,7 +446,7 @@ impl From<&PouType> for ImplementationType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PouIndexEntry {
     Program {
         name: String,
@@ -420,6 +461,7 @@ pub enum PouIndexEntry {
         linkage: LinkageType,
         location: SourceLocation,
         super_class: Option<String>,
+        generics: Vec<GenericBinding>,
     },
     Function {
         name: String,
@@ -436,6 +478,7 @@ pub enum PouIndexEntry {
         linkage: LinkageType,
         location: SourceLocation,
         super_class: Option<String>,
+        generics: Vec<GenericBinding>,
     },
     Method {
         name: String,
@@ -444,6 +487,7 @@ pub enum PouIndexEntry {
         instance_struct_name: String,
         linkage: LinkageType,
         location: SourceLocation,
+        generics: Vec<GenericBinding>,
     },
     Action {
         name: String,
@@ -478,11 +522,13 @@ impl PouIndexEntry {
     /// # Arguments
     /// - `name` the name of the FunctionBlock
     /// - `linkage` the linkage type of the pou
+    /// - `generics` the type parameters of a generic FunctionBlock, it is only instantiated for concrete types
     pub fn create_function_block_entry(
         pou_name: &str,
         linkage: LinkageType,
         location: SourceLocation,
         super_class: Option<&str>,
+        generics: &[GenericBinding],
     ) -> PouIndexEntry {
         PouIndexEntry::FunctionBlock {
             name: pou_name.into(),
@@ -490,6 +536,7 @@ impl PouIndexEntry {
             linkage,
             location,
             super_class: super_class.map(|s| s.to_owned()),
+            generics: generics.to_vec(),
         }
     }
 
@@ -561,6 +608,7 @@ impl PouIndexEntry {
         linkage: LinkageType,
         location: SourceLocation,
         super_class: Option<String>,
+        generics: &[GenericBinding],
     ) -> PouIndexEntry {
         PouIndexEntry::Class {
             name: pou_name.into(),
@@ -568,6 +616,7 @@ impl PouIndexEntry {
             linkage,
             location,
             super_class,
+            generics: generics.to_vec(),
         }
     }
 
@@ -576,12 +625,14 @@ impl PouIndexEntry {
     /// - `name` the name of the method (without the pou-qualifier)
     /// - `return_type` the name of the method's return type
     /// - `owner_class` the name of the parent pou
+    /// - `generics` the type parameters of the parent pou if it is generic
     pub fn create_method_entry(
         name: &str,
         return_type: &str,
         owner_class: &str,
         linkage: LinkageType,
         location: SourceLocation,
+        generics: &[GenericBinding],
     ) -> PouIndexEntry {
         PouIndexEntry::Method {
             name: name.into(),
@@ -590,6 +641,7 @@ impl PouIndexEntry {
             return_type: return_type.into(),
             linkage,
             location,
+            generics: generics.to_vec(),
         }
     }
 
@@ -675,12 +727,15 @@ impl PouIndexEntry {
         }
     }
 
-    /// returns true if this pou is a function with generic parameters, otherwise false
+    /// returns true if this pou is a function, function block, class or method with generic parameters,
+    /// otherwise false
     pub fn is_generic(&self) -> bool {
-        if let PouIndexEntry::Function { generics, .. } = self {
-            !generics.is_empty()
-        } else {
-            false
+        match self {
+            PouIndexEntry::Function { generics, .. }
+            | PouIndexEntry::FunctionBlock { generics, .. }
+            | PouIndexEntry::Class { generics, .. }
+            | PouIndexEntry::Method { generics, .. } => !generics.is_empty(),
+            _ => false,
         }
     }
 
@@ -726,15 +781,26 @@ impl PouIndexEntry {
         }
     }
 
-    fn is_auto_generated_function(&self) -> bool {
+    pub fn is_auto_generated_function(&self) -> bool {
         matches!(self, PouIndexEntry::Function { is_generated: true, .. })
     }
+
+    pub fn set_linkage(&mut self, new_linkage: LinkageType) {
+        match self {
+            PouIndexEntry::Program { linkage, .. }
+            | PouIndexEntry::FunctionBlock { linkage, .. }
+            | PouIndexEntry::Function { linkage, .. }
+            | PouIndexEntry::Method { linkage, .. }
+            | PouIndexEntry::Action { linkage, .. }
+            | PouIndexEntry::Class { linkage, .. } => *linkage = new_linkage,
+        }
+    }
 }
 
 /// the TypeIndex carries all types.
 /// it is extracted into its seaprate struct so it can be
 /// internally borrowed individually from the other maps
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TypeIndex {
     /// all types (structs, enums, type, POUs, etc.)
     types: SymbolMap<String, DataType>,
@@ -796,7 +862,7 @@ impl TypeIndex {
 /// The global index of the rusty-compiler
 ///
 /// The index contains information about all referencable elements.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Index {
     /// all global variables
     global_variables: SymbolMap<String, VariableIndexEntry>, // IndexMap<String, Vec<VariableIndexEntry>>,
@@ -828,6 +894,13 @@ pub struct Index {
 
     /// The labels contained in each pou
     labels: IndexMap<String, SymbolMap<String, Label>>,
+
+    /// The sizes of the process images behind direct addresses
+    process_image: ProcessImage,
+
+    /// The locations of the mapped instances of variables declared at wildcard addresses, by the
+    /// qualified name of their declaration
+    mapped_addresses: SymbolMap<String, MappedAddress>,
 }
 
 impl Index {
@@ -892,7 +965,8 @@ impl Index {
                         DataTypeInformation::String { size, .. } => {
                             *size = self.import_type_size(&mut other.constant_expressions, size);
                         }
-                        DataTypeInformation::Struct { members, .. } => {
+                        DataTypeInformation::Struct { members, .. }
+                        | DataTypeInformation::Union { members, .. } => {
                             let mut variables = members
                                 .drain(..)
                                 .map(|variable| {
@@ -941,6 +1015,10 @@ impl Index {
         //labels
         self.labels.extend(other.labels);
 
+        //process image
+        self.process_image.import(other.process_image);
+        self.mapped_addresses.extend(other.mapped_addresses);
+
         //Constant expressions are intentionally not imported
         // self.constant_expressions.import(other.constant_expressions)
     }
@@ -952,7 +1030,7 @@ impl Index {
     ) -> VariableIndexEntry {
         variable.initial_value = self.maybe_import_const_expr(import_from, &variable.initial_value);
 
-        let binding = if let Some(HardwareBinding { direction, access, entries, location }) =
+        let binding = if let Some(HardwareBinding { direction, access, entries, location, image_address }) =
             variable.get_hardware_binding()
         {
             let mut new_entries = vec![];
@@ -966,6 +1044,7 @@ impl Index {
                 access: *access,
                 entries: new_entries,
                 location: location.clone(),
+                image_address: *image_address,
             })
         } else {
             None
@@ -1317,6 +1396,14 @@ impl Index {
             .collect()
     }
 
+    /// Returns all `RETAIN` and `PERSISTENT` global variables defined by this project
+    pub fn get_retain_variables(&self) -> Vec<&VariableIndexEntry> {
+        self.global_variables
+            .values()
+            .filter(|it| (it.is_retain() || it.is_persistent()) && it.get_linkage() == LinkageType::Internal)
+            .collect()
+    }
+
     /// Returns the map of pous, should not be used to search for pous -->  see find_pou
     pub fn get_pous(&self) -> &SymbolMap<String, PouIndexEntry> {
         &self.pous
@@ -1360,6 +1447,10 @@ impl Index {
         self.pous.get(&pou_name.to_lowercase())
     }
 
+    pub fn find_pou_mut(&mut self, pou_name: &str) -> Option<&mut PouIndexEntry> {
+        self.pous.get_mut(&pou_name.to_lowercase())
+    }
+
     pub fn register_program(&mut self, name: &str, location: SourceLocation, linkage: LinkageType) {
         let instance_variable =
             VariableIndexEntry::create_global(&format!("{}_instance", &name), name, name, location.clone()) // TODO: Naming convention (see plc_util/src/convention.rs)
@@ -1464,6 +1555,23 @@ impl Index {
         })
     }
 
+    /// rearranges the members of the given struct or POU type so that the member at position
+    /// `order[i]` becomes the i-th member. The members' locations in their parent are updated accordingly
+    pub fn reorder_members(&mut self, type_name: &str, order: &[usize]) {
+        let key = type_name.to_lowercase();
+        let Some(data_type) = self.type_index.types.get_mut(&key).or(self.type_index.pou_types.get_mut(&key))
+        else {
+            return;
+        };
+        if let DataTypeInformation::Struct { members, .. } = &mut data_type.information {
+            let mut reordered = order.iter().map(|it| members[*it].clone()).collect::<Vec<_>>();
+            for (location, member) in reordered.iter_mut().enumerate() {
+                member.location_in_parent = location as u32;
+            }
+            *members = reordered;
+        }
+    }
+
     /// returns the mutable reference to all registered ConstExpressions
     pub fn get_mut_const_expressions(&mut self) -> &mut ConstExpressions {
         &mut self.constant_expressions
@@ -1544,6 +1652,11 @@ impl Index {
         &self.data_layout
     }
 
+    /// lays out all types the way the given target expects them
+    pub fn set_type_layout(&mut self, data_layout: DataLayout) {
+        self.data_layout = data_layout;
+    }
+
     /// returns the implementation of the sub-range-check-function for a variable of the given dataType
     pub fn find_range_check_implementation_for(
         &self,
@@ -1585,6 +1698,42 @@ impl Index {
     pub fn get_labels(&self, pou_name: &str) -> Option<&SymbolMap<String, Label>> {
         self.labels.get(pou_name)
     }
+
+    pub fn get_process_image(&self) -> &ProcessImage {
+        &self.process_image
+    }
+
+    /// grows the process image so it contains the given direct address
+    pub fn register_process_image_address(
+        &mut self,
+        direction: HardwareAccessType,
+        access: DirectAccessType,
+        address: ImageAddress,
+    ) {
+        self.process_image.register(direction, access, address);
+    }
+
+    /// grows the image of the given area to at least the given number of bytes
+    pub fn reserve_process_image(&mut self, direction: HardwareAccessType, size: u32) {
+        self.process_image.reserve(direction, size);
+    }
+
+    /// assigns an instance of the given variable, declared at a wildcard address, its location in
+    /// the process image
+    pub fn register_mapped_address(&mut self, variable: &str, address: MappedAddress) {
+        self.mapped_addresses.insert(variable.to_lowercase(), address);
+    }
+
+    /// returns the mapped instances of the given variable declared at a wildcard address
+    pub fn get_mapped_addresses(&self, variable: &str) -> &[MappedAddress] {
+        self.mapped_addresses.get_all(&variable.to_lowercase()).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// returns all instances of variables declared at wildcard addresses that are mapped into the
+    /// process image
+    pub fn get_all_mapped_addresses(&self) -> impl Iterator<Item = &MappedAddress> {
+        self.mapped_addresses.values()
+    }
 }
 
 /// Returns a default initialization name for a variable or type
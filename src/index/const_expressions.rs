@@ -12,7 +12,7 @@ pub type ConstId = generational_arena::Index;
 /// wrapper around ConstExpression stored in the arena
 /// changing expr allows to change the referenced const-expression
 /// without aquiring a new ID in the arena
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct ConstWrapper {
     /// the constant expression
     expr: ConstExpression,
@@ -29,7 +29,7 @@ impl ConstWrapper {
 /// constant expressions registered here are wrapped behind this enum to indicate
 /// whether this expression was already (potentially) resolved or not, or if a
 /// resolving failed.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ConstExpression {
     Unresolved {
         statement: AstNode,
@@ -73,7 +73,7 @@ impl ConstExpression {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum UnresolvableKind {
     /// Indicates that the const expression was not resolvable for any reason not listed in [`UnresolvableKind`].
     Misc(String),
@@ -98,7 +98,7 @@ impl UnresolvableKind {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct ConstExpressions {
     expressions: Arena<ConstWrapper>,
 }
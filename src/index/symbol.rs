@@ -5,7 +5,7 @@ use std::hash::Hash;
 
 /// A multi-map implementation with a stable order of elements. When iterating
 /// the keys or the values, the iterator reflects the order of insertion.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SymbolMap<K, V> {
     /// internal storage of the SymbolMap that uses an *
     /// IndexMap of Vectors
@@ -40,6 +40,15 @@ where
         self.inner_map.get(key)
     }
 
+    /// returns a mutable reference to the first element associated with the given key
+    /// or None if this key was never associated with an element
+    pub fn get_mut<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        Q: Hash + Equivalent<K>,
+    {
+        self.inner_map.get_mut(key).and_then(|it| it.first_mut())
+    }
+
     /// associates the given value with the give key. Existing associations are
     /// not overwritten, rather an additional association is added
     pub fn insert(&mut self, key: K, value: V) {
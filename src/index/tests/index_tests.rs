@@ -1358,6 +1358,8 @@ fn a_program_pou_is_indexed() {
                 initial_value: None,
                 argument_type: ArgumentType::ByVal(VariableType::Global),
                 is_constant: false,
+                is_retain: false,
+                is_persistent: false,
                 data_type_name: "myProgram".into(),
                 location_in_parent: 0,
                 linkage: LinkageType::Internal,
@@ -1389,6 +1391,7 @@ fn a_program_pou_is_indexed() {
             instance_struct_name: "myFunctionBlock".into(),
             location: source_location_factory.create_range(139..154),
             super_class: None,
+            generics: vec![],
         }),
         index.find_pou("myFunctionBlock"),
     );
@@ -1400,6 +1403,7 @@ fn a_program_pou_is_indexed() {
             instance_struct_name: "myClass".into(),
             location: source_location_factory.create_range(197..204),
             super_class: None,
+            generics: vec![],
         }),
         index.find_pou("myClass"),
     );
@@ -1628,6 +1632,8 @@ fn internal_vla_struct_type_is_indexed_correctly() {
                     initial_value: None,
                     argument_type: ArgumentType::ByVal(VariableType::Input),
                     is_constant: false,
+                    is_retain: false,
+                    is_persistent: false,
                     data_type_name: "__ptr_to___arr_vla_1_int".to_string(),
                     location_in_parent: 0,
                     linkage: LinkageType::Internal,
@@ -1641,6 +1647,8 @@ fn internal_vla_struct_type_is_indexed_correctly() {
                     initial_value: None,
                     argument_type: ArgumentType::ByVal(VariableType::Input),
                     is_constant: false,
+                    is_retain: false,
+                    is_persistent: false,
                     data_type_name: "__bounds___arr_vla_1_int".to_string(),
                     location_in_parent: 1,
                     linkage: LinkageType::Internal,
@@ -139,38 +139,24 @@ pub fn visit_pou(index: &mut Index, pou: &Pou) {
             index.register_pou_type(datatype);
         }
         PouType::FunctionBlock => {
-            let global_struct_name = crate::index::get_initializer_name(&pou.name);
-            let variable = VariableIndexEntry::create_global(
-                &global_struct_name,
-                &global_struct_name,
-                &pou.name,
-                pou.name_location.clone(),
-            )
-            .set_constant(true);
-            index.register_global_initializer(&global_struct_name, variable);
+            register_initializer(index, pou);
             index.register_pou(PouIndexEntry::create_function_block_entry(
                 &pou.name,
                 pou.linkage,
                 pou.name_location.clone(),
                 pou.super_class.clone().as_deref(),
+                &pou.generics,
             ));
             index.register_pou_type(datatype);
         }
         PouType::Class => {
-            let global_struct_name = crate::index::get_initializer_name(&pou.name);
-            let variable = VariableIndexEntry::create_global(
-                &global_struct_name,
-                &global_struct_name,
-                &pou.name,
-                pou.name_location.clone(),
-            )
-            .set_constant(true);
-            index.register_global_initializer(&global_struct_name, variable);
+            register_initializer(index, pou);
             index.register_pou(PouIndexEntry::create_class_entry(
                 &pou.name,
                 pou.linkage,
                 pou.name_location.clone(),
                 pou.super_class.clone(),
+                &pou.generics,
             ));
             index.register_pou_type(datatype);
         }
@@ -192,6 +178,7 @@ pub fn visit_pou(index: &mut Index, pou: &Pou) {
                 owner_class,
                 pou.linkage,
                 pou.name_location.clone(),
+                &pou.generics,
             ));
             index.register_pou_type(datatype);
         }
@@ -199,6 +186,23 @@ pub fn visit_pou(index: &mut Index, pou: &Pou) {
     };
 }
 
+/// registers the global instance holding the initial values of a function block or class, generic pous
+/// are only instantiated for concrete types and have no initializer
+fn register_initializer(index: &mut Index, pou: &Pou) {
+    if !pou.generics.is_empty() {
+        return;
+    }
+    let global_struct_name = crate::index::get_initializer_name(&pou.name);
+    let variable = VariableIndexEntry::create_global(
+        &global_struct_name,
+        &global_struct_name,
+        &pou.name,
+        pou.name_location.clone(),
+    )
+    .set_constant(true);
+    index.register_global_initializer(&global_struct_name, variable);
+}
+
 /// returns the declaration type (ByRef or ByVal) for the given VariableBlock (VAR_INPUT, VAR_OUTPUT, VAR_INOUT, etc.)
 fn get_declaration_type_for(block: &VariableBlock, pou_type: &PouType) -> ArgumentType {
     if matches!(
@@ -299,6 +303,8 @@ fn visit_global_var_block(index: &mut Index, block: &VariableBlock) {
         )
         .set_initial_value(initializer)
         .set_constant(block.constant)
+        .set_retain(block.retain)
+        .set_persistent(block.persistent)
         .set_linkage(linkage)
         .set_hardware_binding(
             var.address.as_ref().and_then(|it| HardwareBinding::from_statement(index, it, None)),
@@ -327,6 +333,18 @@ fn visit_data_type(index: &mut Index, type_declaration: &UserTypeDeclaration) {
             visit_struct(name, variables, index, scope, type_declaration, StructSource::OriginalDeclaration);
         }
 
+        DataType::UnionType { name: Some(name), variables } => {
+            let members = visit_members(name, variables, index, scope);
+            // unions are zero-initialized, their members cannot declare initial values
+            index.register_type(typesystem::DataType {
+                name: name.to_string(),
+                initial_value: None,
+                information: DataTypeInformation::Union { name: name.to_owned(), members },
+                nature: TypeNature::Derived,
+                location: type_declaration.location.clone(),
+            });
+        }
+
         DataType::EnumType { name: Some(name), elements, numeric_type, .. } => {
             let enum_name = name.as_str();
 
@@ -739,7 +757,44 @@ fn visit_struct(
     type_declaration: &UserTypeDeclaration,
     source: StructSource,
 ) {
-    let members = variables
+    let members = visit_members(name, variables, index, scope);
+
+    let nature = source.get_type_nature();
+    let information = DataTypeInformation::Struct { name: name.to_owned(), members, source };
+
+    let init = index.get_mut_const_expressions().maybe_add_constant_expression(
+        type_declaration.initializer.clone(),
+        name,
+        scope.clone(),
+    );
+    index.register_type(typesystem::DataType {
+        name: name.to_string(),
+        initial_value: init,
+        information,
+        nature,
+        location: type_declaration.location.clone(),
+    });
+    //Generate an initializer for the struct
+    let global_struct_name = crate::index::get_initializer_name(name);
+    let variable = VariableIndexEntry::create_global(
+        &global_struct_name,
+        &global_struct_name,
+        name,
+        type_declaration.location.clone(),
+    )
+    .set_initial_value(init)
+    .set_constant(true);
+    index.register_global_initializer(&global_struct_name, variable);
+}
+
+/// registers the inline types of the given struct or union members and returns their index entries
+fn visit_members(
+    name: &str,
+    variables: &[Variable],
+    index: &mut Index,
+    scope: &Option<String>,
+) -> Vec<VariableIndexEntry> {
+    variables
         .iter()
         .enumerate()
         .map(|(count, var)| {
@@ -783,32 +838,5 @@ fn visit_struct(
                 count as u32,
             )
         })
-        .collect::<Vec<_>>();
-
-    let nature = source.get_type_nature();
-    let information = DataTypeInformation::Struct { name: name.to_owned(), members, source };
-
-    let init = index.get_mut_const_expressions().maybe_add_constant_expression(
-        type_declaration.initializer.clone(),
-        name,
-        scope.clone(),
-    );
-    index.register_type(typesystem::DataType {
-        name: name.to_string(),
-        initial_value: init,
-        information,
-        nature,
-        location: type_declaration.location.clone(),
-    });
-    //Generate an initializer for the struct
-    let global_struct_name = crate::index::get_initializer_name(name);
-    let variable = VariableIndexEntry::create_global(
-        &global_struct_name,
-        &global_struct_name,
-        name,
-        type_declaration.location.clone(),
-    )
-    .set_initial_value(init)
-    .set_constant(true);
-    index.register_global_initializer(&global_struct_name, variable);
+        .collect()
 }
@@ -99,6 +99,11 @@ impl<'a> ParseSession<'a> {
         }
     }
 
+    /// returns the token following the current one without advancing
+    pub fn peek(&self) -> Token {
+        self.lexer.clone().next().unwrap_or(Token::End)
+    }
+
     pub fn slice_and_advance(&mut self) -> String {
         let slice = self.slice().to_string();
         self.advance();
@@ -134,6 +139,7 @@ impl<'a> ParseSession<'a> {
             | Token::KeywordFunctionBlock
             | Token::KeywordEndFunctionBlock
             | Token::KeywordEndStruct
+            | Token::KeywordEndUnion
             | Token::KeywordEndAction
             | Token::KeywordEndActions
             | Token::KeywordEndIf
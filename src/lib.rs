@@ -24,7 +24,9 @@ use clap::clap_derive::ArgEnum;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
-use inkwell::targets::{self, TargetMachine, TargetTriple};
+use inkwell::targets::{self, CodeModel, InitializationConfig, RelocMode, TargetMachine, TargetTriple};
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::SourceLocation;
 
 #[cfg(test)]
 use resolver::TypeAnnotator;
@@ -33,15 +35,19 @@ use validation::Validator;
 
 pub mod builtins;
 pub mod codegen;
-mod datalayout;
+pub mod datalayout;
 pub mod expression_path;
 pub mod hardware_binding;
 pub mod index;
 pub mod lexer;
 pub mod linker;
+pub mod online_change;
 pub mod output;
 pub mod parser;
+pub mod process_image;
 pub mod resolver;
+pub mod stack_report;
+pub mod symbol_map;
 mod test_utils;
 
 pub mod typesystem;
@@ -86,6 +92,46 @@ impl Target {
         targets::TargetMachine::normalize_triple(&res)
     }
 
+    /// creates the llvm target machine generating code for this target
+    pub fn create_target_machine(
+        &self,
+        reloc: RelocMode,
+        optimization_level: OptimizationLevel,
+    ) -> Result<TargetMachine, Diagnostic> {
+        targets::Target::initialize_all(&InitializationConfig::default());
+        let triple = self.get_target_triple();
+        let target = targets::Target::from_triple(&triple).map_err(|it| {
+            Diagnostic::codegen_error(
+                format!("Invalid target-tripple '{triple}' - {it:?}"),
+                SourceLocation::undefined(),
+            )
+        })?;
+        target
+            .create_target_machine(
+                &triple,
+                //TODO : Add cpu features as optionals
+                "generic", //TargetMachine::get_host_cpu_name().to_string().as_str(),
+                "",        //TargetMachine::get_host_cpu_features().to_string().as_str(),
+                optimization_level.into(),
+                reloc,
+                CodeModel::Default,
+            )
+            .ok_or_else(|| {
+                Diagnostic::codegen_error("Cannot create target machine.", SourceLocation::undefined())
+            })
+    }
+
+    /// returns true if the target runs without an operating system, e.g. `thumbv7em-none-eabihf`
+    pub fn is_freestanding(&self) -> bool {
+        self.get_target_triple().as_str().to_string_lossy().split('-').skip(1).any(|it| it == "none")
+    }
+
+    /// returns true if the target is WebAssembly, e.g. `wasm32-unknown-unknown`
+    pub fn is_wasm(&self) -> bool {
+        let triple = self.get_target_triple().as_str().to_string_lossy().to_string();
+        triple.starts_with("wasm32-") || triple.starts_with("wasm64-")
+    }
+
     pub fn try_get_name(&self) -> Option<&str> {
         match self {
             Target::System => None,
@@ -22,6 +22,8 @@ impl Linker {
             linker: match linker {
                 Some(linker) => Box::new(CcLinker::new(linker)),
 
+                None if crate::Target::from(target).is_wasm() => Box::new(WasmLinker::new()),
+
                 // TODO: Linker for Windows is missing, see also:
                 // https://github.com/PLC-lang/rusty/pull/702/files#r1052446296
                 None => {
@@ -66,6 +68,18 @@ impl Linker {
         self
     }
 
+    /// Use the given linker script to lay out the output
+    pub fn add_linker_script<'a>(&'a mut self, path: &str) -> &'a mut Self {
+        self.linker.add_linker_script(path);
+        self
+    }
+
+    /// Link without the system libraries for a target without an operating system
+    pub fn set_freestanding(&mut self) -> &mut Self {
+        self.linker.set_freestanding();
+        self
+    }
+
     /// Set the output file and run the linker to generate a shared object
     pub fn build_shared_obj(&mut self, path: PathBuf) -> Result<PathBuf, LinkerError> {
         if let Some(file) = self.get_str_from_path(&path) {
@@ -155,6 +169,44 @@ impl LinkerInterface for LdLinker {
     }
 }
 
+/// Links WebAssembly modules, which are loaded by a host (e.g. a browser) instead of being executed
+/// on their own. The module has no entry point, it exports all of its functions and global
+/// variables (the program instances) next to its memory. Functions that are not linked into the
+/// module are imported from the host.
+struct WasmLinker {
+    args: Vec<String>,
+}
+
+impl WasmLinker {
+    fn new() -> WasmLinker {
+        WasmLinker { args: Vec::default() }
+    }
+}
+
+impl LinkerInterface for WasmLinker {
+    fn args(&mut self) -> &mut Vec<String> {
+        &mut self.args
+    }
+
+    fn finalize(&mut self) -> Result<(), LinkerError> {
+        log::debug!("Linker arguments : {}", self.args.join(" "));
+        lld_rs::link(lld_rs::LldFlavor::Wasm, &self.args).ok().map_err(LinkerError::Link)
+    }
+
+    fn build_shared_object(&mut self, path: &str) {
+        // the host instantiates the module like a shared library, there is nothing to load it with
+        self.build_exectuable(path);
+    }
+
+    fn build_exectuable(&mut self, path: &str) {
+        self.args().push("--no-entry".into());
+        self.args().push("--export-dynamic".into());
+        self.args().push("--allow-undefined".into());
+        self.args().push("-o".into());
+        self.args().push(path.into());
+    }
+}
+
 trait LinkerInterface {
     fn args(&mut self) -> &mut Vec<String>;
     fn finalize(&mut self) -> Result<(), LinkerError>;
@@ -175,6 +227,16 @@ trait LinkerInterface {
         self.args().push(format!("--sysroot={path}"));
     }
 
+    fn add_linker_script(&mut self, path: &str) {
+        self.args().push("-T".into());
+        self.args().push(path.into());
+    }
+
+    fn set_freestanding(&mut self) {
+        self.args().push("-static".into());
+        self.args().push("-nostdlib".into());
+    }
+
     fn build_shared_object(&mut self, path: &str) {
         self.args().push("--shared".into());
         self.args().push("-o".into());
@@ -258,3 +320,51 @@ fn non_windows_target_triple_should_result_in_ok() {
         assert!(Linker::new(target, None).is_ok());
     }
 }
+
+#[test]
+fn freestanding_targets_link_statically_with_a_linker_script() {
+    let mut linker = LdLinker::new();
+    linker.set_freestanding();
+    linker.add_linker_script("memory.ld");
+    linker.add_obj("main.o");
+    linker.build_exectuable("main.elf");
+
+    assert_eq!(linker.args, vec!["-static", "-nostdlib", "-T", "memory.ld", "main.o", "-o", "main.elf"]);
+}
+
+#[test]
+fn wasm_targets_link_a_module_exporting_all_symbols() {
+    for target in &["wasm32-unknown-unknown", "wasm32-wasi", "wasm64-unknown-unknown"] {
+        assert!(crate::Target::from(*target).is_wasm(), "{target}");
+        assert!(Linker::new(target, None).is_ok(), "{target}");
+    }
+    assert!(!crate::Target::from("x86_64-linux-gnu").is_wasm());
+
+    let mut linker = WasmLinker::new();
+    linker.add_obj("main.o");
+    linker.add_lib("iec61131std");
+    linker.build_shared_object("main.wasm");
+
+    assert_eq!(
+        linker.args,
+        vec![
+            "main.o",
+            "-liec61131std",
+            "--no-entry",
+            "--export-dynamic",
+            "--allow-undefined",
+            "-o",
+            "main.wasm"
+        ]
+    );
+}
+
+#[test]
+fn targets_without_an_operating_system_are_freestanding() {
+    for target in &["thumbv7em-none-eabihf", "thumbv6m-none-eabi", "riscv32imac-unknown-none-elf"] {
+        assert!(crate::Target::from(*target).is_freestanding(), "{target}");
+    }
+    for target in &["x86_64-linux-gnu", "armv7-unknown-linux-gnueabihf", "aarch64-apple-darwin"] {
+        assert!(!crate::Target::from(*target).is_freestanding(), "{target}");
+    }
+}
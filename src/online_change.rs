@@ -0,0 +1,351 @@
+//! Support for online changes, i.e. replacing a running application by a new build without
+//! losing its state.
+//!
+//! A [`LayoutRecord`] describes the memory layout of all global variables, program instances and
+//! user defined types of a build. When compiling against the record of a previous build, the
+//! members of structs and POUs keep their previous position while new members are appended, so
+//! the layout of unchanged instances stays the same. The record is also used to generate the
+//! functions migrating the state of the previous build into the new one
+//! (see [`crate::codegen::generators::migration_generator`]).
+use plc_ast::ast::{LinkageType, PouType};
+use plc_diagnostics::diagnostics::Diagnostic;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    index::{Index, VariableIndexEntry},
+    symbol_map::{get_member_offsets, get_size, get_type_description, is_allocated},
+    typesystem::{DataType, DataTypeInformation, StructSource},
+};
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayoutRecord {
+    pub instances: Vec<InstanceRecord>,
+    pub types: Vec<TypeRecord>,
+}
+
+/// A global variable or program instance, identified by the name of its symbol
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstanceRecord {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub size: u32,
+}
+
+/// A struct, program, function block or class
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeRecord {
+    pub name: String,
+    pub size: u32,
+    pub members: Vec<MemberRecord>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct MemberRecord {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl LayoutRecord {
+    pub fn find_instance(&self, name: &str) -> Option<&InstanceRecord> {
+        self.instances.iter().find(|it| it.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn find_type(&self, name: &str) -> Option<&TypeRecord> {
+        self.types.iter().find(|it| it.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl TypeRecord {
+    pub fn find_member(&self, name: &str) -> Option<&MemberRecord> {
+        self.members.iter().find(|it| it.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// Records the layout of all instances and stateful types in the index
+pub fn collect_layout_record(index: &Index) -> Result<LayoutRecord, Diagnostic> {
+    record_layout(index).map_err(|message| Diagnostic::error(message).with_error_code("E002"))
+}
+
+pub fn generate_layout_record(record: &LayoutRecord) -> Result<String, Diagnostic> {
+    serde_json::to_string_pretty(record)
+        .map_err(|e| Diagnostic::error(e.to_string()).with_error_code("E002").with_internal_error(e.into()))
+}
+
+pub fn parse_layout_record(source: &str) -> Result<LayoutRecord, Diagnostic> {
+    serde_json::from_str(source).map_err(|e| {
+        Diagnostic::error(format!("Cannot read the layout of the previous build: {e}"))
+            .with_error_code("E002")
+            .with_internal_error(e.into())
+    })
+}
+
+/// Rearranges the members of all stateful types so that the members which are already part of
+/// the previous layout keep their order, new or changed members are appended.
+/// Types whose parameters would change their position are left untouched, since positional
+/// arguments are passed by the position of the parameter. A warning is returned for each of them.
+pub fn stabilize_layout(index: &mut Index, previous: &LayoutRecord) -> Vec<Diagnostic> {
+    let (reorderings, diagnostics) = get_stable_orders(index, previous);
+    for (type_name, order) in reorderings {
+        index.reorder_members(&type_name, &order);
+    }
+    diagnostics
+}
+
+/// returns the stable order of the members of every stateful type whose layout changed since the
+/// previous build (see [`stabilize_layout`]) together with a warning for each type that is left untouched
+pub fn get_stable_orders(
+    index: &Index,
+    previous: &LayoutRecord,
+) -> (Vec<(String, Vec<usize>)>, Vec<Diagnostic>) {
+    let mut diagnostics = vec![];
+    let mut reorderings = vec![];
+    for data_type in get_stateful_types(index) {
+        let Some(record) = previous.find_type(data_type.get_name()) else {
+            continue;
+        };
+        let members = data_type.get_members();
+        let order = get_stable_order(index, members, record);
+        if order.iter().copied().eq(0..members.len()) {
+            continue;
+        }
+        // struct members are declared like inputs but are never passed by position
+        let is_pou = matches!(
+            data_type.get_type_information(),
+            DataTypeInformation::Struct { source: StructSource::Pou(..), .. }
+        );
+        if is_pou
+            && order
+                .iter()
+                .enumerate()
+                .any(|(position, member)| members[*member].is_parameter() && position != *member)
+        {
+            diagnostics.push(
+                Diagnostic::warning(format!(
+                    "The parameters of `{}` changed, its previous layout cannot be kept for an online change",
+                    data_type.get_name()
+                ))
+                .with_error_code("E091")
+                .with_location(data_type.location.clone()),
+            );
+        } else {
+            reorderings.push((data_type.get_name().to_string(), order));
+        }
+    }
+    (reorderings, diagnostics)
+}
+
+/// returns all structs and POUs whose instances keep their state, i.e. all types except functions
+/// and types declared by external POUs whose layout is defined elsewhere
+pub fn get_stateful_types(index: &Index) -> impl Iterator<Item = &DataType> {
+    index.get_types().values().chain(index.get_pou_types().values()).filter(|it| is_stateful(index, it))
+}
+
+fn is_stateful(index: &Index, data_type: &DataType) -> bool {
+    match data_type.get_type_information() {
+        DataTypeInformation::Struct { source: StructSource::OriginalDeclaration, .. } => {
+            !data_type.location.is_internal()
+        }
+        DataTypeInformation::Struct {
+            source: StructSource::Pou(PouType::Program | PouType::FunctionBlock | PouType::Class),
+            ..
+        } => {
+            index.find_pou(data_type.get_name()).is_some_and(|it| *it.get_linkage() == LinkageType::Internal)
+        }
+        _ => false,
+    }
+}
+
+/// returns the global variables and program instances whose state is kept between builds
+pub fn get_stateful_instances(index: &Index) -> impl Iterator<Item = &VariableIndexEntry> {
+    index
+        .get_globals()
+        .values()
+        .chain(index.get_program_instances())
+        .filter(|it| it.get_linkage() == LinkageType::Internal && !it.is_constant())
+}
+
+fn record_layout(index: &Index) -> Result<LayoutRecord, String> {
+    let instances = get_stateful_instances(index)
+        .map(|it| {
+            Ok(InstanceRecord {
+                name: it.get_name().to_string(),
+                type_name: get_type_description(index, it.get_type_name()),
+                size: get_size(index, it.get_type_name())?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let types =
+        get_stateful_types(index).map(|it| record_type(index, it)).collect::<Result<Vec<_>, String>>()?;
+    Ok(LayoutRecord { instances, types })
+}
+
+/// records the current layout of the given struct or POU
+pub(crate) fn record_type(index: &Index, data_type: &DataType) -> Result<TypeRecord, String> {
+    let members = get_member_offsets(index, data_type.get_name())?
+        .into_iter()
+        .map(|(member, offset)| {
+            Ok(MemberRecord {
+                name: member.get_name().to_string(),
+                type_name: get_type_description(index, member.get_type_name()),
+                offset,
+                size: get_size(index, member.get_type_name())?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(TypeRecord {
+        name: data_type.get_name().to_string(),
+        size: get_size(index, data_type.get_name())?,
+        members,
+    })
+}
+
+/// returns the positions of the given members in their stable order: first the members of the
+/// previous layout in their previous order, then all new members in their declared order followed
+/// by the members that are not allocated inside of the instance
+fn get_stable_order(index: &Index, members: &[VariableIndexEntry], record: &TypeRecord) -> Vec<usize> {
+    let mut kept = members
+        .iter()
+        .enumerate()
+        .filter(|(_, member)| is_allocated(member))
+        .filter_map(|(position, member)| {
+            record
+                .members
+                .iter()
+                .position(|it| {
+                    it.name.eq_ignore_ascii_case(member.get_name())
+                        && it.type_name == get_type_description(index, member.get_type_name())
+                })
+                .map(|previous_position| (previous_position, position))
+        })
+        .collect::<Vec<_>>();
+    kept.sort_unstable();
+    let kept = kept.into_iter().map(|(_, position)| position).collect::<Vec<_>>();
+    let added = (0..members.len()).filter(|it| !kept.contains(it) && is_allocated(&members[*it]));
+    let not_allocated = (0..members.len()).filter(|it| !is_allocated(&members[*it]));
+    kept.iter().copied().chain(added).chain(not_allocated).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resolver::const_evaluator::evaluate_constants, test_utils::tests::index};
+
+    use super::*;
+
+    fn annotated_index(src: &str) -> Index {
+        let (_, index) = index(src);
+        let (index, _) = evaluate_constants(index);
+        index
+    }
+
+    fn member_names(index: &Index, type_name: &str) -> Vec<(String, u32)> {
+        index
+            .find_type(type_name)
+            .unwrap()
+            .get_members()
+            .iter()
+            .map(|it| (it.get_name().to_string(), it.get_location_in_parent()))
+            .collect()
+    }
+
+    #[test]
+    fn layout_record_contains_instances_and_types() {
+        let index = annotated_index(
+            "
+            TYPE Point : STRUCT x, y : DINT; END_STRUCT END_TYPE
+
+            VAR_GLOBAL
+                origin : Point;
+                flag : BOOL;
+            END_VAR
+            VAR_GLOBAL CONSTANT
+                LIMIT : INT := 7;
+            END_VAR
+
+            FUNCTION_BLOCK Counter
+            VAR_INPUT step : INT; END_VAR
+            VAR count : DINT; history : ARRAY[0..LIMIT] OF INT; END_VAR
+            VAR_TEMP scratch : LINT; END_VAR
+            END_FUNCTION_BLOCK
+
+            PROGRAM main
+            VAR counter : Counter; END_VAR
+            END_PROGRAM
+
+            FUNCTION foo : INT END_FUNCTION
+            ",
+        );
+        insta::assert_snapshot!(generate_layout_record(&collect_layout_record(&index).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn previous_members_keep_their_position() {
+        let previous = collect_layout_record(&annotated_index(
+            "
+            TYPE Point : STRUCT x, y : DINT; END_STRUCT END_TYPE
+            FUNCTION_BLOCK Counter
+            VAR_INPUT step : INT; END_VAR
+            VAR a : DINT; b : BOOL; c : REAL; END_VAR
+            END_FUNCTION_BLOCK
+            ",
+        ))
+        .unwrap();
+
+        let mut index = annotated_index(
+            "
+            TYPE Point : STRUCT z, y, x : DINT; END_STRUCT END_TYPE
+            FUNCTION_BLOCK Counter
+            VAR_INPUT step : INT; END_VAR
+            VAR_TEMP t : INT; END_VAR
+            VAR new : INT; c : REAL; b : INT; a : DINT; END_VAR
+            END_FUNCTION_BLOCK
+            ",
+        );
+        let diagnostics = stabilize_layout(&mut index, &previous);
+
+        assert!(diagnostics.is_empty());
+        assert_eq!(
+            member_names(&index, "Point"),
+            vec![("x".to_string(), 0), ("y".to_string(), 1), ("z".to_string(), 2)]
+        );
+        // `b` changed its type and is treated like a new member
+        assert_eq!(
+            member_names(&index, "Counter"),
+            vec![
+                ("step".to_string(), 0),
+                ("a".to_string(), 1),
+                ("c".to_string(), 2),
+                ("new".to_string(), 3),
+                ("b".to_string(), 4),
+                ("t".to_string(), 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn moved_parameters_keep_the_declared_layout() {
+        let previous = collect_layout_record(&annotated_index(
+            "
+            FUNCTION_BLOCK Counter
+            VAR_INPUT a : INT; b : INT; END_VAR
+            END_FUNCTION_BLOCK
+            ",
+        ))
+        .unwrap();
+
+        let mut index = annotated_index(
+            "
+            FUNCTION_BLOCK Counter
+            VAR_INPUT b : INT; a : INT; END_VAR
+            END_FUNCTION_BLOCK
+            ",
+        );
+        let diagnostics = stabilize_layout(&mut index, &previous);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(member_names(&index, "Counter"), vec![("b".to_string(), 0), ("a".to_string(), 1)]);
+    }
+}
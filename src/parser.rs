@@ -11,7 +11,10 @@ use plc_ast::{
     },
     provider::IdProvider,
 };
-use plc_diagnostics::{diagnostician::Diagnostician, diagnostics::Diagnostic};
+use plc_diagnostics::{
+    diagnostician::Diagnostician,
+    diagnostics::{Diagnostic, Severity},
+};
 use plc_source::{
     source_location::{SourceLocation, SourceLocationFactory},
     SourceCode, SourceContainer,
@@ -36,12 +39,14 @@ pub mod expressions_parser;
 pub mod tests;
 pub type ParsedAst = (CompilationUnit, Vec<Diagnostic>);
 
+/// parses the given source and reports its syntax errors, returns the unit together with the
+/// highest severity of the reported errors
 pub fn parse_file(
     source: &SourceCode,
     linkage: LinkageType,
     id_provider: IdProvider,
     diagnostician: &mut Diagnostician,
-) -> CompilationUnit {
+) -> (CompilationUnit, Severity) {
     let location_factory = SourceLocationFactory::for_source(source);
     let (unit, errors) = parse(
         lexer::lex_with_ids(&source.source, id_provider, location_factory),
@@ -51,8 +56,8 @@ pub fn parse_file(
     //Register the source file with the diagnostician
     //TODO: We should reduce the clone here
     diagnostician.register_file(source.get_location_str().to_string(), source.source.clone()); // TODO: Remove clone here, generally passing the GlobalContext instead of the actual source here or in the handle method should be sufficient
-    diagnostician.handle(&errors);
-    unit
+    let severity = diagnostician.handle(&errors);
+    (unit, severity)
 }
 
 pub fn parse(mut lexer: ParseSession, lnk: LinkageType, file_name: &str) -> ParsedAst {
@@ -235,6 +240,7 @@ fn parse_pou(
                 name_location,
                 poly_mode,
                 generics,
+                type_arguments: vec![],
                 linkage,
                 super_class,
             }];
@@ -333,12 +339,38 @@ fn parse_polymorphism_mode(lexer: &mut ParseSession, pou_type: &PouType) -> Opti
 fn parse_super_class(lexer: &mut ParseSession) -> Option<String> {
     if lexer.try_consume(&KeywordExtends) {
         let (name, _) = parse_identifier(lexer)?;
-        Some(name)
+        Some(parse_type_arguments(lexer, name))
     } else {
         None
     }
 }
 
+/// parses the optional type arguments of a generic type reference (e.g. `Fifo<DINT>`) and returns
+/// the reference including its arguments. The arguments are resolved by `instantiate_generic_pous`
+fn parse_type_arguments(lexer: &mut ParseSession, name: String) -> String {
+    if !lexer.try_consume(&Token::OperatorLess) {
+        return name;
+    }
+    let mut arguments = vec![];
+    loop {
+        if matches!(lexer.token, Identifier | KeywordString | KeywordWideString) {
+            let argument = lexer.slice_and_advance();
+            arguments.push(parse_type_arguments(lexer, argument));
+        } else {
+            lexer.accept_diagnostic(Diagnostic::unexpected_token_found(
+                "DataTypeReference",
+                lexer.slice(),
+                lexer.location(),
+            ));
+        }
+        if !lexer.try_consume(&KeywordComma) {
+            break;
+        }
+    }
+    lexer.consume_or_report(Token::OperatorGreater);
+    format!("{name}<{}>", arguments.join(", "))
+}
+
 fn parse_return_type(lexer: &mut ParseSession, pou_type: &PouType) -> Option<DataTypeDeclaration> {
     let start_return_type = lexer.range().start;
     if lexer.try_consume(&KeywordColon) {
@@ -366,7 +398,10 @@ fn parse_return_type(lexer: &mut ParseSession, pou_type: &PouType) -> Option<Dat
             }
 
             if let DataTypeDeclaration::DataTypeDefinition { data_type, .. } = &declaration {
-                if matches!(data_type, DataType::EnumType { .. } | DataType::StructType { .. }) {
+                if matches!(
+                    data_type,
+                    DataType::EnumType { .. } | DataType::StructType { .. } | DataType::UnionType { .. }
+                ) {
                     let datatype_name = declaration
                         .get_location()
                         .to_range()
@@ -455,6 +490,7 @@ fn parse_method(
                 name_location,
                 poly_mode,
                 generics,
+                type_arguments: vec![],
                 linkage,
                 super_class: None,
             },
@@ -599,7 +635,11 @@ fn parse_full_data_type_definition(
     lexer: &mut ParseSession,
     name: Option<String>,
 ) -> Option<DataTypeWithInitializer> {
-    let end_keyword = if lexer.token == KeywordStruct { KeywordEndStruct } else { KeywordSemicolon };
+    let end_keyword = match lexer.token {
+        KeywordStruct => KeywordEndStruct,
+        KeywordUnion => KeywordEndUnion,
+        _ => KeywordSemicolon,
+    };
     parse_any_in_region(lexer, vec![end_keyword], |lexer| {
         let sized = lexer.try_consume(&PropertySized);
         if lexer.try_consume(&KeywordDotDotDot) {
@@ -647,6 +687,17 @@ fn parse_data_type_definition(
             },
             None,
         ))
+    } else if lexer.try_consume(&KeywordUnion) {
+        // Parse union
+        let variables = parse_variable_list(lexer);
+        Some((
+            DataTypeDeclaration::DataTypeDefinition {
+                data_type: DataType::UnionType { name, variables },
+                location: start.span(&lexer.location()),
+                scope: lexer.scope.clone(),
+            },
+            None,
+        ))
     } else if lexer.try_consume(&KeywordArray) {
         parse_array_type_definition(lexer, name)
     } else if lexer.try_consume(&KeywordPointer) {
@@ -707,6 +758,7 @@ fn parse_type_reference_type_definition(
     let start = lexer.range().start;
     //Subrange
     let referenced_type = lexer.slice_and_advance();
+    let referenced_type = parse_type_arguments(lexer, referenced_type);
 
     let bounds = if lexer.try_consume(&KeywordParensOpen) {
         // INT (..) :=
@@ -923,6 +975,13 @@ fn parse_array_type_definition(
     })
 }
 
+/// parses the statements of a body which is not part of a POU declaration (e.g. the ST body of a POU declared in XML)
+/// up to the end of the input
+pub fn parse_statements(mut lexer: ParseSession) -> (Vec<AstNode>, Vec<Diagnostic>) {
+    let statements = parse_body_standalone(&mut lexer);
+    (statements, lexer.diagnostics)
+}
+
 /// parse a body and recovers until the given `end_keywords`
 fn parse_body_in_region(lexer: &mut ParseSession, end_keywords: Vec<Token>) -> Vec<AstNode> {
     parse_any_in_region(lexer, end_keywords, parse_body_standalone)
@@ -1023,8 +1082,18 @@ fn parse_variable_block(lexer: &mut ParseSession, linkage: LinkageType) -> Varia
 
     let constant = lexer.try_consume(&KeywordConstant);
 
-    let retain = lexer.try_consume(&KeywordRetain);
-    lexer.try_consume(&KeywordNonRetain);
+    // RETAIN, NON_RETAIN and PERSISTENT may be combined in any order, e.g. `VAR_GLOBAL PERSISTENT RETAIN`
+    let (mut retain, mut persistent) = (false, false);
+    loop {
+        if lexer.try_consume(&KeywordRetain) {
+            retain = true;
+        } else if is_persistent_modifier(lexer, &variable_block_type) {
+            lexer.advance();
+            persistent = true;
+        } else if !lexer.try_consume(&KeywordNonRetain) {
+            break;
+        }
+    }
 
     let access = parse_access_modifier(lexer);
 
@@ -1037,7 +1106,16 @@ fn parse_variable_block(lexer: &mut ParseSession, linkage: LinkageType) -> Varia
         });
     }
 
-    VariableBlock { access, constant, retain, variables, variable_block_type, linkage, location }
+    VariableBlock { access, constant, retain, persistent, variables, variable_block_type, linkage, location }
+}
+
+/// `PERSISTENT` is no keyword, it is only a modifier of `VAR` and `VAR_GLOBAL` blocks if it does not
+/// start the declaration of a variable named `persistent`
+fn is_persistent_modifier(lexer: &ParseSession, block_type: &VariableBlockType) -> bool {
+    matches!(block_type, VariableBlockType::Local | VariableBlockType::Global)
+        && lexer.token == Identifier
+        && lexer.slice().eq_ignore_ascii_case("PERSISTENT")
+        && !matches!(lexer.peek(), KeywordColon | KeywordComma | KeywordAt)
 }
 
 fn parse_variable_list(lexer: &mut ParseSession) -> Vec<Variable> {
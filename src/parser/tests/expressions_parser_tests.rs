@@ -1624,6 +1624,7 @@ fn sized_string_as_function_return() {
         location: SourceLocation::undefined(),
         name_location: SourceLocation::undefined(),
         generics: vec![],
+        type_arguments: vec![],
         linkage: LinkageType::Internal,
         super_class: None,
     };
@@ -1666,6 +1667,7 @@ fn array_type_as_function_return() {
         location: SourceLocation::undefined(),
         name_location: SourceLocation::undefined(),
         generics: vec![],
+        type_arguments: vec![],
         linkage: LinkageType::Internal,
         super_class: None,
     };
@@ -200,6 +200,7 @@ fn varargs_parameters_can_be_parsed() {
             constant: false,
             access: AccessModifier::Protected,
             retain: false,
+            persistent: false,
             variable_block_type: VariableBlockType::Input(ArgumentProperty::ByVal),
             location: SourceLocation::undefined(),
             linkage: LinkageType::Internal,
@@ -238,6 +239,7 @@ fn varargs_parameters_can_be_parsed() {
         name_location: SourceLocation::undefined(),
         poly_mode: None,
         generics: vec![],
+        type_arguments: vec![],
         linkage: LinkageType::Internal,
         super_class: None,
     };
@@ -270,6 +272,7 @@ fn sized_varargs_parameters_can_be_parsed() {
             constant: false,
             access: AccessModifier::Protected,
             retain: false,
+            persistent: false,
             variable_block_type: VariableBlockType::Input(ArgumentProperty::ByVal),
             location: SourceLocation::undefined(),
             linkage: LinkageType::Internal,
@@ -308,6 +311,7 @@ fn sized_varargs_parameters_can_be_parsed() {
         name_location: SourceLocation::undefined(),
         poly_mode: None,
         generics: vec![],
+        type_arguments: vec![],
         linkage: LinkageType::Internal,
         super_class: None,
     };
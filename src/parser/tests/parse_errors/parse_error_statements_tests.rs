@@ -202,6 +202,7 @@ fn invalid_variable_name_error_recovery() {
                 constant: false,
                 access: AccessModifier::Protected,
                 retain: false,
+                persistent: false,
                 location: SourceLocation::undefined(),
                 variables: vec![Variable {
                     name: "c".into(),
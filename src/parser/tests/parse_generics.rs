@@ -93,3 +93,25 @@ fn generic_method_parameters_are_datatypes() {
     let variables = &function.variable_blocks[0].variables;
     assert_debug_snapshot!(variables);
 }
+
+#[test]
+fn type_arguments_are_part_of_the_type_reference() {
+    let src = "
+        FUNCTION_BLOCK fb EXTENDS Box<INT>
+        VAR
+            x : Fifo<DINT>;
+            y : Map<STRING, Fifo<REAL>>;
+        END_VAR
+        END_FUNCTION_BLOCK";
+    let (parse_result, diagnostics) = parse(src);
+    assert!(diagnostics.is_empty());
+
+    let pou = &parse_result.units[0];
+    assert_eq!(pou.super_class.as_deref(), Some("Box<INT>"));
+    let types = pou.variable_blocks[0]
+        .variables
+        .iter()
+        .map(|it| it.data_type_declaration.get_name().unwrap_or_default())
+        .collect::<Vec<_>>();
+    assert_eq!(types, vec!["Fifo<DINT>", "Map<STRING, Fifo<REAL>>"]);
+}
@@ -83,6 +83,21 @@ fn simple_struct_type_can_be_parsed() {
     assert_eq!(ast_string, expected_ast);
 }
 
+#[test]
+fn simple_union_type_can_be_parsed() {
+    let (result, ..) = parse(
+        r#"
+        TYPE SampleUnion :
+            UNION
+                One : DWORD;
+                Two : ARRAY[0..3] OF BYTE;
+            END_UNION
+        END_TYPE
+        "#,
+    );
+    insta::assert_debug_snapshot!(result.user_types[0]);
+}
+
 #[test]
 fn simple_enum_type_can_be_parsed() {
     let (result, ..) = parse(
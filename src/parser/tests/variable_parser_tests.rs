@@ -71,6 +71,48 @@ fn external_global_vars_can_be_parsed() {
     assert!(matches!(vars, VariableBlock { linkage: LinkageType::External, .. }));
 }
 
+#[test]
+fn retain_and_persistent_global_vars_can_be_parsed() {
+    let src = "
+        VAR_GLOBAL RETAIN a : INT; END_VAR
+        VAR_GLOBAL PERSISTENT b : INT; END_VAR
+        VAR_GLOBAL PERSISTENT RETAIN c : INT; END_VAR
+        VAR_GLOBAL NON_RETAIN d : INT; END_VAR
+        ";
+    let (result, diagnostics) = parse(src);
+
+    assert!(diagnostics.is_empty());
+    let flags = result.global_vars.iter().map(|it| (it.retain, it.persistent)).collect::<Vec<_>>();
+    assert_eq!(flags, vec![(true, false), (false, true), (true, true), (false, false)]);
+}
+
+#[test]
+fn persistent_is_only_a_modifier_of_var_and_var_global_blocks() {
+    let src = "
+        VAR_GLOBAL persistent : INT; END_VAR
+        VAR_GLOBAL PERSISTENT persistent, b : INT; END_VAR
+        PROGRAM prg
+        VAR_INPUT persistent : BOOL; END_VAR
+        VAR persistent AT %IX1.0 : BOOL; END_VAR
+        END_PROGRAM
+        ";
+    let (result, diagnostics) = parse(src);
+
+    assert!(diagnostics.is_empty());
+    let globals = result
+        .global_vars
+        .iter()
+        .map(|it| (it.persistent, it.variables.iter().map(|it| it.name.as_str()).collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    assert_eq!(globals, vec![(false, vec!["persistent"]), (true, vec!["persistent", "b"])]);
+    let locals = result.units[0]
+        .variable_blocks
+        .iter()
+        .map(|it| (it.persistent, it.variables[0].name.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(locals, vec![(false, "persistent"), (false, "persistent")]);
+}
+
 #[test]
 fn global_single_line_vars_can_be_parsed() {
     let src = "VAR_GLOBAL x, y,z : INT; f : BOOL; b, c : SINT; END_VAR";
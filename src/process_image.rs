@@ -0,0 +1,226 @@
+//! The process image holds the memory behind direct addresses like `%IX1.2`, `%QW4` or `%MD10`.
+//!
+//! Every area (inputs, outputs and markers) is generated as a byte array that is just big enough
+//! to hold the highest address used in the project. A runtime copies the physical inputs into the
+//! input image before each cycle and the output image to the physical outputs after it.
+use plc_ast::{
+    ast::{AstNode, AstStatement, DirectAccessType, HardwareAccessType},
+    literals::AstLiteral,
+};
+
+/// the exported symbol of the input image
+pub const INPUT_IMAGE: &str = "__PI_INPUT";
+/// the exported symbol of the output image
+pub const OUTPUT_IMAGE: &str = "__PI_OUTPUT";
+/// the exported symbol of the marker image
+pub const MEMORY_IMAGE: &str = "__PI_MEMORY";
+
+/// the position of a direct address inside its process image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageAddress {
+    /// the offset of the first addressed byte from the start of the image
+    pub byte: u32,
+    /// the addressed bit inside the byte for `X` accesses
+    pub bit: Option<u32>,
+}
+
+impl ImageAddress {
+    /// resolves a flat direct address to its position in the image.
+    ///
+    /// Bits are addressed as `byte.bit` (`%IX1.2`), all other accesses are counted in their own width,
+    /// so `%MW10` starts at byte 20 and `%ID3` at byte 12.
+    /// returns `None` for hierarchical addresses like `%IW1.2.3` which are not part of an image
+    pub fn new(access: DirectAccessType, address: &[u32]) -> Option<ImageAddress> {
+        match (access, address) {
+            (DirectAccessType::Bit, [byte, bit]) if *bit < 8 => {
+                Some(ImageAddress { byte: *byte, bit: Some(*bit) })
+            }
+            (DirectAccessType::Bit | DirectAccessType::Template, _) => None,
+            (_, [offset]) => {
+                let width = access.get_bit_width() as u32 / 8;
+                offset.checked_mul(width).map(|byte| ImageAddress { byte, bit: None })
+            }
+            _ => None,
+        }
+    }
+
+    /// resolves the given address-segments of a hardware access, see [`ImageAddress::new`]
+    pub fn from_statements(access: DirectAccessType, address: &[AstNode]) -> Option<ImageAddress> {
+        let address = address
+            .iter()
+            .map(|it| match it.get_stmt() {
+                AstStatement::Literal(AstLiteral::Integer(value)) => u32::try_from(*value).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        ImageAddress::new(access, &address)
+    }
+
+    /// returns the offset of the first byte behind the addressed value
+    fn end(&self, access: DirectAccessType) -> u32 {
+        let width = if self.bit.is_some() { 1 } else { access.get_bit_width() as u32 / 8 };
+        self.byte.saturating_add(width)
+    }
+}
+
+/// An instance of a variable declared at a wildcard address like `%I*`, that was assigned its location in
+/// the process image by an I/O mapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedAddress {
+    /// the symbol of the global variable or program instance containing the instance
+    pub root: String,
+    /// the byte offset of the instance relative to its root
+    pub offset: u32,
+    pub direction: HardwareAccessType,
+    pub address: ImageAddress,
+}
+
+/// The sizes of the input, output and marker image in bytes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessImage {
+    input: u32,
+    output: u32,
+    memory: u32,
+}
+
+impl ProcessImage {
+    /// grows the image of the given area so it contains the given address
+    pub fn register(
+        &mut self,
+        direction: HardwareAccessType,
+        access: DirectAccessType,
+        address: ImageAddress,
+    ) {
+        self.reserve(direction, address.end(access));
+    }
+
+    /// grows the image of the given area to at least the given number of bytes
+    pub fn reserve(&mut self, direction: HardwareAccessType, size: u32) {
+        if let Some(current) = self.get_size_mut(direction) {
+            *current = (*current).max(size);
+        }
+    }
+
+    /// merges the sizes found in another part of the project into this image
+    pub fn import(&mut self, other: ProcessImage) {
+        self.input = self.input.max(other.input);
+        self.output = self.output.max(other.output);
+        self.memory = self.memory.max(other.memory);
+    }
+
+    /// returns the size of the given area in bytes, `0` if it is never addressed
+    pub fn get_size(&self, direction: HardwareAccessType) -> u32 {
+        match direction {
+            HardwareAccessType::Input => self.input,
+            HardwareAccessType::Output => self.output,
+            HardwareAccessType::Memory => self.memory,
+            HardwareAccessType::Global => 0,
+        }
+    }
+
+    fn get_size_mut(&mut self, direction: HardwareAccessType) -> Option<&mut u32> {
+        match direction {
+            HardwareAccessType::Input => Some(&mut self.input),
+            HardwareAccessType::Output => Some(&mut self.output),
+            HardwareAccessType::Memory => Some(&mut self.memory),
+            HardwareAccessType::Global => None,
+        }
+    }
+
+    /// returns the symbol name and size of every area that is addressed in the project
+    pub fn get_used_areas(&self) -> impl Iterator<Item = (&'static str, u32)> + '_ {
+        [HardwareAccessType::Input, HardwareAccessType::Output, HardwareAccessType::Memory]
+            .into_iter()
+            .filter_map(|direction| get_image_name(direction).map(|name| (name, self.get_size(direction))))
+            .filter(|(_, size)| *size > 0)
+    }
+}
+
+/// returns the symbol of the process image behind the given area, `None` for areas that are not
+/// part of the process image
+pub fn get_image_name(direction: HardwareAccessType) -> Option<&'static str> {
+    match direction {
+        HardwareAccessType::Input => Some(INPUT_IMAGE),
+        HardwareAccessType::Output => Some(OUTPUT_IMAGE),
+        HardwareAccessType::Memory => Some(MEMORY_IMAGE),
+        HardwareAccessType::Global => None,
+    }
+}
+
+/// returns the symbol holding the size of the given image in bytes
+pub fn get_size_name(image_name: &str) -> String {
+    format!("{image_name}_SIZE")
+}
+
+#[cfg(test)]
+mod tests {
+    use plc_ast::ast::{DirectAccessType, HardwareAccessType};
+
+    use super::{ImageAddress, ProcessImage};
+
+    #[test]
+    fn addresses_are_counted_in_their_access_width() {
+        assert_eq!(
+            ImageAddress::new(DirectAccessType::Bit, &[1, 2]),
+            Some(ImageAddress { byte: 1, bit: Some(2) })
+        );
+        assert_eq!(
+            ImageAddress::new(DirectAccessType::Byte, &[3]),
+            Some(ImageAddress { byte: 3, bit: None })
+        );
+        assert_eq!(
+            ImageAddress::new(DirectAccessType::Word, &[10]),
+            Some(ImageAddress { byte: 20, bit: None })
+        );
+        assert_eq!(
+            ImageAddress::new(DirectAccessType::DWord, &[3]),
+            Some(ImageAddress { byte: 12, bit: None })
+        );
+        assert_eq!(
+            ImageAddress::new(DirectAccessType::LWord, &[2]),
+            Some(ImageAddress { byte: 16, bit: None })
+        );
+    }
+
+    #[test]
+    fn hierarchical_addresses_are_not_part_of_the_image() {
+        assert_eq!(ImageAddress::new(DirectAccessType::Bit, &[7, 8]), None);
+        assert_eq!(ImageAddress::new(DirectAccessType::Bit, &[7]), None);
+        assert_eq!(ImageAddress::new(DirectAccessType::Byte, &[4, 8]), None);
+        assert_eq!(ImageAddress::new(DirectAccessType::Template, &[]), None);
+    }
+
+    #[test]
+    fn image_sizes_cover_the_highest_address() {
+        let mut image = ProcessImage::default();
+        let address = |access, address: &[u32]| ImageAddress::new(access, address).unwrap();
+        image.register(
+            HardwareAccessType::Input,
+            DirectAccessType::Bit,
+            address(DirectAccessType::Bit, &[4, 7]),
+        );
+        image.register(
+            HardwareAccessType::Input,
+            DirectAccessType::Byte,
+            address(DirectAccessType::Byte, &[2]),
+        );
+        image.register(
+            HardwareAccessType::Memory,
+            DirectAccessType::Word,
+            address(DirectAccessType::Word, &[10]),
+        );
+
+        let mut other = ProcessImage::default();
+        other.register(
+            HardwareAccessType::Memory,
+            DirectAccessType::Byte,
+            address(DirectAccessType::Byte, &[7]),
+        );
+        image.import(other);
+
+        assert_eq!(image.get_size(HardwareAccessType::Input), 5);
+        assert_eq!(image.get_size(HardwareAccessType::Output), 0);
+        assert_eq!(image.get_size(HardwareAccessType::Memory), 22);
+        assert_eq!(image.get_used_areas().collect::<Vec<_>>(), vec![("__PI_INPUT", 5), ("__PI_MEMORY", 22)]);
+    }
+}
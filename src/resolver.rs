@@ -31,6 +31,7 @@ pub mod generics;
 use crate::{
     builtins::{self, BuiltIn},
     index::{ArgumentType, Index, PouIndexEntry, VariableIndexEntry, VariableType},
+    process_image::ImageAddress,
     typesystem::{
         self, get_bigger_type, DataTypeInformation, InternalType, StringEncoding, StructSource, BOOL_TYPE,
         BYTE_TYPE, DATE_AND_TIME_TYPE, DATE_TYPE, DINT_TYPE, DWORD_TYPE, LINT_TYPE, LREAL_TYPE, LWORD_TYPE,
@@ -601,6 +602,11 @@ impl AstAnnotations {
     pub fn get_bool_id(&self) -> AstId {
         self.bool_id
     }
+
+    /// returns the dependencies of the given implementation, e.g. the POUs it calls
+    pub fn get_pou_dependencies(&self, pou_name: &str) -> Option<&IndexSet<Dependency>> {
+        self.annotation_map.pou_dependencies.get(&pou_name.to_lowercase())
+    }
 }
 
 #[derive(Default, Debug)]
@@ -628,6 +634,9 @@ pub struct AnnotationMapImpl {
     /// x := 10; // a call to `CheckRangeUnsigned` is maped to `10`
     hidden_function_calls: IndexMap<AstId, AstNode>,
 
+    /// the dependencies of every implementation, i.e. the call graph of the project
+    pou_dependencies: IndexMap<String, IndexSet<Dependency>>,
+
     //An index of newly created types
     pub new_index: Index,
 }
@@ -642,6 +651,7 @@ impl AnnotationMapImpl {
         self.type_map.extend(other.type_map);
         self.type_hint_map.extend(other.type_hint_map);
         self.hidden_function_calls.extend(other.hidden_function_calls);
+        self.pou_dependencies.extend(other.pou_dependencies);
         self.new_index.import(other.new_index);
     }
 
@@ -757,8 +767,13 @@ impl<'i> TypeAnnotator<'i> {
 
         let body_ctx = ctx.enter_body();
         for i in &unit.implementations {
+            // collect the dependencies of every implementation on its own before adding them to the unit
+            let unit_dependencies = std::mem::take(&mut visitor.dependencies);
             visitor.dependencies.extend(visitor.get_datatype_dependencies(&i.name, IndexSet::new()));
             i.statements.iter().for_each(|s| visitor.visit_statement(&body_ctx.with_pou(i.name.as_str()), s));
+            let pou_dependencies = std::mem::replace(&mut visitor.dependencies, unit_dependencies);
+            visitor.dependencies.extend(pou_dependencies.iter().cloned());
+            visitor.annotation_map.pou_dependencies.insert(i.name.to_lowercase(), pou_dependencies);
         }
 
         // enum initializers may have been introduced by the visitor (indexer)
@@ -1145,7 +1160,7 @@ impl<'i> TypeAnnotator<'i> {
         };
         if resolved_names.insert(Dependency::Datatype(datatype.get_name().to_string())) {
             match datatype.get_type_information() {
-                DataTypeInformation::Struct { members, .. } => {
+                DataTypeInformation::Struct { members, .. } | DataTypeInformation::Union { members, .. } => {
                     for member in members {
                         resolved_names =
                             self.get_datatype_dependencies(member.get_type_name(), resolved_names);
@@ -1171,7 +1186,8 @@ impl<'i> TypeAnnotator<'i> {
             self.dependencies.insert(Dependency::Datatype(name.to_string()));
         }
         match data_type {
-            DataType::StructType { name: Some(name), variables, .. } => {
+            DataType::StructType { name: Some(name), variables, .. }
+            | DataType::UnionType { name: Some(name), variables, .. } => {
                 let ctx = ctx.with_qualifier(name.clone());
                 variables.iter().for_each(|v| self.visit_variable(&ctx, v))
             }
@@ -1285,6 +1301,13 @@ impl<'i> TypeAnnotator<'i> {
                 self.annotate(statement, StatementAnnotation::value(access_type));
             }
             AstStatement::HardwareAccess(data, ..) => {
+                if let Some(address) = ImageAddress::from_statements(data.access, &data.address) {
+                    self.annotation_map.new_index.register_process_image_address(
+                        data.direction,
+                        data.access,
+                        address,
+                    );
+                }
                 let access_type = get_direct_access_type(&data.access);
                 self.annotate(statement, StatementAnnotation::value(access_type));
             }
@@ -9,6 +9,10 @@ use crate::{
 };
 use plc_source::source_location::SourceLocation;
 
+pub use self::interpreter::ConstFunctions;
+
+mod interpreter;
+
 /// a wrapper for an unresolvable const-expression with the reason
 /// why it could not be resolved
 #[derive(PartialEq, Eq, Debug)]
@@ -34,7 +38,16 @@ impl UnresolvableConstant {
 }
 
 /// returns the resolved constants index and a Vec of qualified names of constants that could not be resolved.
-pub fn evaluate_constants(mut index: Index) -> (Index, Vec<UnresolvableConstant>) {
+pub fn evaluate_constants(index: Index) -> (Index, Vec<UnresolvableConstant>) {
+    evaluate_constants_with_functions(index, &ConstFunctions::default())
+}
+
+/// like [`evaluate_constants`] but additionally evaluates calls to the side-effect-free
+/// functions in `functions` (see [`ConstFunctions`]) at compile time
+pub fn evaluate_constants_with_functions(
+    mut index: Index,
+    functions: &ConstFunctions,
+) -> (Index, Vec<UnresolvableConstant>) {
     let mut unresolvable: Vec<UnresolvableConstant> = Vec::new();
     let constants = index.get_const_expressions();
 
@@ -74,6 +87,7 @@ pub fn evaluate_constants(mut index: Index) -> (Index, Vec<UnresolvableConstant>
                     const_expr.get_qualifier(),
                     &index,
                     target_type,
+                    functions,
                 );
 
                 match (initial_value_literal, &candidates_type) {
@@ -178,9 +192,10 @@ fn get_default_initializer(
     target_type: &str,
     index: &Index,
     location: &SourceLocation,
+    functions: &ConstFunctions,
 ) -> Result<Option<AstNode>, UnresolvableKind> {
     if let Some(init) = index.get_initial_value_for_type(target_type) {
-        evaluate(init, None, index) //TODO do we ave a scope here?
+        evaluate_in(init, None, index, functions) //TODO do we ave a scope here?
     } else {
         let dt = index.get_type_information_or_void(target_type);
         let init = match dt {
@@ -210,7 +225,7 @@ fn get_default_initializer(
             )),
             DataTypeInformation::SubRange { referenced_type, .. }
             | DataTypeInformation::Alias { referenced_type, .. } => {
-                return get_default_initializer(id, referenced_type, index, location)
+                return get_default_initializer(id, referenced_type, index, location, functions)
             }
             _ => None,
         };
@@ -303,7 +318,16 @@ pub fn evaluate(
     scope: Option<&str>,
     index: &Index,
 ) -> Result<Option<AstNode>, UnresolvableKind> {
-    evaluate_with_target_hint(initial, scope, index, None)
+    evaluate_in(initial, scope, index, &ConstFunctions::default())
+}
+
+fn evaluate_in(
+    initial: &AstNode,
+    scope: Option<&str>,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> Result<Option<AstNode>, UnresolvableKind> {
+    evaluate_with_target_hint(initial, scope, index, None, functions)
 }
 
 /// evaluates the given Syntax-Tree `initial` to a `LiteralValue` if possible
@@ -311,6 +335,8 @@ pub fn evaluate(
 /// - `initial` the constant expression to resolve
 /// - `scope` an optional qualifier to be used when resolving references
 /// - `index` the global symbol-table
+/// - `target_type` an optional hint of the expected type, used to detect overflows
+/// - `functions` the functions whose calls may be evaluated at compile time
 /// ## Returns
 /// - returns an Err if resolving caused an internal error (e.g. number parsing)
 /// - returns None if the initializer cannot be resolved  (e.g. missing value)
@@ -319,6 +345,7 @@ fn evaluate_with_target_hint(
     scope: Option<&str>,
     index: &Index,
     target_type: Option<&str>,
+    functions: &ConstFunctions,
 ) -> Result<Option<AstNode>, UnresolvableKind> {
     if !needs_evaluation(initial) {
         return Ok(Some(initial.clone())); // TODO hmm ...
@@ -335,7 +362,7 @@ fn evaluate_with_target_hint(
 
                 let inner_elements = AstNode::get_as_list(elements)
                     .iter()
-                    .map(|e| evaluate_with_target_hint(e, scope, index, tt))
+                    .map(|e| evaluate_with_target_hint(e, scope, index, tt, functions))
                     .collect::<Result<Vec<Option<AstNode>>, UnresolvableKind>>()?
                     .into_iter()
                     .collect::<Option<Vec<AstNode>>>();
@@ -374,6 +401,7 @@ fn evaluate_with_target_hint(
                 target_type.unwrap_or(VOID_TYPE),
                 index,
                 &location,
+                functions,
             )
         }
         AstStatement::ReferenceExpr(ReferenceExpr {
@@ -399,8 +427,8 @@ fn evaluate_with_target_hint(
                     }
                 }
                 Some(dti) => {
-                    evaluate_with_target_hint(target, scope, index, Some(dti.get_name()))?;
-                    Some(get_cast_statement_literal(target, dti.get_name(), scope, index)?)
+                    evaluate_with_target_hint(target, scope, index, Some(dti.get_name()), functions)?;
+                    Some(get_cast_statement_literal(target, dti.get_name(), scope, index, functions)?)
                 }
                 None => return Err(UnresolvableKind::Misc("Cannot resolve unknown Type-Cast.".to_string())),
             }
@@ -420,8 +448,8 @@ fn evaluate_with_target_hint(
             }
         }
         AstStatement::BinaryExpression(BinaryExpression { left, right, operator }) => {
-            let eval_left = evaluate(left, scope, index)?;
-            let eval_right = evaluate(right, scope, index)?;
+            let eval_left = evaluate_in(left, scope, index, functions)?;
+            let eval_right = evaluate_in(right, scope, index, functions)?;
             if let Some((left, right)) = eval_left.zip(eval_right).as_ref() {
                 let evalualted = match operator {
                     Operator::Plus => arithmetic_expression!(left, +, right, "+", id)?,
@@ -454,7 +482,7 @@ fn evaluate_with_target_hint(
                 };
 
                 // We have to re-evaluate to detect overflows
-                evaluate_with_target_hint(&evalualted, scope, index, target_type)?
+                evaluate_with_target_hint(&evalualted, scope, index, target_type, functions)?
             } else {
                 None //not all operators can be resolved
             }
@@ -462,13 +490,13 @@ fn evaluate_with_target_hint(
 
         // NOT x
         AstStatement::UnaryExpression(UnaryExpression { operator: Operator::Not, value }) => {
-            let eval = evaluate(value, scope, index)?;
+            let eval = evaluate_in(value, scope, index, functions)?;
             match eval.as_ref() {
                 Some(AstNode { stmt: AstStatement::Literal(AstLiteral::Bool(v)), id, location }) => {
                     Some(AstFactory::create_literal(AstLiteral::Bool(!v), location.clone(), *id))
                 }
                 Some(AstNode { stmt: AstStatement::Literal(AstLiteral::Integer(v)), id, location }) => {
-                    evaluate_with_target_hint(eval.as_ref().unwrap(), scope, index, target_type)?;
+                    evaluate_with_target_hint(eval.as_ref().unwrap(), scope, index, target_type, functions)?;
                     Some(AstFactory::create_literal(AstLiteral::Integer(!v), location.clone(), *id))
                 }
                 None => {
@@ -479,7 +507,7 @@ fn evaluate_with_target_hint(
         }
         // - x
         AstStatement::UnaryExpression(UnaryExpression { operator: Operator::Minus, value }) => {
-            match evaluate(value, scope, index)? {
+            match evaluate_in(value, scope, index, functions)? {
                 Some(AstNode { stmt: AstStatement::Literal(AstLiteral::Integer(v)), id, location }) => {
                     Some(AstNode::new(AstStatement::Literal(AstLiteral::Integer(-v)), id, location))
                 }
@@ -493,7 +521,7 @@ fn evaluate_with_target_hint(
                         id,
                         location,
                     );
-                    evaluate_with_target_hint(&lit, scope, index, target_type)?
+                    evaluate_with_target_hint(&lit, scope, index, target_type, functions)?
                 }
                 None => {
                     None //not yet resolvable
@@ -504,7 +532,7 @@ fn evaluate_with_target_hint(
         AstStatement::ExpressionList(expressions) => {
             let inner_elements = expressions
                 .iter()
-                .map(|e| evaluate(e, scope, index))
+                .map(|e| evaluate_in(e, scope, index, functions))
                 .collect::<Result<Vec<Option<AstNode>>, UnresolvableKind>>()?
                 .into_iter()
                 .collect::<Option<Vec<AstNode>>>();
@@ -515,7 +543,7 @@ fn evaluate_with_target_hint(
         AstStatement::MultipliedStatement(MultipliedStatement { element, multiplier }) => {
             let inner_elements = AstNode::get_as_list(element.as_ref())
                 .iter()
-                .map(|e| evaluate(e, scope, index))
+                .map(|e| evaluate_in(e, scope, index, functions))
                 .collect::<Result<Vec<Option<AstNode>>, UnresolvableKind>>()?
                 .into_iter()
                 .collect::<Option<Vec<AstNode>>>();
@@ -536,18 +564,31 @@ fn evaluate_with_target_hint(
         }
         AstStatement::Assignment(data) => {
             //Right needs evaluation
-            if let Some(right) = evaluate(&data.right, scope, index)? {
+            if let Some(right) = evaluate_in(&data.right, scope, index, functions)? {
                 Some(AstFactory::create_assignment(*data.left.clone(), right, id))
             } else {
                 Some(initial.clone())
             }
         }
         AstStatement::RangeStatement(data) => {
-            let start = evaluate(&data.start, scope, index)?.unwrap_or_else(|| *data.start.to_owned());
-            let end = evaluate(&data.end, scope, index)?.unwrap_or_else(|| *data.end.to_owned());
+            let start =
+                evaluate_in(&data.start, scope, index, functions)?.unwrap_or_else(|| *data.start.to_owned());
+            let end =
+                evaluate_in(&data.end, scope, index, functions)?.unwrap_or_else(|| *data.end.to_owned());
             Some(AstFactory::create_range_statement(start, end, id))
         }
-        AstStatement::ParenExpression(expr) => evaluate_with_target_hint(expr, scope, index, target_type)?,
+        AstStatement::ParenExpression(expr) => {
+            evaluate_with_target_hint(expr, scope, index, target_type, functions)?
+        }
+        AstStatement::CallStatement(CallStatement { operator, parameters }) => interpreter::evaluate_call(
+            initial,
+            operator,
+            parameters.as_deref(),
+            scope,
+            index,
+            target_type,
+            functions,
+        )?,
         _ => return Err(UnresolvableKind::Misc(format!("Cannot resolve constant: {initial:#?}"))),
     };
     Ok(literal)
@@ -582,20 +623,22 @@ fn get_cast_statement_literal(
     type_name: &str,
     scope: Option<&str>,
     index: &Index,
+    functions: &ConstFunctions,
 ) -> Result<AstNode, UnresolvableKind> {
     let dti = index.find_effective_type_info(type_name);
     match dti {
         Some(&DataTypeInformation::Integer { .. }) => {
-            let evaluated_initial = evaluate_with_target_hint(cast_statement, scope, index, Some(type_name))?
-                .as_ref()
-                .map(|v| {
-                    if let AstStatement::Literal(AstLiteral::Integer(value)) = v.get_stmt() {
-                        Ok(*value)
-                    } else {
-                        Err(UnresolvableKind::Misc(format!("Expected integer value, found {v:?}")))
-                    }
-                })
-                .transpose()?;
+            let evaluated_initial =
+                evaluate_with_target_hint(cast_statement, scope, index, Some(type_name), functions)?
+                    .as_ref()
+                    .map(|v| {
+                        if let AstStatement::Literal(AstLiteral::Integer(value)) = v.get_stmt() {
+                            Ok(*value)
+                        } else {
+                            Err(UnresolvableKind::Misc(format!("Expected integer value, found {v:?}")))
+                        }
+                    })
+                    .transpose()?;
 
             if let Some(value) = evaluated_initial {
                 return Ok(AstNode::new(
@@ -609,7 +652,7 @@ fn get_cast_statement_literal(
         }
 
         Some(DataTypeInformation::Float { .. }) => {
-            let evaluated = evaluate(cast_statement, scope, index)?;
+            let evaluated = evaluate_in(cast_statement, scope, index, functions)?;
             let value = match evaluated.as_ref().map(|it| it.get_stmt()) {
                 Some(AstStatement::Literal(AstLiteral::Integer(value))) => Some(*value as f64),
                 Some(AstStatement::Literal(AstLiteral::Real(value))) => value.parse::<f64>().ok(),
@@ -732,8 +775,8 @@ macro_rules! compare_expression {
 use compare_expression;
 use plc_ast::{
     ast::{
-        AstFactory, AstId, AstNode, AstStatement, BinaryExpression, MultipliedStatement, Operator,
-        ReferenceAccess, ReferenceExpr, UnaryExpression,
+        AstFactory, AstId, AstNode, AstStatement, BinaryExpression, CallStatement, MultipliedStatement,
+        Operator, ReferenceAccess, ReferenceExpr, UnaryExpression,
     },
     literals::{Array, AstLiteral, StringValue},
 };
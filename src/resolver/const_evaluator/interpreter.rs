@@ -0,0 +1,827 @@
+//! compile-time evaluation of calls inside const-expressions.
+//!
+//! A call can be evaluated if it targets one of the supported builtin functions (e.g. `SIZEOF`,
+//! `MIN`, `MAX`, `SHL` or a `X_TO_Y` conversion) or if it targets a side-effect-free function
+//! implemented in ST. The latter is evaluated by interpreting the function's body on literals.
+use std::{cell::Cell, collections::HashMap};
+
+use plc_ast::{
+    ast::{
+        flatten_expression_list, Assignment, AstNode, AstStatement, BinaryExpression, CallStatement,
+        CompilationUnit, Implementation, LinkageType, MultipliedStatement, PouType, RangeStatement,
+        ReferenceAccess, ReferenceExpr, UnaryExpression,
+    },
+    control_statements::{AstControlStatement, ForLoopStatement},
+    literals::AstLiteral,
+};
+
+use crate::{
+    datalayout::get_allocated_size,
+    index::{const_expressions::UnresolvableKind, ArgumentType, Index, VariableIndexEntry, VariableType},
+    typesystem::DataTypeInformation,
+};
+
+use super::{cast_if_necessary, evaluate_with_target_hint, get_default_initializer};
+
+/// the maximum number of nested function calls during the evaluation of a single expression
+const MAX_CALL_DEPTH: usize = 16;
+/// the maximum number of statements executed during the evaluation of a single expression
+const MAX_STEPS: usize = 100_000;
+
+/// The functions whose calls may be evaluated at compile time.
+///
+/// Only non-generic functions with an internal ST implementation are considered. Whether a
+/// function is actually side-effect-free is decided while interpreting its body, every
+/// statement or expression that cannot be evaluated on literals aborts the evaluation.
+#[derive(Default)]
+pub struct ConstFunctions<'a> {
+    /// the implementations indexed by their lower-case name
+    implementations: HashMap<String, &'a Implementation>,
+    /// the current depth of nested calls
+    depth: Cell<usize>,
+    /// the remaining number of statements that may be executed
+    steps: Cell<usize>,
+}
+
+impl<'a> ConstFunctions<'a> {
+    pub fn new(units: &'a [CompilationUnit]) -> Self {
+        let implementations = units
+            .iter()
+            .flat_map(|unit| unit.implementations.iter())
+            .filter(|it| {
+                it.pou_type == PouType::Function && it.linkage == LinkageType::Internal && !it.generic
+            })
+            .map(|it| (it.name.to_lowercase(), it))
+            .collect();
+
+        ConstFunctions { implementations, ..Default::default() }
+    }
+
+    fn find_implementation(&self, name: &str) -> Option<&'a Implementation> {
+        self.implementations.get(&name.to_lowercase()).copied()
+    }
+}
+
+/// the result of executing a statement
+enum Flow {
+    Next,
+    Exit,
+    Continue,
+    Return,
+}
+
+/// a local variable of a function that is currently interpreted
+struct Local {
+    value: AstNode,
+    type_name: String,
+}
+
+/// the local variables of a function that is currently interpreted
+struct Frame<'f> {
+    function: &'f str,
+    locals: HashMap<String, Local>,
+}
+
+type EvaluationResult<T> = Result<Option<T>, UnresolvableKind>;
+
+/// evaluates the `call` to `operator` with the given `parameters`.
+/// returns `Ok(None)` if the call cannot be evaluated yet (e.g. because one of its arguments is
+/// not resolved yet)
+pub(super) fn evaluate_call(
+    call: &AstNode,
+    operator: &AstNode,
+    parameters: Option<&AstNode>,
+    scope: Option<&str>,
+    index: &Index,
+    target_type: Option<&str>,
+    functions: &ConstFunctions,
+) -> EvaluationResult<AstNode> {
+    let Some(name) = operator.get_flat_reference_name() else {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot resolve call to {operator:?} in constant evaluation"
+        )));
+    };
+    let arguments = parameters.map(flatten_expression_list).unwrap_or_default();
+
+    let result = if let Some(implementation) = functions.find_implementation(name) {
+        call_function(implementation, operator, &arguments, scope, index, functions)?
+    } else if name.eq_ignore_ascii_case("SIZEOF") {
+        evaluate_sizeof(name, &arguments, scope, index)?
+    } else {
+        let Some(values) = evaluate_arguments(name, &arguments, scope, index, functions)? else {
+            return Ok(None);
+        };
+        let operand_type = arguments.first().and_then(|it| get_argument_type(it, scope, index));
+        evaluate_builtin(name, &values, operand_type, operator, index)?
+    };
+
+    // the literal replaces the call, so it is generated with the call's type rather than the type
+    // of an argument (e.g. the struct passed to `SIZEOF`)
+    let result = result.map(|it| match it.get_stmt() {
+        AstStatement::Literal(literal) => {
+            AstNode::new_literal(literal.clone(), call.get_id(), call.get_location())
+        }
+        _ => it,
+    });
+
+    // we have to re-evaluate to detect overflows
+    result
+        .map(|it| evaluate_with_target_hint(&it, scope, index, target_type, functions))
+        .transpose()
+        .map(Option::flatten)
+}
+
+/// evaluates the given positional arguments, returns `Ok(None)` if one of them is not resolvable yet
+fn evaluate_arguments(
+    name: &str,
+    arguments: &[&AstNode],
+    scope: Option<&str>,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<Vec<AstNode>> {
+    if arguments
+        .iter()
+        .any(|it| matches!(it.get_stmt(), AstStatement::Assignment(..) | AstStatement::OutputAssignment(..)))
+    {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate call to `{name}` with named arguments in constant evaluation"
+        )));
+    }
+
+    Ok(arguments
+        .iter()
+        .map(|it| evaluate_with_target_hint(it, scope, index, None, functions))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .collect::<Option<Vec<_>>>())
+}
+
+/// evaluates `SIZEOF(x)` where `x` is either the name of a datatype or a variable
+fn evaluate_sizeof(
+    name: &str,
+    arguments: &[&AstNode],
+    scope: Option<&str>,
+    index: &Index,
+) -> EvaluationResult<AstNode> {
+    let [argument] = arguments else {
+        return Err(UnresolvableKind::Misc(format!("Expected exactly one parameter for {name}")));
+    };
+
+    let type_name = argument.get_flat_reference_name().and_then(|reference| {
+        index
+            .find_variable(scope, std::slice::from_ref(&reference))
+            .map(|it| it.get_type_name())
+            .or_else(|| index.find_effective_type_by_name(reference).map(|it| it.get_name()))
+    });
+    let Some(type_info) = type_name.and_then(|it| index.find_effective_type_info(it)) else {
+        return Err(UnresolvableKind::Misc(format!("Cannot evaluate {name} of {argument:?}")));
+    };
+
+    // the size is not known yet if it depends on unresolved constants (e.g. array bounds)
+    Ok(get_allocated_size(type_info, index).map(|size| {
+        AstNode::new_literal(AstLiteral::new_integer(size.into()), argument.get_id(), argument.get_location())
+    }))
+}
+
+/// returns the type of the given argument if it is known without annotating it, i.e. for typed
+/// literals (e.g. `SINT#64`) and references to variables
+fn get_argument_type<'idx>(
+    argument: &AstNode,
+    scope: Option<&str>,
+    index: &'idx Index,
+) -> Option<&'idx DataTypeInformation> {
+    let type_name = match argument.get_stmt() {
+        AstStatement::ReferenceExpr(ReferenceExpr { access: ReferenceAccess::Cast(_), base: Some(base) }) => {
+            base.get_flat_reference_name()?
+        }
+        _ => {
+            let name = argument.get_flat_reference_name()?;
+            index.find_variable(scope, std::slice::from_ref(&name))?.get_type_name()
+        }
+    };
+    index.find_effective_type_info(type_name)
+}
+
+/// wraps the given value around to the range of the integer type, the way the value is
+/// truncated at runtime (e.g. `INT_TO_SINT(300)` is `44`)
+fn wrap_integer(value: i128, type_info: &DataTypeInformation) -> i128 {
+    let DataTypeInformation::Integer { signed, size, .. } = type_info else { return value };
+    let Some(unused_bits) = 128_u32.checked_sub(*size).filter(|it| *it > 0) else { return value };
+    if *signed {
+        (value << unused_bits) >> unused_bits
+    } else {
+        (((value as u128) << unused_bits) >> unused_bits) as i128
+    }
+}
+
+/// evaluates a call to one of the supported builtin functions with already evaluated arguments,
+/// `operand_type` is the type of the first argument if it is known
+fn evaluate_builtin(
+    name: &str,
+    arguments: &[AstNode],
+    operand_type: Option<&DataTypeInformation>,
+    operator: &AstNode,
+    index: &Index,
+) -> EvaluationResult<AstNode> {
+    let (id, location) = (operator.get_id(), operator.get_location());
+    let upper_name = name.to_uppercase();
+    let literal = match (upper_name.as_str(), arguments) {
+        ("MIN" | "MAX", [first, rest @ ..]) => {
+            let is_max = upper_name == "MAX";
+            let mut result = first;
+            for candidate in rest {
+                let greater = compare_numbers(candidate, result)?.is_gt();
+                if greater == is_max {
+                    result = candidate;
+                }
+            }
+            return Ok(Some(result.clone()));
+        }
+        ("LIMIT", [min, value, max]) => {
+            let result = if compare_numbers(value, min)?.is_lt() {
+                min
+            } else if compare_numbers(value, max)?.is_gt() {
+                max
+            } else {
+                value
+            };
+            return Ok(Some(result.clone()));
+        }
+        ("ABS", [value]) => match value.get_stmt() {
+            AstStatement::Literal(AstLiteral::Integer(v)) => AstLiteral::new_integer(v.abs()),
+            AstStatement::Literal(AstLiteral::Real(v)) => {
+                AstLiteral::new_real(parse_real(v)?.abs().to_string())
+            }
+            _ => return cannot_evaluate(name, arguments),
+        },
+        ("SHL" | "SHR", [value, bits]) => match (value.get_stmt(), bits.get_stmt()) {
+            (
+                AstStatement::Literal(AstLiteral::Integer(v)),
+                AstStatement::Literal(AstLiteral::Integer(n)),
+            ) if *v >= 0 && (0..64).contains(n) => {
+                // the bits shifted beyond the operand's width are lost
+                let result = if upper_name == "SHL" { v << n } else { v >> n };
+                AstLiteral::new_integer(operand_type.map_or(result, |it| wrap_integer(result, it)))
+            }
+            _ => return cannot_evaluate(name, arguments),
+        },
+        (_, [value]) => {
+            let Some(target_type) = get_conversion_target(&upper_name, index) else {
+                return Err(UnresolvableKind::Misc(format!(
+                    "Cannot resolve call to `{name}` in constant evaluation"
+                )));
+            };
+            convert(value, target_type, index).ok_or_else(|| {
+                UnresolvableKind::Misc(format!("Cannot evaluate {name}({value:?}) in constant evaluation"))
+            })?
+        }
+        _ => {
+            return Err(UnresolvableKind::Misc(format!(
+                "Cannot resolve call to `{name}` in constant evaluation"
+            )))
+        }
+    };
+
+    Ok(Some(AstNode::new_literal(literal, id, location)))
+}
+
+fn cannot_evaluate(name: &str, arguments: &[AstNode]) -> EvaluationResult<AstNode> {
+    Err(UnresolvableKind::Misc(format!("Cannot evaluate {name}{arguments:?} in constant evaluation")))
+}
+
+fn parse_real(value: &str) -> Result<f64, UnresolvableKind> {
+    value.parse::<f64>().map_err(|err| UnresolvableKind::Misc(format!("{err:}: {value:}")))
+}
+
+fn compare_numbers(left: &AstNode, right: &AstNode) -> Result<std::cmp::Ordering, UnresolvableKind> {
+    let as_real = |it: &AstNode| match it.get_stmt() {
+        AstStatement::Literal(AstLiteral::Integer(v)) => Ok(*v as f64),
+        AstStatement::Literal(AstLiteral::Real(v)) => parse_real(v),
+        _ => Err(UnresolvableKind::Misc(format!("Cannot compare {left:?} and {right:?}"))),
+    };
+
+    match (left.get_stmt(), right.get_stmt()) {
+        (AstStatement::Literal(AstLiteral::Integer(l)), AstStatement::Literal(AstLiteral::Integer(r))) => {
+            Ok(l.cmp(r))
+        }
+        _ => as_real(left)?
+            .partial_cmp(&as_real(right)?)
+            .ok_or_else(|| UnresolvableKind::Misc(format!("Cannot compare {left:?} and {right:?}"))),
+    }
+}
+
+/// returns the target type of a conversion function like `INT_TO_REAL` or `TO_REAL`
+fn get_conversion_target<'idx>(name: &str, index: &'idx Index) -> Option<&'idx DataTypeInformation> {
+    let target = name.rsplit_once("_TO_").map(|(_, target)| target).or_else(|| name.strip_prefix("TO_"))?;
+    index.find_effective_type_info(target)
+}
+
+/// converts the given literal into a literal of the `target_type`, following the semantics of
+/// the `X_TO_Y` conversion functions (e.g. reals are rounded when converted to an integer)
+fn convert(value: &AstNode, target_type: &DataTypeInformation, index: &Index) -> Option<AstLiteral> {
+    let AstStatement::Literal(literal) = value.get_stmt() else { return None };
+    let target_type = match target_type {
+        DataTypeInformation::Enum { referenced_type, .. } => {
+            index.find_effective_type_info(referenced_type)?
+        }
+        _ => target_type,
+    };
+
+    match (literal, target_type) {
+        (AstLiteral::Integer(v), DataTypeInformation::Integer { .. }) if target_type.is_bool() => {
+            Some(AstLiteral::new_bool(*v != 0))
+        }
+        (AstLiteral::Real(v), DataTypeInformation::Integer { .. }) if target_type.is_bool() => {
+            Some(AstLiteral::new_bool(v.parse::<f64>().ok()? != 0.0))
+        }
+        (AstLiteral::Bool(v), DataTypeInformation::Integer { .. }) if target_type.is_bool() => {
+            Some(AstLiteral::new_bool(*v))
+        }
+        (AstLiteral::Integer(v), DataTypeInformation::Integer { .. }) => {
+            Some(AstLiteral::new_integer(wrap_integer(*v, target_type)))
+        }
+        (AstLiteral::Real(v), DataTypeInformation::Integer { .. }) => {
+            let value = v.parse::<f64>().ok()?.round() as i128;
+            Some(AstLiteral::new_integer(wrap_integer(value, target_type)))
+        }
+        (AstLiteral::Bool(v), DataTypeInformation::Integer { .. }) => {
+            Some(AstLiteral::new_integer(*v as i128))
+        }
+        (AstLiteral::Integer(v), DataTypeInformation::Float { .. }) => {
+            Some(AstLiteral::new_real((*v as f64).to_string()))
+        }
+        (AstLiteral::Real(v), DataTypeInformation::Float { .. }) => Some(AstLiteral::new_real(v.clone())),
+        (AstLiteral::Bool(v), DataTypeInformation::Float { .. }) => {
+            Some(AstLiteral::new_real((*v as i32 as f64).to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// evaluates the call of the given user-defined function by interpreting its body
+fn call_function(
+    implementation: &Implementation,
+    operator: &AstNode,
+    arguments: &[&AstNode],
+    scope: Option<&str>,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<AstNode> {
+    let name = implementation.name.as_str();
+    if functions.depth.get() >= MAX_CALL_DEPTH {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate `{name}`: exceeded the maximum call depth of {MAX_CALL_DEPTH}"
+        )));
+    }
+    if functions.depth.get() == 0 {
+        functions.steps.set(MAX_STEPS);
+    }
+
+    let Some(mut frame) = create_frame(implementation, operator, arguments, scope, index, functions)? else {
+        return Ok(None);
+    };
+
+    functions.depth.set(functions.depth.get() + 1);
+    let flow = execute_block(&implementation.statements, &mut frame, index, functions);
+    functions.depth.set(functions.depth.get() - 1);
+    if flow?.is_none() {
+        return Ok(None);
+    }
+
+    let Some(return_value) = frame.locals.remove(&name.to_lowercase()) else {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate `{name}` since it does not return a value"
+        )));
+    };
+    Ok(Some(cast_if_necessary(return_value.value, &Some(return_value.type_name.as_str()), index)))
+}
+
+/// creates the local variables for a call to `implementation`, inputs are initialized with the
+/// given arguments, all other variables with their initial values
+fn create_frame<'f>(
+    implementation: &'f Implementation,
+    operator: &AstNode,
+    arguments: &[&AstNode],
+    scope: Option<&str>,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<Frame<'f>> {
+    let name = implementation.name.as_str();
+    let members = index.get_pou_members(name);
+    let parameters = index.get_declared_parameters(name);
+    if index.get_variadic_member(name).is_some() {
+        return Err(UnresolvableKind::Misc(format!("Cannot evaluate variadic function `{name}`")));
+    }
+
+    // match the arguments to the declared parameters
+    let mut bound_arguments = HashMap::new();
+    for (position, argument) in arguments.iter().enumerate() {
+        let (parameter, value) = match argument.get_stmt() {
+            AstStatement::Assignment(Assignment { left, right }) => {
+                let parameter = left
+                    .get_flat_reference_name()
+                    .and_then(|it| parameters.iter().find(|p| p.get_name().eq_ignore_ascii_case(it)));
+                (parameter, right.as_ref())
+            }
+            AstStatement::OutputAssignment(..) => {
+                return Err(UnresolvableKind::Misc(format!(
+                    "Cannot evaluate `{name}` since output-assignments have side-effects"
+                )))
+            }
+            _ => (parameters.get(position), *argument),
+        };
+
+        let Some(parameter) = parameter else {
+            return Err(UnresolvableKind::Misc(format!(
+                "Cannot evaluate `{name}`: unexpected argument {argument:?}"
+            )));
+        };
+        if parameter.get_variable_type() != VariableType::Input {
+            return Err(UnresolvableKind::Misc(format!(
+                "Cannot evaluate `{name}` since `{}` is not an input",
+                parameter.get_name()
+            )));
+        }
+
+        let Some(value) =
+            evaluate_with_target_hint(value, scope, index, Some(parameter.get_type_name()), functions)?
+        else {
+            return Ok(None);
+        };
+        bound_arguments.insert(parameter.get_name().to_lowercase(), value);
+    }
+
+    let mut locals = HashMap::new();
+    for member in members {
+        if member.get_declaration_type() != ArgumentType::ByVal(member.get_variable_type())
+            && member.get_variable_type() != VariableType::Input
+        {
+            return Err(UnresolvableKind::Misc(format!(
+                "Cannot evaluate `{name}` since `{}` is passed by reference",
+                member.get_name()
+            )));
+        }
+
+        let local_name = member.get_name().to_lowercase();
+        let value = if let Some(value) = bound_arguments.remove(&local_name) {
+            value
+        } else {
+            let Some(value) = get_initial_value(member, operator, name, index, functions)? else {
+                return Ok(None);
+            };
+            value
+        };
+        let type_name = member.get_type_name().to_string();
+        locals.insert(
+            local_name,
+            Local { value: cast_if_necessary(value, &Some(&type_name), index), type_name },
+        );
+    }
+
+    Ok(Some(Frame { function: name, locals }))
+}
+
+/// returns the evaluated initial value of the given local variable
+fn get_initial_value(
+    member: &VariableIndexEntry,
+    operator: &AstNode,
+    function: &str,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<AstNode> {
+    let type_name = member.get_type_name();
+    let type_info = index.get_type_information_or_void(type_name);
+    if !(type_info.is_numerical() || type_info.is_string()) {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate `{function}` since `{}` of type {type_name} is not supported in constant evaluation",
+            member.get_name()
+        )));
+    }
+
+    if let Some(initial_value) = index.get_initial_value(&member.initial_value) {
+        evaluate_with_target_hint(initial_value, Some(function), index, Some(type_name), functions)
+    } else {
+        get_default_initializer(operator.get_id(), type_name, index, &operator.get_location(), functions)
+    }
+}
+
+/// executes the given statements. returns `Ok(None)` if a statement cannot be evaluated yet
+fn execute_block(
+    statements: &[AstNode],
+    frame: &mut Frame,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<Flow> {
+    for statement in statements {
+        match execute(statement, frame, index, functions)? {
+            Some(Flow::Next) => {}
+            flow => return Ok(flow),
+        }
+    }
+    Ok(Some(Flow::Next))
+}
+
+/// counts one evaluation step against the budget of [`MAX_STEPS`]. Statements and every
+/// iteration of a loop are charged, so that even loops with an empty body terminate
+fn charge_step(frame: &Frame, functions: &ConstFunctions) -> Result<(), UnresolvableKind> {
+    let Some(steps) = functions.steps.get().checked_sub(1) else {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate `{}`: exceeded the maximum of {MAX_STEPS} evaluation steps",
+            frame.function
+        )));
+    };
+    functions.steps.set(steps);
+    Ok(())
+}
+
+fn execute(
+    statement: &AstNode,
+    frame: &mut Frame,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<Flow> {
+    charge_step(frame, functions)?;
+
+    match statement.get_stmt() {
+        AstStatement::EmptyStatement(..) => {}
+        AstStatement::Assignment(Assignment { left, right }) => {
+            let Some(name) = get_local_name(left, frame) else {
+                return Err(UnresolvableKind::Misc(format!(
+                    "Cannot evaluate `{}` since the assignment to {left:?} has side-effects",
+                    frame.function
+                )));
+            };
+            let type_name = frame.locals[&name].type_name.clone();
+            let Some(value) = evaluate_expression(right, frame, index, Some(&type_name), functions)? else {
+                return Ok(None);
+            };
+            let value = cast_if_necessary(value, &Some(&type_name), index);
+            frame.locals.insert(name, Local { value, type_name });
+        }
+        AstStatement::CallStatement(..) => {
+            if evaluate_expression(statement, frame, index, None, functions)?.is_none() {
+                return Ok(None);
+            }
+        }
+        AstStatement::ControlStatement(AstControlStatement::If(stmt)) => {
+            for block in &stmt.blocks {
+                let Some(condition) = evaluate_condition(&block.condition, frame, index, functions)? else {
+                    return Ok(None);
+                };
+                if condition {
+                    return execute_block(&block.body, frame, index, functions);
+                }
+            }
+            return execute_block(&stmt.else_block, frame, index, functions);
+        }
+        AstStatement::ControlStatement(AstControlStatement::Case(stmt)) => {
+            let Some(selector) = evaluate_expression(&stmt.selector, frame, index, None, functions)? else {
+                return Ok(None);
+            };
+            for block in &stmt.case_blocks {
+                for condition in flatten_expression_list(&block.condition) {
+                    let Some(matches) =
+                        matches_case_condition(&selector, condition, frame, index, functions)?
+                    else {
+                        return Ok(None);
+                    };
+                    if matches {
+                        return execute_block(&block.body, frame, index, functions);
+                    }
+                }
+            }
+            return execute_block(&stmt.else_block, frame, index, functions);
+        }
+        AstStatement::ControlStatement(AstControlStatement::ForLoop(stmt)) => {
+            return execute_for_loop(stmt, frame, index, functions);
+        }
+        AstStatement::ControlStatement(AstControlStatement::WhileLoop(stmt)) => loop {
+            charge_step(frame, functions)?;
+            let Some(condition) = evaluate_condition(&stmt.condition, frame, index, functions)? else {
+                return Ok(None);
+            };
+            if !condition {
+                break;
+            }
+            match execute_block(&stmt.body, frame, index, functions)? {
+                Some(Flow::Next | Flow::Continue) => {}
+                Some(Flow::Exit) => break,
+                flow => return Ok(flow),
+            }
+        },
+        AstStatement::ControlStatement(AstControlStatement::RepeatLoop(stmt)) => loop {
+            charge_step(frame, functions)?;
+            match execute_block(&stmt.body, frame, index, functions)? {
+                Some(Flow::Next | Flow::Continue) => {}
+                Some(Flow::Exit) => break,
+                flow => return Ok(flow),
+            }
+            let Some(condition) = evaluate_condition(&stmt.condition, frame, index, functions)? else {
+                return Ok(None);
+            };
+            if condition {
+                break;
+            }
+        },
+        AstStatement::ExitStatement(..) => return Ok(Some(Flow::Exit)),
+        AstStatement::ContinueStatement(..) => return Ok(Some(Flow::Continue)),
+        AstStatement::ReturnStatement(stmt) => {
+            let Some(condition) = stmt.condition.as_ref() else { return Ok(Some(Flow::Return)) };
+            match evaluate_condition(condition, frame, index, functions)? {
+                Some(true) => return Ok(Some(Flow::Return)),
+                Some(false) => {}
+                None => return Ok(None),
+            }
+        }
+        _ => {
+            return Err(UnresolvableKind::Misc(format!(
+                "Cannot evaluate `{}` since {statement:?} is not supported in constant evaluation",
+                frame.function
+            )))
+        }
+    }
+    Ok(Some(Flow::Next))
+}
+
+fn execute_for_loop(
+    stmt: &ForLoopStatement,
+    frame: &mut Frame,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<Flow> {
+    let Some(counter) = get_local_name(&stmt.counter, frame) else {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate `{}` since the loop-counter {:?} is not a local variable",
+            frame.function, stmt.counter
+        )));
+    };
+    let type_name = frame.locals[&counter].type_name.clone();
+
+    let evaluate_int = |expr: &AstNode, frame: &Frame| -> EvaluationResult<i128> {
+        match evaluate_expression(expr, frame, index, Some(&type_name), functions)? {
+            Some(AstNode { stmt: AstStatement::Literal(AstLiteral::Integer(v)), .. }) => Ok(Some(v)),
+            None => Ok(None),
+            Some(other) => Err(UnresolvableKind::Misc(format!("Expected integer value, found {other:?}"))),
+        }
+    };
+    let (Some(start), Some(end)) = (evaluate_int(&stmt.start, frame)?, evaluate_int(&stmt.end, frame)?)
+    else {
+        return Ok(None);
+    };
+    let step = match stmt.by_step.as_ref() {
+        Some(step) => {
+            let Some(step) = evaluate_int(step, frame)? else { return Ok(None) };
+            step
+        }
+        None => 1,
+    };
+    if step == 0 {
+        return Err(UnresolvableKind::Misc(format!(
+            "Cannot evaluate `{}` since the loop's step is zero",
+            frame.function
+        )));
+    }
+
+    let mut value = start;
+    while (step > 0 && value <= end) || (step < 0 && value >= end) {
+        charge_step(frame, functions)?;
+        let location = stmt.counter.get_location();
+        frame.locals.insert(
+            counter.clone(),
+            Local {
+                value: AstNode::new_literal(AstLiteral::new_integer(value), stmt.counter.get_id(), location),
+                type_name: type_name.clone(),
+            },
+        );
+        match execute_block(&stmt.body, frame, index, functions)? {
+            Some(Flow::Next | Flow::Continue) => {}
+            Some(Flow::Exit) => break,
+            flow => return Ok(flow),
+        }
+
+        let Some(AstNode { stmt: AstStatement::Literal(AstLiteral::Integer(current)), .. }) =
+            frame.locals.get(&counter).map(|it| &it.value)
+        else {
+            return Err(UnresolvableKind::Misc(format!("Expected integer value for {counter}")));
+        };
+        value = current + step;
+    }
+    Ok(Some(Flow::Next))
+}
+
+fn matches_case_condition(
+    selector: &AstNode,
+    condition: &AstNode,
+    frame: &Frame,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<bool> {
+    if let AstStatement::RangeStatement(RangeStatement { start, end }) = condition.get_stmt() {
+        let start = evaluate_expression(start, frame, index, None, functions)?;
+        let end = evaluate_expression(end, frame, index, None, functions)?;
+        let Some((start, end)) = start.zip(end) else { return Ok(None) };
+        return Ok(Some(
+            compare_numbers(selector, &start)?.is_ge() && compare_numbers(selector, &end)?.is_le(),
+        ));
+    }
+
+    let Some(value) = evaluate_expression(condition, frame, index, None, functions)? else { return Ok(None) };
+    Ok(Some(compare_numbers(selector, &value)?.is_eq()))
+}
+
+fn evaluate_condition(
+    condition: &AstNode,
+    frame: &Frame,
+    index: &Index,
+    functions: &ConstFunctions,
+) -> EvaluationResult<bool> {
+    match evaluate_expression(condition, frame, index, None, functions)? {
+        Some(AstNode { stmt: AstStatement::Literal(AstLiteral::Bool(v)), .. }) => Ok(Some(v)),
+        None => Ok(None),
+        Some(other) => Err(UnresolvableKind::Misc(format!("Expected boolean condition, found {other:?}"))),
+    }
+}
+
+/// evaluates the given expression after substituting all references to local variables
+fn evaluate_expression(
+    expression: &AstNode,
+    frame: &Frame,
+    index: &Index,
+    target_type: Option<&str>,
+    functions: &ConstFunctions,
+) -> EvaluationResult<AstNode> {
+    let expression = substitute_locals(expression, frame);
+    evaluate_with_target_hint(&expression, Some(frame.function), index, target_type, functions)
+}
+
+/// returns the lower-case name of the local variable referenced by `reference`, if any
+fn get_local_name(reference: &AstNode, frame: &Frame) -> Option<String> {
+    match reference.get_stmt() {
+        AstStatement::ReferenceExpr(ReferenceExpr { access: ReferenceAccess::Member(_), base: None }) => {
+            reference
+                .get_flat_reference_name()
+                .map(str::to_lowercase)
+                .filter(|it| frame.locals.contains_key(it))
+        }
+        _ => None,
+    }
+}
+
+/// replaces all references to local variables in `expression` with their current values
+fn substitute_locals(expression: &AstNode, frame: &Frame) -> AstNode {
+    if let Some(local) = get_local_name(expression, frame).and_then(|it| frame.locals.get(&it)) {
+        return local.value.clone();
+    }
+
+    let (id, location) = (expression.get_id(), expression.get_location());
+    let substitute = |it: &AstNode| Box::new(substitute_locals(it, frame));
+    let stmt = match expression.get_stmt() {
+        AstStatement::BinaryExpression(BinaryExpression { operator, left, right }) => {
+            AstStatement::BinaryExpression(BinaryExpression {
+                operator: *operator,
+                left: substitute(left),
+                right: substitute(right),
+            })
+        }
+        AstStatement::UnaryExpression(UnaryExpression { operator, value }) => {
+            AstStatement::UnaryExpression(UnaryExpression { operator: *operator, value: substitute(value) })
+        }
+        AstStatement::ParenExpression(expr) => AstStatement::ParenExpression(substitute(expr)),
+        AstStatement::ExpressionList(expressions) => {
+            AstStatement::ExpressionList(expressions.iter().map(|it| substitute_locals(it, frame)).collect())
+        }
+        AstStatement::RangeStatement(RangeStatement { start, end }) => {
+            AstStatement::RangeStatement(RangeStatement { start: substitute(start), end: substitute(end) })
+        }
+        AstStatement::MultipliedStatement(MultipliedStatement { multiplier, element }) => {
+            AstStatement::MultipliedStatement(MultipliedStatement {
+                multiplier: *multiplier,
+                element: substitute(element),
+            })
+        }
+        // named arguments keep their parameter names
+        AstStatement::Assignment(Assignment { left, right }) => {
+            AstStatement::Assignment(Assignment { left: left.clone(), right: substitute(right) })
+        }
+        // SIZEOF resolves the type of its argument, so we keep the reference
+        AstStatement::CallStatement(CallStatement { operator, .. })
+            if operator.get_flat_reference_name().is_some_and(|it| it.eq_ignore_ascii_case("SIZEOF")) =>
+        {
+            return expression.clone()
+        }
+        AstStatement::CallStatement(CallStatement { operator, parameters }) => {
+            AstStatement::CallStatement(CallStatement {
+                operator: operator.clone(),
+                parameters: parameters.as_deref().map(substitute),
+            })
+        }
+        AstStatement::ReferenceExpr(ReferenceExpr { access: access @ ReferenceAccess::Member(_), base }) => {
+            AstStatement::ReferenceExpr(ReferenceExpr {
+                access: access.clone(),
+                base: base.as_deref().map(substitute),
+            })
+        }
+        _ => return expression.clone(),
+    };
+    AstNode::new(stmt, id, location)
+}
@@ -1,16 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use plc_ast::ast::{flatten_expression_list, AstNode, AstStatement, GenericBinding, LinkageType, TypeNature};
+use indexmap::IndexSet;
+use plc_ast::{
+    ast::{
+        self, flatten_expression_list, AstNode, AstStatement, CompilationUnit, DataTypeDeclaration,
+        GenericBinding, Implementation, LinkageType, Pou, PouType, ReferenceAccess, TypeArgument, TypeNature,
+    },
+    control_statements::{AstControlStatement, ConditionalBlock},
+    literals::AstLiteral,
+    provider::IdProvider,
+};
+use plc_diagnostics::diagnostics::Diagnostic;
 use plc_source::source_location::SourceLocation;
 
 use crate::{
     builtins,
     codegen::generators::expression_generator::get_implicit_call_parameter,
     index::{Index, PouIndexEntry},
-    resolver::AnnotationMap,
+    resolver::{AnnotationMap, Dependency, StringLiterals},
     typesystem::{
-        self, DataType, DataTypeInformation, StringEncoding, BOOL_TYPE, CHAR_TYPE, DATE_TYPE, REAL_TYPE,
-        SINT_TYPE, STRING_TYPE, TIME_TYPE, USINT_TYPE, WSTRING_TYPE,
+        self, DataType, DataTypeInformation, Dimension, StringEncoding, TypeSize, BOOL_TYPE, CHAR_TYPE,
+        DATE_TYPE, REAL_TYPE, SINT_TYPE, STRING_TYPE, TIME_TYPE, USINT_TYPE, WSTRING_TYPE,
     },
 };
 
@@ -52,7 +62,20 @@ impl<'i> TypeAnnotator<'i> {
                 _ => statement,
             };
             //Find the statement's type
-            annotation_map.get_type(statement, index).map(|it| (generic_symbol.as_str(), it.get_name()))
+            let statement_type = annotation_map.get_type(statement, index)?;
+            //For `ARRAY OF T` or `POINTER TO T` the inner type of the passed array or pointer is the candidate
+            let candidate_type = match (effective_type, statement_type.get_type_information()) {
+                (
+                    Some(DataTypeInformation::Array { .. }),
+                    DataTypeInformation::Array { inner_type_name, .. },
+                )
+                | (
+                    Some(DataTypeInformation::Pointer { .. }),
+                    DataTypeInformation::Pointer { inner_type_name, .. },
+                ) => index.find_effective_type_by_name(inner_type_name).unwrap_or(statement_type),
+                _ => statement_type,
+            };
+            Some((generic_symbol.as_str(), candidate_type.get_name()))
         } else {
             None
         }
@@ -88,8 +111,13 @@ impl<'i> TypeAnnotator<'i> {
                         generic_name_resolver,
                     );
 
+                    // calls inside of a generic implementation may pass values of its generic types,
+                    // they are resolved by the specializations of that implementation
+                    let is_resolved = generics.iter().all(|it| {
+                        generic_map.get(&it.name).is_some_and(|it| !is_generic(self.index, &it.derived_type))
+                    });
                     // Create a new pou and implementation for the function
-                    if let Some(pou) = self.index.find_pou(qualified_name) {
+                    if let Some(pou) = self.index.find_pou(qualified_name).filter(|_| is_resolved) {
                         // only register concrete typed function if it was not indexed yet
                         if self.index.find_pou(new_name.as_str()).is_none() &&
                             //only register typed function if we did not register it yet
@@ -147,7 +175,9 @@ impl<'i> TypeAnnotator<'i> {
                 new_name,
                 return_type,
                 &[],
-                LinkageType::External, //it has to be external, we should have already found this in the global index if it was internal
+                // external until the generic implementation is copied (see `implement_generic_functions`),
+                // we should have already found this in the global index if it was internal
+                LinkageType::External,
                 generic_function.is_variadic(),
                 generic_function.get_location().clone(),
             ));
@@ -222,6 +252,54 @@ impl<'i> TypeAnnotator<'i> {
 
                 name
             }
+            Some(DataTypeInformation::Pointer { name, inner_type_name, auto_deref: false }) => {
+                // a pointer to a generic (e.g. `REF_TO T`), create a new pointer type to the resolved type
+                let new_inner_type_name = self.find_or_create_datatype(inner_type_name, generics);
+                if new_inner_type_name == *inner_type_name {
+                    return member_name.to_string();
+                }
+                let name = format!("{name}__{new_inner_type_name}"); // TODO: Naming convention (see plc_util/src/convention.rs)
+                self.annotation_map.new_index.register_type(DataType {
+                    information: DataTypeInformation::Pointer {
+                        name: name.clone(),
+                        inner_type_name: new_inner_type_name,
+                        auto_deref: false,
+                    },
+                    initial_value: None,
+                    name: name.clone(),
+                    nature: TypeNature::Any,
+                    location: SourceLocation::internal(),
+                });
+
+                name
+            }
+            Some(DataTypeInformation::Array { name, inner_type_name, dimensions }) => {
+                // an array of generics (e.g. `ARRAY[0..9] OF T`), create a new array type of the resolved type
+                let new_inner_type_name = self.find_or_create_datatype(inner_type_name, generics);
+                if new_inner_type_name == *inner_type_name {
+                    return member_name.to_string();
+                }
+                let name = format!("{name}__{new_inner_type_name}"); // TODO: Naming convention (see plc_util/src/convention.rs)
+                self.annotation_map.new_index.register_type(DataType {
+                    information: DataTypeInformation::Array {
+                        name: name.clone(),
+                        inner_type_name: new_inner_type_name,
+                        dimensions: dimensions
+                            .iter()
+                            .map(|it| Dimension {
+                                start_offset: self.evaluate_type_size(&it.start_offset),
+                                end_offset: self.evaluate_type_size(&it.end_offset),
+                            })
+                            .collect(),
+                    },
+                    initial_value: None,
+                    name: name.clone(),
+                    nature: TypeNature::Any,
+                    location: SourceLocation::internal(),
+                });
+
+                name
+            }
             _ => {
                 // not a generic member, just use the original type
                 member_name.to_string()
@@ -229,6 +307,12 @@ impl<'i> TypeAnnotator<'i> {
         }
     }
 
+    /// the const-expressions of the index cannot be shared with the types of the new index,
+    /// so the array's dimensions are copied as literals (they were already resolved)
+    fn evaluate_type_size(&self, type_size: &TypeSize) -> TypeSize {
+        type_size.as_int_value(self.index).map(TypeSize::from_literal).unwrap_or(TypeSize::Undetermined)
+    }
+
     fn update_generic_function_parameters(
         &mut self,
         s: &AstNode,
@@ -425,6 +509,627 @@ impl<'i> TypeAnnotator<'i> {
     }
 }
 
+/// Implements the generic functions declared with a body (e.g. `FUNCTION foo<T: ANY_NUM> : T`) for
+/// all concrete types they are called with. The generic implementation is copied into the unit
+/// declaring it under the name of the specialization (e.g. `foo__INT`) and annotated again, using
+/// the concrete types of the specialization's members. Since these copies may call other generic
+/// functions, this is repeated until all specializations are implemented.
+///
+/// Generic functions without a body still expect their specializations to be implemented
+/// elsewhere (e.g. `EXPT__REAL__DINT` in the standard library).
+pub fn implement_generic_functions(
+    index: &mut Index,
+    annotations: &mut AnnotationMapImpl,
+    units: &mut [(CompilationUnit, IndexSet<Dependency>, StringLiterals)],
+    id_provider: IdProvider,
+) {
+    loop {
+        let mut implemented = false;
+        for (unit, dependencies, literals) in units.iter_mut() {
+            let implementations = specialize_generic_implementations(index, unit, id_provider.clone());
+            if implementations.is_empty() {
+                continue;
+            }
+            implemented = true;
+            for implementation in &implementations {
+                if let Some(pou) = index.find_pou_mut(&implementation.name) {
+                    pou.set_linkage(LinkageType::Internal);
+                }
+            }
+            let specializations = CompilationUnit::new(&unit.file_name).with_implementations(implementations);
+            let (mut annotation, new_dependencies, new_literals) =
+                TypeAnnotator::visit_unit(index, &specializations, id_provider.clone());
+            index.import(std::mem::take(&mut annotation.new_index));
+            annotations.import(annotation);
+            dependencies.extend(new_dependencies);
+            literals.import(new_literals);
+            unit.implementations.extend(specializations.implementations);
+        }
+        if !implemented {
+            break;
+        }
+    }
+}
+
+/// copies the generic implementations with a body of the given unit for all of their
+/// specializations that are not implemented yet
+fn specialize_generic_implementations(
+    index: &Index,
+    unit: &CompilationUnit,
+    mut id_provider: IdProvider,
+) -> Vec<Implementation> {
+    let generic_implementations = unit
+        .implementations
+        .iter()
+        .filter(|it| it.generic && it.linkage == LinkageType::Internal && !it.statements.is_empty())
+        .collect::<Vec<_>>();
+    if generic_implementations.is_empty() {
+        return vec![];
+    }
+    let generic_functions = index.get_pous().values().filter(|it| it.is_generic()).collect::<Vec<_>>();
+    let mut specialized = HashSet::new();
+    index
+        .get_pous()
+        .values()
+        .filter(|it| it.is_auto_generated_function() && *it.get_linkage() == LinkageType::External)
+        .filter(|it| specialized.insert(it.get_name().to_lowercase()))
+        .filter_map(|specialization| {
+            // the name of a specialization starts with the name of its generic function (e.g. `foo__INT`)
+            let name = specialization.get_name().to_lowercase();
+            let generic_function = generic_functions
+                .iter()
+                .filter(|it| name.starts_with(&format!("{}__", it.get_name().to_lowercase())))
+                .max_by_key(|it| it.get_name().len())?;
+            generic_implementations
+                .iter()
+                .find(|it| it.name.eq_ignore_ascii_case(generic_function.get_name()))
+                .map(|generic| {
+                    specialize_implementation(generic, specialization.get_name(), &mut id_provider)
+                })
+        })
+        .collect()
+}
+
+/// returns true if the given type is a generic type or an array of or pointer to a generic type
+fn is_generic(index: &Index, type_name: &str) -> bool {
+    match index.find_effective_type_info(type_name) {
+        Some(DataTypeInformation::Generic { .. }) => true,
+        Some(DataTypeInformation::Array { inner_type_name, .. })
+        | Some(DataTypeInformation::Pointer { inner_type_name, .. }) => is_generic(index, inner_type_name),
+        _ => false,
+    }
+}
+
+/// copies the given generic implementation under the given name
+fn specialize_implementation(
+    generic: &Implementation,
+    name: &str,
+    id_provider: &mut IdProvider,
+) -> Implementation {
+    let mut statements = generic.statements.clone();
+    for statement in statements.iter_mut() {
+        specialize_statement(statement, &generic.name, name, id_provider);
+    }
+    Implementation {
+        name: name.to_string(),
+        type_name: name.to_string(),
+        linkage: generic.linkage,
+        pou_type: generic.pou_type.clone(),
+        statements,
+        location: generic.location.clone(),
+        name_location: generic.name_location.clone(),
+        overriding: generic.overriding,
+        generic: false,
+        access: generic.access.clone(),
+    }
+}
+
+/// assigns new ids to the given copy of a statement, so it can be annotated independently of the
+/// original statement. References to the generic function (e.g. its return variable) are renamed to
+/// the given specialization.
+fn specialize_statement(
+    statement: &mut AstNode,
+    generic_name: &str,
+    name: &str,
+    id_provider: &mut IdProvider,
+) {
+    statement.id = id_provider.next_id();
+    let mut specialize = |it: &mut AstNode| specialize_statement(it, generic_name, name, id_provider);
+    match &mut statement.stmt {
+        AstStatement::Identifier(identifier) => {
+            if identifier.eq_ignore_ascii_case(generic_name) {
+                *identifier = name.to_string();
+            }
+        }
+        AstStatement::ReferenceExpr(data) => {
+            match &mut data.access {
+                // only unqualified references may refer to the function itself
+                ReferenceAccess::Member(member) | ReferenceAccess::Cast(member)
+                    if data.base.is_some() && matches!(member.stmt, AstStatement::Identifier(..)) =>
+                {
+                    member.id = id_provider.next_id()
+                }
+                ReferenceAccess::Member(it) | ReferenceAccess::Index(it) | ReferenceAccess::Cast(it) => {
+                    specialize_statement(it, generic_name, name, id_provider)
+                }
+                ReferenceAccess::Deref | ReferenceAccess::Address => {}
+            }
+            if let Some(base) = data.base.as_mut() {
+                specialize_statement(base, generic_name, name, id_provider);
+            }
+        }
+        AstStatement::Literal(AstLiteral::Array(array)) => {
+            if let Some(elements) = array.elements.as_mut() {
+                specialize(elements);
+            }
+        }
+        AstStatement::CastStatement(data) => specialize(&mut data.target),
+        AstStatement::MultipliedStatement(data) => specialize(&mut data.element),
+        AstStatement::DirectAccess(data) => specialize(&mut data.index),
+        AstStatement::HardwareAccess(data) => data.address.iter_mut().for_each(specialize),
+        AstStatement::BinaryExpression(data) => {
+            specialize(&mut data.left);
+            specialize(&mut data.right);
+        }
+        AstStatement::UnaryExpression(data) => specialize(&mut data.value),
+        AstStatement::ExpressionList(expressions) => expressions.iter_mut().for_each(specialize),
+        AstStatement::ParenExpression(expression) | AstStatement::CaseCondition(expression) => {
+            specialize(expression)
+        }
+        AstStatement::RangeStatement(data) => {
+            specialize(&mut data.start);
+            specialize(&mut data.end);
+        }
+        AstStatement::Assignment(data) | AstStatement::OutputAssignment(data) => {
+            specialize(&mut data.left);
+            specialize(&mut data.right);
+        }
+        AstStatement::CallStatement(data) => {
+            specialize(&mut data.operator);
+            if let Some(parameters) = data.parameters.as_mut() {
+                specialize(parameters);
+            }
+        }
+        AstStatement::ControlStatement(control) => match control {
+            AstControlStatement::If(data) => {
+                for ConditionalBlock { condition, body } in data.blocks.iter_mut() {
+                    specialize(condition);
+                    body.iter_mut().for_each(&mut specialize);
+                }
+                data.else_block.iter_mut().for_each(specialize);
+            }
+            AstControlStatement::ForLoop(data) => {
+                specialize(&mut data.counter);
+                specialize(&mut data.start);
+                specialize(&mut data.end);
+                if let Some(step) = data.by_step.as_mut() {
+                    specialize(step);
+                }
+                data.body.iter_mut().for_each(specialize);
+            }
+            AstControlStatement::WhileLoop(data) | AstControlStatement::RepeatLoop(data) => {
+                specialize(&mut data.condition);
+                data.body.iter_mut().for_each(specialize);
+            }
+            AstControlStatement::Case(data) => {
+                specialize(&mut data.selector);
+                for ConditionalBlock { condition, body } in data.case_blocks.iter_mut() {
+                    specialize(condition);
+                    body.iter_mut().for_each(&mut specialize);
+                }
+                data.else_block.iter_mut().for_each(specialize);
+            }
+        },
+        AstStatement::ReturnStatement(data) => {
+            if let Some(condition) = data.condition.as_mut() {
+                specialize(condition);
+            }
+        }
+        AstStatement::JumpStatement(data) => {
+            specialize(&mut data.condition);
+            specialize(&mut data.target);
+        }
+        AstStatement::EmptyStatement(..)
+        | AstStatement::DefaultValue(..)
+        | AstStatement::Literal(..)
+        | AstStatement::VlaRangeStatement
+        | AstStatement::ExitStatement(..)
+        | AstStatement::ContinueStatement(..)
+        | AstStatement::LabelStatement(..) => {}
+    }
+}
+
+/// a generic function block or class together with its methods and implementations
+struct GenericPou {
+    /// the index of the unit declaring the generic pou, its instances are added to this unit
+    unit: usize,
+    pou: Pou,
+    methods: Vec<Pou>,
+    implementations: Vec<Implementation>,
+}
+
+/// Creates a copy of a generic function block or class for every combination of type arguments it
+/// is instantiated with (e.g. `q : Fifo<DINT>` creates the function block `Fifo__DINT`) and renames
+/// the references to the copies. The generic pous themselves are kept like generic functions, so they
+/// are validated even if they are never instantiated, but they are not generated. Their methods and
+/// implementations become generic as well.
+///
+/// This runs on all parsed units before they are pre-processed and indexed.
+pub fn instantiate_generic_pous(units: &mut [CompilationUnit], id_provider: IdProvider) -> Vec<Diagnostic> {
+    let mut generics = HashMap::new();
+    for (index, unit) in units.iter().enumerate() {
+        for pou in unit.units.iter().filter(|it| is_generic_pou(it)) {
+            let generic =
+                GenericPou { unit: index, pou: pou.clone(), methods: vec![], implementations: vec![] };
+            generics.insert(generic.pou.name.to_lowercase(), generic);
+        }
+    }
+    if generics.is_empty() {
+        return vec![];
+    }
+
+    // methods and actions may be declared in other units than the generic pou
+    let owner = |name: &str| name.split('.').next().unwrap_or_default().to_lowercase();
+    for unit in units.iter_mut() {
+        for method in unit.units.iter_mut() {
+            let Some(generic) =
+                method.pou_type.get_optional_owner_class().and_then(|it| generics.get_mut(&owner(&it)))
+            else {
+                continue;
+            };
+            generic.methods.push(method.clone());
+            method.generics = generic.pou.generics.clone();
+        }
+
+        for implementation in unit.implementations.iter_mut() {
+            if let Some(generic) = generics.get_mut(&owner(&implementation.type_name)) {
+                generic.implementations.push(implementation.clone());
+                implementation.generic = true;
+            }
+        }
+    }
+
+    let mut instantiator = GenericPouInstantiator {
+        generics,
+        instances: HashSet::new(),
+        queue: VecDeque::new(),
+        diagnostics: vec![],
+        id_provider,
+    };
+    for unit in units.iter_mut() {
+        instantiator.visit_unit(unit);
+    }
+    // instances may instantiate further generic pous (e.g. a `Fifo<T>` inside of a `Queue<T>`)
+    while let Some((unit, mut pous, implementations)) = instantiator.queue.pop_front() {
+        pous.iter_mut().for_each(|it| instantiator.visit_pou(it));
+        units[unit].units.extend(pous);
+        units[unit].implementations.extend(implementations);
+    }
+    instantiator.diagnostics
+}
+
+fn is_generic_pou(pou: &Pou) -> bool {
+    matches!(pou.pou_type, PouType::FunctionBlock | PouType::Class) && !pou.generics.is_empty()
+}
+
+struct GenericPouInstantiator {
+    generics: HashMap<String, GenericPou>,
+    /// the (lowercase) names of all instances created so far
+    instances: HashSet<String>,
+    /// instances which were not visited yet, with the index of their unit
+    queue: VecDeque<(usize, Vec<Pou>, Vec<Implementation>)>,
+    diagnostics: Vec<Diagnostic>,
+    id_provider: IdProvider,
+}
+
+impl GenericPouInstantiator {
+    fn visit_unit(&mut self, unit: &mut CompilationUnit) {
+        for variable in unit.global_vars.iter_mut().flat_map(|it| it.variables.iter_mut()) {
+            self.visit_declaration(&mut variable.data_type_declaration);
+        }
+        for user_type in unit.user_types.iter_mut() {
+            self.visit_data_type(&mut user_type.data_type, &user_type.location);
+        }
+        unit.units.iter_mut().for_each(|it| self.visit_pou(it));
+    }
+
+    fn visit_pou(&mut self, pou: &mut Pou) {
+        if !pou.generics.is_empty() {
+            // generic pous cannot be instantiated for their own type parameters, they refer to the
+            // generic pous instead (e.g. `Fifo<T>` becomes `Fifo`)
+            erase_type_arguments(pou);
+            return;
+        }
+        for variable in pou.variable_blocks.iter_mut().flat_map(|it| it.variables.iter_mut()) {
+            self.visit_declaration(&mut variable.data_type_declaration);
+        }
+        if let Some(return_type) = pou.return_type.as_mut() {
+            self.visit_declaration(return_type);
+        }
+        if let Some(super_class) = pou.super_class.as_mut() {
+            self.resolve(super_class, &pou.name_location);
+        }
+    }
+
+    fn visit_declaration(&mut self, declaration: &mut DataTypeDeclaration) {
+        match declaration {
+            DataTypeDeclaration::DataTypeReference { referenced_type, location } => {
+                self.resolve(referenced_type, location)
+            }
+            DataTypeDeclaration::DataTypeDefinition { data_type, location, .. } => {
+                self.visit_data_type(data_type, location)
+            }
+        }
+    }
+
+    fn visit_data_type(&mut self, data_type: &mut ast::DataType, location: &SourceLocation) {
+        match data_type {
+            ast::DataType::StructType { variables, .. } | ast::DataType::UnionType { variables, .. } => {
+                variables.iter_mut().for_each(|it| self.visit_declaration(&mut it.data_type_declaration))
+            }
+            ast::DataType::SubRangeType { referenced_type, .. } => self.resolve(referenced_type, location),
+            ast::DataType::ArrayType { referenced_type, .. }
+            | ast::DataType::PointerType { referenced_type, .. }
+            | ast::DataType::VarArgs { referenced_type: Some(referenced_type), .. } => {
+                self.visit_declaration(referenced_type)
+            }
+            _ => {}
+        }
+    }
+
+    /// replaces a reference to a generic pou (e.g. `Fifo<DINT>`) with the name of its instance
+    /// and creates the instance if it does not exist yet
+    fn resolve(&mut self, type_name: &mut String, location: &SourceLocation) {
+        let Some((generic_name, arguments)) = split_type_arguments(type_name) else {
+            if let Some(generic) = self.generics.get(&type_name.to_lowercase()) {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "Generic POU `{}` expects {} type argument(s), found 0",
+                        generic.pou.name,
+                        generic.pou.generics.len()
+                    ))
+                    .with_location(location.clone())
+                    .with_error_code("E093"),
+                );
+            }
+            return;
+        };
+        let arguments = arguments
+            .into_iter()
+            .map(|mut it| {
+                self.resolve(&mut it, location);
+                it
+            })
+            .collect::<Vec<_>>();
+
+        let Some(generic) = self.generics.get(&generic_name.to_lowercase()) else {
+            self.diagnostics.push(
+                Diagnostic::error(format!("`{generic_name}` is not a generic function block or class"))
+                    .with_location(location.clone())
+                    .with_error_code("E093"),
+            );
+            *type_name = generic_name;
+            return;
+        };
+        if generic.pou.generics.len() != arguments.len() {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "Generic POU `{}` expects {} type argument(s), found {}",
+                    generic.pou.name,
+                    generic.pou.generics.len(),
+                    arguments.len()
+                ))
+                .with_location(location.clone())
+                .with_error_code("E093"),
+            );
+            return;
+        }
+
+        // TODO: Naming convention (see plc_util/src/convention.rs)
+        let instance_name = format!("{}__{}", generic.pou.name, arguments.join("__"));
+        if self.instances.insert(instance_name.to_lowercase()) {
+            let instance =
+                instantiate_generic_pou(generic, &instance_name, &arguments, location, &mut self.id_provider);
+            self.queue.push_back(instance);
+        }
+        *type_name = instance_name;
+    }
+}
+
+/// copies the given generic pou with its methods and implementations, replacing its type parameters
+/// with the given arguments. The arguments are recorded on the copy of the pou, so they can be
+/// validated against the natures of the type parameters.
+fn instantiate_generic_pou(
+    generic: &GenericPou,
+    name: &str,
+    arguments: &[String],
+    location: &SourceLocation,
+    id_provider: &mut IdProvider,
+) -> (usize, Vec<Pou>, Vec<Implementation>) {
+    let generic_name = generic.pou.name.as_str();
+    let types = generic
+        .pou
+        .generics
+        .iter()
+        .zip(arguments)
+        .map(|(binding, argument)| (binding.name.to_lowercase(), argument.clone()))
+        .collect::<HashMap<_, _>>();
+    // `Fifo.push` becomes `Fifo__DINT.push`
+    let rename = |it: &str| format!("{name}{}", it.get(generic_name.len()..).unwrap_or_default());
+
+    let mut pous = std::iter::once(&generic.pou)
+        .chain(generic.methods.iter())
+        .map(|it| {
+            let mut pou = it.clone();
+            pou.name = rename(&pou.name);
+            pou.generics.clear();
+            if let PouType::Method { owner_class } = &mut pou.pou_type {
+                *owner_class = name.to_string();
+            }
+            for variable in pou.variable_blocks.iter_mut().flat_map(|it| it.variables.iter_mut()) {
+                instantiate_declaration(&mut variable.data_type_declaration, &types, id_provider);
+                for node in variable.initializer.iter_mut().chain(variable.address.iter_mut()) {
+                    specialize_statement(node, generic_name, name, id_provider);
+                }
+            }
+            if let Some(return_type) = pou.return_type.as_mut() {
+                instantiate_declaration(return_type, &types, id_provider);
+            }
+            if let Some(super_class) = pou.super_class.as_mut() {
+                *super_class = substitute_type_arguments(super_class, &types);
+            }
+            pou
+        })
+        .collect::<Vec<_>>();
+    pous[0].type_arguments = generic
+        .pou
+        .generics
+        .iter()
+        .zip(arguments)
+        .map(|(binding, argument)| TypeArgument {
+            binding: binding.clone(),
+            type_name: argument.clone(),
+            location: location.clone(),
+        })
+        .collect();
+
+    let implementations = generic
+        .implementations
+        .iter()
+        .map(|it| {
+            let mut statements = it.statements.clone();
+            for statement in statements.iter_mut() {
+                specialize_statement(statement, generic_name, name, id_provider);
+            }
+            Implementation {
+                name: rename(&it.name),
+                type_name: rename(&it.type_name),
+                linkage: it.linkage,
+                pou_type: match &it.pou_type {
+                    PouType::Method { .. } => PouType::Method { owner_class: name.to_string() },
+                    pou_type => pou_type.clone(),
+                },
+                statements,
+                location: it.location.clone(),
+                name_location: it.name_location.clone(),
+                overriding: it.overriding,
+                generic: false,
+                access: it.access.clone(),
+            }
+        })
+        .collect();
+
+    (generic.unit, pous, implementations)
+}
+
+/// replaces the references to generic pous in the declarations of the given pou with the generic
+/// pous themselves
+fn erase_type_arguments(pou: &mut Pou) {
+    fn erase(type_name: &mut String) {
+        if let Some((generic_name, _)) = split_type_arguments(type_name) {
+            *type_name = generic_name;
+        }
+    }
+    fn erase_declaration(declaration: &mut DataTypeDeclaration) {
+        match declaration {
+            DataTypeDeclaration::DataTypeReference { referenced_type, .. } => erase(referenced_type),
+            DataTypeDeclaration::DataTypeDefinition { data_type, .. } => match data_type {
+                ast::DataType::StructType { variables, .. } | ast::DataType::UnionType { variables, .. } => {
+                    variables.iter_mut().for_each(|it| erase_declaration(&mut it.data_type_declaration))
+                }
+                ast::DataType::SubRangeType { referenced_type, .. } => erase(referenced_type),
+                ast::DataType::ArrayType { referenced_type, .. }
+                | ast::DataType::PointerType { referenced_type, .. }
+                | ast::DataType::VarArgs { referenced_type: Some(referenced_type), .. } => {
+                    erase_declaration(referenced_type)
+                }
+                _ => {}
+            },
+        }
+    }
+
+    for variable in pou.variable_blocks.iter_mut().flat_map(|it| it.variables.iter_mut()) {
+        erase_declaration(&mut variable.data_type_declaration);
+    }
+    if let Some(return_type) = pou.return_type.as_mut() {
+        erase_declaration(return_type);
+    }
+    if let Some(super_class) = pou.super_class.as_mut() {
+        erase(super_class);
+    }
+}
+
+/// replaces the type parameters in the given declaration and assigns new ids to its expressions
+fn instantiate_declaration(
+    declaration: &mut DataTypeDeclaration,
+    types: &HashMap<String, String>,
+    id_provider: &mut IdProvider,
+) {
+    let DataTypeDeclaration::DataTypeDefinition { data_type, .. } = declaration else {
+        if let DataTypeDeclaration::DataTypeReference { referenced_type, .. } = declaration {
+            *referenced_type = substitute_type_arguments(referenced_type, types);
+        }
+        return;
+    };
+    let mut renew_ids = |it: &mut AstNode| specialize_statement(it, "", "", id_provider);
+    match data_type {
+        ast::DataType::StructType { variables, .. } | ast::DataType::UnionType { variables, .. } => {
+            for variable in variables.iter_mut() {
+                instantiate_declaration(&mut variable.data_type_declaration, types, id_provider);
+            }
+        }
+        ast::DataType::EnumType { elements, .. } => renew_ids(elements),
+        ast::DataType::SubRangeType { referenced_type, bounds, .. } => {
+            *referenced_type = substitute_type_arguments(referenced_type, types);
+            bounds.iter_mut().for_each(renew_ids);
+        }
+        ast::DataType::ArrayType { bounds, referenced_type, .. } => {
+            renew_ids(bounds);
+            instantiate_declaration(referenced_type, types, id_provider);
+        }
+        ast::DataType::PointerType { referenced_type, .. }
+        | ast::DataType::VarArgs { referenced_type: Some(referenced_type), .. } => {
+            instantiate_declaration(referenced_type, types, id_provider)
+        }
+        ast::DataType::StringType { size, .. } => size.iter_mut().for_each(renew_ids),
+        ast::DataType::VarArgs { referenced_type: None, .. } | ast::DataType::GenericType { .. } => {}
+    }
+}
+
+/// replaces the type parameters in the given type name, including the arguments of generic
+/// references (e.g. `Fifo<T>` becomes `Fifo<DINT>`)
+fn substitute_type_arguments(type_name: &str, types: &HashMap<String, String>) -> String {
+    if let Some((generic_name, arguments)) = split_type_arguments(type_name) {
+        let arguments = arguments.iter().map(|it| substitute_type_arguments(it, types)).collect::<Vec<_>>();
+        format!("{generic_name}<{}>", arguments.join(", "))
+    } else {
+        types.get(&type_name.to_lowercase()).cloned().unwrap_or_else(|| type_name.to_string())
+    }
+}
+
+/// splits a reference to a generic pou (e.g. `Fifo<DINT>`) into the generic's name and its
+/// type arguments
+fn split_type_arguments(type_name: &str) -> Option<(String, Vec<String>)> {
+    let (generic_name, arguments) = type_name.split_once('<')?;
+    let arguments = arguments.strip_suffix('>')?;
+    let mut result = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (index, char) in arguments.char_indices() {
+        match char {
+            '<' => depth += 1,
+            '>' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(arguments[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(arguments[start..].trim().to_string());
+    Some((generic_name.to_string(), result))
+}
+
 type GenericNameResolver = fn(&str, &[GenericBinding], &HashMap<String, GenericType>) -> String;
 
 /// Builds the correct generic name from the given information
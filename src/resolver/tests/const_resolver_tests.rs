@@ -6,7 +6,9 @@ use plc_source::source_location::SourceLocation;
 use crate::index::const_expressions::ConstExpression;
 use crate::index::Index;
 
-use crate::resolver::const_evaluator::{evaluate_constants, UnresolvableConstant};
+use crate::resolver::const_evaluator::{
+    evaluate_constants, evaluate_constants_with_functions, ConstFunctions, UnresolvableConstant,
+};
 use crate::resolver::AnnotationMap;
 use crate::test_utils::tests::{annotate_with_ids, codegen, index, index_with_ids};
 use crate::typesystem::DataTypeInformation;
@@ -1287,3 +1289,387 @@ fn floating_point_type_casting_of_invalid_types_is_unresolvable() {
         r#"Expected floating point type, got: Some(LiteralString { value: "abc", is_wide: true })"#
     );
 }
+
+#[test]
+fn builtin_function_calls_are_evaluated() {
+    let (_, index) = index(
+        "VAR_GLOBAL CONSTANT
+            a : INT := 3;
+            b : INT := 7;
+            min_ab : INT := MIN(a, b, 5);
+            max_ab : INT := MAX(a, b, 5);
+            max_real : LREAL := MAX(a, 2.5);
+            limited : INT := LIMIT(0, b * 2, 10);
+            abs_value : DINT := ABS(-a);
+            shifted : DWORD := SHL(1, b);
+            shifted_right : WORD := SHR(16#100, 4);
+            rounded : INT := REAL_TO_INT(2.5);
+            as_real : LREAL := DINT_TO_LREAL(b);
+            as_bool : BOOL := INT_TO_BOOL(a);
+            generic_conversion : DINT := TO_DINT(TRUE);
+        END_VAR
+        ",
+    );
+
+    let (index, unresolvable) = evaluate_constants(index);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    debug_assert_eq!(&create_int_literal(3), find_constant_value(&index, "min_ab").unwrap());
+    debug_assert_eq!(&create_int_literal(7), find_constant_value(&index, "max_ab").unwrap());
+    debug_assert_eq!(&create_real_literal(3.0), find_constant_value(&index, "max_real").unwrap());
+    debug_assert_eq!(&create_int_literal(10), find_constant_value(&index, "limited").unwrap());
+    debug_assert_eq!(&create_int_literal(3), find_constant_value(&index, "abs_value").unwrap());
+    debug_assert_eq!(&create_int_literal(128), find_constant_value(&index, "shifted").unwrap());
+    debug_assert_eq!(&create_int_literal(16), find_constant_value(&index, "shifted_right").unwrap());
+    debug_assert_eq!(&create_int_literal(3), find_constant_value(&index, "rounded").unwrap());
+    debug_assert_eq!(&create_real_literal(7.0), find_constant_value(&index, "as_real").unwrap());
+    debug_assert_eq!(&create_bool_literal(true), find_constant_value(&index, "as_bool").unwrap());
+    debug_assert_eq!(&create_int_literal(1), find_constant_value(&index, "generic_conversion").unwrap());
+}
+
+#[test]
+fn builtin_function_calls_wrap_around_to_the_width_of_their_type() {
+    let (_, index) = index(
+        "VAR_GLOBAL CONSTANT
+            narrowed : SINT := INT_TO_SINT(300);
+            narrowed_negative : DINT := INT_TO_SINT(200);
+            narrowed_unsigned : BYTE := DINT_TO_BYTE(-1);
+            narrowed_real : INT := LREAL_TO_INT(65537.4);
+            shifted_byte : BYTE := SHL(BYTE#200, 1);
+            shifted_word : WORD := SHL(WORD#16#8001, 1);
+            shifted_dword : DWORD := SHL(16#8001, 1);
+            shifted_out : BYTE := SHL(BYTE#1, 8);
+        END_VAR
+        ",
+    );
+
+    let (index, unresolvable) = evaluate_constants(index);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    debug_assert_eq!(&create_int_literal(44), find_constant_value(&index, "narrowed").unwrap());
+    debug_assert_eq!(&create_int_literal(-56), find_constant_value(&index, "narrowed_negative").unwrap());
+    debug_assert_eq!(&create_int_literal(255), find_constant_value(&index, "narrowed_unsigned").unwrap());
+    debug_assert_eq!(&create_int_literal(1), find_constant_value(&index, "narrowed_real").unwrap());
+    debug_assert_eq!(&create_int_literal(144), find_constant_value(&index, "shifted_byte").unwrap());
+    debug_assert_eq!(&create_int_literal(2), find_constant_value(&index, "shifted_word").unwrap());
+    debug_assert_eq!(&create_int_literal(0x10002), find_constant_value(&index, "shifted_dword").unwrap());
+    debug_assert_eq!(&create_int_literal(0), find_constant_value(&index, "shifted_out").unwrap());
+}
+
+#[test]
+fn builtin_function_call_overflows_are_reported() {
+    let (_, index) = index(
+        "VAR_GLOBAL CONSTANT
+            a : SINT := MAX(100, 200);
+        END_VAR
+        ",
+    );
+
+    let (_, unresolvable) = evaluate_constants(index);
+
+    assert_eq!(unresolvable.len(), 1);
+    assert_eq!(unresolvable[0].reason, "This will overflow for type SINT");
+}
+
+#[test]
+fn array_bounds_using_function_calls_are_evaluated() {
+    let (_, index) = index(
+        "VAR_GLOBAL CONSTANT
+            A : INT := 3;
+            B : INT := 7;
+        END_VAR
+
+        VAR_GLOBAL
+            arr : ARRAY[0..MAX(A, B)] OF BYTE;
+        END_VAR
+        ",
+    );
+
+    let (index, unresolvable) = evaluate_constants(index);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    let arr_type =
+        index.get_type_information_or_void(index.find_global_variable("arr").unwrap().get_type_name());
+    let DataTypeInformation::Array { dimensions, .. } = arr_type else { panic!("{arr_type:?} is no array") };
+    assert_eq!(dimensions[0].get_range(&index).unwrap(), 0..7);
+}
+
+#[test]
+fn sizeof_types_and_variables_is_evaluated() {
+    let (_, index) = index(
+        "TYPE MyStruct : STRUCT
+            a : DINT;
+            b : SINT;
+        END_STRUCT END_TYPE
+
+        TYPE Nested : STRUCT
+            a : SINT;
+            b : MyStruct;
+            c : ARRAY[0..2] OF INT;
+        END_STRUCT END_TYPE
+
+        VAR_GLOBAL
+            s : MyStruct;
+            str : STRING[10];
+        END_VAR
+
+        VAR_GLOBAL CONSTANT
+            struct_size : ULINT := SIZEOF(MyStruct);
+            nested_size : ULINT := SIZEOF(Nested);
+            variable_size : ULINT := SIZEOF(s);
+            string_size : ULINT := SIZEOF(str);
+            lint_size : ULINT := SIZEOF(LINT);
+        END_VAR
+        ",
+    );
+
+    let (index, unresolvable) = evaluate_constants(index);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    debug_assert_eq!(&create_int_literal(8), find_constant_value(&index, "struct_size").unwrap());
+    debug_assert_eq!(&create_int_literal(20), find_constant_value(&index, "nested_size").unwrap());
+    debug_assert_eq!(&create_int_literal(8), find_constant_value(&index, "variable_size").unwrap());
+    debug_assert_eq!(&create_int_literal(11), find_constant_value(&index, "string_size").unwrap());
+    debug_assert_eq!(&create_int_literal(8), find_constant_value(&index, "lint_size").unwrap());
+}
+
+#[test]
+fn sizeof_function_block_skips_temporary_variables() {
+    let (_, index) = index(
+        "FUNCTION_BLOCK fb
+        VAR_INPUT
+            a : DINT;
+        END_VAR
+        VAR
+            b : SINT;
+        END_VAR
+        VAR_TEMP
+            t : LINT;
+            buffer : ARRAY[0..99] OF BYTE;
+        END_VAR
+        END_FUNCTION_BLOCK
+
+        VAR_GLOBAL
+            instance : fb;
+        END_VAR
+
+        VAR_GLOBAL CONSTANT
+            fb_size : ULINT := SIZEOF(fb);
+            instance_size : ULINT := SIZEOF(instance);
+        END_VAR
+        ",
+    );
+
+    let (index, unresolvable) = evaluate_constants(index);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    debug_assert_eq!(&create_int_literal(8), find_constant_value(&index, "fb_size").unwrap());
+    debug_assert_eq!(&create_int_literal(8), find_constant_value(&index, "instance_size").unwrap());
+}
+
+#[test]
+fn calls_to_user_defined_functions_are_evaluated() {
+    let (unit, index) = index(
+        "FUNCTION square : DINT
+        VAR_INPUT
+            x : DINT;
+        END_VAR
+            square := x * x;
+        END_FUNCTION
+
+        FUNCTION fac : DINT
+        VAR_INPUT
+            n : DINT;
+        END_VAR
+            IF n <= 1 THEN
+                fac := 1;
+                RETURN;
+            END_IF
+            fac := n * fac(n - 1);
+        END_FUNCTION
+
+        FUNCTION sum_up : DINT
+        VAR_INPUT
+            from : DINT;
+            upto : DINT := 10;
+        END_VAR
+        VAR
+            i : DINT;
+        END_VAR
+            FOR i := from TO upto DO
+                IF i = 5 THEN
+                    CONTINUE;
+                END_IF
+                sum_up := sum_up + i;
+            END_FOR
+        END_FUNCTION
+
+        FUNCTION classify : INT
+        VAR_INPUT
+            x : INT;
+        END_VAR
+        VAR
+            counter : INT := 0;
+        END_VAR
+            CASE x OF
+                1, 2: classify := 10;
+                3..5: classify := 20;
+            ELSE
+                WHILE counter < x DO
+                    counter := counter + 1;
+                    IF counter > 7 THEN
+                        EXIT;
+                    END_IF
+                END_WHILE
+                classify := counter;
+            END_CASE
+        END_FUNCTION
+
+        VAR_GLOBAL CONSTANT
+            four : DINT := 4;
+            a : DINT := square(four + 1);
+            b : DINT := fac(5);
+            c : DINT := sum_up(upto := 6, from := 1);
+            d : DINT := sum_up(8);
+            e : INT := classify(2);
+            f : INT := classify(4);
+            g : INT := classify(6);
+            h : INT := classify(20);
+        END_VAR
+
+        VAR_GLOBAL
+            arr : ARRAY[1..square(3)] OF BYTE;
+        END_VAR
+        ",
+    );
+
+    let functions = ConstFunctions::new(std::slice::from_ref(&unit));
+    let (index, unresolvable) = evaluate_constants_with_functions(index, &functions);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    debug_assert_eq!(&create_int_literal(25), find_constant_value(&index, "a").unwrap());
+    debug_assert_eq!(&create_int_literal(120), find_constant_value(&index, "b").unwrap());
+    debug_assert_eq!(&create_int_literal(16), find_constant_value(&index, "c").unwrap());
+    debug_assert_eq!(&create_int_literal(27), find_constant_value(&index, "d").unwrap());
+    debug_assert_eq!(&create_int_literal(10), find_constant_value(&index, "e").unwrap());
+    debug_assert_eq!(&create_int_literal(20), find_constant_value(&index, "f").unwrap());
+    debug_assert_eq!(&create_int_literal(6), find_constant_value(&index, "g").unwrap());
+    debug_assert_eq!(&create_int_literal(8), find_constant_value(&index, "h").unwrap());
+
+    let arr_type =
+        index.get_type_information_or_void(index.find_global_variable("arr").unwrap().get_type_name());
+    let DataTypeInformation::Array { dimensions, .. } = arr_type else { panic!("{arr_type:?} is no array") };
+    assert_eq!(dimensions[0].get_range(&index).unwrap(), 1..9);
+}
+
+#[test]
+fn calls_to_functions_with_side_effects_are_not_evaluated() {
+    let (unit, index) = index(
+        "VAR_GLOBAL
+            counter : DINT;
+        END_VAR
+
+        FUNCTION increment : DINT
+            counter := counter + 1;
+            increment := counter;
+        END_FUNCTION
+
+        FUNCTION read_global : DINT
+            read_global := counter;
+        END_FUNCTION
+
+        FUNCTION endless : DINT
+        VAR_INPUT
+            x : DINT;
+        END_VAR
+            endless := endless(x);
+        END_FUNCTION
+
+        VAR_GLOBAL CONSTANT
+            a : DINT := increment();
+            b : DINT := read_global();
+            c : DINT := endless(1);
+        END_VAR
+        ",
+    );
+
+    let functions = ConstFunctions::new(std::slice::from_ref(&unit));
+    let (_, unresolvable) = evaluate_constants_with_functions(index, &functions);
+
+    assert_eq!(unresolvable.len(), 3);
+    assert!(unresolvable[0].reason.starts_with("Cannot evaluate `increment` since the assignment to"));
+    assert_eq!(unresolvable[1].reason, "`counter` is no const reference");
+    assert_eq!(unresolvable[2].reason, "Cannot evaluate `endless`: exceeded the maximum call depth of 16");
+}
+
+#[test]
+fn loops_with_empty_bodies_exceed_the_evaluation_steps() {
+    let (unit, index) = index(
+        "FUNCTION while_loop : INT
+            WHILE TRUE DO END_WHILE
+            while_loop := 3;
+        END_FUNCTION
+
+        FUNCTION repeat_loop : INT
+            REPEAT UNTIL FALSE END_REPEAT
+            repeat_loop := 3;
+        END_FUNCTION
+
+        FUNCTION for_loop : INT
+        VAR
+            i : DINT;
+        END_VAR
+            FOR i := 0 TO 2000000000 DO END_FOR
+            for_loop := 3;
+        END_FUNCTION
+
+        VAR_GLOBAL CONSTANT
+            a : INT := while_loop();
+            b : INT := repeat_loop();
+            c : INT := for_loop();
+        END_VAR
+        ",
+    );
+
+    let functions = ConstFunctions::new(std::slice::from_ref(&unit));
+    let (_, unresolvable) = evaluate_constants_with_functions(index, &functions);
+
+    assert_eq!(unresolvable.len(), 3);
+    assert_eq!(
+        unresolvable[0].reason,
+        "Cannot evaluate `while_loop`: exceeded the maximum of 100000 evaluation steps"
+    );
+    assert_eq!(
+        unresolvable[1].reason,
+        "Cannot evaluate `repeat_loop`: exceeded the maximum of 100000 evaluation steps"
+    );
+    assert_eq!(
+        unresolvable[2].reason,
+        "Cannot evaluate `for_loop`: exceeded the maximum of 100000 evaluation steps"
+    );
+}
+
+#[test]
+fn calls_to_functions_wait_for_unresolved_constants() {
+    let (unit, index) = index(
+        "FUNCTION add_offset : INT
+        VAR_INPUT
+            x : INT;
+        END_VAR
+            add_offset := x + OFFSET;
+        END_FUNCTION
+
+        VAR_GLOBAL CONSTANT
+            a : INT := add_offset(BASE);
+            BASE : INT := OFFSET * 2;
+            OFFSET : INT := 3;
+        END_VAR
+        ",
+    );
+
+    let functions = ConstFunctions::new(std::slice::from_ref(&unit));
+    let (index, unresolvable) = evaluate_constants_with_functions(index, &functions);
+
+    debug_assert_eq!(EMPTY, unresolvable);
+    debug_assert_eq!(&create_int_literal(9), find_constant_value(&index, "a").unwrap());
+}
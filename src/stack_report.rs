@@ -0,0 +1,271 @@
+//! Reports the stack usage and code size of a project for targets with tight per-task stack
+//! budgets. The frame and code size of every generated function is measured during code
+//! generation (see [`crate::codegen::GeneratedModule::collect_stack_usage`]). The worst case stack
+//! usage of a PROGRAM is the largest sum of frame sizes along any call chain starting at the
+//! program, following the calls the resolver recorded for every implementation.
+use std::collections::HashMap;
+
+use indexmap::IndexSet;
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_source::source_location::SourceLocation;
+use serde::Serialize;
+
+use crate::{
+    index::{ImplementationType, Index, PouIndexEntry},
+    resolver::{AstAnnotations, Dependency},
+};
+
+/// The stack frame and the code size of a generated function in bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionStackUsage {
+    pub name: String,
+    pub frame_size: u64,
+    pub code_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StackReport {
+    functions: Vec<FunctionStackUsage>,
+    programs: Vec<ProgramStackUsage>,
+}
+
+/// The worst case stack usage of a program and the call chain causing it. The stack usage of a
+/// recursive call chain cannot be bounded, only its first iteration is counted.
+#[derive(Debug, Clone, Serialize)]
+struct ProgramStackUsage {
+    name: String,
+    stack_usage: u64,
+    call_chain: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    recursive: bool,
+    #[serde(skip)]
+    location: SourceLocation,
+}
+
+/// The deepest call chain starting at a POU
+#[derive(Clone, Default)]
+struct CallChain {
+    stack_usage: u64,
+    pous: Vec<String>,
+    recursive: bool,
+}
+
+/// Combines the measured functions with the call graph of the project into a report
+pub fn collect_stack_report(
+    index: &Index,
+    annotations: &AstAnnotations,
+    functions: Vec<FunctionStackUsage>,
+) -> StackReport {
+    let frame_sizes = functions.iter().map(|it| (it.name.to_lowercase(), it.frame_size)).collect();
+    let mut collector = CallChainCollector { index, annotations, frame_sizes, chains: HashMap::new() };
+    let programs = index
+        .get_pous()
+        .values()
+        .filter(|it| matches!(it, PouIndexEntry::Program { .. }))
+        .map(|program| {
+            let chain = collector.find_deepest_chain(program.get_name(), &mut vec![]);
+            ProgramStackUsage {
+                name: program.get_name().to_string(),
+                stack_usage: chain.stack_usage,
+                call_chain: chain.pous,
+                recursive: chain.recursive,
+                location: program.get_location().clone(),
+            }
+        })
+        .collect();
+    StackReport { functions, programs }
+}
+
+pub fn generate_stack_report(report: &StackReport) -> Result<String, Diagnostic> {
+    serde_json::to_string_pretty(report)
+        .map_err(|e| Diagnostic::error(e.to_string()).with_error_code("E002").with_internal_error(e.into()))
+}
+
+/// Reports every program whose worst case stack usage exceeds the given limit in bytes
+pub fn validate_stack_limit(report: &StackReport, limit: u64) -> Vec<Diagnostic> {
+    report
+        .programs
+        .iter()
+        .filter(|it| it.stack_usage > limit || it.recursive)
+        .map(|it| {
+            let chain = it.call_chain.join(" -> ");
+            if it.recursive {
+                Diagnostic::warning(format!(
+                    "The stack usage of program '{}' cannot be bounded, its call chain is recursive: {chain}",
+                    it.name
+                ))
+            } else {
+                Diagnostic::error(format!(
+                    "Program '{}' uses up to {} bytes of stack, exceeding the limit of {limit} bytes: {chain}",
+                    it.name, it.stack_usage
+                ))
+            }
+            .with_error_code("E103")
+            .with_location(it.location.clone())
+        })
+        .collect()
+}
+
+struct CallChainCollector<'a> {
+    index: &'a Index,
+    annotations: &'a AstAnnotations,
+    frame_sizes: HashMap<String, u64>,
+    /// the deepest call chains found so far, by lower case POU name
+    chains: HashMap<String, CallChain>,
+}
+
+impl CallChainCollector<'_> {
+    /// returns the call chain using the most stack starting at the given POU. `callers` holds the
+    /// chain leading to the POU, calls back into it are recursive and not followed
+    fn find_deepest_chain(&mut self, pou: &str, callers: &mut Vec<String>) -> CallChain {
+        let key = pou.to_lowercase();
+        if let Some(chain) = self.chains.get(&key) {
+            return chain.clone();
+        }
+
+        callers.push(key.clone());
+        let mut deepest = CallChain::default();
+        for callee in self.get_callees(pou) {
+            if callers.contains(&callee.to_lowercase()) {
+                deepest.recursive = true;
+                continue;
+            }
+            let chain = self.find_deepest_chain(&callee, callers);
+            deepest.recursive |= chain.recursive;
+            if chain.stack_usage > deepest.stack_usage || deepest.pous.is_empty() {
+                deepest = CallChain { recursive: deepest.recursive, ..chain };
+            }
+        }
+        callers.pop();
+
+        deepest.stack_usage += self.frame_sizes.get(&key).copied().unwrap_or_default();
+        deepest.pous.insert(0, pou.to_string());
+        // a recursive chain depends on the callers it was reached from
+        if !deepest.recursive {
+            self.chains.insert(key, deepest.clone());
+        }
+        deepest
+    }
+
+    /// returns the implementations the given POU may call, instances of function blocks count as called.
+    /// A POU depends on its own type (e.g. through its return variable), only a call to itself is recursive
+    fn get_callees(&self, pou: &str) -> Vec<String> {
+        let Some(dependencies) = self.annotations.get_pou_dependencies(pou) else { return vec![] };
+        dependencies
+            .iter()
+            .filter_map(|it| match it {
+                Dependency::Datatype(name) if name.eq_ignore_ascii_case(pou) => None,
+                Dependency::Call(name) | Dependency::Datatype(name) => {
+                    self.index.find_implementation_by_name(name)
+                }
+                Dependency::Variable(_) => None,
+            })
+            .filter(|it| {
+                !it.is_generic() && !matches!(it.get_implementation_type(), ImplementationType::Class)
+            })
+            .map(|it| it.get_call_name().to_string())
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plc_ast::provider::IdProvider;
+
+    use crate::{
+        resolver::AstAnnotations,
+        test_utils::tests::{annotate_with_ids, index_with_ids},
+    };
+
+    use super::*;
+
+    /// reports the given source with the given frame size for every function
+    fn stack_report(src: &str, frame_sizes: &[(&str, u64)]) -> StackReport {
+        let id_provider = IdProvider::default();
+        let (unit, mut index) = index_with_ids(src, id_provider.clone());
+        let annotations = annotate_with_ids(&unit, &mut index, id_provider.clone());
+        let annotations = AstAnnotations::new(annotations, id_provider.clone().next_id());
+        let functions = frame_sizes
+            .iter()
+            .map(|(name, frame_size)| FunctionStackUsage {
+                name: name.to_string(),
+                frame_size: *frame_size,
+                code_size: 0,
+            })
+            .collect();
+        collect_stack_report(&index, &annotations, functions)
+    }
+
+    #[test]
+    fn programs_report_their_deepest_call_chain() {
+        let report = stack_report(
+            "
+            FUNCTION small : DINT small := 1; END_FUNCTION
+            FUNCTION large : DINT large := small(); END_FUNCTION
+
+            FUNCTION_BLOCK filter
+            VAR_OUTPUT y : DINT; END_VAR
+                y := small();
+            END_FUNCTION_BLOCK
+
+            PROGRAM main
+            VAR f : filter; x : DINT; END_VAR
+                f();
+                x := large();
+            END_PROGRAM
+
+            PROGRAM idle
+            END_PROGRAM
+            ",
+            &[("small", 16), ("large", 64), ("filter", 128), ("main", 8), ("idle", 0)],
+        );
+        insta::assert_snapshot!(generate_stack_report(&report).unwrap());
+        assert!(validate_stack_limit(&report, 152).is_empty());
+        insta::assert_debug_snapshot!(validate_stack_limit(&report, 151));
+    }
+
+    #[test]
+    fn recursive_call_chains_cannot_be_bounded() {
+        let report = stack_report(
+            "
+            FUNCTION ping : DINT VAR_INPUT n : DINT; END_VAR ping := pong(n - 1); END_FUNCTION
+            FUNCTION pong : DINT VAR_INPUT n : DINT; END_VAR pong := ping(n - 1); END_FUNCTION
+
+            PROGRAM main
+            VAR x : DINT; END_VAR
+                x := ping(3);
+            END_PROGRAM
+            ",
+            &[("ping", 32), ("pong", 32), ("main", 8)],
+        );
+        let diagnostics = validate_stack_limit(&report, 1024);
+        insta::assert_debug_snapshot!(diagnostics);
+    }
+
+    #[test]
+    fn directly_recursive_call_chains_cannot_be_bounded() {
+        let report = stack_report(
+            "
+            FUNCTION fact : DINT
+            VAR_INPUT n : DINT; END_VAR
+            VAR buffer : ARRAY[0..63] OF DINT; END_VAR
+                IF n <= 1 THEN
+                    fact := 1;
+                ELSE
+                    fact := n * fact(n - 1);
+                END_IF
+            END_FUNCTION
+
+            PROGRAM main
+            VAR x : DINT; END_VAR
+                x := fact(5);
+            END_PROGRAM
+            ",
+            &[("fact", 280), ("main", 8)],
+        );
+        let diagnostics = validate_stack_limit(&report, 1024);
+        insta::assert_debug_snapshot!(diagnostics);
+    }
+}
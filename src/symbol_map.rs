@@ -0,0 +1,754 @@
+//! Describes the memory layout of a project for external tools like HMIs or documentation
+//! generators. The symbol map lists every instance reachable from a global variable or a
+//! program together with its type, its byte offset and its size, as well as the layout of all
+//! user defined structs, enums and POUs.
+use std::collections::HashMap;
+
+use plc_ast::ast::PouType;
+use plc_diagnostics::diagnostics::Diagnostic;
+use plc_index::GlobalContext;
+use plc_source::source_location::SourceLocation;
+use serde::Serialize;
+
+use crate::{
+    datalayout::{self, get_allocated_size},
+    expression_path::{ExpressionPath, ExpressionPathElement},
+    index::{Index, PouIndexEntry, VariableIndexEntry},
+    typesystem::{DataType, DataTypeInformation, StructSource},
+};
+
+#[derive(Debug, Serialize)]
+pub struct SymbolMap {
+    instances: Vec<InstanceSymbol>,
+    types: Vec<TypeSymbol>,
+}
+
+/// A single variable in memory. The offset is relative to the start of the global variable or
+/// program instance the variable is part of, i.e. the first segment of its name.
+#[derive(Debug, Serialize)]
+struct InstanceSymbol {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    offset: u32,
+    size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum TypeSymbol {
+    Struct(StructSymbol),
+    Program(StructSymbol),
+    FunctionBlock(StructSymbol),
+    Class(StructSymbol),
+    Enum(EnumSymbol),
+}
+
+#[derive(Debug, Serialize)]
+struct StructSymbol {
+    name: String,
+    size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    members: Vec<MemberSymbol>,
+}
+
+#[derive(Debug, Serialize)]
+struct MemberSymbol {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    offset: u32,
+    size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct EnumSymbol {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    size: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    elements: Vec<EnumElementSymbol>,
+}
+
+#[derive(Debug, Serialize)]
+struct EnumElementSymbol {
+    name: String,
+    value: i128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+}
+
+/// An instance that is allocated in memory
+pub struct InstanceLayout<'idx> {
+    /// the qualified path of the instance, e.g. `main.motors[1].speed`
+    pub name: String,
+    /// the global variable or program instance this instance is part of
+    pub root: &'idx VariableIndexEntry,
+    /// the declaration of this instance
+    pub variable: &'idx VariableIndexEntry,
+    /// the byte offset relative to the start of the root
+    pub offset: u32,
+    /// the size in bytes
+    pub size: u32,
+}
+
+/// Collects the symbol map of all instances and user defined types in the index.
+/// The given context is used to look up the comments attached to the declarations.
+pub fn collect_symbol_map(index: &Index, ctxt: &GlobalContext) -> Result<SymbolMap, Diagnostic> {
+    let collector = SymbolCollector { index, ctxt };
+    collector.collect().map_err(|message| Diagnostic::error(message).with_error_code("E002"))
+}
+
+pub fn generate_symbol_map(map: &SymbolMap) -> Result<String, Diagnostic> {
+    serde_json::to_string_pretty(map)
+        .map_err(|e| Diagnostic::error(e.to_string()).with_error_code("E002").with_internal_error(e.into()))
+}
+
+/// Collects every instance reachable from a global variable or a program together with its
+/// location in memory. Array elements are only listed if they contain members.
+pub fn collect_instance_layouts(index: &Index) -> Result<Vec<InstanceLayout>, Diagnostic> {
+    find_instance_layouts(index).map_err(|message| Diagnostic::error(message).with_error_code("E002"))
+}
+
+/// The location of a single instance in memory
+#[derive(Debug)]
+pub struct InstanceLocation<'idx> {
+    /// the global variable or program instance this instance is part of
+    pub root: &'idx VariableIndexEntry,
+    /// the byte offset relative to the start of the root
+    pub offset: u32,
+    /// the name of the instance's type
+    pub type_name: &'idx str,
+}
+
+/// Finds the instance with the given qualified name, e.g. `main.motors[1].speed`. Unlike the
+/// instances collected by [`collect_instance_layouts`], the elements of every array can be named.
+/// Returns why the name cannot be resolved otherwise.
+pub fn find_instance<'idx>(index: &'idx Index, name: &str) -> Result<InstanceLocation<'idx>, String> {
+    let mut segments =
+        name.split('.').map(|it| parse_segment(it).ok_or_else(|| format!("Invalid name `{name}`")));
+    let (root_name, root_indices) = segments.next().transpose()?.unwrap_or_default();
+    let root = match index.find_pou(root_name) {
+        Some(PouIndexEntry::Program { instance_variable, .. }) => Some(instance_variable.as_ref()),
+        _ => index.find_global_variable(root_name),
+    }
+    .ok_or_else(|| format!("Cannot find the instance `{root_name}`"))?;
+    let mut location = InstanceLocation { root, offset: 0, type_name: root.get_type_name() };
+    location = locate_elements(index, location, root_name, &root_indices)?;
+    for segment in segments {
+        let (member_name, indices) = segment?;
+        let member_offsets = get_member_offsets(index, location.type_name)?;
+        let Some((member, member_offset)) =
+            member_offsets.iter().find(|(member, _)| member.get_name().eq_ignore_ascii_case(member_name))
+        else {
+            if index.find_member(location.type_name, member_name).is_some_and(|it| it.is_in_process_image()) {
+                return Err(format!("`{name}` is located in the process image"));
+            }
+            return Err(format!("Cannot find the member `{member_name}` in `{}`", location.type_name));
+        };
+        location.offset += member_offset;
+        location.type_name = member.get_type_name();
+        if indices.is_empty() && is_mapped(index, root, member, location.offset) {
+            return Err(format!("`{name}` is mapped into the process image"));
+        }
+        location = locate_elements(index, location, member_name, &indices)?;
+    }
+    Ok(location)
+}
+
+/// returns true if the instance of the given variable at the given offset of its root was assigned
+/// its location in the process image by an I/O mapping
+fn is_mapped(index: &Index, root: &VariableIndexEntry, variable: &VariableIndexEntry, offset: u32) -> bool {
+    index
+        .get_mapped_addresses(variable.get_qualified_name())
+        .iter()
+        .any(|it| it.root.eq_ignore_ascii_case(root.get_name()) && it.offset == offset)
+}
+
+/// splits a segment of a qualified name like `motors[1,2][3]` into its name and the accessed
+/// indices of every dimension
+fn parse_segment(segment: &str) -> Option<(&str, Vec<i64>)> {
+    let (name, mut rest) = segment.split_at(segment.find('[').unwrap_or(segment.len()));
+    let mut indices = vec![];
+    while let Some(access) = rest.strip_prefix('[') {
+        let (access, remainder) = access.split_once(']')?;
+        for index in access.split(',') {
+            indices.push(index.trim().parse().ok()?);
+        }
+        rest = remainder;
+    }
+    (!name.trim().is_empty() && rest.is_empty()).then_some((name.trim(), indices))
+}
+
+/// moves the given location to the array element with the given indices
+fn locate_elements<'idx>(
+    index: &'idx Index,
+    mut location: InstanceLocation<'idx>,
+    name: &str,
+    mut indices: &[i64],
+) -> Result<InstanceLocation<'idx>, String> {
+    while !indices.is_empty() {
+        let DataTypeInformation::Array { inner_type_name, dimensions, .. } =
+            index.get_type_information_or_void(location.type_name)
+        else {
+            return Err(format!("`{name}` is not an array"));
+        };
+        if indices.len() < dimensions.len() {
+            return Err(format!("`{name}` has {} dimensions", dimensions.len()));
+        }
+        //arrays are layed out in row-major order
+        let mut position = 0;
+        for (dimension, value) in dimensions.iter().zip(indices) {
+            let range = dimension.get_range_inclusive(index)?;
+            if !range.contains(value) {
+                return Err(format!("Index {value} is out of the range {range:?} of `{name}`"));
+            }
+            position = position * (range.end() - range.start() + 1) + (value - range.start());
+        }
+        let element_size = get_size(index, inner_type_name)?;
+        location.offset += position as u32 * element_size;
+        location.type_name = inner_type_name;
+        indices = &indices[dimensions.len()..];
+    }
+    Ok(location)
+}
+
+fn find_instance_layouts(index: &Index) -> Result<Vec<InstanceLayout>, String> {
+    //every global variable and program instance, by their lower-case name
+    let mut roots: HashMap<String, &VariableIndexEntry> = HashMap::new();
+    let mut instances = vec![];
+    //temporaries, return values and variables in the process image do not live inside their container
+    for (path, variable) in
+        index.filter_instances(|it, _| is_allocated(it)).filter(|(_, variable)| is_allocated(variable))
+    {
+        if let [ExpressionPathElement::Name(name)] = path.get_elements() {
+            roots.insert(name.to_lowercase(), variable);
+        }
+        let size = get_size(index, variable.get_type_name())?;
+        for (name, root, offset) in locate(index, &path, &roots)? {
+            //mapped instances are accessed in the process image, their own storage is unused
+            if !is_mapped(index, root, variable, offset) {
+                instances.push(InstanceLayout { name, root, variable, offset, size })
+            }
+        }
+    }
+    Ok(instances)
+}
+
+/// expands the given path to all the instances it references and calculates their offset
+/// relative to the root of the path
+fn locate<'idx>(
+    index: &'idx Index,
+    path: &ExpressionPath,
+    roots: &HashMap<String, &'idx VariableIndexEntry>,
+) -> Result<Vec<(String, &'idx VariableIndexEntry, u32)>, String> {
+    let Some(ExpressionPathElement::Name(root_name)) = path.get_elements().first() else {
+        return Ok(vec![]);
+    };
+    let root = *roots
+        .get(&root_name.to_lowercase())
+        .ok_or_else(|| format!("Cannot find the instance `{root_name}`"))?;
+    let mut current: Vec<(String, &str, u32)> = vec![(root_name.to_string(), root.get_type_name(), 0)];
+    for element in &path.get_elements()[1..] {
+        current = match element {
+            ExpressionPathElement::Name(name) => {
+                let mut next = vec![];
+                for (prefix, type_name, offset) in current {
+                    let member_offsets = get_member_offsets(index, type_name)?;
+                    let (member, member_offset) = member_offsets
+                        .iter()
+                        .find(|(member, _)| member.get_name().eq_ignore_ascii_case(name))
+                        .ok_or_else(|| format!("Cannot find the member `{name}` in `{type_name}`"))?;
+                    next.push((format!("{prefix}.{name}"), member.get_type_name(), offset + member_offset));
+                }
+                next
+            }
+            ExpressionPathElement::ArrayAccess(dimensions) => {
+                let mut next = vec![];
+                for (prefix, type_name, offset) in current {
+                    let Some(inner_type_name) =
+                        index.get_type_information_or_void(type_name).get_inner_array_type_name()
+                    else {
+                        return Err(format!("`{prefix}` is not an array"));
+                    };
+                    let element_size = get_size(index, inner_type_name)?;
+                    let ranges = dimensions.iter().map(|it| it.get_range_inclusive(index)).collect::<Result<
+                        Vec<_>,
+                        _,
+                    >>(
+                    )?;
+                    //arrays are layed out in row-major order
+                    let mut elements: Vec<(Vec<i64>, u32)> = vec![(vec![], 0)];
+                    for range in ranges {
+                        let length = (range.end() - range.start() + 1).max(0) as u32;
+                        elements = elements
+                            .into_iter()
+                            .flat_map(|(indices, position)| {
+                                range.clone().enumerate().map(move |(i, it)| {
+                                    let mut indices = indices.clone();
+                                    indices.push(it);
+                                    (indices, position * length + i as u32)
+                                })
+                            })
+                            .collect();
+                    }
+                    next.extend(elements.into_iter().map(|(indices, position)| {
+                        let indices = indices.iter().map(i64::to_string).collect::<Vec<_>>().join(",");
+                        (format!("{prefix}[{indices}]"), inner_type_name, offset + position * element_size)
+                    }));
+                }
+                next
+            }
+        };
+    }
+    Ok(current.into_iter().map(|(name, _, offset)| (name, root, offset)).collect())
+}
+
+pub(crate) fn get_size(index: &Index, type_name: &str) -> Result<u32, String> {
+    get_allocated_size(index.get_type_information_or_void(type_name), index)
+        .ok_or_else(|| format!("Cannot calculate the size of `{type_name}`"))
+}
+
+pub(crate) fn get_member_offsets<'idx>(
+    index: &'idx Index,
+    type_name: &str,
+) -> Result<Vec<(&'idx VariableIndexEntry, u32)>, String> {
+    let type_info = index.get_type_information_or_void(type_name);
+    datalayout::get_member_offsets(type_info, index)
+        .map(|it| it.into_iter().map(|(member, offset)| (member, offset.value())).collect())
+        .ok_or_else(|| format!("Cannot calculate the layout of `{type_name}`"))
+}
+
+struct SymbolCollector<'a> {
+    index: &'a Index,
+    ctxt: &'a GlobalContext,
+}
+
+impl SymbolCollector<'_> {
+    fn collect(&self) -> Result<SymbolMap, String> {
+        Ok(SymbolMap { instances: self.collect_instances()?, types: self.collect_types()? })
+    }
+
+    fn collect_instances(&self) -> Result<Vec<InstanceSymbol>, String> {
+        let instances = find_instance_layouts(self.index)?
+            .into_iter()
+            .map(|it| InstanceSymbol {
+                name: it.name,
+                type_name: self.get_type_display_name(it.variable.get_type_name()),
+                offset: it.offset,
+                size: it.size,
+                comment: self.find_comment(&it.variable.source_location),
+            })
+            .collect();
+        Ok(instances)
+    }
+
+    fn collect_types(&self) -> Result<Vec<TypeSymbol>, String> {
+        let mut types = vec![];
+        for data_type in self.index.get_types().values().chain(self.index.get_pou_types().values()) {
+            if data_type.location.is_internal() {
+                //builtin types
+                continue;
+            }
+            let symbol = match &data_type.information {
+                DataTypeInformation::Struct { source: StructSource::OriginalDeclaration, .. } => {
+                    TypeSymbol::Struct(self.collect_struct(data_type)?)
+                }
+                DataTypeInformation::Struct { source: StructSource::Pou(PouType::Program), .. } => {
+                    TypeSymbol::Program(self.collect_struct(data_type)?)
+                }
+                DataTypeInformation::Struct { source: StructSource::Pou(PouType::FunctionBlock), .. } => {
+                    TypeSymbol::FunctionBlock(self.collect_struct(data_type)?)
+                }
+                DataTypeInformation::Struct { source: StructSource::Pou(PouType::Class), .. } => {
+                    TypeSymbol::Class(self.collect_struct(data_type)?)
+                }
+                DataTypeInformation::Enum { name, referenced_type, elements } => {
+                    TypeSymbol::Enum(self.collect_enum(data_type, name, referenced_type, elements)?)
+                }
+                _ => continue,
+            };
+            types.push(symbol);
+        }
+        Ok(types)
+    }
+
+    fn collect_struct(&self, data_type: &DataType) -> Result<StructSymbol, String> {
+        let members = get_member_offsets(self.index, data_type.get_name())?
+            .into_iter()
+            .map(|(member, offset)| {
+                Ok(MemberSymbol {
+                    name: member.get_name().to_string(),
+                    type_name: self.get_type_display_name(member.get_type_name()),
+                    offset,
+                    size: get_size(self.index, member.get_type_name())?,
+                    comment: self.find_comment(&member.source_location),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(StructSymbol {
+            name: data_type.get_name().to_string(),
+            size: get_size(self.index, data_type.get_name())?,
+            comment: self.find_comment(&data_type.location),
+            members,
+        })
+    }
+
+    fn collect_enum(
+        &self,
+        data_type: &DataType,
+        name: &str,
+        referenced_type: &str,
+        elements: &[String],
+    ) -> Result<EnumSymbol, String> {
+        let elements = elements
+            .iter()
+            .map(|element| {
+                let variable = self
+                    .index
+                    .find_enum_element(name, element)
+                    .ok_or_else(|| format!("Cannot find the enum element `{name}.{element}`"))?;
+                let value = variable
+                    .initial_value
+                    .ok_or_else(|| format!("Enum element `{name}.{element}` has no value"))
+                    .and_then(|it| {
+                        self.index.get_const_expressions().get_constant_int_statement_value(&it)
+                    })?;
+                Ok(EnumElementSymbol {
+                    name: element.to_string(),
+                    value,
+                    comment: self.find_comment(&variable.source_location),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(EnumSymbol {
+            name: name.to_string(),
+            type_name: referenced_type.to_string(),
+            size: get_size(self.index, name)?,
+            comment: self.find_comment(&data_type.location),
+            elements,
+        })
+    }
+
+    /// returns the name of the given type, types that were declared inline (e.g. `ARRAY[0..5] OF INT`)
+    /// are described by their declaration
+    fn get_type_display_name(&self, type_name: &str) -> String {
+        match self.index.find_effective_type_by_name(type_name) {
+            Some(data_type)
+                if data_type.get_name().starts_with("__") && !data_type.location.is_internal() =>
+            {
+                self.ctxt.slice(&data_type.location)
+            }
+            Some(data_type) => data_type.get_name().to_string(),
+            None => type_name.to_string(),
+        }
+    }
+
+    fn find_comment(&self, location: &SourceLocation) -> Option<String> {
+        let source = self.ctxt.get(location.get_file_name().unwrap_or("<internal>"))?;
+        find_comment(&source.source, location.to_range()?)
+    }
+}
+
+/// returns the name of the given type, the names of types that were declared inline
+/// (e.g. `ARRAY[1..3] OF INT`) are replaced by their declaration
+pub fn get_type_description(index: &Index, type_name: &str) -> String {
+    let Some(data_type) = index.find_effective_type_by_name(type_name) else {
+        return type_name.to_string();
+    };
+    if !data_type.get_name().starts_with("__") {
+        return data_type.get_name().to_string();
+    }
+    match data_type.get_type_information() {
+        DataTypeInformation::Array { inner_type_name, dimensions, .. } => {
+            let dimensions = dimensions
+                .iter()
+                .map(|it| match (it.start_offset.as_int_value(index), it.end_offset.as_int_value(index)) {
+                    (Ok(start), Ok(end)) => format!("{start}..{end}"),
+                    _ => "*".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("ARRAY[{dimensions}] OF {}", get_type_description(index, inner_type_name))
+        }
+        DataTypeInformation::Pointer { inner_type_name, .. } => {
+            format!("REF_TO {}", get_type_description(index, inner_type_name))
+        }
+        DataTypeInformation::String { size, .. } => {
+            let name = data_type.get_type_information().get_name();
+            match size.as_int_value(index) {
+                Ok(length) => format!("{name}[{}]", length - 1),
+                Err(_) => name.to_string(),
+            }
+        }
+        _ => data_type.get_name().to_string(),
+    }
+}
+
+/// returns true if the variable is allocated inside of its container
+pub(crate) fn is_allocated(variable: &VariableIndexEntry) -> bool {
+    !variable.is_temp() && !variable.is_return() && !variable.is_in_process_image()
+}
+
+/// Finds the comment documenting the declaration at the given range. This is either a comment
+/// following the declaration on the same line or the comments on the lines right above it.
+fn find_comment(source: &str, range: std::ops::Range<usize>) -> Option<String> {
+    let line_end = source[range.end..].find('\n').map(|it| range.end + it).unwrap_or(source.len());
+    if let Some(start) = find_comment_start(&source[range.end..line_end]) {
+        return read_comment(&source[range.end + start..]);
+    }
+
+    let mut comments = vec![];
+    let mut line_start = source[..range.start].rfind('\n').map(|it| it + 1).unwrap_or(0);
+    while line_start > 0 {
+        let previous_start = source[..line_start - 1].rfind('\n').map(|it| it + 1).unwrap_or(0);
+        let line = source[previous_start..line_start].trim();
+        let comment_start = if line.starts_with("//") {
+            previous_start
+        } else if line.ends_with("*)") || line.ends_with("*/") {
+            let opening = if line.ends_with("*)") { "(*" } else { "/*" };
+            let Some(start) = source[..line_start].rfind(opening) else { break };
+            let start_of_line = source[..start].rfind('\n').map(|it| it + 1).unwrap_or(0);
+            if !source[start_of_line..start].trim().is_empty() {
+                break;
+            }
+            start_of_line
+        } else {
+            break;
+        };
+        let Some(comment) = read_comment(source[comment_start..].trim_start()) else { break };
+        comments.push(comment);
+        line_start = comment_start;
+    }
+    comments.reverse();
+    (!comments.is_empty()).then(|| comments.join("\n"))
+}
+
+/// returns the position of the first comment in the given line, ignoring string literals
+fn find_comment_start(line: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, _) if ["(*", "/*", "//"].iter().any(|it| line[i..].starts_with(it)) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// reads the comment at the start of the given text and returns its trimmed content
+fn read_comment(text: &str) -> Option<String> {
+    let content = if let Some(rest) = text.strip_prefix("//") {
+        rest.lines().next().unwrap_or_default()
+    } else {
+        let closing = if text.starts_with("(*") { "*)" } else { "*/" };
+        let end = text.get(2..)?.find(closing)?;
+        &text[2..end + 2]
+    };
+    Some(content.lines().map(str::trim).filter(|it| !it.is_empty()).collect::<Vec<_>>().join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use plc_ast::ast::HardwareAccessType;
+    use plc_index::GlobalContext;
+    use plc_source::SourceCode;
+
+    use crate::{
+        process_image::{ImageAddress, MappedAddress},
+        resolver::const_evaluator::evaluate_constants,
+        symbol_map::{
+            collect_instance_layouts, collect_symbol_map, find_comment, find_instance, generate_symbol_map,
+        },
+        test_utils::tests::index,
+    };
+
+    fn symbol_map(src: &str) -> String {
+        let mut ctxt = GlobalContext::new();
+        ctxt.insert(&SourceCode::from(src), None).unwrap();
+        let (_, index) = index(src);
+        let (index, _) = evaluate_constants(index);
+        let map = collect_symbol_map(&index, &ctxt).unwrap();
+        generate_symbol_map(&map).unwrap()
+    }
+
+    #[test]
+    fn global_variables_with_offsets_and_sizes() {
+        let map = symbol_map(
+            "
+        TYPE Point : STRUCT
+            x : SINT; (* horizontal position *)
+            y : DINT; // vertical position
+        END_STRUCT
+        END_TYPE
+
+        VAR_GLOBAL
+            // the current position
+            pos : Point;
+            points : ARRAY[1..2] OF Point;
+            name : STRING[10];
+        END_VAR",
+        );
+        insta::assert_snapshot!(map);
+    }
+
+    #[test]
+    fn program_and_function_block_instances() {
+        let map = symbol_map(
+            "
+        FUNCTION_BLOCK Motor
+        VAR_INPUT
+            enable : BOOL;
+        END_VAR
+        VAR_OUTPUT
+            speed : LREAL; (* in rpm *)
+        END_VAR
+        VAR_TEMP
+            t : DINT;
+        END_VAR
+        END_FUNCTION_BLOCK
+
+        (* the main program *)
+        PROGRAM main
+        VAR
+            motors : ARRAY[0..1, 0..1] OF Motor;
+            count : INT;
+        END_VAR
+        VAR_TEMP
+            i : INT;
+        END_VAR
+        END_PROGRAM",
+        );
+        insta::assert_snapshot!(map);
+    }
+
+    #[test]
+    fn enums_with_values() {
+        let map = symbol_map(
+            "
+        TYPE Color : (
+            red := 1,   (* stop *)
+            yellow,
+            green := 10 (* go *)
+        );
+        END_TYPE
+
+        TYPE Size : INT (small, large := 100); END_TYPE
+
+        VAR_GLOBAL
+            color : Color;
+            size : Size;
+        END_VAR",
+        );
+        insta::assert_snapshot!(map);
+    }
+
+    #[test]
+    fn instances_are_found_by_their_qualified_name() {
+        let (_, index) = index(
+            "
+        TYPE Point : STRUCT
+            x : SINT;
+            y : DINT;
+        END_STRUCT
+        END_TYPE
+
+        VAR_GLOBAL
+            points : ARRAY[1..2, 0..2] OF Point;
+        END_VAR
+
+        PROGRAM main
+        VAR
+            count : INT;
+            origin : Point;
+        END_VAR
+        END_PROGRAM",
+        );
+        let (index, _) = evaluate_constants(index);
+        let find = |name| find_instance(&index, name).map(|it| (it.root.get_name(), it.offset, it.type_name));
+
+        assert_eq!(find("main.origin.y"), Ok(("main_instance", 8, "DINT")));
+        assert_eq!(find("MAIN.Count"), Ok(("main_instance", 0, "INT")));
+        // the second row starts after the three points of the first one
+        assert_eq!(find("points[2, 1].x"), Ok(("points", 32, "SINT")));
+        assert_eq!(find("points[2][1]"), Ok(("points", 32, "Point")));
+
+        assert_eq!(find("points[3, 0]"), Err("Index 3 is out of the range 1..=2 of `points`".to_string()));
+        assert_eq!(find("points[1]"), Err("`points` has 2 dimensions".to_string()));
+        assert_eq!(find("main.count[1]"), Err("`count` is not an array".to_string()));
+        assert_eq!(find("main.z"), Err("Cannot find the member `z` in `main`".to_string()));
+        assert_eq!(find("other.x"), Err("Cannot find the instance `other`".to_string()));
+        assert_eq!(find("points[a]"), Err("Invalid name `points[a]`".to_string()));
+    }
+
+    #[test]
+    fn instances_in_the_process_image_are_left_out() {
+        let (_, index) = index(
+            "
+        FUNCTION_BLOCK Drive
+        VAR_OUTPUT
+            out AT %Q* : INT;
+            running AT %QX0.0 : BOOL;
+            count : INT;
+        END_VAR
+        END_FUNCTION_BLOCK
+
+        VAR_GLOBAL
+            d1 : Drive;
+            d2 : Drive;
+        END_VAR",
+        );
+        let (mut index, _) = evaluate_constants(index);
+        let address = MappedAddress {
+            root: "d1".to_string(),
+            offset: 0,
+            direction: HardwareAccessType::Output,
+            address: ImageAddress { byte: 0, bit: None },
+        };
+        index.register_mapped_address("Drive.out", address);
+
+        let layouts = collect_instance_layouts(&index).unwrap();
+        let names = layouts.iter().map(|it| it.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["d1", "d1.count", "d2", "d2.out", "d2.count"]);
+
+        let find = |name| find_instance(&index, name).map(|it| (it.root.get_name(), it.offset, it.type_name));
+        assert_eq!(find("d1.out"), Err("`d1.out` is mapped into the process image".to_string()));
+        assert_eq!(find("d2.running"), Err("`d2.running` is located in the process image".to_string()));
+        assert_eq!(find("d2.out"), Ok(("d2", 0, "INT")));
+    }
+
+    #[test]
+    fn comments_are_found_before_and_after_declarations() {
+        let src = "
+        // first line
+        (* second line *)
+        a : INT; // not this one
+        b : INT; (* a multiline
+                    comment *)
+        c : STRING := '(* no comment *)';
+        ";
+        let location_of = |name: &str| {
+            let start = src.find(name).unwrap();
+            start..start + name.len()
+        };
+        assert_eq!(find_comment(src, location_of("a :")), Some("not this one".to_string()));
+        assert_eq!(find_comment(src, location_of("b :")), Some("a multiline\ncomment".to_string()));
+        assert_eq!(find_comment(src, location_of("c :")), None);
+
+        let src = "
+        // first line
+        (* second line *)
+        a : INT;";
+        let start = src.find("a :").unwrap();
+        assert_eq!(find_comment(src, start..start + 1), Some("first line\nsecond line".to_string()));
+    }
+}
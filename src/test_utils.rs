@@ -18,7 +18,11 @@ pub mod tests {
         codegen::{CodegenContext, GeneratedModule},
         index::{self, Index},
         lexer, parser,
-        resolver::{const_evaluator::evaluate_constants, AnnotationMapImpl, AstAnnotations, TypeAnnotator},
+        resolver::{
+            const_evaluator::{evaluate_constants_with_functions, ConstFunctions},
+            generics::{implement_generic_functions, instantiate_generic_pous},
+            AnnotationMapImpl, AstAnnotations, TypeAnnotator,
+        },
         typesystem::get_builtin_types,
         DebugLevel, Validator,
     };
@@ -72,11 +76,12 @@ pub mod tests {
         }
 
         let range_factory = SourceLocationFactory::for_source(&source);
-        let (mut unit, diagnostics) = parser::parse(
+        let (mut unit, mut diagnostics) = parser::parse(
             lexer::lex_with_ids(source_str, id_provider.clone(), range_factory),
             LinkageType::Internal,
             source_path,
         );
+        diagnostics.extend(instantiate_generic_pous(std::slice::from_mut(&mut unit), id_provider.clone()));
 
         pre_process(&mut unit, id_provider);
         index.import(index::visitor::visit(&unit));
@@ -124,7 +129,8 @@ pub mod tests {
 
         let (unit, index, mut diagnostics) = do_index(src, ctxt.provider());
 
-        let (mut index, ..) = evaluate_constants(index);
+        let functions = ConstFunctions::new(std::slice::from_ref(&unit));
+        let (mut index, ..) = evaluate_constants_with_functions(index, &functions);
         let (mut annotations, ..) = TypeAnnotator::visit_unit(&index, &unit, ctxt.provider());
         index.import(std::mem::take(&mut annotations.new_index));
 
@@ -150,10 +156,14 @@ pub mod tests {
         let (unit, index, diagnostics) = do_index(src, id_provider.clone());
         reporter.handle(&diagnostics);
 
-        let (mut index, ..) = evaluate_constants(index);
+        let functions = ConstFunctions::new(std::slice::from_ref(&unit));
+        let (mut index, ..) = evaluate_constants_with_functions(index, &functions);
         let (mut annotations, dependencies, literals) =
             TypeAnnotator::visit_unit(&index, &unit, id_provider.clone());
         index.import(std::mem::take(&mut annotations.new_index));
+        let mut units = [(unit, dependencies, literals)];
+        implement_generic_functions(&mut index, &mut annotations, &mut units, id_provider.clone());
+        let [(unit, dependencies, literals)] = units;
 
         let context = CodegenContext::create();
         let path = PathBuf::from_str("src").ok();
@@ -210,9 +220,9 @@ pub mod tests {
                 index.import(idx);
             },
         );
-        let (mut index, ..) = evaluate_constants(index);
+        let (mut index, ..) = evaluate_constants_with_functions(index, &ConstFunctions::new(&units));
         let mut all_annotations = AnnotationMapImpl::default();
-        let units = units
+        let mut units = units
             .into_iter()
             .map(|unit| {
                 let (mut annotation, dependencies, literals) =
@@ -222,6 +232,7 @@ pub mod tests {
                 (unit, dependencies, literals)
             })
             .collect::<Vec<_>>();
+        implement_generic_functions(&mut index, &mut all_annotations, &mut units, id_provider.clone());
 
         let path = PathBuf::from_str("src").ok();
         let annotations = AstAnnotations::new(all_annotations, id_provider.next_id());
@@ -68,6 +68,8 @@ fn programs_state_is_stored_in_a_struct() {
                             Input,
                         ),
                         is_constant: false,
+                        is_retain: false,
+                        is_persistent: false,
                         data_type_name: "INT",
                         location_in_parent: 0,
                         linkage: Internal,
@@ -95,6 +97,8 @@ fn programs_state_is_stored_in_a_struct() {
                             InOut,
                         ),
                         is_constant: false,
+                        is_retain: false,
+                        is_persistent: false,
                         data_type_name: "__auto_pointer_to_INT",
                         location_in_parent: 1,
                         linkage: Internal,
@@ -122,6 +126,8 @@ fn programs_state_is_stored_in_a_struct() {
                             Output,
                         ),
                         is_constant: false,
+                        is_retain: false,
+                        is_persistent: false,
                         data_type_name: "INT",
                         location_in_parent: 2,
                         linkage: Internal,
@@ -149,6 +155,8 @@ fn programs_state_is_stored_in_a_struct() {
                             Local,
                         ),
                         is_constant: false,
+                        is_retain: false,
+                        is_persistent: false,
                         data_type_name: "INT",
                         location_in_parent: 3,
                         linkage: Internal,
@@ -176,6 +184,8 @@ fn programs_state_is_stored_in_a_struct() {
                             Temp,
                         ),
                         is_constant: false,
+                        is_retain: false,
+                        is_persistent: false,
                         data_type_name: "INT",
                         location_in_parent: 4,
                         linkage: Internal,
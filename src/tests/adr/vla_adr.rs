@@ -43,6 +43,8 @@ fn representation() {
                         Input,
                     ),
                     is_constant: false,
+                    is_retain: false,
+                    is_persistent: false,
                     data_type_name: "__ptr_to___arr_vla_1_dint",
                     location_in_parent: 0,
                     linkage: Internal,
@@ -60,6 +62,8 @@ fn representation() {
                         Input,
                     ),
                     is_constant: false,
+                    is_retain: false,
+                    is_persistent: false,
                     data_type_name: "__bounds___arr_vla_1_dint",
                     location_in_parent: 1,
                     linkage: Internal,
@@ -269,6 +273,8 @@ fn pass() {
                     Input,
                 ),
                 is_constant: false,
+                is_retain: false,
+                is_persistent: false,
                 data_type_name: "__ptr_to___arr_vla_1_dint",
                 location_in_parent: 0,
                 linkage: Internal,
@@ -286,6 +292,8 @@ fn pass() {
                     Input,
                 ),
                 is_constant: false,
+                is_retain: false,
+                is_persistent: false,
                 data_type_name: "__bounds___arr_vla_1_dint",
                 location_in_parent: 1,
                 linkage: Internal,
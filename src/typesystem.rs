@@ -47,7 +47,6 @@ pub const LINT_SIZE: u32 = NativeLintType::BITS;
 pub const REAL_SIZE: u32 = (size_of::<NativeRealType>() * 8) as u32;
 pub const LREAL_SIZE: u32 = (size_of::<NativeLrealType>() * 8) as u32;
 pub const DATE_TIME_SIZE: u32 = 64;
-pub const POINTER_SIZE: u32 = NativePointerType::BITS;
 
 pub const U1_TYPE: &str = "__U1";
 /// used internally for forced casts to u1
@@ -189,18 +188,15 @@ impl DataType {
     }
 
     pub fn find_member(&self, member_name: &str) -> Option<&VariableIndexEntry> {
-        if let DataTypeInformation::Struct { members, .. } = self.get_type_information() {
-            members.iter().find(|member| member.get_name().eq_ignore_ascii_case(member_name))
-        } else {
-            None
-        }
+        self.get_members().iter().find(|member| member.get_name().eq_ignore_ascii_case(member_name))
     }
 
     pub fn get_members(&self) -> &[VariableIndexEntry] {
-        if let DataTypeInformation::Struct { members, .. } = self.get_type_information() {
-            members
-        } else {
-            &[]
+        match self.get_type_information() {
+            DataTypeInformation::Struct { members, .. } | DataTypeInformation::Union { members, .. } => {
+                members
+            }
+            _ => &[],
         }
     }
 
@@ -312,7 +308,7 @@ impl TypeSize {
             TypeSize::ConstExpression(id) => {
                 index.get_const_expressions().get_constant_int_statement_value(id).map(|it| it as i64)
             }
-            TypeSize::Undetermined => Ok(POINTER_SIZE as i64),
+            TypeSize::Undetermined => Ok(index.get_type_layout().pointer_size.bits() as i64),
         }
     }
 
@@ -359,6 +355,11 @@ pub enum DataTypeInformation {
         members: Vec<VariableIndexEntry>,
         source: StructSource,
     },
+    /// a struct whose members overlay each other, starting at the same address
+    Union {
+        name: TypeId,
+        members: Vec<VariableIndexEntry>,
+    },
     Array {
         name: TypeId,
         inner_type_name: TypeId,
@@ -411,6 +412,7 @@ impl DataTypeInformation {
     pub fn get_name(&self) -> &str {
         match self {
             DataTypeInformation::Struct { name, .. }
+            | DataTypeInformation::Union { name, .. }
             | DataTypeInformation::Array { name, .. }
             | DataTypeInformation::Pointer { name, .. }
             | DataTypeInformation::Integer { name, .. }
@@ -473,6 +475,10 @@ impl DataTypeInformation {
         matches!(self, DataTypeInformation::Struct { .. })
     }
 
+    pub fn is_union(&self) -> bool {
+        matches!(self, DataTypeInformation::Union { .. })
+    }
+
     pub fn is_array(&self) -> bool {
         matches!(self, DataTypeInformation::Array { .. })
     }
@@ -541,6 +547,7 @@ impl DataTypeInformation {
         matches!(
             self,
             DataTypeInformation::Struct { .. }
+                | DataTypeInformation::Union { .. }
                 | DataTypeInformation::Array { .. }
                 | DataTypeInformation::String { .. }
         )
@@ -583,6 +590,10 @@ impl DataTypeInformation {
                     MemoryLocation::new(res)
                 })
                 .into(),
+            DataTypeInformation::Union { members, .. } => members
+                .iter()
+                .map(|it| index.get_type_information_or_void(it.get_type_name()).get_size(index))
+                .fold(Bytes::new(0), |max, it| if it > max { it } else { max }),
             DataTypeInformation::Array { inner_type_name, dimensions, .. } => {
                 let inner_type = index.get_type_information_or_void(inner_type_name);
                 let inner_size = inner_type.get_size_in_bits(index);
@@ -590,7 +601,7 @@ impl DataTypeInformation {
                     dimensions.iter().map(|dim| dim.get_length(index).unwrap()).product();
                 Bytes::from_bits(inner_size * element_count)
             }
-            DataTypeInformation::Pointer { .. } => Bytes::from_bits(POINTER_SIZE),
+            DataTypeInformation::Pointer { .. } => index.get_type_layout().pointer_size,
             DataTypeInformation::Alias { referenced_type, .. }
             | DataTypeInformation::SubRange { referenced_type, .. } => {
                 let inner_type = index.get_type_information_or_void(referenced_type);
@@ -625,9 +636,9 @@ impl DataTypeInformation {
                     type_layout.v64
                 }
             }
-            DataTypeInformation::Struct { .. } => type_layout.aggregate,
+            DataTypeInformation::Struct { .. } | DataTypeInformation::Union { .. } => type_layout.aggregate,
             DataTypeInformation::String { .. } => type_layout.v64, //Strings are arrays
-            DataTypeInformation::Pointer { .. } => type_layout.p64,
+            DataTypeInformation::Pointer { .. } => type_layout.pointer,
             DataTypeInformation::Integer { size, semantic_size, .. } => {
                 if let Some(1) = semantic_size {
                     type_layout.i1
@@ -637,7 +648,7 @@ impl DataTypeInformation {
                         16 => type_layout.i16,
                         32 => type_layout.i32,
                         64 => type_layout.i64,
-                        _ => type_layout.p64,
+                        _ => type_layout.pointer,
                     }
                 }
             }
@@ -647,7 +658,7 @@ impl DataTypeInformation {
             DataTypeInformation::Float { size, .. } => match size {
                 32 => type_layout.f32,
                 64 => type_layout.f64,
-                _ => type_layout.p64,
+                _ => type_layout.pointer,
             },
             DataTypeInformation::SubRange { referenced_type, .. } => {
                 index.get_type_information_or_void(referenced_type).get_alignment(index)
@@ -1229,8 +1240,8 @@ pub fn is_same_type_class(ltype: &DataTypeInformation, rtype: &DataTypeInformati
         // 2. foo := REF(bar)
         // 3. foo := &bar
         DataTypeInformation::Pointer { .. } => match rtype {
-            // Case 1: ADR(bar) returns a LWORD value, thus check if we're working with a LWORD
-            DataTypeInformation::Integer { size, .. } => *size == POINTER_SIZE,
+            // Case 1: ADR(bar) returns a LWORD value, thus check if the value can hold a pointer of the target
+            DataTypeInformation::Integer { size, .. } => *size >= index.get_type_layout().pointer_size.bits(),
 
             // Case 2 & 3:
             // REF(bar) and &bar returns a pointer, thus deduce their inner types and check if they're equal
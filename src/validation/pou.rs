@@ -67,6 +67,31 @@ fn validate_pou<T: AnnotationMap>(validator: &mut Validator, pou: &Pou, context:
     if pou.pou_type == PouType::Program {
         validate_program(validator, pou);
     }
+    validate_type_arguments(validator, pou, context);
+}
+
+/// validates that the type arguments of an instance of a generic pou match the natures of its type
+/// parameters, like the arguments of a generic function
+fn validate_type_arguments<T: AnnotationMap>(
+    validator: &mut Validator,
+    pou: &Pou,
+    context: &ValidationContext<T>,
+) {
+    for argument in &pou.type_arguments {
+        let Some(data_type) = context.index.find_effective_type_by_name(&argument.type_name) else {
+            continue;
+        };
+        if !data_type.has_nature(argument.binding.nature, context.index) {
+            validator.push_diagnostic(
+                Diagnostic::error(format!(
+                    "Invalid type nature for generic argument. {} is no {}",
+                    argument.type_name, argument.binding.nature
+                ))
+                .with_error_code("E062")
+                .with_location(argument.location.clone()),
+            );
+        }
+    }
 }
 
 fn validate_class<T: AnnotationMap>(validator: &mut Validator, pou: &Pou, context: &ValidationContext<T>) {
@@ -41,8 +41,11 @@ impl RecursiveValidator {
         let mut nodes_all: IndexSet<&DataType> = IndexSet::new();
         let mut nodes_visited = IndexSet::new();
 
-        // Structs (includes arrays defined in structs)
-        nodes_all.extend(index.get_types().values().filter(|x| x.get_type_information().is_struct()));
+        // Structs and unions (includes arrays defined in structs)
+        nodes_all.extend(index.get_types().values().filter(|x| {
+            let information = x.get_type_information();
+            information.is_struct() || information.is_union()
+        }));
 
         // Function Blocks
         nodes_all.extend(index.get_pou_types().values().filter(|x| {
@@ -2,8 +2,8 @@ use std::{collections::HashSet, mem::discriminant};
 
 use plc_ast::{
     ast::{
-        flatten_expression_list, AstNode, AstStatement, DirectAccess, DirectAccessType, JumpStatement,
-        Operator, ReferenceAccess,
+        flatten_expression_list, AstNode, AstStatement, DirectAccess, DirectAccessType, HardwareAccess,
+        JumpStatement, Operator, ReferenceAccess,
     },
     control_statements::{AstControlStatement, ConditionalBlock},
     literals::{Array, AstLiteral, StringValue},
@@ -17,10 +17,11 @@ use crate::{
     builtins::{self, BuiltIn},
     codegen::generators::expression_generator::get_implicit_call_parameter,
     index::{ArgumentType, Index, PouIndexEntry, VariableIndexEntry, VariableType},
+    process_image::{self, ImageAddress},
     resolver::{const_evaluator, AnnotationMap, StatementAnnotation},
     typesystem::{
         self, get_equals_function_name_for, get_literal_actual_signed_type_name, DataType,
-        DataTypeInformation, Dimension, StructSource, BOOL_TYPE, POINTER_SIZE,
+        DataTypeInformation, Dimension, StructSource, BOOL_TYPE,
     },
 };
 
@@ -131,11 +132,25 @@ pub fn visit_statement<T: AnnotationMap>(
         // AstStatement::ReturnStatement { location, id } => (),
         // AstStatement::LiteralNull { location, id } => (),
         AstStatement::ParenExpression(expr) => visit_statement(validator, expr, context),
+        AstStatement::HardwareAccess(data) => validate_hardware_access(validator, data, statement),
         _ => {}
     }
     validate_type_nature(validator, statement, context);
 }
 
+/// direct addresses used in a body need to point into the input, output or marker image
+fn validate_hardware_access(validator: &mut Validator, access: &HardwareAccess, statement: &AstNode) {
+    let is_image_address = process_image::get_image_name(access.direction).is_some()
+        && ImageAddress::from_statements(access.access, &access.address).is_some();
+    if !is_image_address {
+        validator.push_diagnostic(
+            Diagnostic::error("Direct address is not part of the input, output or marker image")
+                .with_error_code("E101")
+                .with_location(statement.get_location()),
+        );
+    }
+}
+
 fn validate_reference_expression<T: AnnotationMap>(
     access: &ReferenceAccess,
     validator: &mut Validator,
@@ -931,10 +946,9 @@ fn is_invalid_pointer_assignment(
     if left_type.is_pointer() & right_type.is_pointer() {
         return !typesystem::is_same_type_class(left_type, right_type, index);
     }
-    //check if Datatype can hold a Pointer (u64)
-    else if right_type.is_pointer()
-        && !left_type.is_pointer()
-        && left_type.get_size_in_bits(index) < POINTER_SIZE
+    let pointer_size = index.get_type_layout().pointer_size.bits();
+    //check if Datatype can hold a Pointer of the target
+    if right_type.is_pointer() && !left_type.is_pointer() && left_type.get_size_in_bits(index) < pointer_size
     {
         validator.push_diagnostic(
             Diagnostic::error(format!(
@@ -947,10 +961,10 @@ fn is_invalid_pointer_assignment(
         );
         return true;
     }
-    //check if size allocated to Pointer is standart pointer size (u64)
+    //check if size allocated to Pointer is the pointer size of the target
     else if left_type.is_pointer()
         && !right_type.is_pointer()
-        && right_type.get_size_in_bits(index) < POINTER_SIZE
+        && right_type.get_size_in_bits(index) < pointer_size
     {
         validator.push_diagnostic(
             Diagnostic::error(format!(
@@ -1193,6 +1207,14 @@ fn validate_type_nature<T: AnnotationMap>(
             if let AstStatement::Identifier(_) = statement.get_stmt() {
                 return;
             }
+            // inside of a generic implementation the generic types are resolved by its specializations
+            if context
+                .qualifier
+                .and_then(|it| context.index.find_pou(it))
+                .is_some_and(PouIndexEntry::is_generic)
+            {
+                return;
+            }
             validator.push_diagnostic(
                 Diagnostic::error(format!("Could not resolve generic type {generic_symbol} with {nature}"))
                     .with_error_code("E064")
@@ -1629,3 +1629,103 @@ fn generic_call_with_formal_parameter() {
     let diagnostics = parse_and_validate_buffered(src);
     insta::assert_snapshot!(diagnostics);
 }
+
+#[test]
+fn generic_function_body_with_generic_expressions_is_valid() {
+    let src = r"
+    FUNCTION factorial<U : ANY_NUM> : U
+    VAR_INPUT
+        n : U;
+    END_VAR
+        IF n <= 1 THEN
+            factorial := 1;
+        ELSE
+            factorial := n * factorial(n - 1);
+        END_IF
+    END_FUNCTION
+
+    FUNCTION main : DINT
+        main := factorial(DINT#5);
+    END_FUNCTION
+    ";
+
+    let diagnostics = parse_and_validate_buffered(src);
+    assert!(diagnostics.is_empty(), "{diagnostics}");
+}
+
+#[test]
+fn generic_function_block_instances_need_matching_type_arguments() {
+    let src = r"
+    FUNCTION_BLOCK Fifo<T : ANY>
+    VAR
+        buffer : ARRAY[0..7] OF T;
+    END_VAR
+    END_FUNCTION_BLOCK
+
+    PROGRAM main
+    VAR
+        valid : Fifo<DINT>;
+        missing : Fifo;
+        too_many : Fifo<DINT, REAL>;
+        not_generic : DINT<INT>;
+    END_VAR
+    END_PROGRAM
+    ";
+
+    let diagnostics = parse_and_validate_buffered(src);
+    assert_snapshot!(diagnostics);
+}
+
+#[test]
+fn generic_function_block_type_arguments_need_matching_natures() {
+    let src = r"
+    FUNCTION_BLOCK Accumulator<T : ANY_NUM>
+    VAR
+        total : T;
+    END_VAR
+    END_FUNCTION_BLOCK
+
+    TYPE Point : STRUCT x, y : INT; END_STRUCT END_TYPE
+
+    PROGRAM main
+    VAR
+        valid : Accumulator<LREAL>;
+        flags : Accumulator<BOOL>;
+        points : Accumulator<Point>;
+    END_VAR
+    END_PROGRAM
+    ";
+
+    let diagnostics = parse_and_validate_buffered(src);
+    assert_snapshot!(diagnostics);
+}
+
+#[test]
+fn unused_generic_function_blocks_are_validated() {
+    let src = r"
+    FUNCTION_BLOCK Fifo<T : ANY>
+    VAR
+        buffer : ARRAY[0..3] OF T;
+        head, count : DINT;
+    END_VAR
+
+    METHOD push : BOOL
+    VAR_INPUT
+        value : T;
+    END_VAR
+        buffer[(head + count) MOD 4] := value;
+        count := count + 1;
+        push := TRUE;
+    END_METHOD
+
+    METHOD pop : T
+        pop := buffer[head];
+        head := (head + 1) MOD 4;
+        size := size - 1;
+    END_METHOD
+    END_FUNCTION_BLOCK
+    ";
+
+    let diagnostics = parse_and_validate_buffered(src);
+    assert_snapshot!(diagnostics);
+}
@@ -1388,3 +1388,24 @@ fn invalid_cast_statement_causes_error() {
 
     assert_snapshot!(diagnostics);
 }
+
+#[test]
+fn direct_addresses_outside_of_the_process_image_are_reported() {
+    let diagnostics = parse_and_validate_buffered(
+        "
+        PROGRAM prg
+        VAR
+          x : WORD;
+          b : BOOL;
+        END_VAR
+          x := %IW1;
+          b := %QX1.7;
+          x := %IW1.2.3;
+          b := %QX1.8;
+          x := %GW1;
+        END_PROGRAM
+        ",
+    );
+
+    assert_snapshot!(diagnostics);
+}
@@ -113,6 +113,35 @@ fn constant_on_illegal_var_blocks_cause_validation_issue() {
     assert_snapshot!(&diagnostics);
 }
 
+#[test]
+fn retain_on_non_global_var_blocks_cause_a_warning() {
+    // GIVEN different variable block types with the RETAIN or PERSISTENT modifier
+    // WHEN it is validated
+    let diagnostics = parse_and_validate_buffered(
+        "
+        VAR_GLOBAL RETAIN //OK
+        END_VAR
+
+        VAR_GLOBAL PERSISTENT //OK
+        END_VAR
+
+        PROGRAM prg
+            VAR RETAIN //not retained
+            END_VAR
+
+            VAR PERSISTENT //not retained
+            END_VAR
+
+            VAR NON_RETAIN //ok
+            END_VAR
+        END_PROGRAM
+       ",
+    );
+
+    // THEN only the blocks inside the program are reported
+    assert_snapshot!(&diagnostics);
+}
+
 #[test]
 fn constant_fb_instances_are_illegal() {
     // GIVEN a couple of constants, including FB instances and class-instances
@@ -160,6 +189,24 @@ fn sized_varargs_require_type() {
     assert_snapshot!(&diagnostics);
 }
 
+#[test]
+fn union_members_cannot_be_initialized() {
+    // GIVEN a union where one member has an initial value
+    // WHEN it is validated
+    let diagnostics = parse_and_validate_buffered(
+        "
+        TYPE MyUnion : UNION
+            value : WORD := 16#FFFF;
+            bytes : ARRAY[0..1] OF BYTE;
+        END_UNION
+        END_TYPE
+      ",
+    );
+
+    // THEN the initializer is reported
+    assert_snapshot!(&diagnostics);
+}
+
 mod overflows {
     use crate::test_utils::tests::parse_and_validate_buffered;
     use insta::assert_snapshot;
@@ -415,3 +462,26 @@ fn assignment_suggestion_for_equal_operation_with_no_effect() {
 
     assert_snapshot!(diagnostics);
 }
+
+#[test]
+fn variables_in_the_process_image_cannot_be_initialized() {
+    let diagnostics = parse_and_validate_buffered(
+        "
+        VAR_GLOBAL
+            gStart AT %IX0.0 : BOOL := TRUE;
+            gSpeed AT %QW1 : INT := 100;
+            gLimit AT %MD2 : DINT;
+            gAnalog AT %IW1.2.3 : INT := 7;
+        END_VAR
+
+        PROGRAM main
+        VAR
+            start AT %IX1.0 : BOOL := TRUE;
+            config AT %I* : WORD := 16#FF;
+        END_VAR
+        END_PROGRAM
+        ",
+    );
+
+    assert_snapshot!(diagnostics);
+}
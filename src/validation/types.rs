@@ -30,7 +30,7 @@ pub fn visit_data_type<T: AnnotationMap>(
 
     let context = &context.with_optional_qualifier(data_type.get_name());
     match data_type {
-        DataType::StructType { variables, .. } => {
+        DataType::StructType { variables, .. } | DataType::UnionType { variables, .. } => {
             variables.iter().for_each(|v| visit_variable(validator, v, context))
         }
         DataType::ArrayType { referenced_type, .. } => {
@@ -54,6 +54,22 @@ fn validate_data_type(validator: &mut Validator, data_type: &DataType, location:
                 );
             }
         }
+        DataType::UnionType { variables, .. } => {
+            if variables.is_empty() {
+                validator.push_diagnostic(
+                    Diagnostic::error("Variable block is empty")
+                        .with_error_code("E028")
+                        .with_location(location.clone()),
+                );
+            }
+            for initializer in variables.iter().filter_map(|it| it.initializer.as_ref()) {
+                validator.push_diagnostic(
+                    Diagnostic::error("Union members cannot have an initial value")
+                        .with_error_code("E100")
+                        .with_location(initializer.get_location()),
+                );
+            }
+        }
         DataType::EnumType {
             elements: AstNode { stmt: AstStatement::ExpressionList(expressions), .. },
             ..
@@ -96,7 +112,7 @@ pub fn data_type_is_fb_or_class_instance(type_name: &str, index: &Index) -> bool
     }
 
     match data_type_info {
-        DataTypeInformation::Struct { members, .. } =>
+        DataTypeInformation::Struct { members, .. } | DataTypeInformation::Union { members, .. } =>
         //see if any member is fb or class intance
         {
             members.iter().any(|member| data_type_is_fb_or_class_instance(member.get_type_name(), index))
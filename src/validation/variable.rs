@@ -39,6 +39,16 @@ fn validate_variable_block(validator: &mut Validator, block: &VariableBlock) {
                 .with_location(block.location.clone()),
         )
     }
+
+    if (block.retain || block.persistent) && block.variable_block_type != VariableBlockType::Global {
+        validator.push_diagnostic(
+            Diagnostic::warning(
+                "RETAIN and PERSISTENT are only supported for global variables, this block will not be retained",
+            )
+            .with_error_code("E092")
+            .with_location(block.location.clone()),
+        )
+    }
 }
 
 pub fn visit_variable<T: AnnotationMap>(
@@ -164,6 +174,18 @@ fn validate_variable<T: AnnotationMap>(
             }
         }
 
+        // the process image is owned by the runtime, it is never initialized by the program
+        if let Some(initializer) = variable.initializer.as_ref().filter(|_| v_entry.is_in_process_image()) {
+            validator.push_diagnostic(
+                Diagnostic::error(format!(
+                    "Variable `{}` is located in the process image and cannot have an initial value",
+                    variable.name
+                ))
+                .with_error_code("E106")
+                .with_location(initializer.get_location()),
+            );
+        }
+
         // check if we declared a constant fb-instance or class-instance
         if v_entry.is_constant() && data_type_is_fb_or_class_instance(v_entry.get_type_name(), context.index)
         {
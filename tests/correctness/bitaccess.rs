@@ -214,3 +214,93 @@ fn byteaccess_assignment_should_not_override_current_values() {
     let res: i32 = compile_and_run(prog, &mut crate::MainType::default);
     assert_eq!(res, 0b0000_0000_1100_0011_1010_1010_0101_0101);
 }
+
+#[test]
+fn direct_addresses_share_the_process_image() {
+    let prog = "
+    PROGRAM main
+    VAR
+        low : BYTE;
+        high : BYTE;
+        flags : BYTE;
+        flag : BOOL;
+        output : BYTE;
+        word_at : WORD;
+    END_VAR
+    VAR
+        running AT %QX1.1 : BOOL;
+        counter AT %MW1 : WORD;
+    END_VAR
+    %MW1 := 16#1234;        // bytes 2 and 3 of the marker image
+    low := %MB2;
+    high := %MB3;
+    %MX0.3 := TRUE;
+    %MX0.5 := TRUE;
+    %MX0.3 := FALSE;
+    flags := %MB0;
+    flag := %MX0.5;
+    running := TRUE;
+    output := %QB1;
+    counter := counter + 1;
+    word_at := %MW1;
+    END_PROGRAM";
+
+    #[allow(dead_code)]
+    #[repr(C)]
+    #[derive(Default, Debug)]
+    struct MainType {
+        low: u8,
+        high: u8,
+        flags: u8,
+        flag: bool,
+        output: u8,
+        word_at: u16,
+    }
+
+    let mut main_type = MainType::default();
+    let _: i32 = compile_and_run(prog.to_string(), &mut main_type);
+    assert_eq!(0x34, main_type.low);
+    assert_eq!(0x12, main_type.high);
+    assert_eq!(0b0010_0000, main_type.flags);
+    assert!(main_type.flag);
+    assert_eq!(0b0000_0010, main_type.output);
+    assert_eq!(0x1235, main_type.word_at);
+}
+
+#[test]
+fn variables_at_direct_addresses_have_no_storage_of_their_own() {
+    let prog = "
+    PROGRAM main
+    VAR
+        a : BYTE;
+        start AT %IB0 : BYTE;
+        b : BYTE;
+        speed AT %QW1 : WORD;
+        c : WORD;
+        running AT %QX0.1 : BOOL;
+        d : DINT;
+    END_VAR
+    a := 1;
+    b := 2;
+    speed := 16#ABCD;
+    running := TRUE;
+    c := speed;
+    d := %QB0 + start;
+    END_PROGRAM";
+
+    #[repr(C)]
+    #[derive(Default, Debug)]
+    struct MainType {
+        a: u8,
+        b: u8,
+        c: u16,
+        d: i32,
+    }
+
+    let mut main_type = MainType::default();
+    let _: i32 = compile_and_run(prog.to_string(), &mut main_type);
+    assert_eq!(1, main_type.a);
+    assert_eq!(2, main_type.b);
+    assert_eq!(0xABCD, main_type.c);
+    assert_eq!(0b0000_0010, main_type.d);
+}
@@ -288,3 +288,58 @@ fn global_constant_struct_assignment() {
     assert_eq!(1, main_type.x);
     assert_eq!(2, main_type.y);
 }
+
+#[test]
+fn sizeof_evaluated_in_constants() {
+    let src = r#"
+        TYPE MyStruct : STRUCT
+            a : BYTE;
+            b : LINT;
+        END_STRUCT
+        END_TYPE
+
+        VAR_GLOBAL
+            s : MyStruct;
+            r : REF_TO MyStruct;
+        END_VAR
+
+        VAR_GLOBAL CONSTANT
+            struct_size : ULINT := SIZEOF(MyStruct);
+        END_VAR
+
+        PROGRAM main
+        VAR CONSTANT
+            local_size : ULINT := SIZEOF(MyStruct);
+            variable_size : ULINT := SIZEOF(s);
+            reference_size : ULINT := SIZEOF(r);
+        END_VAR
+        VAR
+            a, b, c, d : ULINT;
+        END_VAR
+            a := struct_size;
+            b := local_size;
+            c := variable_size;
+            d := reference_size;
+        END_PROGRAM
+    "#;
+
+    #[allow(dead_code)]
+    #[derive(Default)]
+    #[repr(C)]
+    struct MainType {
+        local_size: u64,
+        variable_size: u64,
+        reference_size: u64,
+        a: u64,
+        b: u64,
+        c: u64,
+        d: u64,
+    }
+    let mut main_type = MainType::default();
+
+    let _: i32 = compile_and_run(src, &mut main_type);
+    assert_eq!(16, main_type.a);
+    assert_eq!(16, main_type.b);
+    assert_eq!(16, main_type.c);
+    assert_eq!(8, main_type.d);
+}
@@ -0,0 +1,78 @@
+use driver::{runner::compile_with_options, CompileOptions};
+use plc_source::SourceCode;
+use rusty::codegen::CodegenContext;
+
+#[test]
+fn statements_and_branches_are_counted() {
+    let prog = "
+    FUNCTION classify : DINT
+    VAR_INPUT x : DINT; END_VAR
+        classify := 0;
+        IF x > 2 THEN
+            classify := 1;
+        ELSIF x > 1 THEN
+            classify := 2;
+        END_IF
+        CASE x OF
+        1: classify := classify + 10;
+        5..9: classify := classify + 20;
+        END_CASE
+    END_FUNCTION
+
+    PROGRAM main
+    VAR i : DINT; sum : DINT; END_VAR
+        FOR i := 1 TO 3 DO
+            sum := sum + classify(i);
+        END_FOR
+        WHILE sum < 0 DO
+            sum := 0;
+        END_WHILE
+    END_PROGRAM";
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct MainType {
+        i: i32,
+        sum: i32,
+    }
+
+    let directory = tempfile::tempdir().unwrap();
+    let coverage_file = directory.path().join("coverage.info");
+    std::env::set_var("PLC_COVERAGE_FILE", &coverage_file);
+
+    let context = CodegenContext::create();
+    let options = CompileOptions { coverage: true, ..Default::default() };
+    let module = compile_with_options(&context, SourceCode::new(prog, "classify.st"), options).unwrap();
+    let mut main_type = MainType::default();
+    module.run::<_, ()>("main", &mut main_type);
+    module.run::<_, ()>("main", &mut main_type);
+    module.dump_coverage();
+
+    assert_eq!(26, main_type.sum);
+    let expected = "\
+SF:classify.st
+DA:4,6
+DA:5,6
+DA:6,2
+DA:8,2
+DA:10,6
+DA:11,2
+DA:12,0
+DA:18,2
+DA:19,6
+DA:21,2
+DA:22,0
+BRDA:5,0,0,2
+BRDA:5,0,1,2
+BRDA:5,0,2,2
+BRDA:10,1,0,2
+BRDA:10,1,1,0
+BRDA:10,1,2,4
+BRDA:18,2,0,6
+BRDA:18,2,1,2
+BRDA:21,3,0,0
+BRDA:21,3,1,2
+end_of_record
+";
+    assert_eq!(expected, std::fs::read_to_string(coverage_file).unwrap());
+}
@@ -43,6 +43,54 @@ fn using_structs() {
     assert_eq!(10, main_data.my_s.field3);
 }
 
+#[test]
+fn using_unions() {
+    #[repr(C)]
+    #[derive(Debug, Default)]
+    struct MainType {
+        value: u32,
+        low: u8,
+        high: u8,
+        flag: bool,
+    }
+
+    let mut main_data = MainType::default();
+
+    let testcode = r#"
+    TYPE MyUnion:
+        UNION
+            Value : DWORD;
+            Bytes : ARRAY[0..3] OF BYTE;
+        END_UNION
+    END_TYPE
+
+    PROGRAM main
+    VAR
+        value : DWORD;
+        low : BYTE;
+        high : BYTE;
+        flag : BOOL;
+    END_VAR
+    VAR_TEMP
+        u : MyUnion;
+    END_VAR
+        u.Value := 16#12345678;
+        low := u.Bytes[0];
+        high := u.Bytes[3];
+        u.Bytes[0] := 16#FF;
+        value := u.Value;
+        flag := SIZEOF(u) = 4;
+    END_PROGRAM
+    "#;
+
+    let _: i32 = compile_and_run(testcode, &mut main_data);
+    // members share their memory, the bytes are laid out little endian
+    assert_eq!(0x78, main_data.low);
+    assert_eq!(0x12, main_data.high);
+    assert_eq!(0x123456FF, main_data.value);
+    assert!(main_data.flag);
+}
+
 #[test]
 fn using_nested_structs() {
     #[repr(C)]
@@ -0,0 +1,128 @@
+use crate::*;
+
+const FIFO: &str = "
+    FUNCTION_BLOCK Fifo<T : ANY>
+    VAR
+        buffer : ARRAY[0..3] OF T;
+        head, count : DINT;
+    END_VAR
+
+    METHOD push : BOOL
+    VAR_INPUT
+        value : T;
+    END_VAR
+        IF count >= 4 THEN
+            push := FALSE;
+            RETURN;
+        END_IF
+        buffer[(head + count) MOD 4] := value;
+        count := count + 1;
+        push := TRUE;
+    END_METHOD
+
+    METHOD pop : T
+        IF count > 0 THEN
+            pop := buffer[head];
+            head := (head + 1) MOD 4;
+            count := count - 1;
+        END_IF
+    END_METHOD
+    END_FUNCTION_BLOCK
+";
+
+#[test]
+fn generic_function_blocks_are_instantiated_for_each_type() {
+    #[repr(C)]
+    #[derive(Default)]
+    struct MainType {
+        a: i32,
+        b: i32,
+        c: f64,
+        full: bool,
+    }
+
+    let main = "
+        PROGRAM main
+        VAR_TEMP
+            ints : Fifo<DINT>;
+            reals : Fifo<LREAL>;
+        END_VAR
+        VAR
+            a, b : DINT;
+            c : LREAL;
+            full : BOOL;
+        END_VAR
+            ints.push(3);
+            ints.push(4);
+            reals.push(1.5);
+            a := ints.pop();
+            b := ints.pop();
+            c := reals.pop();
+
+            reals.push(1.0);
+            reals.push(2.0);
+            reals.push(3.0);
+            reals.push(4.0);
+            full := NOT reals.push(5.0);
+        END_PROGRAM
+    ";
+
+    let mut main_type = MainType::default();
+    let _: i32 = compile_and_run(format!("{FIFO}{main}"), &mut main_type);
+    assert_eq!(main_type.a, 3);
+    assert_eq!(main_type.b, 4);
+    assert_eq!(main_type.c, 1.5);
+    assert!(main_type.full);
+}
+
+#[test]
+fn generic_function_blocks_can_be_nested_and_used_from_other_units() {
+    #[repr(C)]
+    #[derive(Default)]
+    struct MainType {
+        a: i16,
+        b: i16,
+    }
+
+    let queue = SourceCode::new(
+        "
+        FUNCTION_BLOCK Queue<T : ANY_NUM>
+        VAR
+            items : Fifo<T>;
+            total : T;
+        END_VAR
+        METHOD enqueue
+        VAR_INPUT
+            value : T;
+        END_VAR
+            IF items.push(value) THEN
+                total := total + value;
+            END_IF
+        END_METHOD
+        END_FUNCTION_BLOCK
+        ",
+        "queue.st",
+    );
+    let main = SourceCode::new(
+        "
+        PROGRAM main
+        VAR_TEMP
+            queue : Queue<INT>;
+        END_VAR
+        VAR
+            a, b : INT;
+        END_VAR
+            queue.enqueue(5);
+            queue.enqueue(7);
+            a := queue.total;
+            b := queue.items.pop();
+        END_PROGRAM
+        ",
+        "main.st",
+    );
+
+    let mut main_type = MainType::default();
+    let _: i32 = compile_and_run(vec![SourceCode::new(FIFO, "fifo.st"), queue, main], &mut main_type);
+    assert_eq!(main_type.a, 12);
+    assert_eq!(main_type.b, 5);
+}
@@ -226,3 +226,117 @@ fn test_any_real_called_with_ints() {
     assert_eq!(main_type.e, -12.0f32);
     assert_eq!(main_type.f, 14.0f32);
 }
+
+#[test]
+fn generic_functions_implemented_in_st_are_specialized_for_each_type() {
+    #[repr(C)]
+    #[derive(Default)]
+    struct MainType {
+        a: i16,
+        b: f64,
+        c: i32,
+        d: f32,
+    }
+
+    let prog = "
+    FUNCTION sum_of<T: ANY_NUM> : T
+    VAR_INPUT
+        values : ARRAY[0..2] OF T;
+    END_VAR
+    VAR
+        i : INT;
+        total : T;
+    END_VAR
+        FOR i := 0 TO 2 DO
+            total := add_to(total, values[i]);
+        END_FOR
+        sum_of := total;
+    END_FUNCTION
+
+    FUNCTION add_to<U: ANY_NUM> : U
+    VAR_INPUT
+        a, b : U;
+    END_VAR
+        add_to := a + b;
+    END_FUNCTION
+
+    FUNCTION factorial<T: ANY_INT> : T
+    VAR_INPUT
+        n : T;
+    END_VAR
+        IF n <= 1 THEN
+            factorial := 1;
+        ELSE
+            factorial := n * factorial(n - 1);
+        END_IF
+    END_FUNCTION
+
+    PROGRAM main
+    VAR
+        a : INT;
+        b : LREAL;
+        c : DINT;
+        d : REAL;
+    END_VAR
+    VAR_TEMP
+        ints : ARRAY[0..2] OF INT := [1, 2, 3];
+        reals : ARRAY[0..2] OF LREAL := [0.5, 1.5, 2.0];
+    END_VAR
+        a := sum_of(ints);
+        b := sum_of(reals);
+        c := factorial(DINT#10);
+        d := add_to(REAL#1.5, REAL#2.25);
+    END_PROGRAM
+    ";
+
+    let mut main_type = MainType::default();
+    let _: i32 = compile_and_run(prog.to_string(), &mut main_type);
+    assert_eq!(main_type.a, 6);
+    assert_eq!(main_type.b, 4.0f64);
+    assert_eq!(main_type.c, 3628800);
+    assert_eq!(main_type.d, 3.75f32);
+}
+
+#[test]
+fn generic_functions_implemented_in_st_can_be_called_from_other_units() {
+    #[repr(C)]
+    #[derive(Default)]
+    struct MainType {
+        a: i16,
+        b: f32,
+    }
+
+    let generic = SourceCode::new(
+        "
+        FUNCTION maximum<T: ANY_NUM> : T
+        VAR_INPUT
+            a, b : T;
+        END_VAR
+            IF a > b THEN
+                maximum := a;
+            ELSE
+                maximum := b;
+            END_IF
+        END_FUNCTION
+        ",
+        "generic.st",
+    );
+    let main = SourceCode::new(
+        "
+        PROGRAM main
+        VAR
+            a : INT;
+            b : REAL;
+        END_VAR
+            a := maximum(INT#-3, INT#7);
+            b := maximum(REAL#2.5, REAL#-1.0);
+        END_PROGRAM
+        ",
+        "main.st",
+    );
+
+    let mut main_type = MainType::default();
+    let _: i32 = compile_and_run(vec![generic, main], &mut main_type);
+    assert_eq!(main_type.a, 7);
+    assert_eq!(main_type.b, 2.5f32);
+}
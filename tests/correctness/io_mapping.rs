@@ -0,0 +1,195 @@
+use driver::{runner::compile_with_options, CompileOptions};
+use rusty::{codegen::CodegenContext, hardware_binding::parse_io_mapping, ConfigFormat};
+
+const MAPPING: &str = r#"
+[[HardwareConfiguration]]
+name = 'start'
+direction = 'Input'
+type = 'Bit'
+address = ['0', '3']
+[[HardwareConfiguration]]
+name = 'speed'
+direction = 'Input'
+type = 'Word'
+address = ['1']
+[[HardwareConfiguration]]
+name = 'lamp'
+direction = 'Output'
+type = 'Bit'
+address = ['0', '2']
+[[HardwareConfiguration]]
+name = 'setpoint'
+direction = 'Output'
+type = 'Word'
+address = ['1']
+"#;
+
+#[test]
+fn mapped_wildcard_addresses_are_accessed_in_the_process_image() {
+    let prog = "
+    VAR_GLOBAL
+        start AT %I* : BOOL;
+        speed AT %I* : INT;
+        lamp AT %Q* : BOOL;
+        setpoint AT %Q* : INT;
+    END_VAR
+
+    PROGRAM main
+    VAR
+        phase : INT;
+        outputs : BYTE;
+        written_setpoint : WORD;
+    END_VAR
+    CASE phase OF
+        0: // the runtime fills the input image
+            %IX0.3 := TRUE;
+            %IW1 := 40;
+        1: // the cycle only sees the mapped variables
+            lamp := start;
+            setpoint := speed * 2;
+        2: // the runtime reads the output image
+            outputs := %QB0;
+            written_setpoint := %QW1;
+    END_CASE
+    phase := phase + 1;
+    END_PROGRAM";
+
+    #[repr(C)]
+    #[derive(Default, Debug)]
+    struct MainType {
+        phase: i16,
+        outputs: u8,
+        written_setpoint: u16,
+    }
+
+    let context = CodegenContext::create();
+    let options = CompileOptions {
+        io_mapping: Some(parse_io_mapping(MAPPING, ConfigFormat::TOML).unwrap()),
+        ..Default::default()
+    };
+    let module = compile_with_options(&context, prog, options).unwrap();
+    let mut main_type = MainType::default();
+    for _ in 0..3 {
+        module.run::<_, ()>("main", &mut main_type);
+    }
+
+    assert_eq!(0b0000_0100, main_type.outputs);
+    assert_eq!(80, main_type.written_setpoint);
+}
+
+#[test]
+fn instances_of_function_blocks_access_their_own_mapped_addresses() {
+    let prog = "
+    FUNCTION_BLOCK fb
+    VAR
+        a AT %I* : BOOL;
+        x AT %Q* : INT;
+        q AT %Q* : BOOL;
+    END_VAR
+    IF a THEN
+        x := 10;
+    ELSE
+        x := 20;
+    END_IF
+    q := a;
+    END_FUNCTION_BLOCK
+
+    VAR_GLOBAL
+        gFb : fb;
+        aFb : ARRAY[0..1] OF fb;
+    END_VAR
+
+    PROGRAM main
+    VAR
+        phase : INT;
+        local : fb;
+        results : ARRAY[0..3] OF INT;
+        bits : BYTE;
+        byte : BYTE;
+    END_VAR
+    CASE phase OF
+        0: // the runtime fills the input image, the unmapped instance uses its own storage
+            %IB1 := 2#1111_1011;
+            %IB4 := 2#0000_0001;
+            %QX0.7 := TRUE;
+            local.a := TRUE;
+        1:
+            gFb();
+            aFb[0]();
+            aFb[1]();
+            local();
+        2: // the runtime reads the output image
+            results[0] := %QW3;
+            results[1] := %QW4;
+            results[2] := %QW5;
+            results[3] := local.x;
+            bits := %QB0;
+            byte := %QB1;
+    END_CASE
+    phase := phase + 1;
+    END_PROGRAM";
+
+    let mapping = r#"{ "HardwareConfiguration": [
+        { "name": "gFb.a", "direction": "Input", "type": "Bit", "address": ["1", "2"] },
+        { "name": "gFb.x", "direction": "Output", "type": "Word", "address": ["3"] },
+        { "name": "gFb.q", "direction": "Output", "type": "Bit", "address": ["0", "0"] },
+        { "name": "aFb[0].a", "direction": "Input", "type": "Byte", "address": ["4"] },
+        { "name": "aFb[0].x", "direction": "Output", "type": "Word", "address": ["4"] },
+        { "name": "aFb[0].q", "direction": "Output", "type": "Bit", "address": ["0", "1"] },
+        { "name": "aFb[1].a", "direction": "Input", "type": "Bit", "address": ["4", "0"] },
+        { "name": "aFb[1].x", "direction": "Output", "type": "Word", "address": ["5"] },
+        { "name": "aFb[1].q", "direction": "Output", "type": "Byte", "address": ["1"] }
+    ] }"#;
+
+    #[repr(C)]
+    #[derive(Default, Debug)]
+    struct FbType {
+        a: u8,
+        x: i16,
+        q: u8,
+    }
+
+    #[repr(C)]
+    #[derive(Default, Debug)]
+    struct MainType {
+        phase: i16,
+        local: FbType,
+        results: [i16; 4],
+        bits: u8,
+        byte: u8,
+    }
+
+    let context = CodegenContext::create();
+    let options = CompileOptions {
+        io_mapping: Some(parse_io_mapping(mapping, ConfigFormat::JSON).unwrap()),
+        ..Default::default()
+    };
+    let module = compile_with_options(&context, prog, options).unwrap();
+    let mut main_type = MainType::default();
+    for _ in 0..3 {
+        module.run::<_, ()>("main", &mut main_type);
+    }
+
+    assert_eq!([20, 10, 10, 10], main_type.results);
+    assert_eq!(0b1000_0010, main_type.bits);
+    assert_eq!(1, main_type.byte);
+}
+
+#[test]
+fn invalid_io_mappings_are_reported() {
+    let prog = "
+    VAR_GLOBAL
+        start AT %I* : BOOL;
+    END_VAR";
+    let mapping = r#"{ "HardwareConfiguration": [
+        { "name": "stop", "direction": "Input", "type": "Bit", "address": ["0", "1"] }
+    ] }"#;
+
+    let context = CodegenContext::create();
+    let options = CompileOptions {
+        io_mapping: Some(parse_io_mapping(mapping, ConfigFormat::JSON).unwrap()),
+        ..Default::default()
+    };
+    let error = compile_with_options(&context, prog, options).err().unwrap();
+    assert_eq!(error.get_type(), "E102");
+}
@@ -0,0 +1,226 @@
+use std::ffi::{c_char, c_void, CString};
+
+use driver::{parse_and_annotate, runner::compile_with_options, CompileOptions};
+use plc_source::SourceCode;
+use rusty::{
+    codegen::{CodegenContext, GeneratedModule},
+    online_change::LayoutRecord,
+};
+
+const PREVIOUS_SOURCE: &str = "
+    TYPE Point : STRUCT
+        x : SINT;
+        y : DINT;
+    END_STRUCT
+    END_TYPE
+
+    TYPE Flags : STRUCT
+        a : SINT;
+        b : SINT;
+        c : DINT;
+    END_STRUCT
+    END_TYPE
+
+    FUNCTION_BLOCK Counter
+    VAR_INPUT
+        step : INT;
+    END_VAR
+    VAR
+        count : DINT;
+    END_VAR
+        count := count + step;
+    END_FUNCTION_BLOCK
+
+    VAR_GLOBAL
+        total : LINT;
+    END_VAR
+
+    PROGRAM main
+    VAR
+        a : DINT;
+        removed : BOOL;
+        points : ARRAY[1..2] OF Point;
+        counter : Counter;
+        retyped : INT;
+    END_VAR
+        a := 7;
+        removed := TRUE;
+        points[1].x := 1;
+        points[1].y := 11;
+        points[2].x := 2;
+        points[2].y := 22;
+        counter(step := 5);
+        retyped := 3;
+    END_PROGRAM
+    ";
+
+const SOURCE: &str = "
+    TYPE Point : STRUCT
+        z : BYTE;
+        y : DINT;
+        x : SINT;
+    END_STRUCT
+    END_TYPE
+
+    TYPE Flags : STRUCT
+        a : SINT;
+        c : DINT;
+        b : SINT;
+    END_STRUCT
+    END_TYPE
+
+    FUNCTION_BLOCK Counter
+    VAR_INPUT
+        step : INT;
+    END_VAR
+    VAR
+        added : LREAL;
+        count : DINT;
+    END_VAR
+        count := count + step;
+    END_FUNCTION_BLOCK
+
+    VAR_GLOBAL
+        total : LINT;
+    END_VAR
+
+    VAR_GLOBAL CONSTANT
+        flags_size : ULINT := SIZEOF(Flags);
+    END_VAR
+
+    FUNCTION get_flags_size : ULINT
+        get_flags_size := flags_size;
+    END_FUNCTION
+
+    PROGRAM main
+    VAR
+        new : DINT := 99;
+        counter : Counter;
+        points : ARRAY[1..2] OF Point;
+        retyped : REAL;
+        a : DINT;
+    END_VAR
+    END_PROGRAM
+    ";
+
+#[repr(C)]
+#[derive(Default)]
+struct PreviousPoint {
+    x: i8,
+    y: i32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct PreviousCounter {
+    step: i16,
+    count: i32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct PreviousMain {
+    a: i32,
+    removed: bool,
+    points: [PreviousPoint; 2],
+    counter: PreviousCounter,
+    retyped: i16,
+}
+
+/// the layout of the current build keeps the previous members in place and appends new ones
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i8,
+    y: i32,
+    z: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Counter {
+    step: i16,
+    count: i32,
+    added: f64,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Main {
+    a: i32,
+    points: [Point; 2],
+    counter: Counter,
+    new: i32,
+    retyped: f32,
+}
+
+#[repr(C)]
+struct Symbol {
+    name: *const c_char,
+    type_name: *const c_char,
+    address: *mut c_void,
+    size: u32,
+}
+
+fn previous_layout() -> LayoutRecord {
+    let (_, project) = parse_and_annotate("previous", vec![SourceCode::from(PREVIOUS_SOURCE)]).unwrap();
+    project.collect_layout_record().unwrap()
+}
+
+fn compile_with_migration(context: &CodegenContext) -> GeneratedModule<'_> {
+    let options = CompileOptions {
+        embed_symbols: true,
+        previous_layout: Some(previous_layout()),
+        ..Default::default()
+    };
+    compile_with_options(context, SOURCE, options).unwrap()
+}
+
+fn find_instance<'a, T>(module: &GeneratedModule, name: &str) -> &'a T {
+    let mut name = CString::new(name).unwrap().into_bytes_with_nul();
+    let symbol: *const Symbol = module.run("__plc_find_symbol", &mut name[0]);
+    unsafe { &*((*symbol).address as *const T) }
+}
+
+#[test]
+fn program_state_is_migrated_into_the_new_layout() {
+    let previous_context = CodegenContext::create();
+    let previous_module =
+        compile_with_options(&previous_context, PREVIOUS_SOURCE, CompileOptions::default()).unwrap();
+    let mut previous = PreviousMain::default();
+    previous_module.run::<_, ()>("main", &mut previous);
+    previous_module.run::<_, ()>("main", &mut previous);
+
+    let context = CodegenContext::create();
+    let module = compile_with_migration(&context);
+    module.run::<_, ()>("__migrate_main_instance", &mut previous);
+
+    assert_eq!(
+        find_instance::<Main>(&module, "main"),
+        &Main {
+            a: 7,
+            points: [Point { x: 1, y: 11, z: 0 }, Point { x: 2, y: 22, z: 0 }],
+            counter: Counter { step: 5, count: 10, added: 0.0 },
+            new: 99,
+            retyped: 0.0,
+        }
+    );
+}
+
+#[test]
+fn unchanged_global_variables_are_migrated() {
+    let context = CodegenContext::create();
+    let module = compile_with_migration(&context);
+    let mut previous: i64 = 1234;
+    module.run::<_, ()>("__migrate_total", &mut previous);
+    assert_eq!(*find_instance::<i64>(&module, "total"), 1234);
+}
+
+#[test]
+fn folded_sizes_follow_the_stable_layout() {
+    let context = CodegenContext::create();
+    let module = compile_with_migration(&context);
+    // the members keep their previous order `a, b, c` which needs less padding than `a, c, b`
+    let size: u64 = module.run_no_param("get_flags_size");
+    assert_eq!(size, 8);
+}
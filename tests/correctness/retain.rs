@@ -0,0 +1,118 @@
+use std::ffi::{c_char, c_void, CStr};
+
+use driver::runner::compile;
+use rusty::codegen::{CodegenContext, GeneratedModule};
+
+#[repr(C)]
+struct RetainVariable {
+    name: *const c_char,
+    address: *mut c_void,
+    size: u32,
+    flags: u32,
+}
+
+const SOURCE: &str = "
+    VAR_GLOBAL RETAIN
+        counter : DINT;
+        values : ARRAY[0..3] OF INT;
+    END_VAR
+
+    VAR_GLOBAL PERSISTENT RETAIN
+        operatingHours : LREAL;
+    END_VAR
+
+    VAR_GLOBAL
+        volatile : DINT;
+    END_VAR
+
+    PROGRAM main
+        counter := counter + 1;
+        values[counter MOD 4] := 7;
+        volatile := volatile + 1;
+    END_PROGRAM
+
+    {external}
+    FUNCTION __plc_retain_at : LWORD
+    VAR_INPUT
+        index : UDINT;
+    END_VAR
+    END_FUNCTION
+
+    PROGRAM RetainAt
+    VAR_INPUT
+        index : UDINT;
+    END_VAR
+    VAR_OUTPUT
+        variable : LWORD;
+    END_VAR
+        variable := __plc_retain_at(index);
+    END_PROGRAM
+    ";
+
+fn retain_at<'a>(module: &GeneratedModule, index: u32) -> Option<&'a RetainVariable> {
+    #[repr(C)]
+    struct RetainAt {
+        index: u32,
+        variable: *const RetainVariable,
+    }
+    let mut retain_at = RetainAt { index, variable: std::ptr::null() };
+    module.run::<_, ()>("RetainAt", &mut retain_at);
+    unsafe { retain_at.variable.as_ref() }
+}
+
+fn retain_variables<'a>(module: &GeneratedModule) -> Vec<&'a RetainVariable> {
+    let count: u32 = module.run_no_param("__plc_retain_count");
+    (0..count).map(|index| retain_at(module, index).unwrap()).collect()
+}
+
+fn name(variable: &RetainVariable) -> &str {
+    unsafe { CStr::from_ptr(variable.name) }.to_str().unwrap()
+}
+
+#[test]
+fn retain_variables_are_listed_with_their_size_and_flags() {
+    let context = CodegenContext::create();
+    let module = compile(&context, SOURCE);
+
+    let variables = retain_variables(&module)
+        .into_iter()
+        .map(|it| (name(it).to_string(), it.size, it.flags))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        variables,
+        vec![
+            ("counter".to_string(), 4, 0),
+            ("values".to_string(), 8, 0),
+            ("operatingHours".to_string(), 8, 1)
+        ]
+    );
+    assert!(retain_at(&module, 3).is_none());
+}
+
+#[test]
+fn retain_variables_can_be_saved_and_restored() {
+    let context = CodegenContext::create();
+    let module = compile(&context, SOURCE);
+    module.run::<_, ()>("main", &mut ());
+    module.run::<_, ()>("main", &mut ());
+
+    // save the state of all retain variables
+    let saved = retain_variables(&module)
+        .into_iter()
+        .map(|it| unsafe { std::slice::from_raw_parts(it.address as *const u8, it.size as usize) }.to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(saved[0], 2i32.to_ne_bytes());
+
+    // restore them into a fresh instance of the application
+    let context = CodegenContext::create();
+    let module = compile(&context, SOURCE);
+    for (variable, bytes) in retain_variables(&module).into_iter().zip(&saved) {
+        unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), variable.address as *mut u8, bytes.len()) };
+    }
+    module.run::<_, ()>("main", &mut ());
+
+    let counter = retain_variables(&module)[0];
+    assert_eq!(unsafe { *(counter.address as *const i32) }, 3);
+    let values = retain_variables(&module)[1];
+    assert_eq!(unsafe { *(values.address as *const [i16; 4]) }, [0, 7, 7, 7]);
+}
@@ -0,0 +1,177 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+
+use driver::{runner::compile_with_options, CompileOptions};
+use rusty::codegen::{CodegenContext, GeneratedModule};
+
+#[repr(C)]
+struct Symbol {
+    name: *const c_char,
+    type_name: *const c_char,
+    address: *mut c_void,
+    size: u32,
+}
+
+const SOURCE: &str = "
+    TYPE Point : STRUCT
+        x : SINT;
+        y : DINT;
+    END_STRUCT
+    END_TYPE
+
+    FUNCTION_BLOCK Motor
+    VAR_INPUT
+        enable : BOOL;
+    END_VAR
+    VAR_OUTPUT
+        speed : REAL;
+    END_VAR
+        IF enable THEN
+            speed := 1500.0;
+        END_IF
+    END_FUNCTION_BLOCK
+
+    VAR_GLOBAL
+        position : Point;
+        points : ARRAY[1..3] OF Point;
+    END_VAR
+
+    PROGRAM MainProg
+    VAR
+        counter : DINT;
+        fbMotor : Motor;
+    END_VAR
+        counter := counter + 1;
+        fbMotor(enable := TRUE);
+        position.y := 42;
+        points[3].x := 7;
+    END_PROGRAM
+
+    {external}
+    FUNCTION __plc_symbol_at : LWORD
+    VAR_INPUT
+        index : UDINT;
+    END_VAR
+    END_FUNCTION
+
+    PROGRAM SymbolAt
+    VAR_INPUT
+        index : UDINT;
+    END_VAR
+    VAR_OUTPUT
+        symbol : LWORD;
+    END_VAR
+        symbol := __plc_symbol_at(index);
+    END_PROGRAM
+    ";
+
+fn compile_with_symbols(context: &CodegenContext) -> GeneratedModule<'_> {
+    let options = CompileOptions { embed_symbols: true, ..Default::default() };
+    compile_with_options(context, SOURCE, options).unwrap()
+}
+
+fn find_symbol<'a>(module: &GeneratedModule, name: &str) -> Option<&'a Symbol> {
+    let mut name = CString::new(name).unwrap().into_bytes_with_nul();
+    let symbol: *const Symbol = module.run("__plc_find_symbol", &mut name[0]);
+    unsafe { symbol.as_ref() }
+}
+
+fn symbol_at<'a>(module: &GeneratedModule, index: u32) -> Option<&'a Symbol> {
+    #[repr(C)]
+    struct SymbolAt {
+        index: u32,
+        symbol: *const Symbol,
+    }
+    let mut symbol_at = SymbolAt { index, symbol: std::ptr::null() };
+    module.run::<_, ()>("SymbolAt", &mut symbol_at);
+    unsafe { symbol_at.symbol.as_ref() }
+}
+
+/// runs the main program on its global instance
+fn run_main(module: &GeneratedModule) {
+    let instance = find_symbol(module, "MainProg").unwrap();
+    module.run::<_, ()>("MainProg", unsafe { &mut *(instance.address as *mut u8) });
+}
+
+fn type_name(symbol: &Symbol) -> &str {
+    unsafe { CStr::from_ptr(symbol.type_name) }.to_str().unwrap()
+}
+
+#[test]
+fn symbols_can_be_found_by_name() {
+    let context = CodegenContext::create();
+    let module = compile_with_symbols(&context);
+    run_main(&module);
+    run_main(&module);
+
+    let counter = find_symbol(&module, "MainProg.counter").unwrap();
+    assert_eq!(type_name(counter), "DINT");
+    assert_eq!(counter.size, 4);
+    assert_eq!(unsafe { *(counter.address as *const i32) }, 2);
+
+    //names are case insensitive
+    let speed = find_symbol(&module, "mainprog.FBMOTOR.speed").unwrap();
+    assert_eq!(type_name(speed), "REAL");
+    assert_eq!(unsafe { *(speed.address as *const f32) }, 1500.0);
+
+    let y = find_symbol(&module, "position.y").unwrap();
+    assert_eq!(unsafe { *(y.address as *const i32) }, 42);
+
+    let x = find_symbol(&module, "points[3].x").unwrap();
+    assert_eq!(type_name(x), "SINT");
+    assert_eq!(unsafe { *(x.address as *const i8) }, 7);
+
+    let points = find_symbol(&module, "points").unwrap();
+    assert_eq!(type_name(points), "ARRAY[1..3] OF Point");
+    assert_eq!(points.size, 24);
+
+    assert!(find_symbol(&module, "MainProg.unknown").is_none());
+    assert!(find_symbol(&module, "MainProg.counte").is_none());
+}
+
+#[test]
+fn symbols_can_be_written_by_address() {
+    let context = CodegenContext::create();
+    let module = compile_with_symbols(&context);
+    let counter = find_symbol(&module, "MainProg.counter").unwrap();
+    unsafe { *(counter.address as *mut i32) = 41 };
+    run_main(&module);
+    assert_eq!(unsafe { *(counter.address as *const i32) }, 42);
+}
+
+#[test]
+fn symbols_can_be_enumerated() {
+    let context = CodegenContext::create();
+    let module = compile_with_symbols(&context);
+
+    let count: u32 = module.run_no_param("__plc_symbol_count");
+    let names = (0..count)
+        .map(|index| {
+            let symbol = symbol_at(&module, index).unwrap();
+            unsafe { CStr::from_ptr(symbol.name) }.to_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "position",
+            "position.x",
+            "position.y",
+            "points",
+            "points[1].x",
+            "points[2].x",
+            "points[3].x",
+            "points[1].y",
+            "points[2].y",
+            "points[3].y",
+            "MainProg",
+            "MainProg.counter",
+            "MainProg.fbMotor",
+            "MainProg.fbMotor.enable",
+            "MainProg.fbMotor.speed",
+            "SymbolAt",
+            "SymbolAt.index",
+            "SymbolAt.symbol",
+        ]
+    );
+    assert!(symbol_at(&module, count).is_none());
+}
@@ -175,3 +175,25 @@ fn build_with_clang_linker_windows() {
 
     assert!(dir.path().join("clang_proj.so").is_file());
 }
+
+#[test]
+#[serial]
+fn freestanding_executables_are_linked_with_the_linker_script() {
+    let dir = tempfile::tempdir().unwrap();
+    let parameters = &[
+        "plc",
+        "build",
+        &get_test_file("json/freestanding.json"),
+        "--target",
+        "x86_64-unknown-none",
+        "--build-location",
+        dir.path().to_str().unwrap(),
+    ];
+    compile(parameters).unwrap();
+
+    let elf = std::fs::read(dir.path().join("x86_64-unknown-none").join("proj.elf")).unwrap();
+    assert_eq!(&elf[..4], b"\x7fELF");
+    assert_eq!(u16::from_le_bytes([elf[16], elf[17]]), 2, "statically linked executable");
+    let entry = u64::from_le_bytes(elf[24..32].try_into().unwrap());
+    assert_eq!(entry & !0xffff, 0x8000000, "the program is placed in the text section of the script");
+}
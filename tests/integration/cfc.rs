@@ -17,6 +17,28 @@ fn variables_assigned() {
     assert_eq!(res, 300);
 }
 
+#[test]
+fn project_with_data_types_and_configurations() {
+    // GIVEN a PLCopen XML project declaring data types, a configuration with global variables and a CFC program
+    let st_file = get_test_file("cfc/project.st");
+    let xml_file = get_test_file("cfc/project.xml");
+    // WHEN the program sums up the initialized global variables
+    let res: i32 = compile_and_run(vec![st_file, xml_file], &mut {});
+    // THEN the data types and global variables of the project were used
+    assert_eq!(res, 3 + 20 + 5 + 1000 + 100);
+}
+
+#[test]
+fn feedback_loop_reads_the_previous_cycles_output() {
+    // GIVEN a CFC function block whose second block's output is fed back into the first block
+    let st_file = get_test_file("cfc/feedback.st");
+    let cfc_file = get_test_file("cfc/feedback.cfc");
+    // WHEN calling the function block for three cycles
+    let res: i32 = compile_and_run(vec![st_file, cfc_file], &mut {});
+    // THEN the first block always sees the second block's output of the previous cycle
+    assert_eq!(res, 56);
+}
+
 #[test]
 fn simple_assignment() {
     // GIVEN a CFC program which assigns one variable to another
@@ -83,6 +105,28 @@ fn function_returns() {
     assert_eq!(res, 222);
 }
 
+#[test]
+fn function_with_interface_without_text_declaration() {
+    // GIVEN a CFC function which declares its variables and return type only with XML elements
+    let st_file = get_test_file("cfc/interface_without_text_declaration.st");
+    let cfc_file = get_test_file("cfc/interface_without_text_declaration.cfc");
+    // WHEN passing values into the function
+    let res: i32 = compile_and_run(vec![st_file, cfc_file], &mut {});
+    // THEN the declared inputs and constants are used to compute the result
+    assert_eq!(res, 5 * 2 * 3 + 7);
+}
+
+#[test]
+fn structured_text_bodies_in_xml_project() {
+    // GIVEN an XML project with ST bodies, an ST action and a CFC program using them
+    let st_file = get_test_file("cfc/structured_text_bodies.st");
+    let xml_file = get_test_file("cfc/structured_text_bodies.xml");
+    // WHEN accumulating values in the function block and resetting it with its action
+    let res: i32 = compile_and_run(vec![st_file, xml_file], &mut {});
+    // THEN the ST bodies were compiled alongside the CFC body
+    assert_eq!(res, 40 + 80 + 100 + 1);
+}
+
 #[test]
 fn conditional_return_evaluating_true() {
     // GIVEN a CFC function which returns early if a given argument is 5 and
@@ -5,7 +5,7 @@ use std::io::Read;
 use insta::assert_snapshot;
 
 use crate::get_test_file;
-use driver::compile;
+use driver::{compile, runner::compile_and_run};
 
 #[test]
 fn ir_generation_full_pass() {
@@ -62,6 +62,42 @@ fn hardware_conf_full_pass_toml() {
     let _foo = fs::remove_file("toml");
 }
 
+#[test]
+fn symbol_map_full_pass() {
+    let file = get_test_file("io.st");
+
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let path = temp_file.path().to_string_lossy();
+    let dir = tempfile::tempdir().unwrap();
+    let symbol_map = dir.path().join("symbols.json");
+    let symbol_map = symbol_map.to_string_lossy();
+    compile(&["plc", file.as_str(), "-o", &path, "--ir", "--symbol-map", &symbol_map]).unwrap();
+
+    //Verify file content
+    let content = fs::read_to_string(symbol_map.as_ref()).expect("the symbol map should have been generated");
+    assert_snapshot!(content);
+}
+
+#[test]
+fn online_change_records_the_layout_and_generates_migrations() {
+    let file = get_test_file("io.st");
+
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("io.ll");
+    let output = output.to_string_lossy();
+    let layout = dir.path().join("online_change.json");
+
+    //the first build only records the layout
+    compile(&["plc", file.as_str(), "-o", &output, "--ir", "--online-change"]).unwrap();
+    let recorded = fs::read_to_string(&layout).expect("the layout should have been recorded");
+    assert!(!fs::read_to_string(output.as_ref()).unwrap().contains("__migrate_"));
+
+    //the next build migrates from the recorded layout
+    compile(&["plc", file.as_str(), "-o", &output, "--ir", "--online-change"]).unwrap();
+    assert_eq!(fs::read_to_string(&layout).unwrap(), recorded);
+    assert!(fs::read_to_string(output.as_ref()).unwrap().contains("define void @__migrate_"));
+}
+
 #[test]
 fn stdlib_string_function_headers_compile_to_ir() {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -78,3 +114,257 @@ fn stdlib_string_function_headers_compile_to_ir() {
         "Expected file to compile without errors"
     )
 }
+
+#[test]
+fn exported_xml_project_compiles_to_the_same_program() {
+    let file = get_test_file("export.st");
+
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("export.xml");
+    let project = project.to_string_lossy();
+    compile(&["plc", "export", "--xml", file.as_str(), "-o", &project]).unwrap();
+
+    let content = fs::read_to_string(project.as_ref()).expect("the project should have been exported");
+    assert!(content.contains(r#"<pou name="acc" pouType="functionBlock">"#));
+
+    //the exported project runs like the original code
+    let expected: i32 = compile_and_run(vec![file], &mut {});
+    let actual: i32 = compile_and_run(vec![project.to_string()], &mut {});
+    assert_eq!(expected, 1 + 3 + 5 + 1000 + 7);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn pointer_size_follows_the_target() {
+    let file = get_test_file("pointer_in_dword.st");
+    let check = |targets: &[&str]| {
+        let mut args = vec!["plc", file.as_str(), "--check"];
+        targets.iter().for_each(|it| args.extend(["--target", it]));
+        compile(&args)
+    };
+
+    assert!(check(&["armv7-unknown-linux-gnueabihf"]).is_ok(), "a DWORD can hold a 32bit pointer");
+    assert!(check(&["powerpc-unknown-linux-gnu"]).is_ok(), "a DWORD can hold a 32bit pointer");
+    assert!(check(&["x86_64-unknown-linux-gnu"]).is_err(), "a DWORD cannot hold a 64bit pointer");
+    assert!(
+        check(&["armv7-unknown-linux-gnueabihf", "x86_64-unknown-linux-gnu"]).is_err(),
+        "the project has to fit the widest pointer of all targets"
+    );
+}
+
+#[test]
+fn constants_are_evaluated_with_the_layout_of_the_target() {
+    let file = get_test_file("sizeof_struct.st");
+    let generate = |target: &str| {
+        let temp_file = tempfile::NamedTempFile::new().unwrap();
+        let path = temp_file.path().to_string_lossy();
+        compile(&["plc", file.as_str(), "--ir", "--target", target, "-o", &path]).unwrap();
+        fs::read_to_string(temp_file.path()).unwrap()
+    };
+
+    // a LINT is aligned to 4 bytes on i686
+    assert!(generate("i686-unknown-linux-gnu").contains("@buffer = global [12 x i8]"));
+    assert!(generate("x86_64-unknown-linux-gnu").contains("@buffer = global [16 x i8]"));
+}
+
+#[test]
+fn freestanding_targets_produce_objects_for_the_target() {
+    let file = get_test_file("freestanding.st");
+    let stdlib = format!("{}/libs/stdlib/iec61131-st", env!("CARGO_MANIFEST_DIR"));
+    let timers = format!("{stdlib}/timers.st");
+    let cycle_clock = format!("{stdlib}/freestanding/cycle_clock.st");
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("freestanding.o");
+    let output = output.to_string_lossy();
+    let target = "thumbv7em-none-eabihf";
+
+    let args = ["plc", &file, "-i", &timers, "-i", &cycle_clock, "--target", target, "--single-module"];
+    compile(&[&args[..], &["-c", "-o", &output]].concat()).unwrap();
+
+    let header = fs::read(output.as_ref()).unwrap();
+    assert_eq!(&header[..4], b"\x7fELF");
+    assert_eq!(header[4], 1, "32bit object");
+    assert_eq!(u16::from_le_bytes([header[18], header[19]]), 40, "ARM object");
+
+    // a freestanding target has no dynamic loader
+    let shared = dir.path().join("freestanding.so");
+    assert!(compile(&[&args[..], &["--shared", "-o", &shared.to_string_lossy()]].concat()).is_err());
+}
+
+#[test]
+fn wasm_targets_produce_a_module_exporting_the_programs_and_their_instances() {
+    let file = get_test_file("simulation.st");
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("simulation.wasm");
+    let output = output.to_string_lossy();
+    compile(&["plc", &file, "--target", "wasm32-unknown-unknown", "-o", &output]).unwrap();
+
+    let module = fs::read(output.as_ref()).unwrap();
+    assert_eq!(&module[..8], b"\0asm\x01\0\0\0");
+    let exports = read_wasm_exports(&module);
+    for export in [("memory", 2), ("main", 0), ("main_instance", 3), ("ticks", 3)] {
+        assert!(exports.contains(&(export.0.to_string(), export.1)), "{export:?} in {exports:?}");
+    }
+}
+
+/// returns the name and kind (0 function, 2 memory, 3 global) of the exports of the given module
+fn read_wasm_exports(module: &[u8]) -> Vec<(String, u8)> {
+    fn read_u32(bytes: &[u8], position: &mut usize) -> usize {
+        let mut value = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = bytes[*position];
+            *position += 1;
+            value |= ((byte & 0x7f) as usize) << shift;
+            if byte < 0x80 {
+                break;
+            }
+        }
+        value
+    }
+
+    let mut position = 8;
+    while position < module.len() {
+        let id = module[position];
+        position += 1;
+        let size = read_u32(module, &mut position);
+        if id == 7 {
+            let mut exports = vec![];
+            for _ in 0..read_u32(module, &mut position) {
+                let length = read_u32(module, &mut position);
+                let name = String::from_utf8(module[position..position + length].to_vec()).unwrap();
+                let kind = module[position + length];
+                position += length + 1;
+                read_u32(module, &mut position);
+                exports.push((name, kind));
+            }
+            return exports;
+        }
+        position += size;
+    }
+    vec![]
+}
+
+#[test]
+fn stack_report_lists_functions_and_the_deepest_call_chain_of_programs() {
+    let file = get_test_file("stack_usage.st");
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("stack_usage.o");
+    let output = output.to_string_lossy();
+    let report = dir.path().join("stack.json");
+    let report = report.to_string_lossy();
+    compile(&["plc", file.as_str(), "-c", "-o", &output, "-Onone", "--stack-report", &report]).unwrap();
+
+    let content = fs::read_to_string(report.as_ref()).expect("the stack report should have been generated");
+    let content: serde_json::Value = serde_json::from_str(&content).unwrap();
+    let fill = content["functions"].as_array().unwrap().iter().find(|it| it["name"] == "fill").unwrap();
+    let frame_size = fill["frame_size"].as_u64().unwrap();
+    assert!(frame_size >= 256 * 4, "the frame holds the buffer, got {frame_size}");
+    assert!(fill["code_size"].as_u64().unwrap() > 0);
+    let main = &content["programs"][0];
+    assert_eq!(main["name"], "main");
+    assert_eq!(main["call_chain"], serde_json::json!(["main", "controller", "fill"]));
+    assert!(main["stack_usage"].as_u64().unwrap() >= frame_size);
+
+    // the worst case of main exceeds the limit
+    let args = ["plc", file.as_str(), "-c", "-o", &output, "-Onone"];
+    assert!(compile(&[&args[..], &["--stack-limit", "512"]].concat()).is_err());
+    assert!(compile(&[&args[..], &["--stack-limit", "65536"]].concat()).is_ok());
+}
+
+#[test]
+fn run_logs_the_watched_variables_of_every_cycle() {
+    let file = get_test_file("simulation.st");
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("trace.csv");
+    let csv = csv.to_string_lossy();
+    let watch = ["-w", "count", "-w", "main.even", "-w", "position", "-w", "state", "-w", "ticks"];
+    compile(&[&["plc", "run", &file, "--cycles", "3", "--csv", &csv], &watch[..]].concat()).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(csv.as_ref()).unwrap(),
+        "cycle,count,main.even,position,state,ticks\n\
+         1,1,FALSE,0.5,odd,10\n\
+         2,2,TRUE,1,even,20\n\
+         3,3,FALSE,1.5,odd,30\n"
+    );
+
+    // unknown programs and variables are reported before running
+    assert!(compile(&["plc", "run", &file, "--cycles", "1", "--program", "ticks"]).is_err());
+    assert!(compile(&["plc", "run", &file, "--cycles", "1", "-w", "main.speed"]).is_err());
+}
+
+#[test]
+fn run_does_not_watch_or_trace_instances_mapped_into_the_process_image() {
+    let file = get_test_file("mapped_output.st");
+    let mapping = get_test_file("mapped_output.json");
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("trace.csv");
+    let csv = csv.to_string_lossy();
+    let vcd = dir.path().join("trace.vcd");
+    let vcd = vcd.to_string_lossy();
+    let args = ["plc", "run", &file, "--io-mapping", &mapping, "--cycles", "1"];
+
+    // the mapped output is read through the process image
+    compile(&[&args[..], &["--csv", &csv, "-w", "value"]].concat()).unwrap();
+    assert_eq!(fs::read_to_string(csv.as_ref()).unwrap(), "cycle,value\n1,10\n");
+
+    // its unused storage inside of the instance is not offered instead
+    let error = compile(&[&args[..], &["-w", "d1.out"]].concat()).unwrap_err().to_string();
+    assert!(error.contains("`main.d1.out` is mapped into the process image"), "{error}");
+    compile(&[&args[..], &["--vcd", &vcd, "-t", "d1"]].concat()).unwrap();
+    let trace = fs::read_to_string(vcd.as_ref()).unwrap();
+    assert!(trace.contains(" count $end"), "{trace}");
+    assert!(!trace.contains(" out $end"), "{trace}");
+}
+
+#[test]
+fn run_reports_external_functions_no_library_provides() {
+    let file = get_test_file("unresolved_external.st");
+
+    let error = compile(&["plc", "run", &file, "--cycles", "1"]).unwrap_err().to_string();
+    assert!(error.contains("`missing_function`"), "{error}");
+    // declarations that are never called do not matter
+    assert!(!error.contains("unused_function"), "{error}");
+}
+
+#[test]
+fn run_records_the_traced_variables_to_a_vcd_file() {
+    let file = get_test_file("simulation.st");
+    let dir = tempfile::tempdir().unwrap();
+    let vcd = dir.path().join("trace.vcd");
+    let vcd = vcd.to_string_lossy();
+    let args = ["plc", "run", &file, "--cycles", "2", "--cycle-time", "5", "--vcd", &vcd];
+    compile(&[&args[..], &["-t", "ticks", "-t", "main"]].concat()).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(vcd.as_ref()).unwrap(),
+        "$version RuSTy plc run $end\n\
+         $timescale 1ms $end\n\
+         $var integer 32 ! ticks $end\n\
+         $scope module main $end\n\
+         $var integer 16 \" count $end\n\
+         $var wire 1 # even $end\n\
+         $var real 64 $ position $end\n\
+         $upscope $end\n\
+         $enddefinitions $end\n\
+         #0\n\
+         $dumpvars\n\
+         b0 !\n\
+         b0 \"\n\
+         0#\n\
+         r0 $\n\
+         $end\n\
+         #5\n\
+         b1010 !\n\
+         b1 \"\n\
+         r0.5 $\n\
+         #10\n\
+         b10100 !\n\
+         b10 \"\n\
+         1#\n\
+         r1 $\n"
+    );
+
+    // strings cannot be traced
+    assert!(compile(&[&args[..], &["-t", "state"]].concat()).is_err());
+}
@@ -14,18 +14,24 @@ mod correctness {
     mod classes;
     mod constants;
     mod control_flow;
+    mod coverage;
     mod custom_datatypes;
     mod datatypes;
     mod expressions;
     mod external_functions;
     mod functions;
+    mod generic_function_blocks;
     mod generic_functions;
     mod global_variables;
     mod initial_values;
+    mod io_mapping;
     mod methods;
+    mod online_change;
     mod pointers;
+    mod retain;
     mod strings;
     mod sub_range_types;
+    mod symbol_table;
     mod math_operators {
         pub(super) mod addition;
         mod division;